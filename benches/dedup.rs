@@ -0,0 +1,64 @@
+//! Deduplication of 10 000 000 rows of 100 000 streets in 10 cells each,
+//! where every row occurs 10 times, with both `DedupBackend`s. The rows are
+//! either grouped by street (like the segments of the OpenStreetMap ways
+//! in a file) or interleaved, so that every row is of another street.
+//!
+//! ```text
+//! cargo bench --bench dedup
+//! ```
+
+#[macro_use]
+extern crate criterion;
+extern crate street_index;
+
+use criterion::{Criterion, black_box};
+use street_index::prelude::*;
+use street_index::gridconfig::number_to_alphabet_value;
+
+const ROWS: usize = 10_000_000;
+const STREETS: usize = 100_000;
+
+/// The rows are generated while they are added (10 million `InputStreetValue`s
+/// would take several GB), so every benchmark includes cloning the name and
+/// the position of every row, see the `input` benchmark.
+fn rows<'a>(names: &'a [String], positions: &'a [GridPosition], grouped: bool) -> impl Iterator<Item = InputStreetValue> + 'a {
+    (0..ROWS).map(move |i| {
+        let (street, cell) = match grouped {
+            true => (i / 100, i / 10 % 10),
+            false => (i % STREETS, i / STREETS % 10),
+        };
+        InputStreetValue::new(&names[street], positions[(street + cell) % positions.len()].clone())
+    })
+}
+
+fn bench_dedup(c: &mut Criterion) {
+    let names = (0..STREETS).map(|street| format!("Street {}", street)).collect::<Vec<_>>();
+    let positions = (0..26 * 100).map(|i| GridPosition::parse(&format!("{}{}", number_to_alphabet_value(i % 26), 1 + i / 26)).unwrap()).collect::<Vec<_>>();
+    let mut group = c.benchmark_group("dedup");
+    group.sample_size(10);
+
+    group.bench_function("input", |b| b.iter(|| {
+        rows(black_box(&names), black_box(&positions), true).count()
+    }));
+    for &(order, grouped) in &[("grouped", true), ("interleaved", false)] {
+        group.bench_function(format!("btree_{}", order), |b| b.iter(|| {
+            let mut builder = DeduplicatedRoadsBuilder::new();
+            builder.extend(rows(black_box(&names), black_box(&positions), grouped));
+            builder.finish()
+        }));
+        group.bench_function(format!("flat_{}", order), |b| b.iter(|| {
+            let mut builder = DeduplicatedRoadsBuilder::new().with_backend(DedupBackend::Flat);
+            builder.extend(rows(black_box(&names), black_box(&positions), grouped));
+            builder.finish()
+        }));
+        group.bench_function(format!("flat_dedup_on_insert_{}", order), |b| b.iter(|| {
+            let mut builder = DeduplicatedRoadsBuilder::new().with_backend(DedupBackend::Flat).with_dedup_on_insert(true);
+            builder.extend(rows(black_box(&names), black_box(&positions), grouped));
+            builder.finish()
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_dedup);
+criterion_main!(benches);
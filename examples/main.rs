@@ -22,8 +22,8 @@ fn main() {
     });
 
     let deduplicated = DeduplicatedRoads::from_streets(&grid.street_names());
-    let (processed, unprocessed) = deduplicated.process();
+    let index = deduplicated.process();
 
-    println!("processed:\r\n{}", processed.to_csv("\t"));
-    println!("unprocessed:\r\n{}", unprocessed.to_csv("\t"));
+    println!("processed:\r\n{}", index.processed.to_csv("\t"));
+    println!("unprocessed:\r\n{}", index.unprocessed.to_csv("\t"));
 }
\ No newline at end of file
@@ -0,0 +1,133 @@
+//! Alternative names of streets, i.e. the colloquial `"Boston Post Road"`
+//! for the official `"Route 9"`
+//!
+//! An `AliasTable` folds the aliases into their canonical name while
+//! deduplicating (see `DeduplicatedRoadsBuilder::with_aliases`), so both
+//! spellings end up as one road. Optionally, the index can list the aliases
+//! as cross-references (`"Boston Post Road\tsee Route 9"`, see `CrossReference`).
+//!
+//! ```no_run,ignore
+//! let aliases = AliasTable::new()
+//!     .with_alias("Boston Post Road", "Route 9")
+//!     .with_cross_references(true);
+//! let mut builder = DeduplicatedRoadsBuilder::new().with_aliases(aliases.clone());
+//! builder.extend(streets);
+//! let index = builder.finish().process();
+//! index.to_csv_with_aliases("\t", &aliases)
+//! ```
+
+use std::collections::BTreeMap;
+use roads2csv::{StreetIndex, StreetName, InputStreetValue};
+use cross_reference::CrossReference;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Maps alternative street names to their canonical name
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AliasTable {
+    /// `(alias as written, canonical name)` by the lowercase alias
+    aliases: BTreeMap<String, (String, String)>,
+    /// Whether `to_csv_with_aliases` lists the aliases as cross-references
+    pub cross_references: bool,
+}
+
+impl AliasTable {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an alias, aliases are matched ignoring case and surrounding whitespace
+    pub fn with_alias(mut self, alias: &str, canonical: &str) -> Self {
+        self.insert(alias, canonical);
+        self
+    }
+
+    pub fn with_cross_references(self, cross_references: bool) -> Self {
+        Self { cross_references, .. self }
+    }
+
+    /// Adds an alias (see `with_alias`)
+    pub fn insert(&mut self, alias: &str, canonical: &str) {
+        let alias = alias.trim();
+        self.aliases.insert(alias.to_lowercase(), (alias.to_string(), canonical.trim().to_string()));
+    }
+
+    /// Returns the canonical name of the `name`, or the `name` itself
+    /// if it isn't an alias
+    pub fn canonical<'a>(&'a self, name: &'a str) -> &'a str {
+        match self.aliases.get(&name.trim().to_lowercase()) {
+            Some((_, canonical)) => canonical,
+            None => name,
+        }
+    }
+
+    /// Replaces the names of all streets by their canonical name
+    pub fn apply(&self, streets: &[InputStreetValue]) -> Vec<InputStreetValue> {
+        streets.iter().map(|street| InputStreetValue {
+            street_name: StreetName(self.canonical(&street.street_name.0).to_string()),
+            .. street.clone()
+        }).collect()
+    }
+
+    /// Returns a cross-reference for every alias whose canonical name is in
+    /// the `index`, ordered by the alias
+    pub fn cross_references(&self, index: &StreetIndex) -> Vec<CrossReference> {
+        let mut references = self.aliases.values()
+            .filter(|(_, canonical)| index.iter_all().any(|entry| entry.name().0 == *canonical))
+            .map(|(alias, canonical)| CrossReference::new(alias, canonical))
+            .collect::<Vec<_>>();
+        references.sort();
+        references
+    }
+}
+
+impl StreetIndex {
+
+    /// Adds the cross-references of the `aliases` (see
+    /// `AliasTable::cross_references`) to the index
+    pub fn add_aliases(&mut self, aliases: &AliasTable) {
+        for reference in aliases.cross_references(self) {
+            self.add_cross_reference(reference);
+        }
+    }
+
+    /// Same as `to_csv()`, but if `aliases.cross_references` is set, every
+    /// alias is listed as `"Boston Post Road<delimiter>see Route 9"`, between
+    /// the processed roads (ordered by name)
+    pub fn to_csv_with_aliases(&self, delimiter: &str, aliases: &AliasTable) -> String {
+        if !aliases.cross_references {
+            return self.to_csv(delimiter);
+        }
+
+        let mut index = self.clone();
+        index.add_aliases(aliases);
+        index.to_csv(delimiter)
+    }
+}
+
+#[test]
+fn test_alias_table() {
+    use roads2csv::{DeduplicatedRoadsBuilder, GridPosition};
+
+    let aliases = AliasTable::new()
+        .with_alias("Boston Post Road", "Route 9")
+        .with_alias("Old Mill Road", "Mill Lane");
+    assert_eq!(aliases.canonical("boston post road "), "Route 9");
+    assert_eq!(aliases.canonical("Abbey Road"), "Abbey Road");
+
+    let street = |name: &str, position: &str| InputStreetValue::new(name, position.parse::<GridPosition>().unwrap());
+    let streets = vec![street("Route 9", "A1"), street("Boston Post Road", "A2"), street("Abbey Road", "C3")];
+
+    let mut builder = DeduplicatedRoadsBuilder::new().with_aliases(aliases.clone());
+    builder.extend(streets.clone());
+    let index = builder.finish().process();
+    assert_eq!(index.to_csv("\t"), "Abbey Road\tC3\r\nRoute 9\tA1-A2");
+    assert_eq!(index.to_csv_with_aliases("\t", &aliases), index.to_csv("\t"));
+
+    // "Mill Lane" is not in the index, so "Old Mill Road" is not listed
+    let aliases = aliases.with_cross_references(true);
+    assert_eq!(index.to_csv_with_aliases("\t", &aliases), "Abbey Road\tC3\r\nBoston Post Road\tsee Route 9\r\nRoute 9\tA1-A2");
+    assert_eq!(aliases.apply(&streets)[1].street_name.0, "Route 9");
+}
@@ -0,0 +1,272 @@
+//! Additional attributes of streets (i.e. the postal code), carried through
+//! the deduplication and exported as extra columns
+//!
+//! ```no_run,ignore
+//! let street = InputStreetValue::new("Canterbury Road", position).with_attribute("postal_code", "NW6 5SH");
+//! let index = DeduplicatedRoads::from_streets(&streets).process();
+//! index.to_csv_with_attributes("\t", &["postal_code"])
+//! // "Canterbury Road\tNW6 5SH\tA9-A10"
+//! ```
+//!
+//! If the rows of a road have different values for an attribute (or a
+//! different category), the value is picked with the `ConflictPolicy` of
+//! `DedupOptions::attribute_conflict` (or `DedupOptions::category_conflict`).
+//! With `ConflictPolicy::Error`, `try_finish()` of the builders fails instead:
+//!
+//! ```no_run,ignore
+//! let options = DedupOptions { attribute_conflict: ConflictPolicy::Error, .. DedupOptions::default() };
+//! let mut builder = DeduplicatedRoadsBuilder::new().with_options(options);
+//! builder.extend(streets);
+//! let deduplicated = builder.try_finish()?;
+//! // Error::Conflict: conflicting values for "postal_code" of "Main Street": "NW6 5SH", "NW6 7QB"
+//! ```
+
+use std::{fmt, error};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use roads2csv::{StreetIndex, StreetName, CsvQuoting, DEFAULT_POSITION_SEPARATOR};
+use cross_reference::Interleave;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// What happens if the rows of a road disagree on an attribute (i.e. two
+/// postal codes for `"Main Street"`) or on the category, see
+/// `DedupOptions::attribute_conflict` and `DedupOptions::category_conflict`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConflictPolicy {
+    /// The most frequent value, on a tie the one that comes first in byte order
+    #[default]
+    MostFrequent,
+    /// The value of the first row
+    First,
+    /// The value of the last row
+    Last,
+    /// All values in the order they occurred, attributes are joined by the
+    /// separator (i.e. `"NW6 5SH, NW6 7QB"`), categories stay separate entries
+    Collect(String),
+    /// `try_finish()` fails with `Error::Conflict`, `finish()` leaves the
+    /// attribute out (and keeps the value of the first row for categories)
+    Error,
+}
+
+/// Rows of a street that disagree on an attribute or the category,
+/// see `ConflictPolicy::Error`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Conflict {
+    pub street: StreetName,
+    /// Key of the attribute, `"category"` for the category
+    pub field: String,
+    /// Values in the order they occurred, `""` for rows without a category
+    pub values: Vec<String>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let values = self.values.iter().map(|value| format!("{:?}", value)).collect::<Vec<_>>();
+        write!(f, "conflicting values for {:?} of {:?}: {}", self.field, self.street.0, values.join(", "))
+    }
+}
+
+impl error::Error for Conflict { }
+
+/// How often a value occurred and in which rows it occurred first and last
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct ValueStats {
+    pub count: usize,
+    pub first: usize,
+    pub last: usize,
+}
+
+impl ValueStats {
+
+    /// Value of the row with the index `row`
+    pub fn new(row: usize) -> Self {
+        Self { count: 1, first: row, last: row }
+    }
+
+    /// Moves the rows back by `rows`, for merging builders
+    pub fn offset(self, rows: usize) -> Self {
+        Self { count: self.count, first: self.first + rows, last: self.last + rows }
+    }
+
+    pub fn add(&mut self, other: ValueStats) {
+        self.count += other.count;
+        self.first = self.first.min(other.first);
+        self.last = self.last.max(other.last);
+    }
+}
+
+/// Adds the `stats` of the `value` to the `values`
+pub(crate) fn add_value<T: Ord>(values: &mut BTreeMap<T, ValueStats>, value: T, stats: ValueStats) {
+    values.entry(value).and_modify(|existing| existing.add(stats)).or_insert(stats);
+}
+
+impl ConflictPolicy {
+
+    /// Returns the values that are kept (one value, or all values in the order
+    /// they occurred for `Collect`), or all values if they conflict
+    pub(crate) fn resolve<T: Ord + Clone>(&self, values: &BTreeMap<T, ValueStats>) -> Result<Vec<T>, Vec<T>> {
+        let mut in_order = values.iter().collect::<Vec<_>>();
+        in_order.sort_by_key(|(_, stats)| stats.first);
+        let all = || in_order.iter().map(|(value, _)| (*value).clone()).collect::<Vec<T>>();
+
+        let picked = match self {
+            ConflictPolicy::MostFrequent => values.iter()
+                .max_by(|(a, a_stats), (b, b_stats)| a_stats.count.cmp(&b_stats.count).then_with(|| b.cmp(a))),
+            ConflictPolicy::First => in_order.first().copied(),
+            ConflictPolicy::Last => values.iter().max_by_key(|(_, stats)| stats.last),
+            ConflictPolicy::Collect(_) => return Ok(all()),
+            ConflictPolicy::Error => return if values.len() > 1 { Err(all()) } else { Ok(all()) },
+        };
+        Ok(picked.map(|(value, _)| value.clone()).into_iter().collect())
+    }
+
+    /// `resolve()` for every attribute of the `street`, conflicts are
+    /// added to `conflicts` and left out
+    pub(crate) fn resolve_attributes(&self, street: &StreetName, attributes: BTreeMap<String, BTreeMap<String, ValueStats>>, conflicts: &mut Vec<Conflict>) -> BTreeMap<String, String> {
+        let separator = match self {
+            ConflictPolicy::Collect(separator) => separator.as_str(),
+            _ => "",
+        };
+        let mut resolved = BTreeMap::new();
+        for (key, values) in attributes {
+            match self.resolve(&values) {
+                Ok(values) => if !values.is_empty() {
+                    resolved.insert(key, values.join(separator));
+                },
+                Err(values) => conflicts.push(Conflict { street: street.clone(), field: key, values }),
+            }
+        }
+        resolved
+    }
+}
+
+impl StreetIndex {
+
+    /// Same as `to_csv()`, but with one column per attribute in `keys`
+    /// between the name and the position, i.e. `"Canterbury Road\tNW6 5SH\tA9-A10"`.
+    /// Streets without the attribute and cross-references have empty columns,
+    /// the positions of unprocessed roads are joined into one field.
+    pub fn to_csv_with_attributes(&self, delimiter: &str, keys: &[&str]) -> String {
+        let quoting = CsvQuoting::default();
+        let record = |name: &str, attributes: Option<&BTreeMap<String, String>>, position: String| {
+            let values = keys.iter().map(|key| {
+                Cow::Borrowed(attributes.and_then(|attributes| attributes.get(*key)).map(|value| value.as_str()).unwrap_or(""))
+            });
+            quoting.record(::std::iter::once(Cow::Borrowed(name)).chain(values).chain(::std::iter::once(Cow::Owned(position))), delimiter)
+        };
+
+        let roads = self.processed.processed.iter().map(|road| {
+            (&road.name, record(&road.name.0, self.attributes.get(&road.name), road.position.to_string()))
+        });
+        let processed = Interleave::new(roads, &self.cross_references, |reference| record(&reference.from.0, None, reference.see()));
+        let unprocessed = self.unprocessed.unprocessed.iter().map(|road| {
+            let positions = road.runs().iter().map(|run| run.to_string()).collect::<Vec<_>>().join(DEFAULT_POSITION_SEPARATOR);
+            record(&road.name.0, self.attributes.get(&road.name), positions)
+        });
+
+        processed.chain(unprocessed).collect::<Vec<String>>().join("\r\n")
+    }
+}
+
+#[test]
+fn test_attributes() {
+    use roads2csv::{DeduplicatedRoads, InputStreetValue, StreetName};
+    use cross_reference::CrossReference;
+
+    let street = |name: &str, position: &str, postal_code: &str| {
+        InputStreetValue::new(name, position.parse().unwrap()).with_attribute("postal_code", postal_code)
+    };
+    let streets = vec![
+        street("Canterbury Road", "A9", "NW6 5SH"),
+        street("Canterbury Road", "A10", "NW6 7QB"),
+        street("Canterbury Road", "A10", "NW6 5SH"),
+        street("Mill Lane", "C3", "NW6 1NT").with_attribute("district", "West Hampstead"),
+        InputStreetValue::new("Valley Road", "E1".parse().unwrap()),
+        InputStreetValue::new("Valley Road", "F4".parse().unwrap()),
+        InputStreetValue::new("Valley Road", "C8".parse().unwrap()),
+    ];
+
+    let mut index = DeduplicatedRoads::from_streets(&streets).process();
+    index.add_cross_reference(CrossReference::new("Canterbury Rd", "Canterbury Road"));
+    assert_eq!(index.attributes[&StreetName(String::from("Canterbury Road"))]["postal_code"], "NW6 5SH");
+    assert_eq!(index.to_csv_with_attributes("\t", &["postal_code", "district"]), [
+        "Canterbury Rd\t\t\tsee Canterbury Road",
+        "Canterbury Road\tNW6 5SH\t\tA9-A10",
+        "Mill Lane\tNW6 1NT\tWest Hampstead\tC3",
+        "Valley Road\t\t\tC8; E1; F4",
+    ].join("\r\n"));
+    assert!(index.to_json().contains(r#"{"name":"Mill Lane","attributes":{"district":"West Hampstead","postal_code":"NW6 1NT"},"status":"processed","positions":["C3"]}"#));
+
+    // the attributes of streets from the other index are kept, clashes keep the first value
+    let mut merged = DeduplicatedRoads::from_streets(&streets[..3]).process();
+    merged.merge(DeduplicatedRoads::from_streets(&[street("Canterbury Road", "A9", "NW6 7QB"), streets[3].clone()]).process());
+    assert_eq!(merged.to_csv_with_attributes("\t", &["postal_code", "district"]), [
+        "Canterbury Road\tNW6 5SH\t\tA9",
+        "Canterbury Road\tNW6 5SH\t\tA9-A10",
+        "Mill Lane\tNW6 1NT\tWest Hampstead\tC3",
+    ].join("\r\n"));
+}
+
+#[test]
+fn test_conflict_policy() {
+    use roads2csv::{DeduplicatedRoadsBuilder, InputStreetValue, StreetName};
+    use normalize::DedupOptions;
+    use flat::DedupBackend;
+    use category::Category;
+
+    let street = |name: &str, position: &str, postal_code: &str| {
+        InputStreetValue::new(name, position.parse().unwrap()).with_attribute("postal_code", postal_code)
+    };
+    let streets = [
+        street("Main Street", "A9", "NW6 5SH"),
+        street("Main Street", "A10", "NW6 5SH"),
+        street("Main Street", "B10", "NW6 7QB"),
+        InputStreetValue::new("Elbe", "C2".parse().unwrap()).with_category(Category::Water),
+        InputStreetValue::new("Elbe", "C3".parse().unwrap()),
+    ];
+
+    // the rows are split across two builders that are merged, the same for both builders
+    let build = |options: DedupOptions| {
+        let mut builder = DeduplicatedRoadsBuilder::new().with_options(options.clone());
+        builder.extend(streets[..2].iter().cloned());
+        let mut other = DeduplicatedRoadsBuilder::new();
+        other.extend(streets[2..].iter().cloned());
+        builder.merge(other);
+
+        let mut flat = DeduplicatedRoadsBuilder::new().with_backend(DedupBackend::Flat).with_options(options);
+        flat.extend(streets[..2].iter().cloned());
+        let mut other = DeduplicatedRoadsBuilder::new().with_backend(DedupBackend::Flat);
+        other.extend(streets[2..].iter().cloned());
+        flat.merge(other);
+
+        let deduplicated = builder.try_finish().map_err(|e| e.to_string());
+        assert_eq!(deduplicated, flat.try_finish().map_err(|e| e.to_string()));
+        deduplicated
+    };
+    let postal_code = |policy: ConflictPolicy| {
+        let deduplicated = build(DedupOptions { attribute_conflict: policy, .. DedupOptions::default() }).unwrap();
+        deduplicated.attributes[&StreetName(String::from("Main Street"))]["postal_code"].clone()
+    };
+
+    assert_eq!(postal_code(ConflictPolicy::MostFrequent), "NW6 5SH");
+    assert_eq!(postal_code(ConflictPolicy::First), "NW6 5SH");
+    assert_eq!(postal_code(ConflictPolicy::Last), "NW6 7QB");
+    assert_eq!(postal_code(ConflictPolicy::Collect(String::from(", "))), "NW6 5SH, NW6 7QB");
+    assert_eq!(
+        build(DedupOptions { attribute_conflict: ConflictPolicy::Error, .. DedupOptions::default() }).unwrap_err(),
+        r#"conflicting values for "postal_code" of "Main Street": "NW6 5SH", "NW6 7QB""#,
+    );
+
+    let elbe = StreetName(String::from("Elbe"));
+    let separate = build(DedupOptions::default()).unwrap();
+    assert!(separate.roads.contains_key(&elbe) && separate.categories[&Category::Water].contains_key(&elbe));
+    let merged = build(DedupOptions { category_conflict: Some(ConflictPolicy::First), .. DedupOptions::default() }).unwrap();
+    assert!(!merged.roads.contains_key(&elbe));
+    assert_eq!(merged.categories[&Category::Water][&elbe].len(), 2);
+    assert_eq!(
+        build(DedupOptions { category_conflict: Some(ConflictPolicy::Error), .. DedupOptions::default() }).unwrap_err(),
+        r#"conflicting values for "category" of "Elbe": "water", """#,
+    );
+}
@@ -0,0 +1,156 @@
+//! Indexes of streets with two official names (i.e. Dutch and French in
+//! Brussels): one index per language or both names in one index
+//!
+//! Streets are deduplicated by their primary name, the secondary name is
+//! carried along (see `InputStreetValue::secondary_name`). Both entries of
+//! a street point to the same positions:
+//!
+//! ```no_run,ignore
+//! let street = InputStreetValue::new("Nieuwstraat", position).with_secondary_name("Rue Neuve");
+//! let index = DeduplicatedRoads::from_streets(&streets).process();
+//! index.select_names(NameSelection::Both).to_csv("\t")
+//! // "Nieuwstraat\tC4\r\nRue Neuve\tC4"
+//! ```
+
+use std::collections::BTreeMap;
+use roads2csv::{StreetIndex, StreetName, ProcessedRoadNames, UnprocessedRoadNames};
+use cross_reference::CrossReference;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Which names of a bilingual index are written, see `StreetIndex::select_names`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NameSelection {
+    /// Only the primary names (the index as it is)
+    #[default]
+    Primary,
+    /// The secondary names, streets without a secondary name keep their
+    /// primary name
+    Secondary,
+    /// One entry for the primary and one for the secondary name
+    Both,
+}
+
+impl StreetIndex {
+
+    /// Returns the index with the `selection` of names. The entries of the
+    /// returned index are ordered by name (like `process()`), house numbers,
+    /// cross-references, districts and attributes are renamed / duplicated
+    /// along with the streets.
+    pub fn select_names(&self, selection: NameSelection) -> StreetIndex {
+        let rename = |name: &StreetName| self.secondary_names.get(name).cloned();
+        let renamed = |name: &StreetName| rename(name).unwrap_or_else(|| name.clone());
+
+        let mut processed = Vec::new();
+        let mut unprocessed = Vec::new();
+        let mut house_numbers = BTreeMap::new();
+        let mut cross_references = Vec::new();
+        let mut districts = BTreeMap::new();
+        let mut attributes = BTreeMap::new();
+
+        if selection != NameSelection::Secondary {
+            processed.extend(self.processed.processed.iter().cloned());
+            unprocessed.extend(self.unprocessed.unprocessed.iter().cloned());
+            house_numbers.extend(self.house_numbers.iter().map(|(name, numbers)| (name.clone(), numbers.clone())));
+            cross_references.extend(self.cross_references.iter().cloned());
+            districts.extend(self.districts.iter().map(|(name, street_districts)| (name.clone(), street_districts.clone())));
+            attributes.extend(self.attributes.iter().map(|(name, street_attributes)| (name.clone(), street_attributes.clone())));
+        }
+
+        match selection {
+            NameSelection::Primary => { },
+            NameSelection::Secondary => {
+                processed.extend(self.processed.processed.iter().map(|road| { let mut road = road.clone(); road.name = renamed(&road.name); road }));
+                unprocessed.extend(self.unprocessed.unprocessed.iter().map(|road| { let mut road = road.clone(); road.name = renamed(&road.name); road }));
+                house_numbers.extend(self.house_numbers.iter().map(|(name, numbers)| (renamed(name), numbers.clone())));
+                cross_references.extend(self.cross_references.iter().map(|reference| CrossReference {
+                    from: renamed(&reference.from),
+                    to: renamed(&reference.to),
+                }));
+                districts.extend(self.districts.iter().map(|(name, street_districts)| (renamed(name), street_districts.clone())));
+                attributes.extend(self.attributes.iter().map(|(name, street_attributes)| (renamed(name), street_attributes.clone())));
+            },
+            NameSelection::Both => {
+                let secondary = |name: &StreetName| rename(name).filter(|secondary| secondary != name);
+                processed.extend(self.processed.processed.iter().filter_map(|road| {
+                    secondary(&road.name).map(|name| { let mut road = road.clone(); road.name = name; road })
+                }));
+                unprocessed.extend(self.unprocessed.unprocessed.iter().filter_map(|road| {
+                    secondary(&road.name).map(|name| { let mut road = road.clone(); road.name = name; road })
+                }));
+                house_numbers.extend(self.house_numbers.iter().filter_map(|(name, numbers)| secondary(name).map(|name| (name, numbers.clone()))));
+                cross_references.extend(self.cross_references.iter().filter_map(|reference| {
+                    secondary(&reference.from).map(|from| CrossReference { from, to: renamed(&reference.to) })
+                }));
+                districts.extend(self.districts.iter().filter_map(|(name, street_districts)| secondary(name).map(|name| (name, street_districts.clone()))));
+                attributes.extend(self.attributes.iter().filter_map(|(name, street_attributes)| secondary(name).map(|name| (name, street_attributes.clone()))));
+            },
+        }
+
+        if selection != NameSelection::Primary {
+            processed.sort_by(|a, b| a.tie_break(b));
+            unprocessed.sort_by(|a, b| a.tie_break(b));
+            cross_references.sort();
+            cross_references.dedup();
+        }
+
+        let mut index = StreetIndex::new(ProcessedRoadNames { processed }, UnprocessedRoadNames { unprocessed });
+        index.house_numbers = house_numbers;
+        index.cross_references = cross_references;
+        index.districts = districts;
+        index.attributes = attributes;
+        if selection == NameSelection::Primary {
+            index.secondary_names = self.secondary_names.clone();
+        }
+        index
+    }
+}
+
+#[test]
+fn test_bilingual_index() {
+    use roads2csv::{DeduplicatedRoads, InputStreetValue};
+    use house_numbers::NumberRange;
+
+    let street = |name: &str, secondary: &str, position: &str| {
+        InputStreetValue::new(name, position.parse().unwrap()).with_secondary_name(secondary)
+    };
+    let streets = vec![
+        street("Nieuwstraat", "Rue Neuve", "C4"),
+        street("Nieuwstraat", "Rue Neuve", "C5").with_number_range(NumberRange::new(1, 9)),
+        street("Nieuwstraat", "Rue Neuf", "C5"),
+        street("Kunstberg", "Mont des Arts", "A1"),
+        InputStreetValue::new("Kunstberg", "A2".parse().unwrap()),
+        InputStreetValue::new("Avenue Louise", "F9".parse().unwrap()),
+    ];
+
+    let deduplicated = DeduplicatedRoads::from_streets(&streets);
+    assert_eq!(deduplicated.secondary_names[&StreetName(String::from("Nieuwstraat"))], StreetName(String::from("Rue Neuve")));
+    let mut index = deduplicated.process();
+    index.add_cross_reference(CrossReference::new("Kunstbergplein", "Kunstberg"));
+
+    assert_eq!(index.select_names(NameSelection::Primary), index);
+    assert_eq!(index.select_names(NameSelection::Secondary).to_csv("\t"), [
+        "Avenue Louise\tF9",
+        "Kunstbergplein\tsee Mont des Arts",
+        "Mont des Arts\tA1-A2",
+        "Rue Neuve\tC4-C5",
+    ].join("\r\n"));
+
+    let both = index.select_names(NameSelection::Both);
+    assert_eq!(both.to_csv("\t"), [
+        "Avenue Louise\tF9",
+        "Kunstberg\tA1-A2",
+        "Kunstbergplein\tsee Kunstberg",
+        "Mont des Arts\tA1-A2",
+        "Nieuwstraat\tC4-C5",
+        "Rue Neuve\tC4-C5",
+    ].join("\r\n"));
+    assert_eq!(both.house_numbers.keys().map(|name| name.0.as_str()).collect::<Vec<_>>(), ["Nieuwstraat", "Rue Neuve"]);
+
+    // the secondary names of streets from the other index are kept
+    let mut merged = DeduplicatedRoads::from_streets(&streets[..3]).process();
+    merged.merge(DeduplicatedRoads::from_streets(&streets[3..]).process());
+    assert_eq!(merged.secondary_names, index.secondary_names);
+    assert_eq!(merged.select_names(NameSelection::Secondary).to_csv("\t"), "Avenue Louise\tF9\r\nMont des Arts\tA1-A2\r\nRue Neuve\tC4-C5");
+}
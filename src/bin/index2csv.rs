@@ -0,0 +1,306 @@
+//! Command-line tool: reads input streets (CSV or GeoJSON), deduplicates and
+//! processes them and writes the street index as CSV.
+//!
+//! ```text
+//! index2csv [OPTIONS] [INPUT]
+//!
+//! INPUT                      input file, reads from stdin if missing or "-"
+//! --format csv|geojson       input format (default: by file extension, otherwise csv)
+//! --delimiter DELIMITER      field delimiter of the input and output CSV (default: tab)
+//! --sort bytes|natural|LOCALE sort order of the index (default: bytes), LOCALE is one of
+//!                            german, german-phonebook, spanish, swedish, finnish, danish, norwegian
+//! --ranges                   collapse straight runs of cells into ranges (A4-A7)
+//! --cluster MAX_GAP          split roads into clusters of cells at most MAX_GAP cells apart
+//! --grid X,Y,WIDTH,HEIGHT    grid origin and cell size for GeoJSON input
+//! --name-property NAME       GeoJSON property with the street name (default: name)
+//! --output FILE              processed roads (default: stdout)
+//! --review FILE              roads that need to be reviewed (default: stderr)
+//! --review-file FILE         also write the roads that need to be reviewed as a TOML review file
+//! --apply-review FILE        resolve roads with the decisions of a filled-in review file
+//! --table ascii|unicode      print an aligned table instead of CSV to stdout (default: unicode
+//!                            if stdout is a terminal and there is no --output)
+//! --csv                      always write CSV, also to a terminal
+//! ```
+
+extern crate street_index;
+
+use std::{env, fs, process};
+use std::io::{self, IsTerminal, Read, Write};
+use street_index::prelude::*;
+use street_index::roads2table::TableStyle;
+
+const USAGE: &str = "usage: index2csv [--format csv|geojson] [--delimiter DELIMITER] [--sort bytes|natural|LOCALE] \
+[--ranges] [--cluster MAX_GAP] [--grid X,Y,WIDTH,HEIGHT] [--name-property NAME] [--output FILE] [--review FILE] \
+[--review-file FILE] [--apply-review FILE] [--table ascii|unicode] [--csv] [INPUT]";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Format {
+    Csv,
+    GeoJson,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Sort {
+    Bytes,
+    Natural(Locale),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    input: Option<String>,
+    format: Option<Format>,
+    delimiter: String,
+    sort: Sort,
+    ranges: bool,
+    cluster: Option<usize>,
+    grid: Option<GeoGrid>,
+    name_property: String,
+    output: Option<String>,
+    review: Option<String>,
+    review_file: Option<String>,
+    apply_review: Option<String>,
+    table: Option<TableStyle>,
+    csv: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            input: None,
+            format: None,
+            delimiter: String::from("\t"),
+            sort: Sort::Bytes,
+            ranges: false,
+            cluster: None,
+            grid: None,
+            name_property: String::from("name"),
+            output: None,
+            review: None,
+            review_file: None,
+            apply_review: None,
+            table: None,
+            csv: false,
+        }
+    }
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        },
+        Err(e) => {
+            eprintln!("error: {}\r\n{}", e, USAGE);
+            process::exit(2);
+        },
+    };
+
+    if let Err(e) = run(&options) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+/// Returns `Ok(None)` if the help should be printed
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("missing value for {}", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--format" => options.format = Some(match value("--format")?.as_str() {
+                "csv" => Format::Csv,
+                "geojson" => Format::GeoJson,
+                other => return Err(format!("unknown format \"{}\"", other)),
+            }),
+            "--delimiter" => options.delimiter = match value("--delimiter")?.as_str() {
+                "\\t" | "tab" => String::from("\t"),
+                other => other.to_string(),
+            },
+            "--sort" => options.sort = parse_sort(&value("--sort")?)?,
+            "--ranges" => options.ranges = true,
+            "--cluster" => {
+                let max_gap = value("--cluster")?;
+                options.cluster = Some(max_gap.parse().map_err(|_| format!("invalid --cluster value \"{}\"", max_gap))?);
+            },
+            "--grid" => {
+                let grid = value("--grid")?;
+                let numbers = grid.split(',').map(|n| n.trim().parse::<f64>()).collect::<Result<Vec<f64>, _>>();
+                options.grid = match numbers.as_deref() {
+                    Ok([x, y, width, height]) => Some(GeoGrid::new(*x, *y, *width, *height)),
+                    _ => return Err(format!("invalid --grid value \"{}\", expected X,Y,WIDTH,HEIGHT", grid)),
+                };
+            },
+            "--name-property" => options.name_property = value("--name-property")?,
+            "--output" | "-o" => options.output = Some(value("--output")?),
+            "--review" => options.review = Some(value("--review")?),
+            "--review-file" => options.review_file = Some(value("--review-file")?),
+            "--apply-review" => options.apply_review = Some(value("--apply-review")?),
+            "--table" => options.table = Some(match value("--table")?.as_str() {
+                "ascii" => TableStyle::Ascii,
+                "unicode" => TableStyle::Unicode,
+                other => return Err(format!("unknown table style \"{}\"", other)),
+            }),
+            "--csv" => options.csv = true,
+            "-" => options.input = None,
+            other if other.starts_with('-') => return Err(format!("unknown option \"{}\"", other)),
+            other => options.input = Some(other.to_string()),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+fn parse_sort(sort: &str) -> Result<Sort, String> {
+    let locale = match sort {
+        "bytes" => return Ok(Sort::Bytes),
+        "natural" => Locale::Root,
+        "german" => Locale::German,
+        "german-phonebook" => Locale::GermanPhonebook,
+        "spanish" => Locale::Spanish,
+        "swedish" => Locale::Swedish,
+        "finnish" => Locale::Finnish,
+        "danish" => Locale::Danish,
+        "norwegian" => Locale::Norwegian,
+        other => return Err(format!("unknown sort order \"{}\"", other)),
+    };
+    Ok(Sort::Natural(locale))
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+
+    let input = match &options.input {
+        Some(path) => fs::read_to_string(path)?,
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            input
+        },
+    };
+
+    let is_geojson_file = options.input.as_ref().is_some_and(|path| path.ends_with(".geojson") || path.ends_with(".json"));
+    let format = options.format.unwrap_or(if is_geojson_file { Format::GeoJson } else { Format::Csv });
+
+    let deduplicated = match format {
+        Format::Csv => DeduplicatedRoads::from_csv(&input, &options.delimiter)?,
+        Format::GeoJson => read_geojson(&input, options)?,
+    };
+
+    let mut index = match (options.cluster, options.ranges) {
+        (Some(max_gap), _) => deduplicated.process_with_clustering(max_gap),
+        (None, true) => deduplicated.process_with_ranges(),
+        (None, false) => deduplicated.process(),
+    };
+
+    if let Some(path) = &options.apply_review {
+        index.apply_review_file(path)?;
+    }
+
+    if let Sort::Natural(locale) = options.sort {
+        index.processed.sort_by_strategy(&NaturalOrder::new(locale));
+        index.unprocessed.sort_by_strategy(&NaturalOrder::new(locale));
+    }
+
+    if let Some(path) = &options.review_file {
+        fs::write(path, index.to_review_file())?;
+    }
+
+    if let Some(style) = table_style(options) {
+        // the table already lists the roads to review, only write them if asked to
+        print!("{}", index.to_table(style));
+        if options.review.is_some() {
+            write_output(options.review.as_deref(), true, |w| index.unprocessed.write_csv(w, &options.delimiter))?;
+        }
+        return Ok(());
+    }
+
+    write_output(options.output.as_deref(), false, |w| index.processed.write_csv(w, &options.delimiter))?;
+    if !index.unprocessed.unprocessed.is_empty() || options.review.is_some() {
+        write_output(options.review.as_deref(), true, |w| index.unprocessed.write_csv(w, &options.delimiter))?;
+    }
+
+    Ok(())
+}
+
+/// Style of the table printed to stdout, `None` if the CSV should be written
+fn table_style(options: &Options) -> Option<TableStyle> {
+    if options.csv || options.output.is_some() {
+        None
+    } else if options.table.is_some() {
+        options.table
+    } else if io::stdout().is_terminal() {
+        Some(TableStyle::Unicode)
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "geojson")]
+fn read_geojson(input: &str, options: &Options) -> Result<DeduplicatedRoads, Box<dyn std::error::Error>> {
+    let grid = options.grid.as_ref().ok_or("GeoJSON input needs a --grid")?;
+    let streets = street_index::geojson2roads::from_geojson_with_name_property(input, &options.name_property, grid)?;
+    Ok(DeduplicatedRoads::from_streets(&streets))
+}
+
+#[cfg(not(feature = "geojson"))]
+fn read_geojson(_: &str, _: &Options) -> Result<DeduplicatedRoads, Box<dyn std::error::Error>> {
+    Err("GeoJSON input requires index2csv to be built with the \"geojson\" feature".into())
+}
+
+/// Streams the CSV to the file at `path`, or to stdout / stderr
+fn write_output<F>(path: Option<&str>, to_stderr: bool, write_csv: F) -> io::Result<()>
+    where F: FnOnce(&mut dyn Write) -> io::Result<()>
+{
+    match path {
+        Some(path) => {
+            let mut file = io::BufWriter::new(fs::File::create(path)?);
+            write_csv(&mut file)?;
+            file.flush()
+        },
+        None if to_stderr => {
+            let mut stderr = io::stderr();
+            write_csv(&mut stderr)?;
+            writeln!(stderr)
+        },
+        None => {
+            let mut stdout = io::BufWriter::new(io::stdout().lock());
+            write_csv(&mut stdout)?;
+            writeln!(stdout)
+        },
+    }
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |args: &[&str]| parse_args(args.iter().map(|a| a.to_string()));
+
+    let options = args(&["--delimiter", ",", "--sort", "german", "--cluster", "1", "--output", "index.csv", "roads.csv"]).unwrap().unwrap();
+    assert_eq!(options.delimiter, ",");
+    assert_eq!(options.sort, Sort::Natural(Locale::German));
+    assert_eq!(options.cluster, Some(1));
+    assert_eq!(options.output.as_deref(), Some("index.csv"));
+    assert_eq!(options.input.as_deref(), Some("roads.csv"));
+
+    let options = args(&["--review-file", "review.toml", "--apply-review", "reviewed.toml"]).unwrap().unwrap();
+    assert_eq!(options.review_file.as_deref(), Some("review.toml"));
+    assert_eq!(options.apply_review.as_deref(), Some("reviewed.toml"));
+
+    let options = args(&["--grid", "0,10,1,1", "--format", "geojson"]).unwrap().unwrap();
+    assert_eq!(options.grid, Some(GeoGrid::new(0.0, 10.0, 1.0, 1.0)));
+    assert_eq!(options.format, Some(Format::GeoJson));
+
+    let options = args(&["--table", "ascii"]).unwrap().unwrap();
+    assert_eq!(options.table, Some(TableStyle::Ascii));
+    assert_eq!(table_style(&options), Some(TableStyle::Ascii));
+    assert_eq!(table_style(&Options { csv: true, ..options.clone() }), None);
+    assert_eq!(table_style(&Options { output: Some(String::from("index.csv")), ..options }), None);
+
+    assert_eq!(args(&["--help"]), Ok(None));
+    assert!(args(&["--cluster"]).is_err());
+    assert!(args(&["--grid", "1,2,3"]).is_err());
+    assert!(args(&["--sort", "klingon"]).is_err());
+    assert!(args(&["--table", "fancy"]).is_err());
+}
@@ -0,0 +1,176 @@
+//! Geographic bounding boxes of the streets, so that the index can double as
+//! a geocoding lookup table (street name -> area to zoom to)
+//!
+//! The bounds are kept next to the index in a `StreetBounds` table. Fill it
+//! with the road geometries while reading them (`add_linestring`), or
+//! approximate the bounds from the grid cells of every road (`from_cells`):
+//!
+//! ```no_run,ignore
+//! let bounds = StreetBounds::from_cells(&index, &grid);
+//! index.to_csv_with_bounds("\t", &bounds)
+//! // "Canterbury Road\tA1-B1\t0\t9\t2\t10"
+//! ```
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use roads2csv::{StreetIndex, StreetName, CsvQuoting};
+use gridconfig::CellGeometry;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Bounding box in input coordinates (longitude / latitude for a `GeoGrid`
+/// with the `WebMercator` projection)
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GeoBounds {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+}
+
+impl GeoBounds {
+
+    /// Bounds of all finite points, `None` if there are no finite points
+    pub fn from_points(points: &[(f64, f64)]) -> Option<Self> {
+        points.iter()
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .map(|&(x, y)| GeoBounds { min_lon: x, min_lat: y, max_lon: x, max_lat: y })
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// Smallest bounds that contain both `self` and `other`
+    pub fn union(&self, other: &GeoBounds) -> GeoBounds {
+        GeoBounds {
+            min_lon: self.min_lon.min(other.min_lon),
+            min_lat: self.min_lat.min(other.min_lat),
+            max_lon: self.max_lon.max(other.max_lon),
+            max_lat: self.max_lat.max(other.max_lat),
+        }
+    }
+}
+
+/// Bounding boxes by street name
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StreetBounds {
+    bounds: BTreeMap<StreetName, GeoBounds>,
+}
+
+impl StreetBounds {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Approximates the bounds of every road in the `index` with the
+    /// outlines of its cells (see `CellGeometry`)
+    pub fn from_cells<G: CellGeometry + ?Sized>(index: &StreetIndex, grid: &G) -> Self {
+        let mut bounds = StreetBounds::new();
+        let processed = index.processed.processed.iter().map(|road| (&road.name, road.position.cells()));
+        let unprocessed = index.unprocessed.unprocessed.iter().map(|road| (&road.name, road.positions.clone()));
+
+        for (name, cells) in processed.chain(unprocessed) {
+            for (min_lon, min_lat, max_lon, max_lat) in cells.iter().filter_map(|cell| grid.cell_bounds(cell)) {
+                bounds.add(&name.0, GeoBounds { min_lon, min_lat, max_lon, max_lat });
+            }
+        }
+
+        bounds
+    }
+
+    /// Extends the bounds of the street `name`. The name has to be the
+    /// name as it appears in the index (after normalizing / aliasing).
+    pub fn add(&mut self, name: &str, bounds: GeoBounds) {
+        self.bounds.entry(StreetName(name.to_string()))
+            .and_modify(|existing| *existing = existing.union(&bounds))
+            .or_insert(bounds);
+    }
+
+    /// Extends the bounds of the street `name` with the points of a road
+    /// geometry (in the same coordinates that are passed to the `GeoGrid`)
+    pub fn add_linestring(&mut self, name: &str, points: &[(f64, f64)]) {
+        if let Some(bounds) = GeoBounds::from_points(points) {
+            self.add(name, bounds);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&GeoBounds> {
+        self.bounds.get(&StreetName(name.to_string()))
+    }
+}
+
+impl StreetIndex {
+
+    /// Same as `to_csv()`, but every line ends with the bounds of the street
+    /// (`min_lon`, `min_lat`, `max_lon`, `max_lat`), empty fields if the
+    /// street has no bounds. The positions of an unprocessed road are joined
+    /// with `"; "` into one field, so that every line has six fields.
+    /// Cross-references get the bounds of the street they refer to.
+    pub fn to_csv_with_bounds(&self, delimiter: &str, bounds: &StreetBounds) -> String {
+        let quoting = CsvQuoting::default();
+        let record = |name: &str, position: String, bounds: Option<&GeoBounds>| {
+            let bounds = match bounds {
+                Some(b) => vec![b.min_lon.to_string(), b.min_lat.to_string(), b.max_lon.to_string(), b.max_lat.to_string()],
+                None => vec![String::new(); 4],
+            };
+            let fields = vec![Cow::Borrowed(name), Cow::Owned(position)];
+            quoting.record(fields.into_iter().chain(bounds.into_iter().map(Cow::Owned)), delimiter)
+        };
+
+        let processed = self.processed.processed.iter()
+            .map(|road| (road.name.0.as_str(), road.position.to_string(), bounds.get(&road.name.0)));
+        let cross_references = self.cross_references.iter()
+            .map(|reference| (reference.from.0.as_str(), reference.see(), bounds.get(&reference.to.0)));
+        let unprocessed = self.unprocessed.unprocessed.iter().map(|road| {
+            let mut positions = road.positions.clone();
+            positions.sort();
+            let positions = positions.iter().map(|position| position.to_string()).collect::<Vec<_>>().join("; ");
+            (road.name.0.as_str(), positions, bounds.get(&road.name.0))
+        });
+
+        // processed roads and cross-references are ordered by name, like in `to_csv()`
+        let mut lines = processed.chain(cross_references).collect::<Vec<_>>();
+        lines.sort_by(|a, b| a.0.cmp(b.0));
+        lines.into_iter().chain(unprocessed)
+            .map(|(name, position, bounds)| record(name, position, bounds))
+            .collect::<Vec<String>>()
+            .join("\r\n")
+    }
+}
+
+#[test]
+fn test_street_bounds() {
+    use roads2csv::{DeduplicatedRoads, InputStreetValue};
+    use gridconfig::GeoGrid;
+    use cross_reference::CrossReference;
+
+    let grid = GeoGrid::new(0.0, 10.0, 1.0, 1.0);
+    let streets = [
+        ("Canterbury Road", vec![(0.5, 9.5), (1.5, 9.25)]),
+        ("Mayer Street", vec![(3.5, 8.5)]),
+    ];
+
+    let mut exact = StreetBounds::new();
+    let mut input = Vec::new();
+    for (name, line) in &streets {
+        exact.add_linestring(name, line);
+        input.extend(grid.cells_for_linestring(line).into_iter().map(|cell| InputStreetValue::new(name, cell)));
+    }
+    exact.add_linestring("Mayer Street", &[(3.75, 8.75), (f64::NAN, 0.0)]);
+
+    let index = DeduplicatedRoads::from_streets(&input).process()
+        .with_cross_reference(CrossReference::new("Old Mayer Street", "Mayer Street"));
+
+    assert_eq!(exact.get("Mayer Street"), Some(&GeoBounds { min_lon: 3.5, min_lat: 8.5, max_lon: 3.75, max_lat: 8.75 }));
+    assert_eq!(index.to_csv_with_bounds("\t", &exact), [
+        "Canterbury Road\tA1-B1\t0.5\t9.25\t1.5\t9.5",
+        "Mayer Street\tD2\t3.5\t8.5\t3.75\t8.75",
+        "Old Mayer Street\tsee Mayer Street\t3.5\t8.5\t3.75\t8.75",
+    ].join("\r\n"));
+
+    let cells = StreetBounds::from_cells(&index, &grid);
+    assert_eq!(cells.get("Canterbury Road"), Some(&GeoBounds { min_lon: 0.0, min_lat: 9.0, max_lon: 2.0, max_lat: 10.0 }));
+    assert_eq!(StreetBounds::new().get("Canterbury Road"), None);
+    assert!(index.to_csv_with_bounds(",", &StreetBounds::new()).starts_with("Canterbury Road,A1-B1,,,,\r\n"));
+}
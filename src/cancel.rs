@@ -0,0 +1,56 @@
+//! Cooperative cancellation of long index builds, i.e. when the user of a
+//! desktop application closes the project while the index is still built
+//!
+//! The token is checked between rows while reading, between roads while
+//! deduplicating and processing. A cancelled run returns `Error::Cancelled`.
+//!
+//! ```no_run,ignore
+//! let token = CancellationToken::new();
+//! let pipeline = Pipeline::new().with_cancellation(token.clone());
+//! let build = thread::spawn(move || pipeline.try_run(streets));
+//!
+//! // on the UI thread, when the project is closed
+//! token.cancel();
+//! assert!(matches!(build.join().unwrap(), Err(Error::Cancelled)));
+//! ```
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use error::{Error, Result};
+
+/// Shared flag to abort a running index build, all clones of a token are
+/// cancelled together. A token can not be reset, use a new one per build.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests the cancellation, the build stops at the next check
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns `Error::Cancelled` if the token has been cancelled
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(Error::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// `true` if there is a `cancellation` token and it has been cancelled
+pub(crate) fn is_cancelled(cancellation: Option<&CancellationToken>) -> bool {
+    cancellation.is_some_and(|token| token.is_cancelled())
+}
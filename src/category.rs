@@ -0,0 +1,167 @@
+//! Categories of index entries (streets, points of interest, waterways,
+//! landmarks), so that the index can be printed in separate sections
+//!
+//! Roads are deduplicated per name and category, `"Elbe"` the river and
+//! `"Elbe"` the street stay two entries. Entries without a category are
+//! exported first, without a section title.
+
+use std::fmt;
+use std::convert::Infallible;
+use roads2csv::{StreetIndex, ProcessedRoadNames, UnprocessedRoadNames};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
+
+/// Category of an index entry, sections are ordered like the variants
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "String", from = "String"))]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+pub enum Category {
+    /// Streets, roads and squares
+    Street,
+    /// Points of interest, i.e. public buildings
+    Poi,
+    /// Rivers, lakes and canals
+    Water,
+    /// Parks, monuments and other landmarks
+    Landmark,
+    /// User-defined category
+    Other(String),
+}
+
+impl Category {
+    /// Returns the title of the section, i.e. `"Waterways"`
+    pub fn title(&self) -> &str {
+        use self::Category::*;
+        match self {
+            Street => "Streets",
+            Poi => "Points of interest",
+            Water => "Waterways",
+            Landmark => "Landmarks",
+            Other(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Category::*;
+        match self {
+            Street => write!(f, "street"),
+            Poi => write!(f, "poi"),
+            Water => write!(f, "water"),
+            Landmark => write!(f, "landmark"),
+            Other(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Parses `"street"`, `"poi"`, `"water"` or `"landmark"` (case-insensitive),
+/// any other name becomes `Category::Other`
+impl ::std::str::FromStr for Category {
+    type Err = Infallible;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use self::Category::*;
+        Ok(match s.trim().to_lowercase().as_str() {
+            "street" => Street,
+            "poi" => Poi,
+            "water" => Water,
+            "landmark" => Landmark,
+            _ => Other(s.trim().to_string()),
+        })
+    }
+}
+
+impl From<String> for Category {
+    fn from(s: String) -> Self {
+        match s.parse() {
+            Ok(category) => category,
+            Err(e) => match e { },
+        }
+    }
+}
+
+impl From<Category> for String {
+    fn from(category: Category) -> Self {
+        category.to_string()
+    }
+}
+
+impl StreetIndex {
+
+    /// Splits the index into one index per category, ordered by the category
+    /// (entries without a category first). The roads keep their order, the
+    /// cross-references are part of the section without a category.
+    pub fn sections(&self) -> Vec<(Option<Category>, StreetIndex)> {
+        let mut sections: Vec<(Option<Category>, StreetIndex)> = Vec::new();
+
+        fn section<'a>(sections: &'a mut Vec<(Option<Category>, StreetIndex)>, category: &Option<Category>) -> &'a mut StreetIndex {
+            let index = match sections.iter().position(|(c, _)| c == category) {
+                Some(index) => index,
+                None => {
+                    let empty = StreetIndex::new(ProcessedRoadNames { processed: Vec::new() }, UnprocessedRoadNames { unprocessed: Vec::new() });
+                    sections.push((category.clone(), empty));
+                    sections.len() - 1
+                },
+            };
+            &mut sections[index].1
+        }
+
+        for road in &self.processed.processed {
+            section(&mut sections, &road.category).processed.processed.push(road.clone());
+        }
+        for road in &self.unprocessed.unprocessed {
+            section(&mut sections, &road.category).unprocessed.unprocessed.push(road.clone());
+        }
+        if !self.cross_references.is_empty() {
+            section(&mut sections, &None).cross_references = self.cross_references.clone();
+        }
+
+        sections.sort_by(|a, b| a.0.cmp(&b.0));
+        sections
+    }
+
+    /// Same as `to_csv()`, but every category is written as its own section,
+    /// starting with a line containing the section title (see `Category::title`).
+    /// Sections are separated by an empty line.
+    pub fn to_csv_sections(&self, delimiter: &str) -> String {
+        self.sections().iter().map(|(category, section)| match category {
+            Some(category) => format!("{}\r\n{}", category.title(), section.to_csv(delimiter)),
+            None => section.to_csv(delimiter),
+        })
+        .collect::<Vec<String>>()
+        .join("\r\n\r\n")
+    }
+}
+
+#[test]
+fn test_categories() {
+    use roads2csv::{DeduplicatedRoads, InputStreetValue};
+
+    let street = |name: &str, position: &str, category: Option<Category>| InputStreetValue {
+        category,
+        .. InputStreetValue::new(name, position.parse().unwrap())
+    };
+
+    let streets = vec![
+        street("Elbe", "A1", Some(Category::Water)),
+        street("Elbe", "A2", Some(Category::Water)),
+        street("Elbe", "C3", Some(Category::Street)),
+        street("Town Hall", "B2", Some(Category::Poi)),
+        street("Abbey Road", "D4", None),
+        street("Alster", "E1", Some("Water".parse().unwrap())),
+    ];
+
+    let deduplicated = DeduplicatedRoads::from_streets(&streets);
+    assert_eq!(deduplicated.roads.len(), 1);
+    assert_eq!(deduplicated.categories[&Category::Water].len(), 2);
+
+    let index = deduplicated.process();
+    assert_eq!(index.to_csv_sections("\t"), "Abbey Road\tD4\r\n\r\nStreets\r\nElbe\tC3\r\n\r\nPoints of interest\r\nTown Hall\tB2\r\n\r\nWaterways\r\nAlster\tE1\r\nElbe\tA1-A2");
+
+    let categories = index.sections().into_iter().map(|(category, _)| category).collect::<Vec<_>>();
+    assert_eq!(categories, vec![None, Some(Category::Street), Some(Category::Poi), Some(Category::Water)]);
+    assert_eq!("Ferry".parse::<Category>(), Ok(Category::Other(String::from("Ferry"))));
+}
@@ -0,0 +1,452 @@
+//! Alloc-only core of the index generator (deduplication and processing of
+//! roads on one grid page) that needs neither `std` nor `std::io`, i.e.
+//! for embedded map renderers. With `default-features = false` the crate
+//! is `#![no_std]` and only this module is compiled.
+//!
+//! The clustering, rectangle and run detection of `DeduplicatedRoads::process`
+//! is implemented here, so both produce the same ranges:
+//!
+//! ```no_run,ignore
+//! let mut builder = CoreIndexBuilder::new();
+//! builder.push("Mill Lane", CellId::parse("A1")?);
+//! builder.push("Mill Lane", CellId::parse("A2")?);
+//! builder.push("Valley Road", CellId::parse("C1")?);
+//! builder.push("Valley Road", CellId::parse("E5")?);
+//! builder.push("Valley Road", CellId::parse("F9")?);
+//!
+//! let mut csv = String::new();
+//! builder.process(&CoreOptions::default()).write_csv(&mut csv, "\t")?;
+//! // "Mill Lane\tA1-A2\r\nValley Road\tC1; E5; F9"
+//! ```
+
+use core::fmt;
+use core::convert::TryFrom;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Column index and row of a cell packed into one `u64` (32 bits each),
+/// used instead of a `GridPosition` while clustering and finalizing the
+/// positions of a road, so that comparing cells does not compare strings.
+/// Ordered like a `GridPosition` on one page (by column, then by row), the
+/// page is not part of the id.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CellId(u64);
+
+impl CellId {
+
+    /// Returns `None` if the column index or the row is larger than `u32::MAX`
+    pub fn new(column: usize, row: usize) -> Option<Self> {
+        let column = u32::try_from(column).ok()?;
+        let row = u32::try_from(row).ok()?;
+        Some(CellId((u64::from(column) << 32) | u64::from(row)))
+    }
+
+    /// Parses a cell such as `"C4"` or `"AA12"` (without a page)
+    pub fn parse(cell: &str) -> Option<Self> {
+        let split = cell.find(|c: char| !c.is_ascii_uppercase())?;
+        let (column, row) = cell.split_at(split);
+        Self::new(alphabet_value_to_number(column)?, row.parse().ok()?)
+    }
+
+    pub fn column(self) -> usize {
+        (self.0 >> 32) as usize
+    }
+
+    pub fn row(self) -> usize {
+        (self.0 & 0xFFFF_FFFF) as usize
+    }
+}
+
+impl fmt::Display for CellId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", number_to_alphabet_value(self.column()), self.row())
+    }
+}
+
+/// Maps an index number to a value, necessary for creating the street index. i.e.:
+///
+/// ```no_run,ignore
+/// 0   -> A
+/// 25  -> Z
+/// 26  -> AA
+/// 27  -> AB
+/// ```
+///
+/// ... and so on
+pub fn number_to_alphabet_value(num: usize) -> String {
+    // Maximum character count range is 26 characters, A to Z
+    const ALPHABET_LEN: usize = 26;
+    // usize::MAX is "GKGWBYLWRXTLPP" with a length of 15 characters
+    const MAX_LEN: usize = 15;
+
+    // Initialize an array of 15 characters all to 0
+    let mut result = [0;MAX_LEN + 1];
+
+    // ** For this example, assume that we get the number 80000 **
+
+    // How many times does 26 fit in the target number?
+    // 80000 / 26 = 3076
+    let mut multiple_of_alphabet = num / ALPHABET_LEN;
+
+    // How many characters have we created in the loop?
+    let mut character_count = 0;
+
+
+    // If multiple_of_alphabet is 0, that means that `num` has decreased
+    // to a range between 0 and 26.
+    //
+    // `counter < MAX_LEN` is just so that the optimizer can
+    // unroll the loop without bounds-checking.
+    while multiple_of_alphabet != 0 && character_count < MAX_LEN {
+        // The "remainder" is our target character that we push into the array.
+        //
+        // For example, if `multiple_of_alphabet` is 3076, that means that
+        // num is in the range of (26 * 3076) to (26 * 3077).
+        //
+        // Therefore, we want to take the remainder of the last place, essentially:
+        // (3076 - 1) % 26 = 7 = "H"
+        let remainder = (multiple_of_alphabet - 1) % ALPHABET_LEN;
+        // Push the "H" into the array
+        result[(MAX_LEN - 1) - character_count] = u8_to_char(remainder as u8);
+        // We pushed one character, increase the character_count by 1
+        character_count += 1;
+        // Now we prepare the next character - currently, multiple_of_alphabet is 3075.
+        // Integer division always rounds down, which is useful property:
+        // Now our array is:
+        //
+        // [0, 0, 0, ... "H", 0]
+        // (3076 - 1) / 26 = 118
+        //
+        // The next iteration will be:
+        //
+        // (118 - 1) % 26 = 13 = "N"
+        // [0, 0, 0, ... "N", "H", 0]
+        // (118 - 1) / 26 = 4
+        //
+        // (4 - 1) % 26 = 4 = "D"
+        // [0, 0, 0, ... "D", "N", "H", 0]
+        // (4 - 1) / 26 = 0 = quit the loop
+        multiple_of_alphabet = (multiple_of_alphabet - 1) / ALPHABET_LEN;
+    }
+
+    // Last character: 80.000 % 26 = 24 = "Y"
+    // [0, 0, 0, ... "D", "N", "H", "Y"]
+    result[MAX_LEN] = u8_to_char((num % ALPHABET_LEN) as u8);
+
+    // count is 3, since we pushed 3 characters
+    // zeroed_characters will be the offset from the start of the array
+    //
+    // so: 15 - 3 = 12, to take the characters from 12 to 16 (the array is [MAX_LEN + 1]).
+    //
+    // zeroed_characters is the number of characters that are still set to 0
+    // in the result array. We want to ignore all characters that are set to 0.
+    //
+    // Note that this is MAX_LEN, not MAX_LEN + 1
+    let zeroed_characters = MAX_LEN.saturating_sub(character_count);
+
+    // We take a slice from the zeroed_characters to the end of the array
+    // (i.e. MAX_LEN + 1). Note that we have to include the final character.
+    let slice = unsafe { result.get_unchecked(zeroed_characters..) };
+
+    // Cast the slice to a string, since we know that we only have ASCII
+    // characters in the range from A to Z, there won't be any UTF-8 problems
+    unsafe { ::core::str::from_utf8_unchecked(slice) }.to_string()
+}
+
+// Transform from 0 to A, 1 to B, etc.
+#[inline(always)]
+fn u8_to_char(input: u8) -> u8 {
+    // use b'a' to create lowercase characters
+    b'A' + input
+}
+
+/// Inverse of `number_to_alphabet_value`, i.e.:
+///
+/// ```no_run,ignore
+/// A   -> 0
+/// Z   -> 25
+/// AA  -> 26
+/// AB  -> 27
+/// ```
+///
+/// Returns `None` if the string is empty, contains anything other than
+/// the characters `A` to `Z` or if the value would overflow a `usize`.
+pub fn alphabet_value_to_number(value: &str) -> Option<usize> {
+    const ALPHABET_LEN: usize = 26;
+
+    if value.is_empty() {
+        return None;
+    }
+
+    // "AB" is (1 * 26 + 2) - 1 = 27, since there is no "zero" character
+    // in the alphabet (bijective base-26)
+    let mut result: usize = 0;
+    for c in value.bytes() {
+        if !c.is_ascii_uppercase() {
+            return None;
+        }
+        let digit = (c - b'A') as usize + 1;
+        result = result.checked_mul(ALPHABET_LEN)?.checked_add(digit)?;
+    }
+
+    Some(result - 1)
+}
+
+/// If the (distinct) cells exactly fill the rectangle between their
+/// extremes and span at least two columns and two rows (i.e. `A4, A5, B4,
+/// B5`), returns the top left and the bottom right corner (`A4-B5`)
+pub fn filled_rectangle(cells: &[CellId]) -> Option<(CellId, CellId)> {
+
+    let min_column = cells.iter().map(|cell| cell.column()).min()?;
+    let max_column = cells.iter().map(|cell| cell.column()).max()?;
+    let min_row = cells.iter().map(|cell| cell.row()).min()?;
+    let max_row = cells.iter().map(|cell| cell.row()).max()?;
+
+    // a rectangle whose area doesn't fit into a `usize` can't be filled
+    let area = (max_column - min_column).checked_add(1)?.checked_mul((max_row - min_row).checked_add(1)?)?;
+    let distinct = cells.iter().collect::<BTreeSet<_>>();
+    if min_column == max_column || min_row == max_row || distinct.len() != area {
+        return None;
+    }
+
+    Some((CellId::new(min_column, min_row)?, CellId::new(max_column, max_row)?))
+}
+
+/// If the cells form a straight run within one column or one row (where
+/// no more than `max_gap` cells are missing between two cells), returns
+/// the first and the last cell of the run.
+///
+/// Columns are compared by their numeric value, so `Z` and `AA` are adjacent.
+pub fn contiguous_run(cells: &[CellId], max_gap: usize) -> Option<(CellId, CellId)> {
+
+    let first = *cells.first()?;
+    let same_column = cells.iter().all(|cell| cell.column() == first.column());
+    let same_row = cells.iter().all(|cell| cell.row() == first.row());
+
+    if !same_column && !same_row {
+        return None;
+    }
+    // within one column or one row, the ids are ordered along the run
+    let mut cells = cells.to_vec();
+    cells.sort();
+
+    let is_gap_free = cells.windows(2).all(|w| {
+        let (a, b) = (w[0], w[1]);
+        let step = if same_column { b.row() - a.row() } else { b.column() - a.column() };
+        step >= 1 && step <= max_gap.saturating_add(1)
+    });
+
+    if !is_gap_free {
+        return None;
+    }
+
+    Some((cells[0], cells[cells.len() - 1]))
+}
+
+/// Groups the cells into clusters, where each cell of a cluster is at most
+/// `max_gap` empty cells away from another cell of the same cluster.
+/// Returns the indices of the cells of each cluster.
+///
+/// The clusters are ordered by their first cell, the cells inside of each
+/// cluster keep the order of the input.
+pub fn cluster_cells(cells: &[CellId], max_gap: usize) -> Vec<Vec<usize>> {
+
+    let max_distance = max_gap.saturating_add(1);
+
+    // union-find over the indices of the cells, the root of every cluster
+    // is its smallest index (i.e. its first member)
+    let mut parents = (0..cells.len()).collect::<Vec<usize>>();
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            // path halving
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    // cells that are more than `max_distance` columns apart can't be near,
+    // so every cell is only compared to the next cells in column order
+    let mut by_column = (0..cells.len()).collect::<Vec<usize>>();
+    by_column.sort_by_key(|&i| cells[i]);
+    for (k, &i) in by_column.iter().enumerate() {
+        for &j in &by_column[k + 1..] {
+            if cells[j].column() - cells[i].column() > max_distance {
+                break;
+            }
+            if cells[i].row().abs_diff(cells[j].row()) > max_distance {
+                continue;
+            }
+            let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+            parents[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut clusters = BTreeMap::new();
+    for i in 0..cells.len() {
+        clusters.entry(root(&mut parents, i)).or_insert_with(Vec::new).push(i);
+    }
+
+    clusters.into_values().collect()
+}
+
+/// Cells of a processed road, see `FinalizedGridPositon`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CellRange {
+    /// `"A6"`
+    Single(CellId),
+    /// Exactly two cells, `"A6-B6"`
+    Two(CellId, CellId),
+    /// A run of cells in one row or column or a filled rectangle, `"A4-B5"`
+    Range(CellId, CellId),
+}
+
+impl fmt::Display for CellRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CellRange::Single(cell) => write!(f, "{}", cell),
+            CellRange::Two(a, b) | CellRange::Range(a, b) => write!(f, "{}-{}", a, b),
+        }
+    }
+}
+
+/// Subset of `ProcessOptions` that the core understands, the defaults are
+/// the same
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CoreOptions {
+    /// See `ProcessOptions::max_auto_cells`
+    pub max_auto_cells: usize,
+    /// See `ProcessOptions::allow_ranges`
+    pub allow_ranges: bool,
+    /// See `ProcessOptions::cluster_gap`
+    pub cluster_gap: Option<usize>,
+}
+
+impl Default for CoreOptions {
+    fn default() -> Self {
+        Self {
+            max_auto_cells: 2,
+            allow_ranges: false,
+            cluster_gap: None,
+        }
+    }
+}
+
+/// Turns the (ordered) cells of one road into a `CellRange`, returns `None`
+/// if the road needs to be reviewed manually (see `finalize_positions`)
+pub fn finalize_cells(cells: &[CellId], options: &CoreOptions) -> Option<CellRange> {
+    let max_gap = options.cluster_gap.unwrap_or(0);
+    match cells.len() {
+        0 => None,
+        1 => Some(CellRange::Single(cells[0])),
+        n if n > options.max_auto_cells && !options.allow_ranges => {
+            filled_rectangle(cells).map(|(first, last)| CellRange::Range(first, last))
+        },
+        2 => Some(CellRange::Two(cells[0], cells[1])),
+        _ => contiguous_run(cells, max_gap)
+            .or_else(|| filled_rectangle(cells))
+            .map(|(first, last)| CellRange::Range(first, last)),
+    }
+}
+
+/// Deduplicates roads by their exact name, see `DeduplicatedRoadsBuilder`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoreIndexBuilder {
+    roads: BTreeMap<String, BTreeSet<CellId>>,
+}
+
+impl CoreIndexBuilder {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, name: &str, cell: CellId) {
+        self.roads.entry(String::from(name)).or_default().insert(cell);
+    }
+
+    /// Processes the roads like `DeduplicatedRoads::process_with_options`,
+    /// the roads of both lists are ordered by name
+    pub fn process(&self, options: &CoreOptions) -> CoreIndex {
+        let mut index = CoreIndex::default();
+        for (name, cells) in &self.roads {
+            let cells = cells.iter().cloned().collect::<Vec<CellId>>();
+            let clusters = match options.cluster_gap {
+                Some(gap) => cluster_cells(&cells, gap).into_iter().map(|cluster| cluster.into_iter().map(|i| cells[i]).collect()).collect(),
+                None => alloc::vec![cells.clone()],
+            };
+            let finalized = clusters.iter().map(|cluster: &Vec<CellId>| finalize_cells(cluster, options)).collect::<Option<Vec<CellRange>>>();
+            match finalized {
+                Some(ranges) => index.processed.extend(ranges.into_iter().map(|range| (name.clone(), range))),
+                None => index.unprocessed.push((name.clone(), cells)),
+            }
+        }
+        index
+    }
+}
+
+/// Processed roads (one entry per cluster) and roads that need to be reviewed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoreIndex {
+    pub processed: Vec<(String, CellRange)>,
+    pub unprocessed: Vec<(String, Vec<CellId>)>,
+}
+
+impl CoreIndex {
+
+    /// Writes the processed roads followed by the unprocessed roads (cells
+    /// separated by `"; "`) without quoting, one road per line
+    pub fn write_csv<W: fmt::Write>(&self, out: &mut W, delimiter: &str) -> fmt::Result {
+        let processed = self.processed.iter().map(|(name, range)| (name, range.to_string()));
+        let unprocessed = self.unprocessed.iter().map(|(name, cells)| {
+            (name, cells.iter().map(|cell| cell.to_string()).collect::<Vec<_>>().join("; "))
+        });
+        for (i, (name, position)) in processed.chain(unprocessed).enumerate() {
+            if i > 0 {
+                out.write_str("\r\n")?;
+            }
+            write!(out, "{}{}{}", name, delimiter, position)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_core_index() {
+    use roads2csv::{DeduplicatedRoads, InputStreetValue, ProcessOptions};
+
+    let streets = [
+        ("Mill Lane", "A1"), ("Mill Lane", "A2"), ("Mill Lane", "A3"),
+        ("Park Road", "C4"), ("Park Road", "C5"), ("Park Road", "D4"), ("Park Road", "D5"),
+        ("Valley Road", "C1"), ("Valley Road", "E5"), ("Valley Road", "F9"),
+        ("Zion Street", "Z2"), ("Zion Street", "AA2"),
+    ];
+    let mut builder = CoreIndexBuilder::new();
+    for (name, cell) in &streets {
+        builder.push(name, CellId::parse(cell).unwrap());
+    }
+    assert_eq!(CellId::parse("AA12").map(|cell| (cell.column(), cell.row())), Some((26, 12)));
+    assert_eq!(CellId::parse("12"), None);
+    assert_eq!(CellId::parse("B70000").map(|cell| (cell.column(), cell.row())), Some((1, 70_000)));
+
+    // same result as the std index for the same options
+    let deduplicated = DeduplicatedRoads::from_streets(&streets.iter().map(|(name, cell)| InputStreetValue::new(name, cell.parse().unwrap())).collect::<Vec<_>>());
+    for (options, process_options) in [
+        (CoreOptions::default(), ProcessOptions::default()),
+        (CoreOptions { allow_ranges: true, cluster_gap: Some(1), .. CoreOptions::default() }, ProcessOptions::default().with_ranges(true).with_cluster_gap(Some(1))),
+    ] {
+        let mut csv = String::new();
+        builder.process(&options).write_csv(&mut csv, "\t").unwrap();
+        let index = deduplicated.process_with_options(&process_options);
+        let unprocessed = index.unprocessed.unprocessed.iter().map(|road| {
+            format!("{}\t{}", road.name.0, road.positions.iter().map(|position| position.to_string()).collect::<Vec<_>>().join("; "))
+        });
+        assert_eq!(csv, index.processed.processed.iter().map(|road| format!("{}\t{}", road.name.0, road.position)).chain(unprocessed).collect::<Vec<_>>().join("\r\n"));
+    }
+
+    let mut csv = String::new();
+    builder.process(&CoreOptions::default()).write_csv(&mut csv, "\t").unwrap();
+    assert_eq!(csv, "Park Road\tC4-D5\r\nZion Street\tZ2-AA2\r\nMill Lane\tA1; A2; A3\r\nValley Road\tC1; E5; F9");
+}
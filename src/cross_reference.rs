@@ -0,0 +1,121 @@
+//! "See" entries for renamed or co-signed streets, i.e.
+//! `Old Market St → see Market St`
+//!
+//! Cross-references are stored in the `StreetIndex` and written by every
+//! exporter between the processed roads (ordered by name): the CSV line is
+//! `"Old Market St<delimiter>see Market St"`, JSON, GeoJSON and SQLite have
+//! their own fields / table.
+//!
+//! ```no_run,ignore
+//! let mut index = deduplicated.process();
+//! index.add_cross_reference(CrossReference::new("Old Market St", "Market St"));
+//! index.to_csv("\t")
+//! ```
+
+use std::fmt;
+use std::iter::Peekable;
+use std::slice;
+use roads2csv::{StreetIndex, StreetName};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Refers from one street name (`from`) to the name under which the street
+/// is listed in the index (`to`)
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CrossReference {
+    pub from: StreetName,
+    pub to: StreetName,
+}
+
+impl CrossReference {
+
+    pub fn new(from: &str, to: &str) -> Self {
+        Self {
+            from: StreetName(from.trim().to_string()),
+            to: StreetName(to.trim().to_string()),
+        }
+    }
+
+    /// Text of the position column, i.e. `"see Market St"`
+    pub fn see(&self) -> String {
+        format!("see {}", self.to.0)
+    }
+}
+
+impl fmt::Display for CrossReference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} \u{2192} {}", self.from, self.see())
+    }
+}
+
+impl StreetIndex {
+
+    /// Adds a cross-reference, the cross-references are kept ordered by
+    /// `from` and added only once
+    pub fn add_cross_reference(&mut self, reference: CrossReference) {
+        if let Err(index) = self.cross_references.binary_search(&reference) {
+            self.cross_references.insert(index, reference);
+        }
+    }
+
+    /// Same as `add_cross_reference`, for builder-style construction
+    pub fn with_cross_reference(mut self, reference: CrossReference) -> Self {
+        self.add_cross_reference(reference);
+        self
+    }
+}
+
+/// Merges the cross-references into the lines of the processed roads, so
+/// that every cross-reference comes before the first road whose name is
+/// greater than `from`. The roads are expected to be ordered by name.
+pub(crate) struct Interleave<'a, T, I: Iterator<Item = (&'a StreetName, T)>, F> {
+    roads: Peekable<I>,
+    references: Peekable<slice::Iter<'a, CrossReference>>,
+    reference_line: F,
+}
+
+impl<'a, T, I: Iterator<Item = (&'a StreetName, T)>, F: FnMut(&'a CrossReference) -> T> Interleave<'a, T, I, F> {
+    pub(crate) fn new(roads: I, references: &'a [CrossReference], reference_line: F) -> Self {
+        Self { roads: roads.peekable(), references: references.iter().peekable(), reference_line }
+    }
+}
+
+impl<'a, T, I: Iterator<Item = (&'a StreetName, T)>, F: FnMut(&'a CrossReference) -> T> Iterator for Interleave<'a, T, I, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let reference_first = match (self.roads.peek(), self.references.peek()) {
+            (Some((name, _)), Some(reference)) => reference.from < **name,
+            (None, Some(_)) => true,
+            (_, None) => false,
+        };
+
+        if reference_first {
+            self.references.next().map(|reference| (self.reference_line)(reference))
+        } else {
+            self.roads.next().map(|(_, line)| line)
+        }
+    }
+}
+
+#[test]
+fn test_cross_references() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Abbey Road\tA1\r\nMarket St\tB2-B3\r\nValley View Road\tE1\tE2\tF4";
+    let mut index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+    index.add_cross_reference(CrossReference::new("Old Market St", "Market St"));
+    index.add_cross_reference(CrossReference::new("Zeppelin Road", "Abbey Road"));
+    index.add_cross_reference(CrossReference::new("Old Market St", "Market St"));
+
+    assert_eq!(index.cross_references.len(), 2);
+    assert_eq!(index.cross_references[0].to_string(), "Old Market St \u{2192} see Market St");
+
+    let expected = "Abbey Road\tA1\r\nMarket St\tB2-B3\r\nOld Market St\tsee Market St\r\nZeppelin Road\tsee Abbey Road\r\nValley View Road\tE1-E2; F4";
+    assert_eq!(index.to_csv("\t"), expected);
+
+    let mut written = Vec::new();
+    index.write_csv(&mut written, "\t").unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), expected);
+}
@@ -0,0 +1,142 @@
+//! Exports the processed and the unprocessed roads into one CSV file, with
+//! an optional status column instead of writing two files
+//!
+//! ```no_run,ignore
+//! let exporter = CsvExporter::new()
+//!     .with_delimiter(';')
+//!     .with_status_column(true);
+//! exporter.export(&index)
+//! // "Abbey Road;A1-A2;processed\r\nValley Road;\"E1; E2; F4\";unprocessed"
+//! ```
+//!
+//! Unlike `StreetIndex::to_csv()`, every line has the same number of fields:
+//! the positions of an unprocessed road are joined with `"; "` into one field
+//! (quoted if necessary).
+
+use std::{io, borrow::Cow};
+use roads2csv::{StreetIndex, CsvOptions, write_records_with_options};
+use cross_reference::Interleave;
+
+/// Status of the processed roads in the status column
+pub const STATUS_PROCESSED: &str = "processed";
+/// Status of the roads that need to be reviewed manually
+pub const STATUS_UNPROCESSED: &str = "unprocessed";
+/// Status of the "see" entries (see `CrossReference`)
+pub const STATUS_CROSS_REFERENCE: &str = "cross_reference";
+
+/// Exports a `StreetIndex` as a single CSV
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CsvExporter {
+    /// Delimiter, line endings, quoting and header row. The
+    /// `section_headers` are not supported and ignored.
+    pub options: CsvOptions,
+    /// Appends the unprocessed roads after the processed roads
+    pub include_unprocessed: bool,
+    /// Adds a third column with the status of the line (`STATUS_PROCESSED`,
+    /// `STATUS_UNPROCESSED` or `STATUS_CROSS_REFERENCE`)
+    pub status_column: bool,
+}
+
+impl Default for CsvExporter {
+    fn default() -> Self {
+        Self {
+            options: CsvOptions::default(),
+            include_unprocessed: true,
+            status_column: false,
+        }
+    }
+}
+
+impl CsvExporter {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_options(self, options: CsvOptions) -> Self {
+        Self { options, .. self }
+    }
+
+    pub fn with_delimiter(self, delimiter: char) -> Self {
+        Self { options: self.options.clone().with_delimiter(delimiter), .. self }
+    }
+
+    /// Writes a header row (`Name`, `Position` and `Status`), see `CsvOptions::header`
+    pub fn with_header(self, header: bool) -> Self {
+        Self { options: self.options.clone().with_header(header), .. self }
+    }
+
+    pub fn with_unprocessed(self, include_unprocessed: bool) -> Self {
+        Self { include_unprocessed, .. self }
+    }
+
+    pub fn with_status_column(self, status_column: bool) -> Self {
+        Self { status_column, .. self }
+    }
+
+    /// Exports the `index` as one CSV string
+    pub fn export(&self, index: &StreetIndex) -> String {
+        let delimiter = self.options.delimiter.to_string();
+        self.records(index, &delimiter).collect::<Vec<String>>().join(self.options.line_ending.as_str())
+    }
+
+    /// Same as `export()`, but writes the CSV line by line to the `writer`,
+    /// in the encoding of the `options` (see `CsvOptions::encoding`)
+    pub fn write<W: io::Write>(&self, index: &StreetIndex, writer: W) -> io::Result<()> {
+        let delimiter = self.options.delimiter.to_string();
+        write_records_with_options(writer, self.records(index, &delimiter), &self.options)
+    }
+
+    fn records<'a>(&'a self, index: &'a StreetIndex, delimiter: &'a str) -> impl Iterator<Item = String> + 'a {
+        let record = move |name: &'a str, position: String, status: &'static str| {
+            let status = if self.status_column { Some(Cow::Borrowed(status)) } else { None };
+            self.options.quoting.record(vec![Cow::Borrowed(name), Cow::Owned(position)].into_iter().chain(status), delimiter)
+        };
+
+        let header_names: &[&str] = if self.status_column { &["Name", "Position", "Status"] } else { &["Name", "Position"] };
+        let header = self.options.header_record(header_names, delimiter);
+
+        let roads = index.processed.iter().map(move |road| (&road.name, record(&road.name.0, road.position.to_string(), STATUS_PROCESSED)));
+        let processed = Interleave::new(roads, &index.cross_references, move |reference| {
+            record(&reference.from.0, reference.see(), STATUS_CROSS_REFERENCE)
+        });
+
+        let unprocessed = index.unprocessed.iter().filter(move |_| self.include_unprocessed).map(move |road| {
+            let positions = road.positions.iter().map(|position| position.to_string()).collect::<Vec<_>>().join("; ");
+            record(&road.name.0, positions, STATUS_UNPROCESSED)
+        });
+
+        header.into_iter().chain(processed).chain(unprocessed)
+    }
+}
+
+#[test]
+fn test_csv_exporter() {
+    use roads2csv::{DeduplicatedRoads, LineEnding};
+    use cross_reference::CrossReference;
+
+    let csv = "Abbey Road\tA1-A2\r\nMarket St\tB2\r\nValley Road\tE1\tE2\tF4";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process()
+        .with_cross_reference(CrossReference::new("Old Market St", "Market St"));
+
+    let exporter = CsvExporter::new()
+        .with_delimiter(';')
+        .with_header(true)
+        .with_status_column(true);
+    assert_eq!(exporter.export(&index), [
+        "Name;Position;Status",
+        "Abbey Road;A1-A2;processed",
+        "Market St;B2;processed",
+        "Old Market St;see Market St;cross_reference",
+        "Valley Road;\"E1; E2; F4\";unprocessed",
+    ].join("\r\n"));
+
+    let mut written = Vec::new();
+    exporter.write(&index, &mut written).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), exporter.export(&index));
+
+    let exporter = CsvExporter::new()
+        .with_options(CsvOptions::default().with_line_ending(LineEnding::Lf))
+        .with_unprocessed(false);
+    assert_eq!(exporter.export(&index), "Abbey Road\tA1-A2\nMarket St\tB2\nOld Market St\tsee Market St");
+}
@@ -0,0 +1,133 @@
+//! Differences between two editions of a street index, i.e. for the
+//! change log of a new map edition
+//!
+//! ```no_run,ignore
+//! let diff = index.diff(&previous_edition);
+//! diff.to_csv("\t")
+//! // "changed\tMayer Street\tA1\tA1-A2\r\nadded\tMill Lane\t\tC3-C8\r\nremoved\tOld Road\tB4\t"
+//! ```
+//!
+//! Streets are compared by their name and their positions as printed in the
+//! index (processed and unprocessed entries), cross-references are ignored.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use roads2csv::{StreetIndex, StreetName, CsvQuoting};
+use roads2json::json_string;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Streets that were added, removed or moved between two indexes,
+/// ordered by name. Positions are formatted like in `to_csv()`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IndexDiff {
+    /// Streets that are only in the new index, with their positions
+    pub added: BTreeMap<StreetName, Vec<String>>,
+    /// Streets that are only in the previous index, with their positions
+    pub removed: BTreeMap<StreetName, Vec<String>>,
+    /// Streets whose positions changed, as `(previous, current)`
+    pub changed: BTreeMap<StreetName, (Vec<String>, Vec<String>)>,
+}
+
+impl StreetIndex {
+
+    /// Compares this index with the `previous` edition
+    pub fn diff(&self, previous: &StreetIndex) -> IndexDiff {
+        let mut current = self.printed_positions();
+        let mut diff = IndexDiff::default();
+
+        for (name, previous_positions) in previous.printed_positions() {
+            match current.remove(&name) {
+                None => { diff.removed.insert(name, previous_positions); },
+                Some(positions) if positions != previous_positions => { diff.changed.insert(name, (previous_positions, positions)); },
+                Some(_) => { },
+            }
+        }
+
+        diff.added = current;
+        diff
+    }
+
+    /// Sorted positions of every street, as they are printed in the index
+    fn printed_positions(&self) -> BTreeMap<StreetName, Vec<String>> {
+        let mut streets: BTreeMap<StreetName, Vec<String>> = BTreeMap::new();
+        for road in &self.processed.processed {
+            streets.entry(road.name.clone()).or_default().push(road.position.to_string());
+        }
+        for road in &self.unprocessed.unprocessed {
+            let mut positions = road.positions.clone();
+            positions.sort();
+            streets.entry(road.name.clone()).or_default().extend(positions.iter().map(|position| position.to_string()));
+        }
+        for positions in streets.values_mut() {
+            positions.sort();
+            positions.dedup();
+        }
+        streets
+    }
+}
+
+impl IndexDiff {
+
+    /// Whether both indexes contain the same streets at the same positions
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Exports the diff as `change<delimiter>name<delimiter>previous<delimiter>current`
+    /// lines, ordered by name. `change` is `added`, `removed` or `changed`,
+    /// multiple positions are joined with `"; "`.
+    pub fn to_csv(&self, delimiter: &str) -> String {
+        let empty = Vec::new();
+        let mut lines = Vec::new();
+        lines.extend(self.added.iter().map(|(name, positions)| ("added", name, &empty, positions)));
+        lines.extend(self.removed.iter().map(|(name, positions)| ("removed", name, positions, &empty)));
+        lines.extend(self.changed.iter().map(|(name, (previous, current))| ("changed", name, previous, current)));
+        lines.sort_by(|a, b| a.1.cmp(b.1));
+
+        lines.into_iter().map(|(change, name, previous, current)| {
+            CsvQuoting::default().record(vec![
+                Cow::Borrowed(change),
+                Cow::Borrowed(name.0.as_str()),
+                Cow::Owned(previous.join("; ")),
+                Cow::Owned(current.join("; ")),
+            ], delimiter)
+        })
+        .collect::<Vec<String>>()
+        .join("\r\n")
+    }
+
+    /// Exports the diff as JSON, i.e. `{"added":[{"name":"Mill Lane","positions":["C3-C8"]}],
+    /// "removed":[...],"changed":[{"name":"Mayer Street","previous":["A1"],"current":["A1-A2"]}]}`
+    pub fn to_json(&self) -> String {
+        let positions = |positions: &[String]| positions.iter().map(|p| json_string(p)).collect::<Vec<String>>().join(",");
+        let streets = |streets: &BTreeMap<StreetName, Vec<String>>| streets.iter().map(|(name, p)| {
+            format!("{{\"name\":{},\"positions\":[{}]}}", json_string(&name.0), positions(p))
+        }).collect::<Vec<String>>().join(",");
+        let changed = self.changed.iter().map(|(name, (previous, current))| {
+            format!("{{\"name\":{},\"previous\":[{}],\"current\":[{}]}}", json_string(&name.0), positions(previous), positions(current))
+        }).collect::<Vec<String>>().join(",");
+
+        format!("{{\"added\":[{}],\"removed\":[{}],\"changed\":[{}]}}", streets(&self.added), streets(&self.removed), changed)
+    }
+}
+
+#[test]
+fn test_index_diff() {
+    use roads2csv::DeduplicatedRoads;
+
+    let previous = DeduplicatedRoads::from_csv("Mayer Street\tA1\r\nOld Road\tB4\r\nCanterbury Road\tA9-A10", "\t").unwrap().process();
+    let current = DeduplicatedRoads::from_csv("Mayer Street\tA1-A2\r\nMill Lane\tC3\r\nCanterbury Road\tA9-A10", "\t").unwrap().process();
+
+    let diff = current.diff(&previous);
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.changed[&StreetName(String::from("Mayer Street"))], (vec![String::from("A1")], vec![String::from("A1-A2")]));
+    assert_eq!(diff.to_csv("\t"), "changed\tMayer Street\tA1\tA1-A2\r\nadded\tMill Lane\t\tC3\r\nremoved\tOld Road\tB4\t");
+    assert_eq!(diff.to_json(), concat!(
+        r#"{"added":[{"name":"Mill Lane","positions":["C3"]}],"removed":[{"name":"Old Road","positions":["B4"]}],"#,
+        r#""changed":[{"name":"Mayer Street","previous":["A1"],"current":["A1-A2"]}]}"#,
+    ));
+
+    assert!(current.diff(&current).is_empty());
+}
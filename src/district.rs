@@ -0,0 +1,117 @@
+//! One index per district (or other sub-area) of a city, i.e. for an atlas
+//! with one chapter per commune
+//!
+//! Every street is deduplicated once for the whole map, the districts of
+//! the street are carried along (see `InputStreetValue::district`). A street
+//! that crosses several districts is listed in the index of each of them:
+//!
+//! ```no_run,ignore
+//! let street = InputStreetValue::new("Canterbury Road", position).with_district("Kilburn");
+//! let index = DeduplicatedRoads::from_streets(&streets).process();
+//! for (district, index) in index.split_by_district() {
+//!     // district == None for the streets without a district
+//!     write_chapter(district, &index.to_csv("\t"))?;
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use roads2csv::{StreetIndex, StreetName, ProcessedRoadNames, UnprocessedRoadNames};
+
+impl StreetIndex {
+
+    /// Returns one index per district, the streets without a district are in
+    /// the index of `None`. The entries keep the order of this index, house
+    /// numbers, secondary names, attributes and cross-references go to the
+    /// districts of their street (of the target street for cross-references).
+    pub fn split_by_district(&self) -> BTreeMap<Option<String>, StreetIndex> {
+        let districts_of = |name: &StreetName| -> Vec<Option<String>> {
+            match self.districts.get(name) {
+                Some(districts) if !districts.is_empty() => districts.iter().cloned().map(Some).collect(),
+                _ => vec![None],
+            }
+        };
+
+        let mut indexes = BTreeMap::<Option<String>, StreetIndex>::new();
+
+        for road in &self.processed.processed {
+            for district in districts_of(&road.name) {
+                index_of(&mut indexes, district).processed.processed.push(road.clone());
+            }
+        }
+        for road in &self.unprocessed.unprocessed {
+            for district in districts_of(&road.name) {
+                index_of(&mut indexes, district).unprocessed.unprocessed.push(road.clone());
+            }
+        }
+        for (name, numbers) in &self.house_numbers {
+            for district in districts_of(name) {
+                index_of(&mut indexes, district).house_numbers.insert(name.clone(), numbers.clone());
+            }
+        }
+        for (name, secondary_name) in &self.secondary_names {
+            for district in districts_of(name) {
+                index_of(&mut indexes, district).secondary_names.insert(name.clone(), secondary_name.clone());
+            }
+        }
+        for (name, attributes) in &self.attributes {
+            for district in districts_of(name) {
+                index_of(&mut indexes, district).attributes.insert(name.clone(), attributes.clone());
+            }
+        }
+        for reference in &self.cross_references {
+            for district in districts_of(&reference.to) {
+                index_of(&mut indexes, district).cross_references.push(reference.clone());
+            }
+        }
+        for (name, districts) in &self.districts {
+            for district in districts {
+                index_of(&mut indexes, Some(district.clone())).districts.insert(name.clone(), districts.clone());
+            }
+        }
+
+        indexes
+    }
+}
+
+fn index_of(indexes: &mut BTreeMap<Option<String>, StreetIndex>, district: Option<String>) -> &mut StreetIndex {
+    indexes.entry(district).or_insert_with(|| {
+        StreetIndex::new(ProcessedRoadNames { processed: Vec::new() }, UnprocessedRoadNames { unprocessed: Vec::new() })
+    })
+}
+
+#[test]
+fn test_split_by_district() {
+    use roads2csv::{DeduplicatedRoads, InputStreetValue};
+    use cross_reference::CrossReference;
+
+    let street = |name: &str, position: &str, district: &str| InputStreetValue::new(name, position.parse().unwrap()).with_district(district);
+    let streets = vec![
+        street("Canterbury Road", "A1", "Kilburn"),
+        street("Canterbury Road", "A2", "Kilburn"),
+        street("Canterbury Road", "A3", "Maida Vale"),
+        street("Abbey Road", "B2", "St John's Wood"),
+        street("Elgin Avenue", "C4", "Maida Vale"),
+        InputStreetValue::new("Harrow Road", "D1".parse().unwrap()),
+    ];
+
+    let deduplicated = DeduplicatedRoads::from_streets(&streets);
+    assert_eq!(deduplicated.districts[&StreetName(String::from("Canterbury Road"))].len(), 2);
+    let mut index = deduplicated.process();
+    index.add_cross_reference(CrossReference::new("Canterbury Rd", "Canterbury Road"));
+
+    let districts = index.split_by_district();
+    let csv = |district: Option<&str>| districts[&district.map(String::from)].to_csv("\t");
+    assert_eq!(districts.len(), 4);
+    assert_eq!(csv(Some("Kilburn")), "Canterbury Rd\tsee Canterbury Road\r\nCanterbury Road\tA1-A3");
+    assert_eq!(csv(Some("Maida Vale")), "Canterbury Rd\tsee Canterbury Road\r\nElgin Avenue\tC4\r\nCanterbury Road\tA1-A3");
+    assert_eq!(csv(Some("St John's Wood")), "Abbey Road\tB2");
+    assert_eq!(csv(None), "Harrow Road\tD1");
+
+    // the districts of both indexes are kept when they are merged
+    let mut merged = DeduplicatedRoads::from_streets(&streets[..3]).process();
+    merged.merge(DeduplicatedRoads::from_streets(&streets[3..]).process());
+    assert_eq!(merged.districts, index.districts);
+    let districts = merged.split_by_district();
+    assert_eq!(districts.len(), 4);
+    assert_eq!(districts[&Some(String::from("St John's Wood"))].to_csv("\t"), "Abbey Road\tB2");
+}
@@ -0,0 +1,97 @@
+//! Detection of probable typos in street names, i.e. `"Cantebury Road"`
+//! next to `"Canterbury Road"`, so that the source data can be fixed
+//! before the index is printed
+
+use roads2csv::{DeduplicatedRoads, StreetName, GridPosition};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Two street names that are probably the same street
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProbableDuplicate {
+    pub first: StreetName,
+    pub first_positions: Vec<GridPosition>,
+    pub second: StreetName,
+    pub second_positions: Vec<GridPosition>,
+    /// Case-insensitive Levenshtein distance between the two names
+    pub distance: usize,
+}
+
+impl DeduplicatedRoads {
+
+    /// Returns all pairs of names whose (case-insensitive) Levenshtein distance
+    /// is at most `max_distance`, ordered by their distance and then by name.
+    /// A `max_distance` of `1` or `2` catches most typos.
+    ///
+    /// Every name is compared with every other name, so this is meant to be
+    /// used as a report, not as part of the processing.
+    pub fn find_probable_duplicates(&self, max_distance: usize) -> Vec<ProbableDuplicate> {
+
+        let names = self.roads.keys()
+            .map(|name| (name, name.0.to_lowercase().chars().collect::<Vec<char>>()))
+            .collect::<Vec<_>>();
+
+        let mut duplicates = Vec::new();
+
+        for (i, (first, first_chars)) in names.iter().enumerate() {
+            for (second, second_chars) in &names[i + 1..] {
+                if first_chars.len().abs_diff(second_chars.len()) > max_distance {
+                    continue;
+                }
+                let distance = levenshtein(first_chars, second_chars);
+                if distance <= max_distance {
+                    duplicates.push(ProbableDuplicate {
+                        first: (*first).clone(),
+                        first_positions: self.roads[*first].iter().cloned().collect(),
+                        second: (*second).clone(),
+                        second_positions: self.roads[*second].iter().cloned().collect(),
+                        distance,
+                    });
+                }
+            }
+        }
+
+        duplicates.sort_by(|a, b| a.distance.cmp(&b.distance).then_with(|| (&a.first, &a.second).cmp(&(&b.first, &b.second))));
+        duplicates
+    }
+}
+
+/// Number of single-character insertions, deletions or substitutions
+/// needed to turn `a` into `b`
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + if a_char == b_char { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        ::std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[test]
+fn test_find_probable_duplicates() {
+    let csv = "Canterbury Road\tA9\r\nCantebury Road\tA10\r\ncanterbury road\tB1\r\nMill Lane\tC3\r\nHill Lane\tD4";
+    let roads = DeduplicatedRoads::from_csv(csv, "\t").unwrap();
+
+    let pairs = |max_distance| roads.find_probable_duplicates(max_distance).into_iter()
+        .map(|d| (d.first.0, d.second.0, d.distance))
+        .collect::<Vec<_>>();
+
+    assert_eq!(pairs(0), vec![(String::from("Canterbury Road"), String::from("canterbury road"), 0)]);
+    assert_eq!(pairs(1), vec![
+        (String::from("Canterbury Road"), String::from("canterbury road"), 0),
+        (String::from("Cantebury Road"), String::from("Canterbury Road"), 1),
+        (String::from("Cantebury Road"), String::from("canterbury road"), 1),
+        (String::from("Hill Lane"), String::from("Mill Lane"), 1),
+    ]);
+
+    let report = roads.find_probable_duplicates(1);
+    assert_eq!(report[1].first_positions, vec![GridPosition { column: String::from("A"), row: 10, page: None }]);
+}
@@ -0,0 +1,130 @@
+//! Character encodings of written CSV files, i.e. UTF-8 with a byte order
+//! mark for Excel on Windows (which reads CSVs without a BOM as ANSI) or
+//! Windows-1252 for legacy clients
+//!
+//! ```no_run,ignore
+//! let options = CsvOptions::default()
+//!     .with_encoding(CsvEncoding::Windows1252(Unmappable::Replace));
+//! index.processed.write_csv_with_options(File::create("index.csv")?, &options)?;
+//! ```
+
+use std::{fmt, error::Error, convert::TryFrom};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Encoding of the bytes written by `write_csv_with_options()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CsvEncoding {
+    /// UTF-8 without a byte order mark (the default)
+    #[default]
+    Utf8,
+    /// UTF-8, starting with the byte order mark `EF BB BF`
+    Utf8Bom,
+    /// ISO-8859-1, only the characters up to `U+00FF` can be encoded
+    Latin1(Unmappable),
+    /// Windows-1252 (Latin-1 with `€`, `Š`, `Œ`, typographic quotes, ...)
+    Windows1252(Unmappable),
+}
+
+/// What to do with characters that can not be encoded
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Unmappable {
+    /// Replaces the character with `?`
+    #[default]
+    Replace,
+    /// Fails with an `EncodingError`
+    Error,
+}
+
+/// A character of the CSV can not be encoded in the `CsvEncoding`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct EncodingError {
+    pub character: char,
+    /// Byte offset of the character in the text that was encoded
+    pub offset: usize,
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "character '{}' (U+{:04X}) at byte {} can not be encoded", self.character, self.character as u32, self.offset)
+    }
+}
+
+impl Error for EncodingError { }
+
+/// Characters of Windows-1252 at `0x80..=0x9F` (`None` for unused bytes)
+const WINDOWS_1252_HIGH: [Option<char>; 32] = [
+    Some('\u{20AC}'), None, Some('\u{201A}'), Some('\u{0192}'), Some('\u{201E}'), Some('\u{2026}'), Some('\u{2020}'), Some('\u{2021}'),
+    Some('\u{02C6}'), Some('\u{2030}'), Some('\u{0160}'), Some('\u{2039}'), Some('\u{0152}'), None, Some('\u{017D}'), None,
+    None, Some('\u{2018}'), Some('\u{2019}'), Some('\u{201C}'), Some('\u{201D}'), Some('\u{2022}'), Some('\u{2013}'), Some('\u{2014}'),
+    Some('\u{02DC}'), Some('\u{2122}'), Some('\u{0161}'), Some('\u{203A}'), Some('\u{0153}'), None, Some('\u{017E}'), Some('\u{0178}'),
+];
+
+impl CsvEncoding {
+
+    /// Bytes that are written once at the start of the file
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            CsvEncoding::Utf8Bom => b"\xEF\xBB\xBF",
+            _ => b"",
+        }
+    }
+
+    /// Encodes the `text` (without the BOM)
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, EncodingError> {
+        let (single_byte, unmappable): (fn(char) -> Option<u8>, Unmappable) = match *self {
+            CsvEncoding::Utf8 | CsvEncoding::Utf8Bom => return Ok(text.as_bytes().to_vec()),
+            CsvEncoding::Latin1(unmappable) => (latin1_byte, unmappable),
+            CsvEncoding::Windows1252(unmappable) => (windows_1252_byte, unmappable),
+        };
+
+        let mut bytes = Vec::with_capacity(text.len());
+        for (offset, character) in text.char_indices() {
+            match (single_byte(character), unmappable) {
+                (Some(byte), _) => bytes.push(byte),
+                (None, Unmappable::Replace) => bytes.push(b'?'),
+                (None, Unmappable::Error) => return Err(EncodingError { character, offset }),
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+fn latin1_byte(character: char) -> Option<u8> {
+    u8::try_from(character as u32).ok()
+}
+
+fn windows_1252_byte(character: char) -> Option<u8> {
+    match character as u32 {
+        0x80..=0x9F => None,
+        code @ 0..=0xFF => Some(code as u8),
+        _ => WINDOWS_1252_HIGH.iter().position(|c| *c == Some(character)).map(|i| 0x80 + i as u8),
+    }
+}
+
+#[test]
+fn test_csv_encoding() {
+    use roads2csv::{DeduplicatedRoads, CsvOptions};
+
+    assert_eq!(CsvEncoding::Utf8Bom.bom(), [0xEF, 0xBB, 0xBF]);
+    assert_eq!(CsvEncoding::Utf8Bom.encode("Straße").unwrap(), "Straße".as_bytes());
+    assert_eq!(CsvEncoding::Latin1(Unmappable::Error).encode("Straße").unwrap(), b"Stra\xDFe");
+    assert_eq!(CsvEncoding::Windows1252(Unmappable::Error).encode("Œuvre – €").unwrap(), b"\x8Cuvre \x96 \x80");
+    assert_eq!(CsvEncoding::Latin1(Unmappable::Replace).encode("Œuvre").unwrap(), b"?uvre");
+    assert_eq!(CsvEncoding::Windows1252(Unmappable::Error).encode("Łódź"), Err(EncodingError { character: 'Ł', offset: 0 }));
+    assert_eq!(CsvEncoding::Windows1252(Unmappable::Error).encode("\u{80}"), Err(EncodingError { character: '\u{80}', offset: 0 }));
+
+    let index = DeduplicatedRoads::from_csv("Hauptstraße\tA1\r\nŁąkowa\tB2", "\t").unwrap().process();
+    let mut written = Vec::new();
+    index.processed.write_csv_with_options(&mut written, &CsvOptions::default().with_encoding(CsvEncoding::Utf8Bom)).unwrap();
+    assert_eq!(written, CsvOptions::default().with_encoding(CsvEncoding::Utf8Bom).encode(&index.processed.to_csv("\t")).unwrap());
+    assert!(written.starts_with(b"\xEF\xBB\xBFHauptstra\xC3\x9Fe\tA1\r\n"));
+
+    let options = CsvOptions::default().with_encoding(CsvEncoding::Windows1252(Unmappable::Error));
+    let error = index.processed.write_csv_with_options(Vec::new(), &options).unwrap_err();
+    assert_eq!(error.kind(), ::std::io::ErrorKind::InvalidData);
+    let options = CsvOptions::default().with_encoding(CsvEncoding::Windows1252(Unmappable::Replace));
+    assert_eq!(options.encode(&index.processed.to_csv("\t")).unwrap(), b"Hauptstra\xDFe\tA1\r\n??kowa\tB2");
+}
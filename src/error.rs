@@ -0,0 +1,208 @@
+//! Crate-level error type that wraps the errors of all readers and writers,
+//! so that a pipeline (read, process, export) can use a single `?`
+//!
+//! The module-specific errors (`ParseError`, `ShapefileError`, ...) are
+//! still returned by the individual functions and convert into `Error`.
+
+use std::{fmt, io, error};
+use roads2csv::{ParseError, GridPositionError};
+use encoding::EncodingError;
+use attributes::Conflict;
+#[cfg(feature = "geojson")]
+use geojson2roads::GeoJsonError;
+#[cfg(feature = "osm")]
+use osm2roads::OsmError;
+#[cfg(feature = "shapefile")]
+use shapefile2roads::ShapefileError;
+#[cfg(feature = "kml")]
+use kml2roads::KmlError;
+#[cfg(feature = "gpx")]
+use gpx2roads::GpxError;
+#[cfg(feature = "overpass")]
+use overpass::OverpassError;
+#[cfg(feature = "postgres")]
+use postgis2roads::PostgisError;
+
+/// Any error that can happen in this crate
+#[derive(Debug)]
+pub enum Error {
+    /// A grid position such as `"A9"` could not be parsed
+    InvalidGridPosition(GridPositionError),
+    /// A previously exported CSV could not be parsed
+    Parse(ParseError),
+    /// Reading or writing a file failed
+    Io(io::Error),
+    /// The input contains no streets
+    EmptyInput,
+    /// A character can not be encoded in the `CsvEncoding` of the output
+    Encoding(EncodingError),
+    /// There is no exporter for the output format (see `ExporterRegistry`)
+    UnknownFormat(String),
+    /// Rows of a street disagree on an attribute or the category
+    /// (see `ConflictPolicy::Error`)
+    Conflict(Conflict),
+    /// The build was aborted with a `CancellationToken`
+    Cancelled,
+    /// A review file (see `StreetIndex::apply_review`) is invalid at the line
+    InvalidReviewFile(usize, String),
+    /// The JSON input of `wasm::generate_index` is invalid
+    #[cfg(feature = "wasm")]
+    InvalidJson(String),
+    #[cfg(feature = "geojson")]
+    GeoJson(GeoJsonError),
+    #[cfg(feature = "osm")]
+    Osm(OsmError),
+    #[cfg(feature = "shapefile")]
+    Shapefile(ShapefileError),
+    #[cfg(feature = "kml")]
+    Kml(KmlError),
+    #[cfg(feature = "gpx")]
+    Gpx(GpxError),
+    #[cfg(feature = "gdal")]
+    Gdal(::gdal::errors::GdalError),
+    #[cfg(feature = "overpass")]
+    Overpass(OverpassError),
+    #[cfg(feature = "postgres")]
+    Postgis(PostgisError),
+    #[cfg(feature = "xlsx")]
+    Xlsx(::rust_xlsxwriter::XlsxError),
+    #[cfg(feature = "pdf")]
+    Pdf(::printpdf::Error),
+    #[cfg(feature = "sqlite")]
+    Sqlite(::rusqlite::Error),
+}
+
+/// Shorthand for `Result<T, street_index::Error>`
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Error::*;
+        match self {
+            InvalidGridPosition(e) => write!(f, "{}", e),
+            Parse(e) => write!(f, "{}", e),
+            Io(e) => write!(f, "I/O error: {}", e),
+            EmptyInput => write!(f, "the input contains no streets"),
+            Encoding(e) => write!(f, "{}", e),
+            UnknownFormat(format) => write!(f, "unknown output format \"{}\"", format),
+            Conflict(e) => write!(f, "{}", e),
+            Cancelled => write!(f, "the index build was cancelled"),
+            InvalidReviewFile(line, reason) => write!(f, "invalid review file, line {}: {}", line, reason),
+            #[cfg(feature = "wasm")]
+            InvalidJson(reason) => write!(f, "invalid JSON input: {}", reason),
+            #[cfg(feature = "geojson")]
+            GeoJson(e) => write!(f, "{}", e),
+            #[cfg(feature = "osm")]
+            Osm(e) => write!(f, "{}", e),
+            #[cfg(feature = "shapefile")]
+            Shapefile(e) => write!(f, "{}", e),
+            #[cfg(feature = "kml")]
+            Kml(e) => write!(f, "{}", e),
+            #[cfg(feature = "gpx")]
+            Gpx(e) => write!(f, "{}", e),
+            #[cfg(feature = "gdal")]
+            Gdal(e) => write!(f, "GDAL error: {}", e),
+            #[cfg(feature = "overpass")]
+            Overpass(e) => write!(f, "{}", e),
+            #[cfg(feature = "postgres")]
+            Postgis(e) => write!(f, "{}", e),
+            #[cfg(feature = "xlsx")]
+            Xlsx(e) => write!(f, "XLSX error: {}", e),
+            #[cfg(feature = "pdf")]
+            Pdf(e) => write!(f, "PDF error: {}", e),
+            #[cfg(feature = "sqlite")]
+            Sqlite(e) => write!(f, "SQLite error: {}", e),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use self::Error::*;
+        match self {
+            InvalidGridPosition(e) => Some(e),
+            Parse(e) => Some(e),
+            Io(e) => Some(e),
+            EmptyInput => None,
+            Encoding(e) => Some(e),
+            UnknownFormat(_) => None,
+            Conflict(e) => Some(e),
+            Cancelled => None,
+            InvalidReviewFile(..) => None,
+            #[cfg(feature = "wasm")]
+            InvalidJson(_) => None,
+            #[cfg(feature = "geojson")]
+            GeoJson(e) => Some(e),
+            #[cfg(feature = "osm")]
+            Osm(e) => Some(e),
+            #[cfg(feature = "shapefile")]
+            Shapefile(e) => Some(e),
+            #[cfg(feature = "kml")]
+            Kml(e) => Some(e),
+            #[cfg(feature = "gpx")]
+            Gpx(e) => Some(e),
+            #[cfg(feature = "gdal")]
+            Gdal(e) => Some(e),
+            #[cfg(feature = "overpass")]
+            Overpass(e) => Some(e),
+            #[cfg(feature = "postgres")]
+            Postgis(e) => Some(e),
+            #[cfg(feature = "xlsx")]
+            Xlsx(e) => Some(e),
+            #[cfg(feature = "pdf")]
+            Pdf(e) => Some(e),
+            #[cfg(feature = "sqlite")]
+            Sqlite(e) => Some(e),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($(#[$attr:meta])* $from:ty, $variant:ident) => {
+        $(#[$attr])*
+        impl From<$from> for Error {
+            fn from(e: $from) -> Self {
+                Error::$variant(e)
+            }
+        }
+    };
+}
+
+impl_from!(GridPositionError, InvalidGridPosition);
+impl_from!(ParseError, Parse);
+impl_from!(io::Error, Io);
+impl_from!(EncodingError, Encoding);
+impl_from!(Conflict, Conflict);
+impl_from!(#[cfg(feature = "geojson")] GeoJsonError, GeoJson);
+impl_from!(#[cfg(feature = "osm")] OsmError, Osm);
+impl_from!(#[cfg(feature = "shapefile")] ShapefileError, Shapefile);
+impl_from!(#[cfg(feature = "kml")] KmlError, Kml);
+impl_from!(#[cfg(feature = "gpx")] GpxError, Gpx);
+impl_from!(#[cfg(feature = "gdal")] ::gdal::errors::GdalError, Gdal);
+impl_from!(#[cfg(feature = "overpass")] OverpassError, Overpass);
+impl_from!(#[cfg(feature = "postgres")] PostgisError, Postgis);
+impl_from!(#[cfg(feature = "xlsx")] ::rust_xlsxwriter::XlsxError, Xlsx);
+impl_from!(#[cfg(feature = "pdf")] ::printpdf::Error, Pdf);
+impl_from!(#[cfg(feature = "sqlite")] ::rusqlite::Error, Sqlite);
+
+#[test]
+fn test_error() {
+    use roads2csv::{DeduplicatedRoads, GridPosition};
+
+    fn parse(csv: &str) -> Result<usize> {
+        let deduplicated = DeduplicatedRoads::from_csv_reader(csv.as_bytes(), "\t")?;
+        let position: GridPosition = "AA12".parse()?;
+        Ok(deduplicated.roads.len() + position.row)
+    }
+
+    assert_eq!(parse("Abbey Road\tA1\r\n\r\nMill Lane\tB2-B3\r\n").unwrap(), 14);
+
+    match parse("\r\n\r\n") {
+        Err(Error::EmptyInput) => { },
+        other => panic!("expected an empty input error, got {:?}", other),
+    }
+
+    let error = parse("Abbey Road\tA1\nMill Lane\t9Z").unwrap_err();
+    assert_eq!(error.to_string(), "line 2: invalid grid position \"9Z\"");
+    assert!(::std::error::Error::source(&error).is_some());
+}
@@ -0,0 +1,259 @@
+//! Common interface of the output formats, so that a CLI or a web service
+//! can pick the format from a user-supplied string (`"csv"`, `"json"`, ...)
+//! and users can plug in their own formats
+//!
+//! ```no_run,ignore
+//! let mut registry = ExporterRegistry::builtin();
+//! registry.register("intranet", MyIntranetExporter::new());
+//!
+//! // per request
+//! registry.export(&format, &index, &mut response)?;
+//! ```
+
+use std::{io, fmt, collections::BTreeMap};
+use roads2csv::StreetIndex;
+use roads2latex::LatexOptions;
+use roads2markdown::MarkdownStyle;
+use roads2indesign::TaggedTextOptions;
+use csv_exporter::CsvExporter;
+#[cfg(feature = "pdf")]
+use roads2pdf::PdfOptions;
+use error::{Error, Result};
+
+/// Writes a `StreetIndex` in one output format
+///
+/// Implementors only have to implement `write`, which takes a trait object
+/// so that exporters can be stored in an `ExporterRegistry`.
+pub trait Exporter: Send + Sync {
+
+    /// Writes the `index` to the `writer`
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()>;
+
+    /// Same as `write()`, but takes the `writer` by value
+    fn export<W: io::Write>(&self, index: &StreetIndex, mut writer: W) -> Result<()> where Self: Sized {
+        self.write(index, &mut writer)
+    }
+}
+
+fn write_text(writer: &mut dyn io::Write, text: &str) -> Result<()> {
+    writer.write_all(text.as_bytes())?;
+    Ok(())
+}
+
+/// Exports the index as JSON (see `StreetIndex::to_json`)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct JsonExporter;
+
+/// Exports the index as YAML (see `StreetIndex::to_yaml`)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct YamlExporter;
+
+/// Exports the index as TOML (see `StreetIndex::to_toml`)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct TomlExporter;
+
+/// Exports the index as XML (see `StreetIndex::to_xml`)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct XmlExporter;
+
+/// Exports the index as an HTML page (see `StreetIndex::to_html`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct HtmlExporter {
+    /// Title of the page
+    pub title: String,
+}
+
+impl HtmlExporter {
+    pub fn new(title: &str) -> Self {
+        Self { title: String::from(title) }
+    }
+}
+
+/// Exports the index as an Excel workbook (see `StreetIndex::to_xlsx`)
+#[cfg(feature = "xlsx")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct XlsxExporter;
+
+impl Exporter for CsvExporter {
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+        CsvExporter::write(self, index, writer)?;
+        Ok(())
+    }
+}
+
+impl Exporter for JsonExporter {
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+        write_text(writer, &index.to_json())
+    }
+}
+
+impl Exporter for YamlExporter {
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+        write_text(writer, &index.to_yaml())
+    }
+}
+
+impl Exporter for TomlExporter {
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+        write_text(writer, &index.to_toml())
+    }
+}
+
+impl Exporter for XmlExporter {
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+        write_text(writer, &index.to_xml())
+    }
+}
+
+impl Exporter for HtmlExporter {
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+        write_text(writer, &index.to_html(&self.title))
+    }
+}
+
+impl Exporter for MarkdownStyle {
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+        write_text(writer, &index.to_markdown(*self))
+    }
+}
+
+impl Exporter for LatexOptions {
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+        write_text(writer, &index.to_latex(self))
+    }
+}
+
+impl Exporter for TaggedTextOptions {
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+        write_text(writer, &index.to_tagged_text(self))
+    }
+}
+
+#[cfg(feature = "xlsx")]
+impl Exporter for XlsxExporter {
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+        writer.write_all(&index.to_xlsx_buffer()?)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "pdf")]
+impl Exporter for PdfOptions {
+    fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+        writer.write_all(&index.to_pdf_bytes(self)?)?;
+        Ok(())
+    }
+}
+
+/// Exporters by format name, names are case-insensitive
+#[derive(Default)]
+pub struct ExporterRegistry {
+    exporters: BTreeMap<String, Box<dyn Exporter>>,
+}
+
+impl fmt::Debug for ExporterRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExporterRegistry").field("formats", &self.formats().collect::<Vec<_>>()).finish()
+    }
+}
+
+impl ExporterRegistry {
+
+    /// Registry without any formats
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with all formats of this crate and their default options:
+    /// `csv`, `json`, `yaml`, `toml`, `xml`, `html`, `markdown`, `latex`,
+    /// `indesign` as well as `xlsx` and `pdf` if the features are enabled
+    pub fn builtin() -> Self {
+        let registry = Self::new()
+            .with_exporter("csv", CsvExporter::default())
+            .with_exporter("json", JsonExporter)
+            .with_exporter("yaml", YamlExporter)
+            .with_exporter("toml", TomlExporter)
+            .with_exporter("xml", XmlExporter)
+            .with_exporter("html", HtmlExporter::new("Street index"))
+            .with_exporter("markdown", MarkdownStyle::default())
+            .with_exporter("latex", LatexOptions::default())
+            .with_exporter("indesign", TaggedTextOptions::default());
+        #[cfg(feature = "xlsx")]
+        let registry = registry.with_exporter("xlsx", XlsxExporter);
+        #[cfg(feature = "pdf")]
+        let registry = registry.with_exporter("pdf", PdfOptions::default());
+        registry
+    }
+
+    pub fn with_exporter<E: Exporter + 'static>(mut self, format: &str, exporter: E) -> Self {
+        self.register(format, exporter);
+        self
+    }
+
+    /// Adds the `exporter` for the `format`, replaces the previous exporter
+    /// of the format (i.e. to change the options of a built-in format)
+    pub fn register<E: Exporter + 'static>(&mut self, format: &str, exporter: E) {
+        self.exporters.insert(format.to_lowercase(), Box::new(exporter));
+    }
+
+    /// Removes the `format`, returns `false` if it was not registered
+    pub fn unregister(&mut self, format: &str) -> bool {
+        self.exporters.remove(&format.to_lowercase()).is_some()
+    }
+
+    pub fn get(&self, format: &str) -> Option<&dyn Exporter> {
+        self.exporters.get(&format.to_lowercase()).map(|exporter| exporter.as_ref())
+    }
+
+    /// Names of the registered formats, in alphabetical order
+    pub fn formats(&self) -> impl Iterator<Item = &str> {
+        self.exporters.keys().map(|format| format.as_str())
+    }
+
+    /// Writes the `index` in the `format`, fails with `Error::UnknownFormat`
+    /// if there is no exporter for the format
+    pub fn export<W: io::Write>(&self, format: &str, index: &StreetIndex, mut writer: W) -> Result<()> {
+        let exporter = self.get(format).ok_or_else(|| Error::UnknownFormat(String::from(format)))?;
+        exporter.write(index, &mut writer)
+    }
+}
+
+#[test]
+fn test_exporter_registry() {
+    use roads2csv::DeduplicatedRoads;
+
+    struct NameList;
+
+    impl Exporter for NameList {
+        fn write(&self, index: &StreetIndex, writer: &mut dyn io::Write) -> Result<()> {
+            for road in &index.processed.processed {
+                writeln!(writer, "{}", road.name.0)?;
+            }
+            Ok(())
+        }
+    }
+
+    let index = DeduplicatedRoads::from_csv("Abbey Road\tA1-A2\r\nMarket St\tB2", "\t").unwrap().process();
+    let mut registry = ExporterRegistry::builtin();
+    assert!(registry.formats().any(|format| format == "latex"));
+
+    let mut written = Vec::new();
+    registry.export("JSON", &index, &mut written).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), index.to_json());
+
+    let mut written = Vec::new();
+    Exporter::export(&CsvExporter::new().with_delimiter(';'), &index, &mut written).unwrap();
+    assert_eq!(written, b"Abbey Road;A1-A2\r\nMarket St;B2");
+
+    registry.register("names", NameList);
+    let mut written = Vec::new();
+    registry.export("names", &index, &mut written).unwrap();
+    assert_eq!(written, b"Abbey Road\nMarket St\n");
+
+    match registry.export("docx", &index, Vec::new()) {
+        Err(Error::UnknownFormat(format)) => assert_eq!(format, "docx"),
+        other => panic!("expected an unknown format error, got {:?}", other),
+    }
+    assert!(registry.unregister("Names"));
+    assert!(registry.get("names").is_none());
+}
@@ -0,0 +1,138 @@
+//! Stop-list for streets that should never end up in the index, i.e.
+//! `"unnamed road"`, empty names or internal parking-aisle names
+//!
+//! The filter is applied before the deduplication, either directly
+//! (`Filter::apply`) or while building (`DeduplicatedRoadsBuilder::with_filter`):
+//!
+//! ```no_run,ignore
+//! let filter = Filter::new()
+//!     .exclude_empty()
+//!     .exclude_names(&["unnamed road", "parking aisle *"]);
+//! let deduplicated = DeduplicatedRoads::from_streets(&filter.apply(&streets));
+//! ```
+
+use roads2csv::InputStreetValue;
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+/// Excludes streets by their name
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// Lowercase patterns, `*` matches any text
+    patterns: Vec<String>,
+    #[cfg(feature = "regex")]
+    regexes: Vec<Regex>,
+    exclude_empty: bool,
+}
+
+impl Filter {
+
+    /// Filter that doesn't exclude anything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Excludes names that match one of the `patterns`, ignoring case and
+    /// surrounding whitespace. `*` matches any text, i.e. `"parking *"`
+    /// matches `"Parking Aisle 4"`.
+    pub fn exclude_names<S: AsRef<str>>(mut self, patterns: &[S]) -> Self {
+        self.patterns.extend(patterns.iter().map(|pattern| pattern.as_ref().trim().to_lowercase()));
+        self
+    }
+
+    /// Excludes names that match the regular expression (anywhere in the
+    /// name, use `^...$` to match the whole name)
+    #[cfg(feature = "regex")]
+    pub fn exclude_regex(mut self, pattern: &str) -> Result<Self, ::regex::Error> {
+        self.regexes.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Excludes names that are empty or only consist of whitespace
+    pub fn exclude_empty(self) -> Self {
+        Self { exclude_empty: true, .. self }
+    }
+
+    /// Returns whether the street with the `name` is excluded
+    pub fn is_excluded(&self, name: &str) -> bool {
+        let trimmed = name.trim();
+        if self.exclude_empty && trimmed.is_empty() {
+            return true;
+        }
+
+        let lowercase = trimmed.to_lowercase();
+        if self.patterns.iter().any(|pattern| matches_pattern(pattern, &lowercase)) {
+            return true;
+        }
+
+        #[cfg(feature = "regex")]
+        {
+            if self.regexes.iter().any(|regex| regex.is_match(name)) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Returns the streets that are not excluded
+    pub fn apply(&self, streets: &[InputStreetValue]) -> Vec<InputStreetValue> {
+        streets.iter().filter(|street| !self.is_excluded(&street.street_name.0)).cloned().collect()
+    }
+}
+
+/// Matches `text` against a `pattern` where `*` matches any text
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !text.starts_with(first) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..];
+    let mut parts = parts.collect::<Vec<&str>>();
+    let last = match parts.pop() {
+        Some(last) => last,
+        // no `*` in the pattern
+        None => return rest.is_empty(),
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[test]
+fn test_filter() {
+    use roads2csv::{DeduplicatedRoadsBuilder, GridPosition};
+
+    let filter = Filter::new().exclude_empty().exclude_names(&["Unnamed Road", "parking * aisle", "*(private)"]);
+    assert!(filter.is_excluded("  "));
+    assert!(filter.is_excluded("unnamed road "));
+    assert!(filter.is_excluded("Parking Lot 4 Aisle"));
+    assert!(filter.is_excluded("Mill Lane (private)"));
+    assert!(!filter.is_excluded("Unnamed Road 2"));
+    assert!(!filter.is_excluded("Parking Aisle Road"));
+    assert!(!filter.is_excluded("Mill Lane"));
+
+    let position: GridPosition = "A1".parse().unwrap();
+    let streets = ["Mill Lane", "", "Unnamed road"].iter().map(|name| InputStreetValue::new(name, position.clone())).collect::<Vec<_>>();
+    assert_eq!(filter.apply(&streets).len(), 1);
+
+    let mut builder = DeduplicatedRoadsBuilder::new().with_filter(filter);
+    builder.extend(streets);
+    assert_eq!(builder.finish().roads.keys().map(|name| name.0.as_str()).collect::<Vec<_>>(), vec!["Mill Lane"]);
+
+    #[cfg(feature = "regex")]
+    {
+        let filter = Filter::new().exclude_regex(r"^(Service|Access) \d+$").unwrap();
+        assert!(filter.is_excluded("Service 12"));
+        assert!(!filter.is_excluded("Service Road"));
+        assert!(Filter::new().exclude_regex("(").is_err());
+    }
+}
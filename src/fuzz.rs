@@ -0,0 +1,93 @@
+//! Entry points for fuzzing the parsers and the processing with untrusted
+//! input (i.e. user-uploaded files), used by the targets in `fuzz/`:
+//!
+//! ```no_run,ignore
+//! fuzz_target!(|data: &[u8]| street_index::fuzz::parse_csv(data));
+//! ```
+//!
+//! The entry points don't panic on invalid input, only if the crate itself
+//! is broken (i.e. if a parsed position does not survive a round trip or
+//! the processed index fails `StreetIndex::verify`).
+//! Ranges are limited to `InputLimits::default().max_range_cells`, which is
+//! lower with `cfg(fuzzing)` so that `"A1-A4000000000"` doesn't time out.
+
+use std::str;
+use arbitrary::{Arbitrary, Unstructured, Result};
+use roads2csv::{GridPosition, DeduplicatedRoads, InputStreetValue, ProcessOptions};
+use validation::InputLimits;
+use sorting::ByteOrder;
+
+/// Parses the data as a `GridPosition`, a valid position has to be parsed
+/// back to the same position from its `Display` output
+pub fn parse_position(data: &[u8]) {
+    let input = match str::from_utf8(data) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+    if let Ok(position) = GridPosition::parse(input) {
+        assert_eq!(GridPosition::parse(&position.to_string()), Ok(position));
+    }
+}
+
+/// Reads the data as a tab-delimited CSV (see `DeduplicatedRoads::from_csv_with_limits`),
+/// processes and exports it, the index has to pass `StreetIndex::verify`
+pub fn parse_csv(data: &[u8]) {
+    let csv = String::from_utf8_lossy(data);
+    if let Ok(deduplicated) = DeduplicatedRoads::from_csv_with_limits(&csv, "\t", &InputLimits::default()) {
+        let index = deduplicated.process();
+        index.to_csv("\t");
+        assert_eq!(index.verify(&deduplicated), []);
+    }
+}
+
+/// Deduplicates, processes, exports and verifies arbitrary streets with
+/// arbitrary `ProcessOptions`, fails only if the data is too short
+pub fn process_streets(data: &[u8]) -> Result<()> {
+    let mut u = Unstructured::new(data);
+    let streets = Vec::<InputStreetValue>::arbitrary(&mut u)?;
+    let options = ProcessOptions::arbitrary(&mut u)?;
+    check_streets(&streets, &options);
+    Ok(())
+}
+
+fn check_streets(streets: &[InputStreetValue], options: &ProcessOptions) {
+    let deduplicated = DeduplicatedRoads::from_streets(streets);
+    let index = deduplicated.process_with_options(options);
+    index.to_csv("\t");
+    assert_eq!(index.verify_with_options(&deduplicated, options, &ByteOrder), []);
+}
+
+#[test]
+fn test_fuzz_entry_points() {
+    use roads2csv::ParseErrorKind;
+
+    let limits = InputLimits::default();
+    let error = DeduplicatedRoads::from_csv_with_limits("Mill Lane\tA1\r\nValley Road\tA1-A4000000000", "\t", &limits).unwrap_err();
+    assert_eq!((error.line, error.kind), (Some(2), ParseErrorKind::RangeTooLong(String::from("A1-A4000000000"))));
+    assert!(InputStreetValue::parse_line_with_limits("Valley Road\tA1-ZZZ1", "\t", &limits).is_err());
+    assert_eq!(InputStreetValue::parse_line_with_limits("Valley Road\tA1-A9; C1-C3", "\t", &limits).unwrap().len(), 12);
+
+    // the largest gap must not overflow
+    let streets = ["A1", "A2", "A9"].iter().map(|cell| InputStreetValue::new("Valley Road", cell.parse().unwrap())).collect::<Vec<_>>();
+    let options = ProcessOptions { max_auto_cells: usize::MAX, allow_ranges: true, cluster_gap: Some(usize::MAX), primary_cell: false };
+    assert_eq!(DeduplicatedRoads::from_streets(&streets).process_with_options(&options).to_csv("\t"), "Valley Road\tA1-A9");
+
+    // the area between the extreme cells overflows a `usize`, a range
+    // over all rows must not be expanded
+    let far = |cells: &[&str]| cells.iter().map(|cell| InputStreetValue::new("Far Road", cell.parse().unwrap())).collect::<Vec<_>>();
+    check_streets(&far(&["A0", "A4294967295", "MWLQKWV0"]), &ProcessOptions::default());
+    check_streets(&far(&["A0", "A1", "A4294967295"]), &options);
+
+    // pseudo-random inputs (xorshift), biased towards grid positions and delimiters
+    let alphabet = b"AZ019:-;\t\r\n\" \xff";
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || { state ^= state << 13; state ^= state >> 7; state ^= state << 17; state };
+    for _ in 0..2_000 {
+        let data = (0..next() % 64).map(|_| alphabet[next() as usize % alphabet.len()]).collect::<Vec<u8>>();
+        parse_position(&data);
+        parse_csv(&data);
+        let random = (0..next() % 512).map(|_| next() as u8).collect::<Vec<u8>>();
+        let _ = process_streets(&random);
+    }
+}
+
@@ -0,0 +1,175 @@
+//! Reads road geometries from a GeoJSON `FeatureCollection` and assigns
+//! grid cells to them
+
+use std::{fmt, error::Error, collections::BTreeMap};
+use serde_json::{self, Value};
+use roads2csv::{InputStreetValue, StreetName};
+use gridconfig::GeoGrid;
+
+/// Error that can happen while reading a GeoJSON document
+#[derive(Debug)]
+pub enum GeoJsonError {
+    /// The input is not valid JSON
+    Json(serde_json::Error),
+    /// The top-level object is not a `FeatureCollection`
+    NotAFeatureCollection,
+    /// The geometry of the feature at the given index (in the `features`
+    /// array) has malformed coordinates
+    InvalidGeometry { feature: usize },
+}
+
+impl fmt::Display for GeoJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::GeoJsonError::*;
+        match self {
+            Json(e) => write!(f, "invalid JSON: {}", e),
+            NotAFeatureCollection => write!(f, "GeoJSON is not a FeatureCollection"),
+            InvalidGeometry { feature } => write!(f, "feature {} has an invalid geometry", feature),
+        }
+    }
+}
+
+impl Error for GeoJsonError { }
+
+impl From<serde_json::Error> for GeoJsonError {
+    fn from(e: serde_json::Error) -> Self {
+        GeoJsonError::Json(e)
+    }
+}
+
+/// Reads all `LineString` and `MultiLineString` features that have a `"name"`
+/// property and returns one `InputStreetValue` for every cell of the `grid`
+/// that a road touches.
+///
+/// Features without a name and features with other geometry types
+/// (i.e. points or polygons) are ignored.
+pub fn from_geojson(geojson: &str, grid: &GeoGrid) -> Result<Vec<InputStreetValue>, GeoJsonError> {
+    from_geojson_with_name_property(geojson, "name", grid)
+}
+
+/// Same as `from_geojson`, but reads the street name from a custom property
+/// (such as `"addr:street"` or `"STRNAME"`) instead of `"name"`.
+pub fn from_geojson_with_name_property(geojson: &str, name_property: &str, grid: &GeoGrid)
+-> Result<Vec<InputStreetValue>, GeoJsonError>
+{
+    let root: Value = serde_json::from_str(geojson)?;
+
+    if root.get("type").and_then(Value::as_str) != Some("FeatureCollection") {
+        return Err(GeoJsonError::NotAFeatureCollection);
+    }
+
+    let features = root.get("features").and_then(Value::as_array).ok_or(GeoJsonError::NotAFeatureCollection)?;
+
+    let mut streets = Vec::new();
+
+    for (feature_index, feature) in features.iter().enumerate() {
+
+        let name = match feature.get("properties").and_then(|p| p.get(name_property)).and_then(Value::as_str) {
+            Some(name) if !name.trim().is_empty() => name,
+            _ => continue,
+        };
+
+        let geometry = match feature.get("geometry") {
+            Some(geometry) if !geometry.is_null() => geometry,
+            _ => continue,
+        };
+
+        let invalid = GeoJsonError::InvalidGeometry { feature: feature_index };
+        let coordinates = geometry.get("coordinates");
+
+        let lines = match geometry.get("type").and_then(Value::as_str) {
+            Some("LineString") => {
+                vec![parse_line(coordinates).ok_or(invalid)?]
+            },
+            Some("MultiLineString") => {
+                coordinates
+                    .and_then(Value::as_array)
+                    .ok_or(invalid)?
+                    .iter()
+                    .map(|line| parse_line(Some(line)))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(GeoJsonError::InvalidGeometry { feature: feature_index })?
+            },
+            _ => continue,
+        };
+
+        let mut cells = Vec::new();
+        for line in &lines {
+            for cell in grid.cells_for_linestring(line) {
+                if !cells.contains(&cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+
+        streets.extend(cells.into_iter().map(|position| InputStreetValue {
+            street_name: StreetName(name.to_string()),
+            position,
+            category: None,
+            number_range: None,
+            weight: None,
+            secondary_name: None,
+            district: None,
+            attributes: BTreeMap::new(),
+        }));
+    }
+
+    Ok(streets)
+}
+
+/// Parses `[[x, y], [x, y], ...]` (additional ordinates such as the
+/// elevation are ignored)
+fn parse_line(coordinates: Option<&Value>) -> Option<Vec<(f64, f64)>> {
+    coordinates?.as_array()?.iter().map(|point| {
+        let point = point.as_array()?;
+        Some((point.first()?.as_f64()?, point.get(1)?.as_f64()?))
+    }).collect()
+}
+
+#[test]
+fn test_from_geojson() {
+    use roads2csv::GridPosition;
+
+    let geojson = r#"{
+        "type": "FeatureCollection",
+        "features": [
+            {
+                "type": "Feature",
+                "properties": { "name": "Canterbury Road" },
+                "geometry": { "type": "LineString", "coordinates": [[0.5, 9.5], [1.5, 9.5], [1.6, 9.4]] }
+            },
+            {
+                "type": "Feature",
+                "properties": { "name": "Mayer Street" },
+                "geometry": { "type": "MultiLineString", "coordinates": [[[0.5, 8.5]], [[3.5, 8.5, 100.0]]] }
+            },
+            {
+                "type": "Feature",
+                "properties": { "highway": "service" },
+                "geometry": { "type": "LineString", "coordinates": [[0.5, 9.5], [1.5, 9.5]] }
+            },
+            {
+                "type": "Feature",
+                "properties": { "name": "Town Hall" },
+                "geometry": { "type": "Point", "coordinates": [0.5, 9.5] }
+            }
+        ]
+    }"#;
+
+    let grid = GeoGrid::new(0.0, 10.0, 1.0, 1.0);
+    let streets = from_geojson(geojson, &grid).unwrap();
+
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let cells = streets.iter().map(|s| (s.street_name.0.as_str(), s.position.clone())).collect::<Vec<_>>();
+    assert_eq!(cells, vec![
+        ("Canterbury Road", cell("A", 1)),
+        ("Canterbury Road", cell("B", 1)),
+        ("Mayer Street", cell("A", 2)),
+        ("Mayer Street", cell("D", 2)),
+    ]);
+
+    assert!(from_geojson(r#"{ "type": "Feature" }"#, &grid).is_err());
+    assert!(from_geojson(r#"{ "type": "FeatureCollection", "features": [
+        { "type": "Feature", "properties": { "name": "A" }, "geometry": { "type": "LineString", "coordinates": [["x", 1]] } }
+    ] }"#, &grid).is_err());
+}
@@ -0,0 +1,175 @@
+//! Reads named tracks from a GPX file and assigns grid cells to them, so
+//! that trail indexes of hiking maps work the same way as street indexes
+//!
+//! Every `<trk>` with a `<name>` becomes one trail, all of its `<trkseg>`
+//! segments are read. Waypoints, routes and unnamed tracks are ignored.
+
+use std::{fmt, error::Error};
+use quick_xml::{Reader, events::{Event, BytesStart}};
+use roads2csv::InputStreetValue;
+use gridconfig::GeoGrid;
+
+/// Error that can happen while reading a GPX file
+#[derive(Debug)]
+pub enum GpxError {
+    /// The GPX is not well-formed XML
+    Xml(quick_xml::Error),
+    /// A `<trkpt>` of the track at the given index (counted from the start
+    /// of the document) has a missing or invalid `lat` / `lon` attribute
+    InvalidTrackPoint { track: usize },
+}
+
+impl fmt::Display for GpxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::GpxError::*;
+        match self {
+            Xml(e) => write!(f, "invalid GPX: {}", e),
+            InvalidTrackPoint { track } => write!(f, "track {} has an invalid track point", track),
+        }
+    }
+}
+
+impl Error for GpxError { }
+
+impl From<quick_xml::Error> for GpxError {
+    fn from(e: quick_xml::Error) -> Self {
+        GpxError::Xml(e)
+    }
+}
+
+/// Reads all named tracks and returns one `InputStreetValue` for every cell
+/// of the `grid` that a track touches. Each segment is a separate line, so
+/// the gap between two segments doesn't cross any cells.
+pub fn from_gpx(gpx: &str, grid: &GeoGrid) -> Result<Vec<InputStreetValue>, GpxError> {
+
+    let mut reader = Reader::from_str(gpx);
+    reader.config_mut().trim_text(true);
+
+    // local names of the currently open elements
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut text = String::new();
+    let mut track_index = 0;
+    let mut name: Option<String> = None;
+    let mut segments: Vec<Vec<(f64, f64)>> = Vec::new();
+    let mut streets = Vec::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) => {
+                let local_name = element.local_name().as_ref().to_vec();
+                match local_name.as_slice() {
+                    b"trk" => {
+                        name = None;
+                        segments.clear();
+                    },
+                    b"trkseg" => segments.push(Vec::new()),
+                    b"trkpt" => push_point(&element, &mut segments, track_index)?,
+                    _ => { },
+                }
+                path.push(local_name);
+                text.clear();
+            },
+            Event::Empty(element) if element.local_name().as_ref() == b"trkpt" => {
+                push_point(&element, &mut segments, track_index)?;
+            },
+            Event::Text(content) => {
+                text.push_str(&content.unescape()?);
+            },
+            Event::CData(content) => {
+                text.push_str(&String::from_utf8_lossy(&content));
+            },
+            Event::End(_) => {
+                let element = path.pop().unwrap_or_default();
+                let parent = path.last().map(|parent| parent.as_slice());
+
+                match (element.as_slice(), parent) {
+                    (b"name", Some(b"trk")) => {
+                        name = Some(text.trim().to_string());
+                    },
+                    (b"trk", _) => {
+                        if let Some(name) = name.take().filter(|name| !name.is_empty()) {
+                            let mut cells = Vec::new();
+                            for segment in &segments {
+                                for cell in grid.cells_for_linestring(segment) {
+                                    if !cells.contains(&cell) {
+                                        cells.push(cell);
+                                    }
+                                }
+                            }
+                            streets.extend(cells.into_iter().map(|position| InputStreetValue::new(&name, position)));
+                        }
+                        track_index += 1;
+                    },
+                    _ => { },
+                }
+                text.clear();
+            },
+            Event::Eof => break,
+            _ => { },
+        }
+    }
+
+    Ok(streets)
+}
+
+/// Adds the `lon` / `lat` of a `<trkpt>` to the last segment
+fn push_point(element: &BytesStart, segments: &mut [Vec<(f64, f64)>], track: usize) -> Result<(), GpxError> {
+    let invalid = GpxError::InvalidTrackPoint { track };
+    let mut lat = None;
+    let mut lon = None;
+
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|e| GpxError::Xml(e.into()))?;
+        let value = String::from_utf8_lossy(&attribute.value).trim().parse::<f64>().ok();
+        match attribute.key.local_name().as_ref() {
+            b"lat" => lat = value,
+            b"lon" => lon = value,
+            _ => { },
+        }
+    }
+
+    match (lon, lat, segments.last_mut()) {
+        (Some(lon), Some(lat), Some(segment)) => {
+            segment.push((lon, lat));
+            Ok(())
+        },
+        _ => Err(invalid),
+    }
+}
+
+#[test]
+fn test_from_gpx() {
+    use roads2csv::GridPosition;
+
+    let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+    <gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+      <wpt lat="9.5" lon="0.5"><name>Summit</name></wpt>
+      <trk>
+        <name>Ridge Trail</name>
+        <trkseg>
+          <trkpt lat="9.5" lon="0.5"><ele>1200</ele></trkpt>
+          <trkpt lat="9.5" lon="1.5"/>
+        </trkseg>
+        <trkseg>
+          <trkpt lat="8.5" lon="3.5"/>
+        </trkseg>
+      </trk>
+      <trk>
+        <trkseg><trkpt lat="9.5" lon="0.5"/></trkseg>
+      </trk>
+    </gpx>"#;
+
+    let grid = GeoGrid::new(0.0, 10.0, 1.0, 1.0);
+    let streets = from_gpx(gpx, &grid).unwrap();
+
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let cells = streets.iter().map(|s| (s.street_name.0.as_str(), s.position.clone())).collect::<Vec<_>>();
+    assert_eq!(cells, vec![
+        ("Ridge Trail", cell("A", 1)),
+        ("Ridge Trail", cell("B", 1)),
+        ("Ridge Trail", cell("D", 2)),
+    ]);
+
+    let invalid = r#"<gpx><trk><name>A</name><trkseg><trkpt lat="x" lon="1"/></trkseg></trk></gpx>"#;
+    assert_eq!(from_gpx(invalid, &grid).unwrap_err().to_string(), "track 0 has an invalid track point");
+}
@@ -1,4 +1,9 @@
+use std::collections::{HashMap, BTreeMap};
 use roads2csv::{InputStreetValue, StreetName, GridPosition};
+use labeling::{GridLabeler, PositionFormatter};
+use utm;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
 
 /// The Grid is your street-name grid. Right now there is
 /// no support for curved / rotated / translated grids.
@@ -20,10 +25,12 @@ pub struct Grid {
 /// Unit struct just so it's easier to read that certain values
 /// should be in millimeter scale.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Millimeter(pub f32);
 
 /// Bounding box (usually the page extents)
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Bbox {
     pub width: Millimeter,
     pub height: Millimeter,
@@ -37,6 +44,7 @@ pub struct Bbox {
 /// likely be configurable although I haven't seen a map where
 // the grid didn't start at the top left).
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GridConfig {
     pub cell_height: Millimeter,
     pub cell_width: Millimeter,
@@ -56,6 +64,7 @@ pub struct GridConfig {
 /// fonts into this coordinate space before adding them, obviously.
 /// `street_index` does not take care of any geographic reprojections.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct StreetNameRect {
     pub street_name: String,
     pub x_from_left: Millimeter,
@@ -64,6 +73,219 @@ pub struct StreetNameRect {
     pub height: Millimeter,
 }
 
+/// Grid in map coordinates (i.e. longitude / latitude or projected meters),
+/// used to assign grid cells to road geometries instead of to laid-out labels.
+///
+/// Input coordinates are first transformed with the `projection`, the origin
+/// and the cell size are in projected coordinates. The origin is the top left
+/// corner of the grid. Columns grow to the right (increasing x), rows grow
+/// downwards (decreasing y), so the origin is usually the minimum x and the
+/// maximum y of your map extent. For grids that count the rows from the
+/// bottom (`RowDirection::BottomUp`), the origin is the bottom left corner.
+///
+/// ```no_run,ignore
+/// // 1 km cells in Web Mercator, 20 x 20 cells, columns labeled 1, 2, 3, ...
+/// let (x, y) = Projection::WebMercator.project(13.3, 52.6).unwrap();
+/// let grid = GeoGrid::new(x, y, 1000.0, 1000.0)
+///     .with_size(20, 20)
+///     .with_projection(Projection::WebMercator)
+///     .with_labeler(GridLabeler::new(ColumnScheme::Numeric, "/"));
+///
+/// grid.cell_for(13.4, 52.5) // Some(GridPosition { column: "L", row: 19 }), labeled "12/19"
+/// ```
+///
+/// Real-world grids (UTM cells or MGRS squares) are created with
+/// `GeoGrid::from_kind`, see `GridKind`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GeoGrid {
+    pub origin_x: f64,
+    pub origin_y: f64,
+    pub cell_width: f64,
+    pub cell_height: f64,
+    /// Number of columns, `None` if the grid has no right border
+    pub columns: Option<usize>,
+    /// Number of rows, `None` if the grid has no bottom border
+    pub rows: Option<usize>,
+    pub projection: Projection,
+    /// How the cells are labeled on the map, see `GeoGrid::label`
+    pub labeler: GridLabeler,
+    /// Number of the row at the origin, usually 1 (or 0)
+    #[cfg_attr(feature = "serde", serde(default = "default_first_row"))]
+    pub first_row: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub row_direction: RowDirection,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: GridKind,
+    /// Minimum length of a line string inside a cell before the cell is
+    /// assigned to it (see `cells_for_linestring`), `None` to assign every
+    /// cell that the line touches
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub min_length_in_cell: Option<MinLength>,
+}
+
+/// Minimum length of a line in a cell, so that streets which only clip the
+/// corner of a cell (i.e. at the neatline of the map) aren't indexed there
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MinLength {
+    /// Length in the units of the projected coordinates (meters for
+    /// `Projection::WebMercator`, UTM and MGRS grids)
+    Absolute(f64),
+    /// Length as a fraction of the cell size, i.e. `0.1` for a tenth of a cell
+    Fraction(f64),
+}
+
+/// Regular grid or real-world grid of a `GeoGrid`
+///
+/// UTM and MGRS grids expect longitude / latitude (WGS 84) as input, they
+/// ignore the origin, cell size, number of cells, projection, labeler and
+/// row numbering of the `GeoGrid`.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GridKind {
+    /// Cells defined by the origin and the cell size of the `GeoGrid`
+    #[default]
+    Regular,
+    /// Square cells of `cell_size_m` meters in the UTM `zone`, labeled with
+    /// the easting / northing of their south west corner, i.e.
+    /// `"32 512000 5340000"`. Zones on the southern hemisphere are negative
+    /// (`-56` for 56S).
+    Utm { zone: i8, cell_size_m: f64 },
+    /// 1 km squares of the Military Grid Reference System, i.e.
+    /// `"32U MV 12 34"`. The UTM zone is chosen for every point, the grid
+    /// zone designator (`"32U"`) is stored as the `page` of the position.
+    Mgrs,
+}
+
+#[cfg(feature = "serde")]
+fn default_first_row() -> usize {
+    1
+}
+
+/// Direction in which the row numbers of a `GeoGrid` grow
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RowDirection {
+    /// Rows are counted from the top (decreasing y)
+    #[default]
+    TopDown,
+    /// Rows are counted from the bottom (increasing y)
+    BottomUp,
+}
+
+/// Transforms input coordinates into the coordinate system of a `GeoGrid`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Projection {
+    /// Coordinates are used as they are (already projected, or a grid in degrees)
+    #[default]
+    Identity,
+    /// Longitude / latitude (WGS 84) to Web Mercator meters (EPSG:3857)
+    WebMercator,
+}
+
+impl Projection {
+
+    /// Projects a point, returns `None` if the point can't be projected
+    /// (i.e. latitudes beyond ±85.06° in Web Mercator)
+    pub fn project(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        const EARTH_RADIUS: f64 = 6_378_137.0;
+        const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
+        match self {
+            Projection::Identity => Some((x, y)),
+            Projection::WebMercator => {
+                if !(-180.0..=180.0).contains(&x) || !(-MAX_LATITUDE..=MAX_LATITUDE).contains(&y) {
+                    return None;
+                }
+                let projected_x = EARTH_RADIUS * x.to_radians();
+                let projected_y = EARTH_RADIUS * (::std::f64::consts::FRAC_PI_4 + y.to_radians() / 2.0).tan().ln();
+                Some((projected_x, projected_y))
+            },
+        }
+    }
+
+    /// Inverse of `project`, transforms projected coordinates back
+    /// into input coordinates (i.e. Web Mercator meters to WGS 84)
+    pub fn unproject(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        const EARTH_RADIUS: f64 = 6_378_137.0;
+
+        let (x, y) = match self {
+            Projection::Identity => (x, y),
+            Projection::WebMercator => {
+                let longitude = (x / EARTH_RADIUS).to_degrees();
+                let latitude = (2.0 * (y / EARTH_RADIUS).exp().atan() - ::std::f64::consts::FRAC_PI_2).to_degrees();
+                (longitude, latitude)
+            },
+        };
+
+        if x.is_finite() && y.is_finite() { Some((x, y)) } else { None }
+    }
+}
+
+/// Grid that can return the outline of its cells, used by
+/// `StreetIndex::to_geojson` to draw the cells of every road
+pub trait CellGeometry {
+    /// Returns the bounds of the cell as `(min_x, min_y, max_x, max_y)`,
+    /// with y growing upwards. `None` if the cell lies outside of the grid.
+    fn cell_bounds(&self, position: &GridPosition) -> Option<(f64, f64, f64, f64)>;
+
+    /// Returns the label of the cell as printed on the map
+    fn cell_label(&self, position: &GridPosition) -> String {
+        position.to_string()
+    }
+}
+
+/// Cells in millimeters from the top left corner of the page. Since y
+/// grows upwards, the rows have negative y coordinates (`A1` spans from
+/// `y = -cell_height` to `y = 0`).
+impl CellGeometry for Grid {
+    fn cell_bounds(&self, position: &GridPosition) -> Option<(f64, f64, f64, f64)> {
+        let column = position.column_index()? as f64;
+        let row = position.row.checked_sub(1)? as f64;
+        let (width, height) = (self.config.cell_width.0 as f64, self.config.cell_height.0 as f64);
+        Some((column * width, -(row + 1.0) * height, (column + 1.0) * width, -row * height))
+    }
+}
+
+/// Cells in input coordinates (i.e. longitude / latitude for
+/// a grid with the `WebMercator` projection)
+impl CellGeometry for GeoGrid {
+    fn cell_bounds(&self, position: &GridPosition) -> Option<(f64, f64, f64, f64)> {
+        let column = position.column_index()?;
+
+        match self.kind {
+            GridKind::Regular => { },
+            GridKind::Utm { zone, cell_size_m } => {
+                let (easting, northing) = (column as f64 * cell_size_m, position.row as f64 * cell_size_m);
+                return utm::utm_bounds(zone.unsigned_abs(), zone < 0, easting, northing, cell_size_m);
+            },
+            GridKind::Mgrs => {
+                let (zone, south) = utm::grid_zone(position.page.as_deref()?)?;
+                return utm::utm_bounds(zone, south, column as f64 * 1000.0, position.row as f64 * 1000.0, 1000.0);
+            },
+        }
+
+        let row = position.row.checked_sub(self.first_row)?;
+        if self.columns.is_some_and(|columns| column >= columns) || self.rows.is_some_and(|rows| row >= rows) {
+            return None;
+        }
+
+        let left = self.origin_x + column as f64 * self.cell_width;
+        let bottom = match self.row_direction {
+            RowDirection::TopDown => self.origin_y - (row + 1) as f64 * self.cell_height,
+            RowDirection::BottomUp => self.origin_y + row as f64 * self.cell_height,
+        };
+        let (min_x, min_y) = self.projection.unproject(left, bottom)?;
+        let (max_x, max_y) = self.projection.unproject(left + self.cell_width, bottom + self.cell_height)?;
+        Some((min_x, min_y, max_x, max_y))
+    }
+
+    fn cell_label(&self, position: &GridPosition) -> String {
+        self.label(position)
+    }
+}
 
 impl Grid {
 
@@ -131,7 +353,14 @@ impl Grid {
                 position: GridPosition {
                     column,
                     row,
-                }
+                    page: None,
+                },
+                category: None,
+                number_range: None,
+                weight: None,
+                secondary_name: None,
+                district: None,
+                attributes: BTreeMap::new(),
             });
         }
     }
@@ -140,105 +369,302 @@ impl Grid {
     pub fn street_names(&self) -> Vec<InputStreetValue> {
         self.fonts.clone()
     }
+
+    /// Number of columns of the grid (a partial column at the right edge counts)
+    pub fn columns(&self) -> usize {
+        (self.bbox.width.0 / self.config.cell_width.0).ceil() as usize
+    }
+
+    /// Number of rows of the grid (a partial row at the bottom edge counts),
+    /// the rows are numbered from 1
+    pub fn rows(&self) -> usize {
+        (self.bbox.height.0 / self.config.cell_height.0).ceil() as usize
+    }
 }
 
-/// Maps an index number to a value, necessary for creating the street index. i.e.:
-///
-/// ```no_run,ignore
-/// 0   -> A
-/// 25  -> Z
-/// 26  -> AA
-/// 27  -> AB
-/// ```
-///
-/// ... and so on
-pub fn number_to_alphabet_value(num: usize) -> String {
-    // Maximum character count range is 26 characters, A to Z
-    const ALPHABET_LEN: usize = 26;
-    // usize::MAX is "GKGWBYLWRXTLPP" with a length of 15 characters
-    const MAX_LEN: usize = 15;
-
-    // Initialize an array of 15 characters all to 0
-    let mut result = [0;MAX_LEN + 1];
-
-    // ** For this example, assume that we get the number 80000 **
-
-    // How many times does 26 fit in the target number?
-    // 80000 / 26 = 3076
-    let mut multiple_of_alphabet = num / ALPHABET_LEN;
-
-    // How many characters have we created in the loop?
-    let mut character_count = 0;
-
-
-    // If multiple_of_alphabet is 0, that means that `num` has decreased
-    // to a range between 0 and 26.
-    //
-    // `counter < MAX_LEN` is just so that the optimizer can
-    // unroll the loop without bounds-checking.
-    while multiple_of_alphabet != 0 && character_count < MAX_LEN {
-        // The "remainder" is our target character that we push into the array.
-        //
-        // For example, if `multiple_of_alphabet` is 3076, that means that
-        // num is in the range of (26 * 3076) to (26 * 3077).
-        //
-        // Therefore, we want to take the remainder of the last place, essentially:
-        // (3076 - 1) % 26 = 7 = "H"
-        let remainder = (multiple_of_alphabet - 1) % ALPHABET_LEN;
-        // Push the "H" into the array
-        result[(MAX_LEN - 1) - character_count] = u8_to_char(remainder as u8);
-        // We pushed one character, increase the character_count by 1
-        character_count += 1;
-        // Now we prepare the next character - currently, multiple_of_alphabet is 3075.
-        // Integer division always rounds down, which is useful property:
-        // Now our array is:
-        //
-        // [0, 0, 0, ... "H", 0]
-        // (3076 - 1) / 26 = 118
-        //
-        // The next iteration will be:
-        //
-        // (118 - 1) % 26 = 13 = "N"
-        // [0, 0, 0, ... "N", "H", 0]
-        // (118 - 1) / 26 = 4
-        //
-        // (4 - 1) % 26 = 4 = "D"
-        // [0, 0, 0, ... "D", "N", "H", 0]
-        // (4 - 1) / 26 = 0 = quit the loop
-        multiple_of_alphabet = (multiple_of_alphabet - 1) / ALPHABET_LEN;
+impl GeoGrid {
+
+    /// Creates an unbounded grid without projection, with the default labels
+    pub fn new(origin_x: f64, origin_y: f64, cell_width: f64, cell_height: f64) -> Self {
+        Self {
+            origin_x,
+            origin_y,
+            cell_width,
+            cell_height,
+            columns: None,
+            rows: None,
+            projection: Projection::default(),
+            labeler: GridLabeler::default(),
+            first_row: 1,
+            row_direction: RowDirection::default(),
+            kind: GridKind::default(),
+            min_length_in_cell: None,
+        }
+    }
+
+    /// Creates a UTM or MGRS grid (for `GridKind::Regular`, this is the
+    /// same as `GeoGrid::new(0.0, 0.0, 1.0, 1.0)`)
+    pub fn from_kind(kind: GridKind) -> Self {
+        Self { kind, .. Self::new(0.0, 0.0, 1.0, 1.0) }
+    }
+
+    /// Limits the grid to `columns` x `rows` cells, points outside of
+    /// the grid don't get a cell
+    pub fn with_size(self, columns: usize, rows: usize) -> Self {
+        Self { columns: Some(columns), rows: Some(rows), .. self }
+    }
+
+    pub fn with_projection(self, projection: Projection) -> Self {
+        Self { projection, .. self }
+    }
+
+    pub fn with_labeler(self, labeler: GridLabeler) -> Self {
+        Self { labeler, .. self }
+    }
+
+    /// Numbers the rows starting at `first_row` (i.e. 0) instead of 1
+    pub fn with_first_row(self, first_row: usize) -> Self {
+        Self { first_row, .. self }
+    }
+
+    /// Counts the rows from the bottom (`RowDirection::BottomUp`), the
+    /// origin is then the bottom left corner of the grid
+    pub fn with_row_direction(self, row_direction: RowDirection) -> Self {
+        Self { row_direction, .. self }
+    }
+
+    /// Only assigns cells to a line string that it runs through for at
+    /// least `min_length` (see `cells_for_linestring`)
+    pub fn with_min_length_in_cell(self, min_length: MinLength) -> Self {
+        Self { min_length_in_cell: Some(min_length), .. self }
+    }
+
+    /// Returns the grid position (such as "A2") of the cell that the point
+    /// lies in or `None` if the point lies outside of the grid (or can't
+    /// be projected).
+    pub fn cell_for(&self, x: f64, y: f64) -> Option<GridPosition> {
+        let (zone, column, row) = self.grid_coordinates(x, y)?;
+        self.cell_at(zone, column.floor(), row.floor())
+    }
+
+    /// Returns every cell that a line string (`[(x, y), ...]`) passes through,
+    /// in the order of the line, without duplicates. Cells that a segment only
+    /// crosses (without a vertex inside the cell) are included as well.
+    ///
+    /// With a `min_length_in_cell`, cells in which the line is shorter are
+    /// skipped. A line is never dropped completely: if no cell reaches the
+    /// minimum, the cell with the longest part of the line is returned.
+    pub fn cells_for_linestring(&self, points: &[(f64, f64)]) -> Vec<GridPosition> {
+
+        // segments longer than this (in cells) only register their end points,
+        // so that a single broken coordinate can't stall the traversal
+        const MAX_SEGMENT_CELLS: f64 = 1_000_000.0;
+
+        let coordinates = points.iter()
+            .filter_map(|&(x, y)| self.grid_coordinates(x, y))
+            .collect::<Vec<(i8, f64, f64)>>();
+
+        // length of a step of one cell in x / y direction
+        let (scale_x, scale_y) = match self.min_length_in_cell {
+            Some(MinLength::Absolute(_)) => self.cell_size(),
+            _ => (1.0, 1.0),
+        };
+
+        // cells in the order of the line and the length of the line in them
+        let mut cells = Vec::<(GridPosition, f64)>::new();
+        let mut indices = HashMap::new();
+        let mut add = |zone: i8, column: f64, row: f64, length: f64| {
+            if let Some(cell) = self.cell_at(zone, column, row) {
+                let index = *indices.entry(cell.clone()).or_insert_with(|| {
+                    cells.push((cell, 0.0));
+                    cells.len() - 1
+                });
+                cells[index].1 += length;
+            }
+        };
+
+        if let Some(&(zone, column, row)) = coordinates.first() {
+            add(zone, column.floor(), row.floor(), 0.0);
+        }
+
+        for segment in coordinates.windows(2) {
+            let ((start_zone, start_x, start_y), (zone, end_x, end_y)) = (segment[0], segment[1]);
+            let steps = (end_x.floor() - start_x.floor()).abs() + (end_y.floor() - start_y.floor()).abs();
+            // the cells of different UTM zones are not connected
+            if steps > MAX_SEGMENT_CELLS || start_zone != zone {
+                add(zone, end_x.floor(), end_y.floor(), 0.0);
+                continue;
+            }
+
+            let length = ((end_x - start_x) * scale_x).hypot((end_y - start_y) * scale_y);
+            let mut cell = (start_x.floor(), start_y.floor());
+            let mut entered = 0.0;
+            traverse_segment((start_x, start_y), (end_x, end_y), steps as usize, &mut |column, row, t| {
+                add(zone, cell.0, cell.1, (t - entered) * length);
+                cell = (column, row);
+                entered = t;
+            });
+            add(zone, cell.0, cell.1, (1.0 - entered) * length);
+        }
+
+        let min_length = match self.min_length_in_cell {
+            Some(MinLength::Absolute(min_length)) | Some(MinLength::Fraction(min_length)) => min_length,
+            None => return cells.into_iter().map(|(cell, _)| cell).collect(),
+        };
+        let longest = cells.iter().map(|(_, length)| *length).fold(0.0, f64::max);
+        cells.into_iter()
+            .filter(|(_, length)| *length >= min_length.min(longest))
+            .map(|(cell, _)| cell)
+            .collect()
+    }
+
+    /// Width and height of a cell in the projected coordinates
+    fn cell_size(&self) -> (f64, f64) {
+        match self.kind {
+            GridKind::Regular => (self.cell_width, self.cell_height),
+            GridKind::Utm { cell_size_m, .. } => (cell_size_m, cell_size_m),
+            GridKind::Mgrs => (1000.0, 1000.0),
+        }
     }
 
-    // Last character: 80.000 % 26 = 24 = "Y"
-    // [0, 0, 0, ... "D", "N", "H", "Y"]
-    result[MAX_LEN] = u8_to_char((num % ALPHABET_LEN) as u8);
-
-    // count is 3, since we pushed 3 characters
-    // zeroed_characters will be the offset from the start of the array
-    //
-    // so: 15 - 3 = 12, to take the characters from 12 to 16 (the array is [MAX_LEN + 1]).
-    //
-    // zeroed_characters is the number of characters that are still set to 0
-    // in the result array. We want to ignore all characters that are set to 0.
-    //
-    // Note that this is MAX_LEN, not MAX_LEN + 1
-    let zeroed_characters = MAX_LEN.saturating_sub(character_count);
-
-    // We take a slice from the zeroed_characters to the end of the array
-    // (i.e. MAX_LEN + 1). Note that we have to include the final character.
-    let slice = unsafe { result.get_unchecked(zeroed_characters..) };
-
-    // Cast the slice to a string, since we know that we only have ASCII
-    // characters in the range from A to Z, there won't be any UTF-8 problems
-    unsafe { ::std::str::from_utf8_unchecked(slice) }.to_string()
+    /// Projects the point and returns its UTM zone (negative on the southern
+    /// hemisphere, 0 for regular grids) and its (fractional) column and row index
+    fn grid_coordinates(&self, x: f64, y: f64) -> Option<(i8, f64, f64)> {
+        let (zone, column, row) = match self.kind {
+            GridKind::Regular => {
+                let (x, y) = self.projection.project(x, y)?;
+                let row = match self.row_direction {
+                    RowDirection::TopDown => (self.origin_y - y) / self.cell_height,
+                    RowDirection::BottomUp => (y - self.origin_y) / self.cell_height,
+                };
+                (0, (x - self.origin_x) / self.cell_width, row)
+            },
+            GridKind::Utm { zone, cell_size_m } => {
+                let (easting, northing) = utm::to_utm(x, y, zone.unsigned_abs(), zone < 0)?;
+                (zone, easting / cell_size_m, northing / cell_size_m)
+            },
+            GridKind::Mgrs => {
+                let zone = utm::utm_zone(x, y)?;
+                let (easting, northing) = utm::to_utm(x, y, zone, y < 0.0)?;
+                (if y < 0.0 { -(zone as i8) } else { zone as i8 }, easting / 1000.0, northing / 1000.0)
+            },
+        };
+
+        // is_finite() also rejects NaN values
+        if column.is_finite() && row.is_finite() {
+            Some((zone, column, row))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the cell at the (zero-based, already floored) column and row
+    /// index, `None` if the cell lies outside of the grid
+    fn cell_at(&self, zone: i8, column: f64, row: f64) -> Option<GridPosition> {
+        if column < 0.0 || row < 0.0 {
+            return None;
+        }
+
+        let (column, row) = (column as usize, row as usize);
+        match self.kind {
+            GridKind::Regular => { },
+            GridKind::Utm { .. } => return Some(GridPosition { column: number_to_alphabet_value(column), row, page: None }),
+            GridKind::Mgrs => return utm::mgrs_position(zone.unsigned_abs(), zone < 0, column, row),
+        }
+
+        if self.columns.is_some_and(|columns| column >= columns) || self.rows.is_some_and(|rows| row >= rows) {
+            return None;
+        }
+
+        // Rows start at 1 by default, see `Grid::insert_street`
+        Some(GridPosition {
+            column: number_to_alphabet_value(column),
+            row: row + self.first_row,
+            page: None,
+        })
+    }
+
+    /// Returns the label of the cell as printed on the map (see `labeler`
+    /// and `kind`)
+    pub fn label(&self, position: &GridPosition) -> String {
+        self.format_position(position)
+    }
 }
 
-// Transform from 0 to A, 1 to B, etc.
-#[inline(always)]
-fn u8_to_char(input: u8) -> u8 {
-    // use 'a' as u8 to create lowercase characters
-    'A' as u8 + input
+/// Formats the positions like `GeoGrid::label`
+impl PositionFormatter for GeoGrid {
+    fn format_cell(&self, position: &GridPosition) -> String {
+        let label = match self.kind {
+            GridKind::Regular => return self.labeler.format_cell(position),
+            GridKind::Utm { zone, cell_size_m } => utm::utm_label(position, zone.unsigned_abs(), cell_size_m),
+            GridKind::Mgrs => utm::mgrs_square(position),
+        };
+        label.unwrap_or_else(|| format!("{}{}", position.column, position.row))
+    }
+
+    /// MGRS positions are written with their grid zone designator,
+    /// i.e. `"32U MV 12 34"`
+    fn format_position(&self, position: &GridPosition) -> String {
+        match (&self.kind, &position.page) {
+            (GridKind::Mgrs, Some(page)) => format!("{} {}", page, self.format_cell(position)),
+            (_, Some(page)) => format!("{}:{}", page, self.format_cell(position)),
+            (_, None) => self.format_cell(position),
+        }
+    }
+}
+
+/// Walks from the cell of `start` to the cell of `end` (in grid coordinates),
+/// calling `visit` with every cell after the start cell that the segment
+/// passes through and the fraction of the segment at which it enters the
+/// cell (Amanatides & Woo). `steps` is the number of cell borders that the
+/// segment crosses.
+fn traverse_segment<F: FnMut(f64, f64, f64)>(start: (f64, f64), end: (f64, f64), steps: usize, visit: &mut F) {
+
+    let (mut column, mut row) = (start.0.floor(), start.1.floor());
+    let (end_column, end_row) = (end.0.floor(), end.1.floor());
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+
+    // distance (as a fraction of the segment) to the next vertical /
+    // horizontal cell border and between two borders
+    let next_border = |position: f64, cell: f64, delta: f64| {
+        if delta > 0.0 {
+            (cell + 1.0 - position) / delta
+        } else if delta < 0.0 {
+            (cell - position) / delta
+        } else {
+            f64::INFINITY
+        }
+    };
+    let mut t_max_x = next_border(start.0, column, dx);
+    let mut t_max_y = next_border(start.1, row, dy);
+    let t_delta_x = if dx != 0.0 { (1.0 / dx).abs() } else { f64::INFINITY };
+    let t_delta_y = if dy != 0.0 { (1.0 / dy).abs() } else { f64::INFINITY };
+
+    for _ in 0..steps {
+        // rounding errors must not step past the end cell
+        let step_x = if column == end_column {
+            false
+        } else if row == end_row {
+            true
+        } else {
+            t_max_x < t_max_y
+        };
+
+        let entered = if step_x { t_max_x } else { t_max_y };
+        if step_x {
+            column += dx.signum();
+            t_max_x += t_delta_x;
+        } else {
+            row += dy.signum();
+            t_max_y += t_delta_y;
+        }
+
+        visit(column, row, entered.clamp(0.0, 1.0));
+    }
 }
 
+pub use core_index::{number_to_alphabet_value, alphabet_value_to_number};
+
 #[test]
 fn test_number_to_alphabet_value() {
     assert_eq!(number_to_alphabet_value(0), String::from("A"));
@@ -249,6 +675,131 @@ fn test_number_to_alphabet_value() {
     assert_eq!(number_to_alphabet_value(225), String::from("HR"));
 }
 
+#[test]
+fn test_alphabet_value_to_number() {
+    for i in 0..1000 {
+        assert_eq!(alphabet_value_to_number(&number_to_alphabet_value(i)), Some(i));
+    }
+    assert_eq!(alphabet_value_to_number(""), None);
+    assert_eq!(alphabet_value_to_number("A1"), None);
+    assert_eq!(alphabet_value_to_number("a"), None);
+}
+
+#[test]
+fn test_geo_grid_cells() {
+    let grid = GeoGrid::new(10.0, 50.0, 0.5, 0.25);
+
+    assert_eq!(grid.cell_for(10.1, 49.9), Some(GridPosition { column: String::from("A"), row: 1, page: None }));
+    assert_eq!(grid.cell_for(11.2, 49.3), Some(GridPosition { column: String::from("C"), row: 3, page: None }));
+    assert_eq!(grid.cell_for(9.9, 49.9), None);
+    assert_eq!(grid.cell_for(10.1, 50.1), None);
+
+    let cells = grid.cells_for_linestring(&[(10.1, 49.9), (10.2, 49.8), (10.6, 49.8)]);
+    assert_eq!(cells, vec![
+        GridPosition { column: String::from("A"), row: 1, page: None },
+        GridPosition { column: String::from("B"), row: 1, page: None },
+    ]);
+}
+
+#[test]
+fn test_geo_grid_projection() {
+    use labeling::ColumnScheme;
+
+    let (x, y) = Projection::WebMercator.project(13.3, 52.6).unwrap();
+    assert!((x - 1_480_549.2).abs() < 0.1 && (y - 6_909_348.8).abs() < 0.1);
+    assert_eq!(Projection::WebMercator.project(0.0, 89.0), None);
+
+    let grid = GeoGrid::new(x, y, 1000.0, 1000.0)
+        .with_size(20, 20)
+        .with_projection(Projection::WebMercator)
+        .with_labeler(GridLabeler::new(ColumnScheme::Numeric, "/"));
+
+    let cell = grid.cell_for(13.4, 52.5).unwrap();
+    assert_eq!(cell, GridPosition { column: String::from("L"), row: 19, page: None });
+    assert_eq!(grid.label(&cell), "12/19");
+    assert_eq!(grid.cell_for(13.4, 52.3), None);
+
+    let (longitude, latitude) = Projection::WebMercator.unproject(x, y).unwrap();
+    assert!((longitude - 13.3).abs() < 1e-9 && (latitude - 52.6).abs() < 1e-9);
+    let (min_x, min_y, max_x, max_y) = grid.cell_bounds(&cell).unwrap();
+    assert!(min_x <= 13.4 && 13.4 < max_x && min_y < 52.5 && 52.5 <= max_y);
+}
+
+#[test]
+fn test_geo_grid_supercover() {
+    let grid = GeoGrid::new(0.0, 10.0, 1.0, 1.0).with_size(5, 5);
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+
+    // no vertex lies in B1 or B2, but the segment crosses both cells
+    assert_eq!(grid.cells_for_linestring(&[(0.5, 9.5), (2.5, 8.5)]), vec![cell("A", 1), cell("B", 1), cell("B", 2), cell("C", 2)]);
+    assert_eq!(grid.cells_for_linestring(&[(4.5, 5.5), (0.5, 5.5)]), vec![cell("E", 5), cell("D", 5), cell("C", 5), cell("B", 5), cell("A", 5)]);
+
+    // cells outside of the grid are skipped, the rest of the line is kept
+    assert_eq!(grid.cells_for_linestring(&[(3.5, 9.5), (6.5, 9.5), (6.5, 8.5), (4.5, 8.5)]), vec![cell("D", 1), cell("E", 1), cell("E", 2)]);
+}
+
+#[test]
+fn test_geo_grid_min_length_in_cell() {
+    let grid = GeoGrid::new(0.0, 1000.0, 100.0, 100.0).with_size(5, 5);
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let line = [(95.0, 950.0), (250.0, 950.0)];
+
+    assert_eq!(grid.cells_for_linestring(&line), vec![cell("A", 1), cell("B", 1), cell("C", 1)]);
+    let clipped = grid.clone().with_min_length_in_cell(MinLength::Absolute(10.0));
+    assert_eq!(clipped.cells_for_linestring(&line), vec![cell("B", 1), cell("C", 1)]);
+    let half = grid.clone().with_min_length_in_cell(MinLength::Fraction(0.6));
+    assert_eq!(half.cells_for_linestring(&line), vec![cell("B", 1)]);
+
+    // the lengths of all segments in a cell add up
+    assert_eq!(clipped.cells_for_linestring(&[(95.0, 950.0), (98.0, 990.0), (105.0, 990.0)]), vec![cell("A", 1)]);
+    // short streets keep their longest cell
+    assert_eq!(clipped.cells_for_linestring(&[(98.0, 950.0), (101.0, 950.0)]), vec![cell("A", 1)]);
+}
+
+#[test]
+fn test_geo_grid_row_direction() {
+    let grid = GeoGrid::new(0.0, 0.0, 1.0, 1.0)
+        .with_size(5, 5)
+        .with_first_row(0)
+        .with_row_direction(RowDirection::BottomUp);
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+
+    assert_eq!(grid.cell_for(0.5, 0.5), Some(cell("A", 0)));
+    assert_eq!(grid.cell_for(2.5, 4.5), Some(cell("C", 4)));
+    assert_eq!(grid.cell_for(0.5, -0.5), None);
+    assert_eq!(grid.cells_for_linestring(&[(0.5, 0.5), (0.5, 2.5)]), vec![cell("A", 0), cell("A", 1), cell("A", 2)]);
+    assert_eq!(grid.label(&cell("C", 4)), "C4");
+    assert_eq!(grid.cell_bounds(&cell("C", 4)), Some((2.0, 4.0, 3.0, 5.0)));
+    assert_eq!(grid.cell_bounds(&cell("C", 5)), None);
+}
+
+#[test]
+fn test_geo_grid_utm_mgrs() {
+    let cell = |column: usize, row: usize, page: Option<&str>| GridPosition { column: number_to_alphabet_value(column), row, page: page.map(String::from) };
+
+    // Eiffel Tower, 31U DQ 48251 11932
+    let mgrs = GeoGrid::from_kind(GridKind::Mgrs);
+    let position = mgrs.cell_for(2.294_5, 48.858_2).unwrap();
+    assert_eq!(position, cell(448, 5411, Some("31U")));
+    assert_eq!(mgrs.label(&position), "31U DQ 48 11");
+    assert_eq!(mgrs.cell_for(2.294_5, 85.0), None);
+
+    let (min_x, min_y, max_x, max_y) = mgrs.cell_bounds(&position).unwrap();
+    assert!(min_x < 2.294_5 && 2.294_5 < max_x && min_y < 48.858_2 && 48.858_2 < max_y);
+
+    // lines crossing into the next zone only register the cells of their vertices
+    let cells = mgrs.cells_for_linestring(&[(2.294_5, 48.858_2), (2.308, 48.858_2), (6.01, 48.858_2)]);
+    assert_eq!(cells.len(), 3);
+    assert_eq!(cells[1], cell(449, 5411, Some("31U")));
+    assert_eq!(cells[2].page.as_deref(), Some("32U"));
+
+    let utm = GeoGrid::from_kind(GridKind::Utm { zone: 31, cell_size_m: 500.0 });
+    let position = utm.cell_for(2.294_5, 48.858_2).unwrap();
+    assert_eq!(position, cell(896, 10823, None));
+    assert_eq!(utm.label(&position), "31 448000 5411500");
+    assert_eq!(GeoGrid::from_kind(GridKind::Utm { zone: -31, cell_size_m: 500.0 }).cell_for(2.294_5, 48.858_2).unwrap().row, 30823);
+}
+
 #[cfg(all(test, feature = "nightly"))]
 mod tests {
     use super::*;
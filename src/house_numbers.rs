@@ -0,0 +1,170 @@
+//! House number ranges as sub-entries of a street, for large-scale city maps
+//! that index long streets per cell: `"Hauptstraße 1–99: B4, 100–200: B5"`
+//!
+//! Every `InputStreetValue` can carry a `number_range`. The ranges are
+//! deduplicated together with the street and processed like a road of their
+//! own, so `process()` returns the cells of every range in
+//! `StreetIndex::house_numbers`:
+//!
+//! ```no_run,ignore
+//! let street = InputStreetValue::new("Hauptstraße", "B4".parse()?)
+//!     .with_number_range(NumberRange::new(1, 99));
+//! let index = DeduplicatedRoads::from_streets(&streets).process();
+//! index.to_csv_with_house_numbers("\t")
+//! // "Hauptstraße\t\tB4-B5\r\nHauptstraße\t1–99\tB4\r\nHauptstraße\t100–200\tB5"
+//! ```
+
+use std::{fmt, num::ParseIntError, str::FromStr, borrow::Cow, collections::{BTreeMap, BTreeSet, HashSet}};
+use roads2csv::{StreetIndex, StreetName, GridPosition, FinalizedGridPositon, ProcessOptions, CsvQuoting, process_road};
+use cross_reference::Interleave;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Range of house numbers, ordered by its first number
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NumberRange {
+    pub from: u32,
+    pub to: u32,
+}
+
+impl NumberRange {
+    /// Range between `from` and `to` (in any order)
+    pub fn new(from: u32, to: u32) -> Self {
+        Self { from: from.min(to), to: from.max(to) }
+    }
+}
+
+/// Keeps `from <= to` like `NumberRange::new`
+#[cfg(feature = "arbitrary")]
+impl<'a> ::arbitrary::Arbitrary<'a> for NumberRange {
+    fn arbitrary(u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {
+        Ok(Self::new(u.arbitrary()?, u.arbitrary()?))
+    }
+}
+
+/// `"1–99"` (with an en dash), or `"7"` for a single number
+impl fmt::Display for NumberRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.from == self.to {
+            write!(f, "{}", self.from)
+        } else {
+            write!(f, "{}\u{2013}{}", self.from, self.to)
+        }
+    }
+}
+
+/// Parses `"1–99"`, `"1-99"` or `"7"`
+impl FromStr for NumberRange {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(['-', '\u{2013}']) {
+            Some((from, to)) => Ok(NumberRange::new(from.trim().parse()?, to.trim().parse()?)),
+            None => s.trim().parse().map(|number| NumberRange::new(number, number)),
+        }
+    }
+}
+
+/// Positions of one house number range of a street
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HouseNumbers {
+    pub range: NumberRange,
+    /// Processed positions of the range, or every cell of the range if the
+    /// cells could not be processed (see `DeduplicatedRoads::process`)
+    pub positions: Vec<FinalizedGridPositon>,
+}
+
+/// Processes the cells of every house number range of the street `name`
+pub(crate) fn process_house_numbers(name: &StreetName, ranges: &BTreeMap<NumberRange, BTreeSet<GridPosition>>, options: &ProcessOptions) -> Vec<HouseNumbers> {
+    ranges.iter().map(|(range, cells)| {
+        let positions = match process_road(name, None, cells, options) {
+            Some(Ok(roads)) => roads.into_iter().map(|road| road.position).collect(),
+            _ => cells.iter().cloned().map(FinalizedGridPositon::SingleRect).collect(),
+        };
+        HouseNumbers { range: *range, positions }
+    }).collect()
+}
+
+impl StreetIndex {
+
+    /// Same as `to_csv()`, but with three fields per line: name, house
+    /// numbers and position. The sub-entries of a street (`"Hauptstraße<delimiter>1–99<delimiter>B4"`)
+    /// follow the last line of the street, street lines and cross-references
+    /// have an empty house number field. The positions of an unprocessed road
+    /// or a range are joined with `"; "` into one field.
+    pub fn to_csv_with_house_numbers(&self, delimiter: &str) -> String {
+        let quoting = CsvQuoting::default();
+        let record = |name: &str, numbers: String, position: String| {
+            quoting.record(vec![Cow::Borrowed(name), Cow::Owned(numbers), Cow::Owned(position)], delimiter)
+        };
+        let sub_entries = |name: &StreetName| self.house_numbers.get(name).into_iter().flatten().map(|entry| {
+            let positions = entry.positions.iter().map(|position| position.to_string()).collect::<Vec<_>>().join("; ");
+            record(&name.0, entry.range.to_string(), positions)
+        }).collect::<Vec<String>>();
+
+        let processed = &self.processed.processed;
+        let roads = processed.iter().enumerate().map(|(i, road)| {
+            let mut lines = vec![record(&road.name.0, String::new(), road.position.to_string())];
+            if processed.get(i + 1).is_none_or(|next| next.name != road.name) {
+                lines.extend(sub_entries(&road.name));
+            }
+            (&road.name, lines)
+        });
+        let mut lines = Interleave::new(roads, &self.cross_references, |reference| {
+            vec![record(&reference.from.0, String::new(), reference.see())]
+        }).flatten().collect::<Vec<String>>();
+
+        // sub-entries of streets that are only in the unprocessed roads
+        let mut written = processed.iter().map(|road| &road.name).collect::<HashSet<_>>();
+        for road in &self.unprocessed.unprocessed {
+            let mut positions = road.positions.clone();
+            positions.sort();
+            let positions = positions.iter().map(|position| position.to_string()).collect::<Vec<_>>().join("; ");
+            lines.push(record(&road.name.0, String::new(), positions));
+            if written.insert(&road.name) {
+                lines.extend(sub_entries(&road.name));
+            }
+        }
+
+        lines.join("\r\n")
+    }
+}
+
+#[test]
+fn test_house_numbers() {
+    use roads2csv::{DeduplicatedRoads, InputStreetValue};
+
+    let street = |name: &str, position: &str, numbers: &str| {
+        InputStreetValue::new(name, position.parse().unwrap()).with_number_range(numbers.parse().unwrap())
+    };
+    let streets = vec![
+        street("Hauptstraße", "B4", "1-99"),
+        street("Hauptstraße", "B5", "100\u{2013}200"),
+        street("Hauptstraße", "B4", "100-200"),
+        InputStreetValue::new("Mühlweg", "C1".parse().unwrap()),
+        street("Ringstraße", "A1", "1-9"),
+        street("Ringstraße", "A2", "1-9"),
+        street("Ringstraße", "H2", "10-20"),
+    ];
+
+    let deduplicated = DeduplicatedRoads::from_streets(&streets);
+    assert_eq!(deduplicated.number_ranges.len(), 2);
+    let index = deduplicated.process();
+    assert_eq!(index.house_numbers[&StreetName(String::from("Hauptstraße"))].len(), 2);
+
+    assert_eq!(index.to_csv_with_house_numbers("\t"), [
+        "Hauptstraße\t\tB4-B5",
+        "Hauptstraße\t1\u{2013}99\tB4",
+        "Hauptstraße\t100\u{2013}200\tB4-B5",
+        "Mühlweg\t\tC1",
+        "Ringstraße\t\tA1; A2; H2",
+        "Ringstraße\t1\u{2013}9\tA1-A2",
+        "Ringstraße\t10\u{2013}20\tH2",
+    ].join("\r\n"));
+
+    assert_eq!(NumberRange::new(9, 1).to_string(), "1\u{2013}9");
+    assert_eq!("7".parse::<NumberRange>(), Ok(NumberRange::new(7, 7)));
+    assert!("1-".parse::<NumberRange>().is_err());
+}
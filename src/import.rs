@@ -0,0 +1,134 @@
+//! Common streaming interface of the input sources, so that a `Pipeline`
+//! can read streets from CSV, OpenStreetMap or any other source (i.e. a
+//! proprietary database) without knowing the format
+//!
+//! ```no_run,ignore
+//! let importer: Box<dyn Importer> = match format {
+//!     "csv" => Box::new(CsvImporter::new(BufReader::new(file), "\t")),
+//!     "osm" => Box::new(OsmRoadReader::new(BufReader::new(file), grid)),
+//!     "geojson" => Box::new(StreetsImporter::new(from_geojson(&text, &grid)?)),
+//!     _ => return Err(...),
+//! };
+//! let index = pipeline.run_importer(importer)?;
+//! ```
+//!
+//! Readers that return all streets at once (`from_geojson`, `from_postgis`,
+//! `from_kml`, ...) can be wrapped in a `StreetsImporter`.
+
+use std::{io, collections::VecDeque};
+use roads2csv::{InputStreetValue, ParseError};
+use error::Result;
+#[cfg(feature = "osm")]
+use osm2roads::OsmRoadReader;
+
+/// Source of input streets, returns one street per call to `read()` and
+/// `None` once the source is exhausted
+pub trait Importer {
+    fn read(&mut self) -> Result<Option<InputStreetValue>>;
+}
+
+impl<T: Importer + ?Sized> Importer for Box<T> {
+    fn read(&mut self) -> Result<Option<InputStreetValue>> {
+        (**self).read()
+    }
+}
+
+impl<T: Importer + ?Sized> Importer for &mut T {
+    fn read(&mut self) -> Result<Option<InputStreetValue>> {
+        (**self).read()
+    }
+}
+
+/// Reads a CSV (in the format of `DeduplicatedRoads::from_csv`) line by
+/// line. Empty lines are skipped, parse errors contain the line number.
+#[derive(Debug)]
+pub struct CsvImporter<R: io::BufRead> {
+    lines: io::Lines<R>,
+    delimiter: String,
+    line_number: usize,
+    /// Streets of the last line that weren't returned yet
+    pending: VecDeque<InputStreetValue>,
+}
+
+impl<R: io::BufRead> CsvImporter<R> {
+    pub fn new(reader: R, delimiter: &str) -> Self {
+        Self {
+            lines: reader.lines(),
+            delimiter: String::from(delimiter),
+            line_number: 0,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: io::BufRead> Importer for CsvImporter<R> {
+    fn read(&mut self) -> Result<Option<InputStreetValue>> {
+        loop {
+            if let Some(street) = self.pending.pop_front() {
+                return Ok(Some(street));
+            }
+
+            let line = match self.lines.next() {
+                Some(line) => line?,
+                None => return Ok(None),
+            };
+            self.line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed = InputStreetValue::parse_line(&line, &self.delimiter)
+                .map_err(|e| ParseError { line: Some(self.line_number), kind: e.kind })?;
+            self.pending.extend(parsed);
+        }
+    }
+}
+
+/// Returns streets that were already read, i.e. by `from_geojson`
+#[derive(Debug, Clone)]
+pub struct StreetsImporter<I: Iterator<Item = InputStreetValue>> {
+    streets: I,
+}
+
+impl<I: Iterator<Item = InputStreetValue>> StreetsImporter<I> {
+    pub fn new<T: IntoIterator<IntoIter = I, Item = InputStreetValue>>(streets: T) -> Self {
+        Self { streets: streets.into_iter() }
+    }
+}
+
+impl<I: Iterator<Item = InputStreetValue>> Importer for StreetsImporter<I> {
+    fn read(&mut self) -> Result<Option<InputStreetValue>> {
+        Ok(self.streets.next())
+    }
+}
+
+#[cfg(feature = "osm")]
+impl<R: io::Read> Importer for OsmRoadReader<R> {
+    fn read(&mut self) -> Result<Option<InputStreetValue>> {
+        Ok(self.next().transpose()?)
+    }
+}
+
+#[test]
+fn test_importers() {
+    use error::Error;
+
+    let csv = "Abbey Road\tA1-A2\r\n\r\nMill Lane\tB2\tC3\r\n";
+    let mut importer = CsvImporter::new(csv.as_bytes(), "\t");
+    let mut names = Vec::new();
+    while let Some(street) = importer.read().unwrap() {
+        names.push(street.street_name.0);
+    }
+    assert_eq!(names, ["Abbey Road", "Abbey Road", "Mill Lane", "Mill Lane"]);
+    assert!(importer.read().unwrap().is_none());
+
+    let mut importer: Box<dyn Importer> = Box::new(CsvImporter::new("Abbey Road\tA1\n\nMill Lane\t9Z".as_bytes(), "\t"));
+    assert!(importer.read().unwrap().is_some());
+    match importer.read() {
+        Err(Error::Parse(e)) => assert_eq!(e.line, Some(3)),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+
+    let mut importer = StreetsImporter::new(vec![InputStreetValue::new("Abbey Road", "A1".parse().unwrap())]);
+    assert_eq!(importer.read().unwrap().map(|street| street.street_name.0), Some(String::from("Abbey Road")));
+    assert!(importer.read().unwrap().is_none());
+}
@@ -0,0 +1,67 @@
+//! Reverse index: which streets cross a grid cell
+//!
+//! Used for the legends of single pages / cells and to find cells that are
+//! overloaded with labels:
+//!
+//! ```no_run,ignore
+//! for (cell, streets) in deduplicated.invert() {
+//!     if streets.len() > 20 {
+//!         eprintln!("{} has {} streets", cell, streets.len());
+//!     }
+//! }
+//! ```
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use roads2csv::{DeduplicatedRoads, StreetName, GridPosition, CsvQuoting};
+use roads2json::json_string;
+
+impl DeduplicatedRoads {
+
+    /// Returns the streets of every cell (roads of all categories),
+    /// ordered by the cell (page, column, row)
+    pub fn invert(&self) -> BTreeMap<GridPosition, BTreeSet<StreetName>> {
+        let mut cells: BTreeMap<GridPosition, BTreeSet<StreetName>> = BTreeMap::new();
+        for (_, name, positions) in self.all_roads() {
+            for position in positions {
+                cells.entry(position.clone()).or_default().insert(name.clone());
+            }
+        }
+        cells
+    }
+
+    /// Exports the reverse index as `cell<delimiter>street<delimiter>street...`
+    /// lines, fields are quoted if necessary (see `CsvQuoting::Necessary`)
+    pub fn to_inverted_csv(&self, delimiter: &str) -> String {
+        self.invert().iter().map(|(cell, streets)| {
+            let streets = streets.iter().map(|street| Cow::Borrowed(street.0.as_str()));
+            CsvQuoting::default().record(::std::iter::once(Cow::Owned(cell.to_string())).chain(streets), delimiter)
+        })
+        .collect::<Vec<String>>()
+        .join("\r\n")
+    }
+
+    /// Exports the reverse index as a JSON object, i.e.
+    /// `{ "A1": ["Canterbury Road", "Mayer Street"], "A2": [...] }`
+    pub fn to_inverted_json(&self) -> String {
+        let cells = self.invert().iter().map(|(cell, streets)| {
+            let streets = streets.iter().map(|street| json_string(&street.0)).collect::<Vec<String>>();
+            format!("{}:[{}]", json_string(&cell.to_string()), streets.join(","))
+        })
+        .collect::<Vec<String>>();
+        format!("{{{}}}", cells.join(","))
+    }
+}
+
+#[test]
+fn test_invert() {
+    let csv = "Mayer Street\tA1\r\nCanterbury Road\tA1-A2\r\nValley View Road\tB1\tA2";
+    let deduplicated = DeduplicatedRoads::from_csv(csv, "\t").unwrap();
+
+    let inverted = deduplicated.invert();
+    assert_eq!(inverted.len(), 3);
+    assert_eq!(inverted[&"A1".parse::<GridPosition>().unwrap()].iter().map(|s| s.0.as_str()).collect::<Vec<_>>(), vec!["Canterbury Road", "Mayer Street"]);
+
+    assert_eq!(deduplicated.to_inverted_csv("\t"), "A1\tCanterbury Road\tMayer Street\r\nA2\tCanterbury Road\tValley View Road\r\nB1\tValley View Road");
+    assert_eq!(deduplicated.to_inverted_json(), r#"{"A1":["Canterbury Road","Mayer Street"],"A2":["Canterbury Road","Valley View Road"],"B1":["Valley View Road"]}"#);
+}
@@ -0,0 +1,220 @@
+//! Reads named `LineString` placemarks from a KML document (or a zipped
+//! `.kmz` file) and assigns grid cells to them
+//!
+//! Only `<Placemark>`s with a `<name>` and `<LineString>` geometries (also
+//! inside of a `<MultiGeometry>`) are read, all other placemarks, styles
+//! and folders are ignored. The coordinates are `lon,lat[,alt]` tuples.
+
+use std::{fmt, error::Error};
+use std::io::{self, Read, Seek};
+use quick_xml::{Reader, events::Event};
+use zip::{ZipArchive, result::ZipError};
+use roads2csv::InputStreetValue;
+use gridconfig::GeoGrid;
+
+/// Error that can happen while reading a KML or KMZ file
+#[derive(Debug)]
+pub enum KmlError {
+    /// Reading the file failed
+    Io(io::Error),
+    /// The KML is not well-formed XML
+    Xml(quick_xml::Error),
+    /// The `<coordinates>` of the placemark at the given index (counted
+    /// from the start of the document) are malformed
+    InvalidCoordinates { placemark: usize },
+    /// The `.kmz` file is not a valid zip archive
+    Kmz(ZipError),
+    /// The `.kmz` file contains no `.kml` document
+    MissingKml,
+}
+
+impl fmt::Display for KmlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::KmlError::*;
+        match self {
+            Io(e) => write!(f, "I/O error: {}", e),
+            Xml(e) => write!(f, "invalid KML: {}", e),
+            InvalidCoordinates { placemark } => write!(f, "placemark {} has invalid coordinates", placemark),
+            Kmz(e) => write!(f, "invalid .kmz file: {}", e),
+            MissingKml => write!(f, "the .kmz file contains no .kml document"),
+        }
+    }
+}
+
+impl Error for KmlError { }
+
+impl From<io::Error> for KmlError {
+    fn from(e: io::Error) -> Self {
+        KmlError::Io(e)
+    }
+}
+
+impl From<quick_xml::Error> for KmlError {
+    fn from(e: quick_xml::Error) -> Self {
+        KmlError::Xml(e)
+    }
+}
+
+impl From<ZipError> for KmlError {
+    fn from(e: ZipError) -> Self {
+        KmlError::Kmz(e)
+    }
+}
+
+/// Reads all placemarks with a name and a `LineString` geometry and returns
+/// one `InputStreetValue` for every cell of the `grid` that a road touches.
+///
+/// Placemarks without a name and placemarks with other geometry types
+/// (i.e. points or polygons) are ignored.
+pub fn from_kml(kml: &str, grid: &GeoGrid) -> Result<Vec<InputStreetValue>, KmlError> {
+
+    let mut reader = Reader::from_str(kml);
+    reader.config_mut().trim_text(true);
+
+    // local names of the currently open elements
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut text = String::new();
+    let mut placemark_index = 0;
+    let mut name: Option<String> = None;
+    let mut lines: Vec<Vec<(f64, f64)>> = Vec::new();
+    let mut streets = Vec::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) => {
+                let local_name = element.local_name().as_ref().to_vec();
+                if local_name == b"Placemark" {
+                    name = None;
+                    lines.clear();
+                }
+                path.push(local_name);
+                text.clear();
+            },
+            Event::Text(content) => {
+                text.push_str(&content.unescape()?);
+            },
+            Event::CData(content) => {
+                text.push_str(&String::from_utf8_lossy(&content));
+            },
+            Event::End(_) => {
+                let element = path.pop().unwrap_or_default();
+                let parent = path.last().map(|parent| parent.as_slice());
+
+                match (element.as_slice(), parent) {
+                    (b"name", Some(b"Placemark")) => {
+                        name = Some(text.trim().to_string());
+                    },
+                    (b"coordinates", Some(b"LineString")) => {
+                        let line = parse_coordinates(&text).ok_or(KmlError::InvalidCoordinates { placemark: placemark_index })?;
+                        lines.push(line);
+                    },
+                    (b"Placemark", _) => {
+                        if let Some(name) = name.take().filter(|name| !name.is_empty()) {
+                            let mut cells = Vec::new();
+                            for line in &lines {
+                                for cell in grid.cells_for_linestring(line) {
+                                    if !cells.contains(&cell) {
+                                        cells.push(cell);
+                                    }
+                                }
+                            }
+                            streets.extend(cells.into_iter().map(|position| InputStreetValue::new(&name, position)));
+                        }
+                        placemark_index += 1;
+                    },
+                    _ => { },
+                }
+                text.clear();
+            },
+            Event::Eof => break,
+            _ => { },
+        }
+    }
+
+    Ok(streets)
+}
+
+/// Same as `from_kml`, but reads the KML document from a `.kmz` archive
+/// (`doc.kml`, or the first `.kml` file if there is no `doc.kml`)
+pub fn from_kmz<R: Read + Seek>(kmz: R, grid: &GeoGrid) -> Result<Vec<InputStreetValue>, KmlError> {
+    let mut archive = ZipArchive::new(kmz)?;
+
+    let file_name = match archive.file_names().find(|name| *name == "doc.kml") {
+        Some(name) => name.to_string(),
+        None => archive.file_names()
+            .filter(|name| name.to_lowercase().ends_with(".kml"))
+            .min()
+            .ok_or(KmlError::MissingKml)?
+            .to_string(),
+    };
+
+    let mut kml = String::new();
+    archive.by_name(&file_name)?.read_to_string(&mut kml)?;
+    from_kml(&kml, grid)
+}
+
+/// Parses `"lon,lat[,alt] lon,lat[,alt] ..."` (the altitude is ignored)
+fn parse_coordinates(coordinates: &str) -> Option<Vec<(f64, f64)>> {
+    coordinates.split_whitespace().map(|tuple| {
+        let mut ordinates = tuple.split(',');
+        let x = ordinates.next()?.parse::<f64>().ok()?;
+        let y = ordinates.next()?.parse::<f64>().ok()?;
+        Some((x, y))
+    }).collect()
+}
+
+#[test]
+fn test_from_kml() {
+    use std::io::{Cursor, Write};
+    use roads2csv::GridPosition;
+
+    let kml = r#"<?xml version="1.0" encoding="UTF-8"?>
+    <kml xmlns="http://www.opengis.net/kml/2.2">
+      <Document>
+        <name>Survey</name>
+        <Placemark>
+          <name>Canterbury Road</name>
+          <LineString><coordinates>0.5,9.5,12 1.5,9.5,12
+            1.6,9.4,12</coordinates></LineString>
+        </Placemark>
+        <Folder>
+          <Placemark>
+            <name><![CDATA[Mayer & Sons Street]]></name>
+            <MultiGeometry>
+              <LineString><coordinates>0.5,8.5</coordinates></LineString>
+              <LineString><coordinates>3.5,8.5</coordinates></LineString>
+            </MultiGeometry>
+          </Placemark>
+        </Folder>
+        <Placemark>
+          <LineString><coordinates>0.5,9.5 1.5,9.5</coordinates></LineString>
+        </Placemark>
+        <Placemark>
+          <name>Town Hall</name>
+          <Point><coordinates>0.5,9.5</coordinates></Point>
+        </Placemark>
+      </Document>
+    </kml>"#;
+
+    let grid = GeoGrid::new(0.0, 10.0, 1.0, 1.0);
+    let streets = from_kml(kml, &grid).unwrap();
+
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let cells = streets.iter().map(|s| (s.street_name.0.as_str(), s.position.clone())).collect::<Vec<_>>();
+    assert_eq!(cells, vec![
+        ("Canterbury Road", cell("A", 1)),
+        ("Canterbury Road", cell("B", 1)),
+        ("Mayer & Sons Street", cell("A", 2)),
+        ("Mayer & Sons Street", cell("D", 2)),
+    ]);
+
+    let mut kmz = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    kmz.start_file("doc.kml", zip::write::SimpleFileOptions::default()).unwrap();
+    kmz.write_all(kml.as_bytes()).unwrap();
+    let kmz = kmz.finish().unwrap();
+    assert_eq!(from_kmz(kmz, &grid).unwrap(), streets);
+
+    let invalid = "<kml><Placemark><name>A</name><LineString><coordinates>x,1</coordinates></LineString></Placemark></kml>";
+    assert_eq!(from_kml(invalid, &grid).unwrap_err().to_string(), "placemark 0 has invalid coordinates");
+    assert!(from_kml("<kml><Placemark></kml>", &grid).is_err());
+}
@@ -0,0 +1,497 @@
+//! Configurable labels for grid columns and rows, i.e. `AA9`, `1/4`, `IV-5`
+//! or `3F` (numbered columns, lettered rows)
+//!
+//! Internally, `GridPosition::column` always stores the column in the
+//! default `A, B, ..., Z, AA, ...` form and `GridPosition::row` the row number
+//! (as created by the `Grid`), so that processing and ordering don't depend on
+//! the labeling scheme. A `GridLabeler` only translates these positions to
+//! and from the labels printed on the map.
+
+use std::cmp::Ordering;
+use roads2csv::{GridPosition, FinalizedGridPositon, split_page};
+use gridconfig::{number_to_alphabet_value, alphabet_value_to_number};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// How the columns (or the rows, see `GridLabeler::rows`) of a grid are labeled
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ColumnScheme {
+    /// `A, B, ..., Z, AA, AB, ...` (the default)
+    #[default]
+    AlphaUpper,
+    /// `a, b, ..., z, aa, ab, ...`
+    AlphaLower,
+    /// `1, 2, 3, ...`
+    Numeric,
+    /// `I, II, III, IV, ...`
+    Roman,
+    /// User-supplied labels, one label per column
+    Custom(Vec<String>),
+    /// Like `AlphaUpper` (or `AlphaLower`), but without the `excluded`
+    /// letters, i.e. `A, ..., H, J, ..., N, P, ..., Z, AA, AB, ...` for
+    /// `"IO"`. The excluded letters are case-insensitive.
+    AlphaExcluding { excluded: String, lowercase: bool },
+}
+
+impl ColumnScheme {
+
+    /// Upper case letters without `I` and `O`, which are easily confused
+    /// with `1` and `0` on printed maps
+    pub fn alpha_without_io() -> Self {
+        ColumnScheme::AlphaExcluding { excluded: String::from("IO"), lowercase: false }
+    }
+
+    /// Returns the label of the column at the (zero-based) `index`,
+    /// `None` if a `Custom` scheme has no label for this column
+    pub fn label(&self, index: usize) -> Option<String> {
+        use self::ColumnScheme::*;
+        match self {
+            AlphaUpper => Some(number_to_alphabet_value(index)),
+            AlphaLower => Some(number_to_alphabet_value(index).to_lowercase()),
+            Numeric => Some((index + 1).to_string()),
+            Roman => Some(to_roman(index + 1)),
+            Custom(labels) => labels.get(index).cloned(),
+            AlphaExcluding { excluded, lowercase } => {
+                let alphabet = reduced_alphabet(excluded, *lowercase);
+                if alphabet.is_empty() {
+                    return None;
+                }
+                // bijective base-n, same as `number_to_alphabet_value`
+                let mut label = Vec::new();
+                let mut remaining = index + 1;
+                while remaining > 0 {
+                    remaining -= 1;
+                    label.push(alphabet[remaining % alphabet.len()]);
+                    remaining /= alphabet.len();
+                }
+                Some(label.into_iter().rev().collect())
+            },
+        }
+    }
+
+    /// Returns the (zero-based) index of the column with the `label`,
+    /// `None` if the label is not valid for this scheme
+    pub fn index_of(&self, label: &str) -> Option<usize> {
+        use self::ColumnScheme::*;
+        match self {
+            AlphaUpper => alphabet_value_to_number(label),
+            AlphaLower if label.bytes().all(|c| c.is_ascii_lowercase()) => alphabet_value_to_number(&label.to_uppercase()),
+            AlphaLower => None,
+            Numeric if label.bytes().all(|c| c.is_ascii_digit()) && !label.starts_with('0') => label.parse::<usize>().ok()?.checked_sub(1),
+            Numeric => None,
+            Roman => from_roman(label)?.checked_sub(1),
+            Custom(labels) => labels.iter().position(|l| l == label),
+            AlphaExcluding { excluded, lowercase } => {
+                let alphabet = reduced_alphabet(excluded, *lowercase);
+                let mut value = 0_usize;
+                for c in label.chars() {
+                    let digit = alphabet.iter().position(|letter| *letter == c)?;
+                    value = value.checked_mul(alphabet.len())?.checked_add(digit + 1)?;
+                }
+                value.checked_sub(1)
+            },
+        }
+    }
+}
+
+/// Letters of `ColumnScheme::AlphaExcluding`, in alphabetical order
+fn reduced_alphabet(excluded: &str, lowercase: bool) -> Vec<char> {
+    let letters = if lowercase { 'a'..='z' } else { 'A'..='Z' };
+    letters.filter(|letter| !excluded.chars().any(|c| c.eq_ignore_ascii_case(letter))).collect()
+}
+
+/// Translates `GridPosition`s to the labels printed on the map and back.
+///
+/// ```no_run,ignore
+/// let labeler = GridLabeler::new(ColumnScheme::Numeric, "/");
+/// labeler.format(&GridPosition::parse("D7")?) // "4/7"
+/// labeler.parse("4/7") // GridPosition::parse("D7").ok()
+/// // ranges are formatted with `PositionFormatter::format_finalized`, i.e. "4/7-5/7"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GridLabeler {
+    pub columns: ColumnScheme,
+    /// Labels of the rows, `ColumnScheme::Numeric` (the default) prints the
+    /// row number as it is
+    #[cfg_attr(feature = "serde", serde(default = "numeric_rows"))]
+    pub rows: ColumnScheme,
+    /// Text between the column and the row label, i.e. `"/"` for `1/4`.
+    /// Should not be empty if both labels can start with the same
+    /// characters (i.e. numeric columns and rows, or letters on both axes).
+    pub separator: String,
+}
+
+#[cfg(feature = "serde")]
+fn numeric_rows() -> ColumnScheme {
+    ColumnScheme::Numeric
+}
+
+impl Default for GridLabeler {
+    /// `A1, B1, ..., AA1, ...`
+    fn default() -> Self {
+        Self::new(ColumnScheme::default(), "")
+    }
+}
+
+impl GridLabeler {
+
+    /// Labeler with numeric rows, see `with_rows`
+    pub fn new(columns: ColumnScheme, separator: &str) -> Self {
+        Self {
+            columns,
+            rows: ColumnScheme::Numeric,
+            separator: separator.to_string(),
+        }
+    }
+
+    /// Labels the rows with the `rows` scheme, i.e. `ColumnScheme::AlphaUpper`
+    /// for `"3F"` (with `ColumnScheme::Numeric` columns)
+    pub fn with_rows(self, rows: ColumnScheme) -> Self {
+        Self { rows, .. self }
+    }
+
+    /// Returns the position of the cell at the (zero-based) column
+    /// `column` and the row `row` (rows start at 1)
+    pub fn position(&self, column: usize, row: usize) -> GridPosition {
+        GridPosition {
+            column: number_to_alphabet_value(column),
+            row,
+            page: None,
+        }
+    }
+
+    /// Returns the label of the position, i.e. `"4/7"`. Falls back to the
+    /// default label (`"D7"`) if the column has no label in this scheme.
+    pub fn format(&self, position: &GridPosition) -> String {
+        match &position.page {
+            Some(page) => format!("{}:{}", page, self.format_cell(position)),
+            None => self.format_cell(position),
+        }
+    }
+
+    /// Formats the position without its page
+    pub(crate) fn format_cell(&self, position: &GridPosition) -> String {
+        let column = position.column_index().and_then(|index| self.columns.label(index));
+        match (column, self.row_label(position.row)) {
+            (Some(column), Some(row)) => format!("{}{}{}", column, self.separator, row),
+            _ => format!("{}{}", position.column, position.row),
+        }
+    }
+
+    /// Label of the row number `row` (rows start at 1)
+    fn row_label(&self, row: usize) -> Option<String> {
+        match &self.rows {
+            ColumnScheme::Numeric => Some(row.to_string()),
+            rows => rows.label(row.checked_sub(1)?),
+        }
+    }
+
+    /// Row number of the row `label`, the inverse of `row_label`
+    fn row_number(&self, label: &str) -> Option<usize> {
+        match &self.rows {
+            ColumnScheme::Numeric if !label.is_empty() && label.bytes().all(|c| c.is_ascii_digit()) => label.parse().ok(),
+            ColumnScheme::Numeric => None,
+            rows => Some(rows.index_of(label)? + 1),
+        }
+    }
+
+    /// Parses a label such as `"4/7"` (or `"12:4/7"`) back into a `GridPosition`
+    pub fn parse(&self, label: &str) -> Option<GridPosition> {
+        let (page, label) = split_page(label.trim());
+        if page == Some("") {
+            return None;
+        }
+        // without a separator, the shortest column label that leaves a valid row label wins
+        let splits = if self.separator.is_empty() {
+            (1..label.len()).filter(|index| label.is_char_boundary(*index)).map(|index| label.split_at(index)).collect::<Vec<_>>()
+        } else {
+            let separator_start = label.rfind(self.separator.as_str())?;
+            vec![(&label[..separator_start], &label[separator_start + self.separator.len()..])]
+        };
+
+        let position = splits.into_iter().find_map(|(column, row)| {
+            Some(self.position(self.columns.index_of(column)?, self.row_number(row)?))
+        })?;
+        Some(match page {
+            Some(page) => position.with_page(page),
+            None => position,
+        })
+    }
+
+    /// Orders positions by their column index first, then by their row, so
+    /// that `Z` comes before `AA` and `IX` comes before `X` (the same order
+    /// as `GridPosition`'s `Ord`, independent of the labeling scheme)
+    pub fn compare(&self, a: &GridPosition, b: &GridPosition) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+/// Formats positions for the exporters, so that the index follows the
+/// typographic house style of the publisher (`A9–I5`, `a 9 / i 5`, ...)
+///
+/// Only `format_cell` has to be implemented, pages are prefixed as
+/// `"12:"` and ranges on one page only print the page once.
+pub trait PositionFormatter {
+    /// Formats one cell without its page, i.e. `"A9"`
+    fn format_cell(&self, position: &GridPosition) -> String;
+
+    /// Text between the two ends of a range, i.e. `"-"` for `"A9-I5"`
+    fn range_separator(&self) -> &str {
+        "-"
+    }
+
+    /// Formats one cell, i.e. `"A9"` or `"12:A9"`
+    fn format_position(&self, position: &GridPosition) -> String {
+        match &position.page {
+            Some(page) => format!("{}:{}", page, self.format_cell(position)),
+            None => self.format_cell(position),
+        }
+    }
+
+    /// Formats a processed position, i.e. `"A9-I5"` or `"12:A9-I5"`
+    fn format_finalized(&self, position: &FinalizedGridPositon) -> String {
+        use self::FinalizedGridPositon::*;
+        match position {
+            SingleRect(single) => self.format_position(single),
+            TwoRect(a, b) | Range(a, b) if a.page.is_some() && a.page == b.page => {
+                format!("{}{}{}", self.format_position(a), self.range_separator(), self.format_cell(b))
+            },
+            TwoRect(a, b) | Range(a, b) => format!("{}{}{}", self.format_position(a), self.range_separator(), self.format_position(b)),
+        }
+    }
+}
+
+impl PositionFormatter for GridLabeler {
+    fn format_cell(&self, position: &GridPosition) -> String {
+        GridLabeler::format_cell(self, position)
+    }
+}
+
+/// Typographic style of the default `A, B, ..., Z, AA, ...` positions
+///
+/// ```no_run,ignore
+/// PositionStyle::default()                      // "A9-I5"
+/// PositionStyle::en_dash()                      // "A9–I5"
+/// PositionStyle::slash()                        // "A9/I5"
+/// PositionStyle::spaced()                       // "A 9 – I 5"
+/// PositionStyle::en_dash().with_lowercase()     // "a9–i5"
+/// PositionStyle::default().with_row_width(2)    // "A09-I05"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PositionStyle {
+    /// Text between the column and the row, i.e. `" "` for `"A 9"`
+    pub cell_separator: String,
+    /// Text between the two ends of a range
+    pub range_separator: String,
+    /// Whether the columns are written in lowercase
+    pub lowercase: bool,
+    /// Rows are padded with zeros to this number of digits
+    pub row_width: usize,
+}
+
+impl Default for PositionStyle {
+    fn default() -> Self {
+        Self {
+            cell_separator: String::new(),
+            range_separator: String::from("-"),
+            lowercase: false,
+            row_width: 0,
+        }
+    }
+}
+
+impl PositionStyle {
+
+    /// `"A9–I5"`
+    pub fn en_dash() -> Self {
+        Self { range_separator: String::from("\u{2013}"), .. Self::default() }
+    }
+
+    /// `"A9/I5"`
+    pub fn slash() -> Self {
+        Self { range_separator: String::from("/"), .. Self::default() }
+    }
+
+    /// `"A 9 – I 5"`
+    pub fn spaced() -> Self {
+        Self { cell_separator: String::from(" "), range_separator: String::from(" \u{2013} "), .. Self::default() }
+    }
+
+    pub fn with_lowercase(self) -> Self {
+        Self { lowercase: true, .. self }
+    }
+
+    pub fn with_row_width(self, row_width: usize) -> Self {
+        Self { row_width, .. self }
+    }
+}
+
+impl PositionFormatter for PositionStyle {
+    fn format_cell(&self, position: &GridPosition) -> String {
+        let column = if self.lowercase { position.column.to_lowercase() } else { position.column.clone() };
+        format!("{}{}{:0width$}", column, self.cell_separator, position.row, width = self.row_width)
+    }
+
+    fn range_separator(&self) -> &str {
+        &self.range_separator
+    }
+}
+
+/// Converts a number (starting at 1) to a roman numeral,
+/// numbers above 3999 are written with repeated `M`s
+fn to_roman(mut num: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"), (900, "CM"), (500, "D"), (400, "CD"),
+        (100, "C"), (90, "XC"), (50, "L"), (40, "XL"),
+        (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I"),
+    ];
+
+    let mut result = String::new();
+    for &(value, numeral) in NUMERALS.iter() {
+        while num >= value {
+            result.push_str(numeral);
+            num -= value;
+        }
+    }
+    result
+}
+
+/// Parses a roman numeral, only accepts the canonical form (`IV`, not `IIII`)
+fn from_roman(numeral: &str) -> Option<usize> {
+    let mut result = 0_usize;
+    let mut previous = 0;
+    for c in numeral.chars().rev() {
+        let value = match c {
+            'I' => 1, 'V' => 5, 'X' => 10, 'L' => 50,
+            'C' => 100, 'D' => 500, 'M' => 1000,
+            _ => return None,
+        };
+        if value < previous {
+            result = result.checked_sub(value)?;
+        } else {
+            result = result.checked_add(value)?;
+            previous = value;
+        }
+    }
+
+    if result == 0 || to_roman(result) != numeral {
+        return None;
+    }
+
+    Some(result)
+}
+
+#[test]
+fn test_grid_labeler() {
+    use roads2csv::GridPositionError;
+
+    let position = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+
+    let numeric = GridLabeler::new(ColumnScheme::Numeric, "/");
+    assert_eq!(numeric.format(&position("A", 4)), "1/4");
+    assert_eq!(numeric.parse("27/4"), Some(position("AA", 4)));
+
+    let roman = GridLabeler::new(ColumnScheme::Roman, "-");
+    assert_eq!(roman.format(&position("I", 5)), "IX-5");
+    assert_eq!(roman.parse("XIV-5"), Some(position("N", 5)));
+    assert_eq!(roman.parse("IIII-5"), None);
+
+    let lower = GridLabeler::new(ColumnScheme::AlphaLower, "");
+    assert_eq!(lower.format_finalized(&FinalizedGridPositon::TwoRect(position("Z", 9), position("AA", 9))), "z9-aa9");
+    assert_eq!(lower.parse("ab12"), Some(position("AB", 12)));
+    assert_eq!(lower.parse("AB12"), None);
+
+    let custom = GridLabeler::new(ColumnScheme::Custom(vec![String::from("North"), String::from("South")]), " ");
+    assert_eq!(custom.format(&position("B", 1)), "South 1");
+    assert_eq!(custom.format(&position("C", 1)), "C1");
+    assert_eq!(custom.parse("North 3"), Some(position("A", 3)));
+    assert_eq!(GridPosition::from_indices(1, 3, &custom.columns).map(|position| custom.format(&position)), Ok(String::from("South 3")));
+    assert_eq!(GridPosition::from_indices(2, 3, &custom.columns), Err(GridPositionError::UnlabeledColumn(2)));
+
+    // positions created from indices round-trip through non-alphabetic schemes
+    for labeler in [&roman, &numeric, &custom] {
+        let cell = GridPosition::from_indices(1, 5, &labeler.columns).unwrap();
+        assert_eq!(cell, position("B", 5));
+        assert_eq!(labeler.parse(&labeler.format(&cell)), Some(cell));
+    }
+
+    let paged = |page: &str, column: &str, row: usize| position(column, row).with_page(page);
+    assert_eq!(numeric.format_finalized(&FinalizedGridPositon::Range(paged("12", "C", 4), paged("12", "C", 6))), "12:3/4-3/6");
+    assert_eq!(numeric.parse("12:3/4"), Some(paged("12", "C", 4)));
+
+    assert_eq!(PositionFormatter::format_finalized(&numeric, &FinalizedGridPositon::TwoRect(position("A", 9), position("I", 5))), "1/9-9/5");
+
+    let mut positions = vec![position("AA", 1), position("B", 2), position("Z", 1), position("B", 1)];
+    positions.sort_by(|a, b| roman.compare(a, b));
+    assert_eq!(positions, vec![position("B", 1), position("B", 2), position("Z", 1), position("AA", 1)]);
+}
+
+#[test]
+fn test_alpha_excluding() {
+    let position = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let scheme = ColumnScheme::alpha_without_io();
+
+    assert_eq!(scheme.label(7), Some(String::from("H")));
+    assert_eq!(scheme.label(8), Some(String::from("J")));
+    assert_eq!(scheme.label(13), Some(String::from("P")));
+    assert_eq!(scheme.label(23), Some(String::from("Z")));
+    assert_eq!(scheme.label(24), Some(String::from("AA")));
+    assert_eq!(scheme.label(24 + 24 * 24), Some(String::from("AAA")));
+    for index in 0..1000 {
+        assert_eq!(scheme.index_of(&scheme.label(index).unwrap()), Some(index));
+    }
+    assert_eq!(scheme.index_of("I"), None);
+    assert_eq!(scheme.index_of("AO"), None);
+    assert_eq!(scheme.index_of("j"), None);
+
+    let lowercase = ColumnScheme::AlphaExcluding { excluded: String::from("io"), lowercase: true };
+    assert_eq!(lowercase.label(8), Some(String::from("j")));
+    assert_eq!(ColumnScheme::AlphaExcluding { excluded: String::from("ABCDEFGHIJKLMNOPQRSTUVWXYZ"), lowercase: false }.label(0), None);
+
+    // the ordering follows the column index, so "J" comes directly after "H"
+    let labeler = GridLabeler::new(ColumnScheme::alpha_without_io(), "");
+    assert_eq!(labeler.parse("J4"), Some(position("I", 4)));
+    assert_eq!(labeler.format(&position("I", 4)), "J4");
+    assert_eq!(labeler.compare(&position("H", 4), &position("I", 4)), ::std::cmp::Ordering::Less);
+}
+
+#[test]
+fn test_row_labels() {
+    let position = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+
+    let lettered_rows = GridLabeler::new(ColumnScheme::Numeric, "").with_rows(ColumnScheme::AlphaUpper);
+    assert_eq!(lettered_rows.format(&position("C", 6)), "3F");
+    assert_eq!(lettered_rows.format(&position("L", 27)), "12AA");
+    assert_eq!(lettered_rows.parse("3F"), Some(position("C", 6)));
+    assert_eq!(lettered_rows.parse("12AA"), Some(position("L", 27)));
+    assert_eq!(lettered_rows.parse("F3"), None);
+
+    let letters = GridLabeler::new(ColumnScheme::AlphaUpper, "-").with_rows(ColumnScheme::AlphaLower);
+    assert_eq!(letters.format_finalized(&FinalizedGridPositon::Range(position("B", 1), position("B", 3))), "B-a-B-c");
+    assert_eq!(letters.parse("AB-c"), Some(position("AB", 3)));
+
+    // the ordering follows the grid, not the labels
+    let mut positions = [position("C", 6), position("B", 7), position("C", 2)];
+    positions.sort_by(|a, b| lettered_rows.compare(a, b));
+    assert_eq!(positions.iter().map(|p| lettered_rows.format(p)).collect::<Vec<_>>(), vec!["2G", "3B", "3F"]);
+    assert_eq!(GridLabeler::default().parse("A05"), Some(position("A", 5)));
+}
+
+#[test]
+fn test_position_style() {
+    let position = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let range = FinalizedGridPositon::TwoRect(position("A", 9), position("I", 5));
+
+    assert_eq!(PositionStyle::default().format_finalized(&range), range.to_string());
+    assert_eq!(PositionStyle::en_dash().format_finalized(&range), "A9\u{2013}I5");
+    assert_eq!(PositionStyle::slash().format_finalized(&range), "A9/I5");
+    assert_eq!(PositionStyle::spaced().format_finalized(&range), "A 9 \u{2013} I 5");
+    assert_eq!(PositionStyle::en_dash().with_lowercase().format_finalized(&range), "a9\u{2013}i5");
+    assert_eq!(PositionStyle::default().with_row_width(2).format_finalized(&range), "A09-I05");
+
+    let paged = FinalizedGridPositon::Range(position("C", 4).with_page("12"), position("C", 6).with_page("12"));
+    assert_eq!(PositionStyle::en_dash().format_finalized(&paged), "12:C4\u{2013}C6");
+}
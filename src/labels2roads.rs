@@ -0,0 +1,78 @@
+//! Indexes streets by the position of their placed label instead of their
+//! geometry, for cartographic software that exports one label coordinate
+//! per street (or several, for long streets with repeated labels)
+//!
+//! ```no_run,ignore
+//! // "Canterbury Road;13.404;52.520"
+//! let streets = from_label_csv(&labels, ";", &grid)?;
+//! let index = DeduplicatedRoads::from_streets(&streets).process();
+//! ```
+//!
+//! The coordinates are projected with the `Projection` of the `GeoGrid`,
+//! so they can be longitude / latitude or already projected coordinates.
+
+use roads2csv::{InputStreetValue, ParseError, ParseErrorKind, split_csv_fields};
+use gridconfig::GeoGrid;
+
+impl InputStreetValue {
+
+    /// Street in the cell of the `grid` that its label point lies in, `None`
+    /// if the point lies outside of the grid
+    pub fn from_label_point(street_name: &str, lon: f64, lat: f64, grid: &GeoGrid) -> Option<Self> {
+        grid.cell_for(lon, lat).map(|position| InputStreetValue::new(street_name, position))
+    }
+}
+
+/// Returns one `InputStreetValue` per label (`(name, lon, lat)`), labels
+/// outside of the `grid` are ignored
+pub fn from_label_points<'a, I: IntoIterator<Item = (&'a str, f64, f64)>>(labels: I, grid: &GeoGrid) -> Vec<InputStreetValue> {
+    labels.into_iter()
+        .filter_map(|(name, lon, lat)| InputStreetValue::from_label_point(name, lon, lat, grid))
+        .collect()
+}
+
+/// Reads `name<delimiter>lon<delimiter>lat` lines (names may be quoted like
+/// in `DeduplicatedRoads::from_csv`), empty lines and labels outside of the
+/// `grid` are ignored
+pub fn from_label_csv(csv: &str, delimiter: &str, grid: &GeoGrid) -> Result<Vec<InputStreetValue>, ParseError> {
+    let mut labels = Vec::new();
+    for (line_number, line) in csv.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let error = |kind| ParseError { line: Some(line_number + 1), kind };
+        let fields = split_csv_fields(line.trim_end_matches('\r'), delimiter);
+        let name = fields.first().map(|name| name.trim()).unwrap_or("");
+        if name.is_empty() {
+            return Err(error(ParseErrorKind::EmptyName));
+        }
+        let coordinate = |index: usize| {
+            let field = fields.get(index).map(|field| field.trim()).ok_or_else(|| error(ParseErrorKind::MissingPosition))?;
+            field.parse::<f64>().ok().filter(|value| value.is_finite())
+                .ok_or_else(|| error(ParseErrorKind::InvalidCoordinate(field.to_string())))
+        };
+        let (lon, lat) = (coordinate(1)?, coordinate(2)?);
+        labels.extend(InputStreetValue::from_label_point(name, lon, lat, grid));
+    }
+    Ok(labels)
+}
+
+#[test]
+fn test_label_points() {
+    use roads2csv::{DeduplicatedRoads, GridPosition};
+
+    let grid = GeoGrid::new(10.0, 50.0, 0.5, 0.25).with_size(4, 4);
+    let street = InputStreetValue::from_label_point("Canterbury Road", 11.2, 49.3, &grid).unwrap();
+    assert_eq!(street.position, GridPosition { column: String::from("C"), row: 3, page: None });
+    assert!(InputStreetValue::from_label_point("Canterbury Road", 9.0, 49.3, &grid).is_none());
+
+    // the road crosses B1-D1, but only its two labels are indexed
+    let labels = "Canterbury Road;10.6;49.9\r\n\r\n\"Mill Lane; North\";10.1;49.8\r\nCanterbury Road;11.6;49.9\r\nOutside Road;12.5;49.9";
+    let streets = from_label_csv(labels, ";", &grid).unwrap();
+    assert_eq!(streets, from_label_points(vec![("Canterbury Road", 10.6, 49.9), ("Mill Lane; North", 10.1, 49.8), ("Canterbury Road", 11.6, 49.9)], &grid));
+    assert_eq!(DeduplicatedRoads::from_streets(&streets).process().to_csv("\t"), "Canterbury Road\tB1-D1\r\nMill Lane; North\tA1");
+
+    let error = from_label_csv("Canterbury Road;10.6;49.9\nMill Lane;10.1;north", ";", &grid).unwrap_err();
+    assert_eq!(error.to_string(), "line 2: invalid coordinate \"north\"");
+    assert_eq!(from_label_csv("Mill Lane;10.1", ";", &grid).unwrap_err().kind, ParseErrorKind::MissingPosition);
+}
@@ -0,0 +1,194 @@
+//! Plans how the index flows into columns and pages for a printed layout,
+//! i.e. to reserve the right number of pages or to print a letter register
+//! ("A: page 1, B: page 1, C: page 2, ...")
+//!
+//! Like `to_pdf()`, the layout contains the processed roads (in the order of
+//! `StreetIndex::processed`, so sort them first) and the cross-references,
+//! with a header letter before every group of roads starting with the same
+//! letter. A header is never the last line of a column.
+//!
+//! ```no_run,ignore
+//! let layout = LayoutPlanner::from_font_size(Millimeter(267.0), 8.0, 3).plan(&index);
+//! for section in &layout.sections {
+//!     println!("{}: page {}, column {}", section.letter, section.page + 1, section.column + 1);
+//! }
+//! ```
+
+use roads2csv::StreetIndex;
+use gridconfig::Millimeter;
+use cross_reference::Interleave;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Line height, relative to the font size (same as in the PDF export)
+const LINE_SPACING: f32 = 1.4;
+/// Millimeters per typographic point
+const MM_PER_PT: f32 = 25.4 / 72.0;
+
+/// Splits an index into pages and columns
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LayoutPlanner {
+    /// Number of lines per column (at least 1)
+    pub lines_per_column: usize,
+    /// Number of columns per page (at least 1)
+    pub columns_per_page: usize,
+    /// Whether to insert a header letter before every group of roads
+    pub letter_headers: bool,
+    /// Number of lines a header letter takes up (default: 2, for a larger
+    /// font and some space above the letter)
+    pub header_lines: usize,
+}
+
+impl Default for LayoutPlanner {
+    /// 60 lines per column, three columns per page
+    fn default() -> Self {
+        Self::new(60, 3)
+    }
+}
+
+impl LayoutPlanner {
+
+    pub fn new(lines_per_column: usize, columns_per_page: usize) -> Self {
+        Self { lines_per_column, columns_per_page, letter_headers: true, header_lines: 2 }
+    }
+
+    /// Calculates the lines per column from the height of a column and the
+    /// font size of the road names in pt
+    pub fn from_font_size(column_height: Millimeter, font_size: f32, columns_per_page: usize) -> Self {
+        let line_height = font_size * LINE_SPACING * MM_PER_PT;
+        let lines = if line_height > 0.0 { (column_height.0 / line_height) as usize } else { 0 };
+        Self::new(lines.max(1), columns_per_page)
+    }
+
+    pub fn with_letter_headers(self, letter_headers: bool) -> Self {
+        Self { letter_headers, .. self }
+    }
+
+    pub fn with_header_lines(self, header_lines: usize) -> Self {
+        Self { header_lines, .. self }
+    }
+
+    /// Distributes the lines of the `index` over the columns and pages
+    pub fn plan(&self, index: &StreetIndex) -> IndexLayout {
+
+        let lines_per_column = self.lines_per_column.max(1);
+        let columns_per_page = self.columns_per_page.max(1);
+
+        let mut layout = IndexLayout::default();
+        let mut columns: Vec<Vec<LayoutLine>> = vec![Vec::new()];
+        let mut used = 0;
+        let mut current_letter = None;
+
+        let roads = index.processed.processed.iter().map(|road| (&road.name, (&road.name, road.position.to_string())));
+        let lines = Interleave::new(roads, &index.cross_references, |reference| (&reference.from, reference.see()));
+
+        for (name, position) in lines {
+
+            let letter = name.initial();
+            let header = if self.letter_headers && letter.is_some() && letter != current_letter {
+                current_letter = letter.clone();
+                letter
+            } else {
+                None
+            };
+
+            // keep the header together with the first road of the group, but
+            // always put at least one line into every column
+            let height = 1 + if header.is_some() { self.header_lines } else { 0 };
+            if used + height > lines_per_column && used > 0 {
+                columns.push(Vec::new());
+                used = 0;
+            }
+
+            let column = columns.len() - 1;
+            if let Some(letter) = header {
+                layout.sections.push(SectionStart {
+                    letter: letter.clone(),
+                    page: column / columns_per_page,
+                    column: column % columns_per_page,
+                });
+                columns[column].push(LayoutLine::Header(letter));
+            }
+
+            columns[column].push(LayoutLine::Entry { name: name.0.clone(), position });
+            used += height;
+        }
+
+        if columns.iter().any(|column| !column.is_empty()) {
+            layout.pages = columns.chunks(columns_per_page).map(|page| page.to_vec()).collect();
+        }
+
+        layout
+    }
+}
+
+/// Result of `LayoutPlanner::plan()`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IndexLayout {
+    /// Lines of every column of every page (`pages[page][column]`), the
+    /// last page may have fewer columns
+    pub pages: Vec<Vec<Vec<LayoutLine>>>,
+    /// Where every header letter starts, in the order of the index
+    pub sections: Vec<SectionStart>,
+}
+
+impl IndexLayout {
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Zero-based page on which the section of `letter` starts
+    pub fn page_of(&self, letter: &str) -> Option<usize> {
+        self.sections.iter().find(|section| section.letter == letter).map(|section| section.page)
+    }
+}
+
+/// One line of a column
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LayoutLine {
+    Header(String),
+    /// Road or cross-reference (with `"see Market St"` as the position)
+    Entry { name: String, position: String },
+}
+
+/// Start of a letter section, page and column are zero-based
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SectionStart {
+    pub letter: String,
+    pub page: usize,
+    pub column: usize,
+}
+
+#[test]
+fn test_layout_planner() {
+    use roads2csv::DeduplicatedRoads;
+    use cross_reference::CrossReference;
+
+    let csv = "Abbey Road\tA1\r\nAcre Lane\tA2\r\nBaker Street\tB1\r\nBridge Road\tB2\r\nCanal Street\tC1\r\nDock Road\tD1";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process()
+        .with_cross_reference(CrossReference::new("Church Lane", "Canal Street"));
+
+    let layout = LayoutPlanner::new(5, 2).plan(&index);
+    let sections = layout.sections.iter().map(|s| (s.letter.as_str(), s.page, s.column)).collect::<Vec<_>>();
+    assert_eq!(sections, vec![("A", 0, 0), ("B", 0, 1), ("C", 1, 0), ("D", 1, 1)]);
+    assert_eq!(layout.page_count(), 2);
+    assert_eq!(layout.page_of("C"), Some(1));
+    assert_eq!(layout.page_of("Z"), None);
+    assert_eq!(layout.pages[1][0], vec![
+        LayoutLine::Header(String::from("C")),
+        LayoutLine::Entry { name: String::from("Canal Street"), position: String::from("C1") },
+        LayoutLine::Entry { name: String::from("Church Lane"), position: String::from("see Canal Street") },
+    ]);
+
+    let plain = LayoutPlanner::new(4, 2).with_letter_headers(false).plan(&index);
+    assert!(plain.sections.is_empty());
+    assert_eq!(plain.pages.iter().map(|page| page.len()).collect::<Vec<_>>(), vec![2]);
+
+    assert_eq!(LayoutPlanner::from_font_size(Millimeter(267.0), 8.0, 3).lines_per_column, 67);
+    assert_eq!(LayoutPlanner::default().plan(&DeduplicatedRoads::from_csv("", "\t").unwrap().process()).page_count(), 0);
+}
@@ -48,8 +48,8 @@
 //! use street_index::prelude::*;
 //!
 //! fn main() {
-//! 	// Create a grid, with the page extensions being 200 x 200 millimeter
-//! 	// Each cell is 20x20 millimeter large (usually 50x50 is recommended, though)
+//!     // Create a grid, with the page extensions being 200 x 200 millimeter
+//!     // Each cell is 20x20 millimeter large (usually 50x50 is recommended, though)
 //!     let mut grid = Grid::new(
 //!             Bbox {
 //!                 width: Millimeter(200.0),
@@ -89,37 +89,313 @@
 //!
 //!     // As described above, we get both processed and unprocessed
 //!     // road names back
-//!     let (processed, unprocessed) = deduplicated.process();
+//!     let index = deduplicated.process();
 //!
 //!     // In this case, "Canterbury Road" spans from B1-B2, so we get a
 //!     // `ProcessedRoad` back, delimited by a TAB character.
 //!     //
-//!  	// You can then write this to a CSV file if you want.
-//!     println!("processed:\r\n{}", processed.to_csv("\t"));
-//!     println!("unprocessed:\r\n{}", unprocessed.to_csv("\t"));
+//!     // You can then write this to a CSV file if you want.
+//!     println!("processed:\r\n{}", index.processed.to_csv("\t"));
+//!     println!("unprocessed:\r\n{}", index.unprocessed.to_csv("\t"));
 //! }
 //! ```
 //!
 //! ## License
 //!
 //! This library is licensed under the MIT license.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![cfg_attr(feature = "nightly", feature(test))]
+// `no_std` already links `core`
+#[cfg(any(feature = "std", test))]
+extern crate core;
+extern crate alloc;
 #[cfg(feature = "nightly")]
 extern crate test;
+#[cfg(feature = "std")]
+extern crate rustc_hash;
+#[cfg(feature = "std")]
+extern crate smallvec;
+#[cfg(any(feature = "geojson", feature = "wasm", feature = "overpass", test))]
+extern crate serde_json;
+#[cfg(feature = "osm")]
+extern crate flate2;
+#[cfg(feature = "collation")]
+extern crate feruca;
+#[cfg(feature = "transliterate")]
+extern crate any_ascii;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "xlsx")]
+extern crate rust_xlsxwriter;
+#[cfg(feature = "pdf")]
+extern crate printpdf;
+#[cfg(feature = "sqlite")]
+extern crate rusqlite;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+#[cfg(feature = "regex")]
+extern crate regex;
+#[cfg(feature = "indicatif")]
+extern crate indicatif;
+#[cfg(any(feature = "kml", feature = "gpx"))]
+extern crate quick_xml;
+#[cfg(feature = "kml")]
+extern crate zip;
+#[cfg(feature = "gdal")]
+extern crate gdal;
+#[cfg(feature = "overpass")]
+extern crate reqwest;
+#[cfg(feature = "overpass")]
+extern crate futures_util;
+#[cfg(feature = "postgres")]
+extern crate postgres;
+#[cfg(feature = "unicode")]
+extern crate unicode_normalization;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "arrow")]
+extern crate arrow;
+#[cfg(feature = "arrow")]
+extern crate parquet;
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
 
+/// Module for the alloc-only core of deduplication and processing (no `std`)
+pub mod core_index;
+/// Module for the crate-level `Error` type
+#[cfg(feature = "std")]
+pub mod error;
 /// Module for configuring the grid and assigning cell positions to road names
+#[cfg(feature = "std")]
 pub mod gridconfig;
 /// Module for deduplicating road names and exporting / processing them to CSV
+#[cfg(feature = "std")]
 pub mod roads2csv;
+/// Module for exporting processed and unprocessed roads into one CSV with a status column
+#[cfg(feature = "std")]
+pub mod csv_exporter;
+/// Module for the character encodings of written CSV files (UTF-8 with BOM, Latin-1, Windows-1252)
+#[cfg(feature = "std")]
+pub mod encoding;
+/// Module for the `Exporter` trait and the registry of output formats by name
+#[cfg(feature = "std")]
+pub mod export;
+/// Module for exporting processed / unprocessed roads to JSON
+#[cfg(feature = "std")]
+pub mod roads2json;
+/// Module for exporting a street index to YAML
+#[cfg(feature = "std")]
+pub mod roads2yaml;
+/// Module for exporting a street index to TOML
+#[cfg(feature = "std")]
+pub mod roads2toml;
+/// Module for exporting a street index to HTML
+#[cfg(feature = "std")]
+pub mod roads2html;
+/// Module for exporting a street index to LaTeX
+#[cfg(feature = "std")]
+pub mod roads2latex;
+/// Module for exporting a street index to Markdown
+#[cfg(feature = "std")]
+pub mod roads2markdown;
+/// Module for rendering a street index as an aligned terminal table
+#[cfg(feature = "std")]
+pub mod roads2table;
+/// Module for exporting a street index as Adobe InDesign tagged text
+#[cfg(feature = "std")]
+pub mod roads2indesign;
+/// Module for exporting a street index as namespaced XML
+#[cfg(feature = "std")]
+pub mod roads2xml;
+/// Module for exporting the cells of a street index as GeoJSON polygons
+#[cfg(feature = "std")]
+pub mod roads2geojson;
+/// Module for exporting a street index to an Excel workbook
+#[cfg(feature = "xlsx")]
+pub mod roads2xlsx;
+/// Module for rendering a street index as a PDF
+#[cfg(feature = "pdf")]
+pub mod roads2pdf;
+/// Module for exporting a street index to an SQLite database
+#[cfg(feature = "sqlite")]
+pub mod roads2sqlite;
+/// Module for exporting a street index to Apache Arrow / Parquet (requires the `arrow` feature)
+#[cfg(feature = "arrow")]
+pub mod roads2arrow;
+/// Module for the flat deduplication backend for very large inputs (see `DedupBackend`)
+#[cfg(feature = "std")]
+pub mod flat;
+/// Module for normalizing street names (abbreviations, case, Unicode) before deduplication
+#[cfg(feature = "std")]
+pub mod normalize;
+/// Module for finding probable typos in street names
+#[cfg(feature = "std")]
+pub mod duplicates;
+/// Module for resolving roads that need to be reviewed manually
+#[cfg(feature = "std")]
+pub mod review;
+/// Module for exporting the roads to review to a file and applying the decisions
+#[cfg(feature = "std")]
+pub mod review_file;
+/// Module for categorizing index entries (streets, points of interest, waterways, landmarks)
+#[cfg(feature = "std")]
+pub mod category;
+/// Module for summary statistics of a street index
+#[cfg(feature = "std")]
+pub mod statistics;
+/// Module for excluding unnamed or internal roads before deduplication
+#[cfg(feature = "std")]
+pub mod filter;
+/// Module for folding alternative street names into their canonical name
+#[cfg(feature = "std")]
+pub mod alias;
+/// Module for "see" entries of renamed or co-signed streets
+#[cfg(feature = "std")]
+pub mod cross_reference;
+/// Module for indexes of streets with two official names (bilingual regions)
+#[cfg(feature = "std")]
+pub mod bilingual;
+/// Module for splitting an index into one index per district
+#[cfg(feature = "std")]
+pub mod district;
+/// Module for additional street attributes (i.e. postal codes) and their export
+#[cfg(feature = "std")]
+pub mod attributes;
+/// Module for house number ranges as sub-entries of a street
+#[cfg(feature = "std")]
+pub mod house_numbers;
+/// Module for plausibility checks of the processed roads and the input streets
+#[cfg(feature = "std")]
+pub mod validation;
+/// Module for checking the invariants of a processed index against its input
+#[cfg(feature = "std")]
+pub mod verify;
+/// Module for the reverse index (cell -> street names)
+#[cfg(feature = "std")]
+pub mod invert;
+/// Module for the geographic bounding boxes of the streets
+#[cfg(feature = "std")]
+pub mod bounds;
+/// Module for comparing two editions of a street index
+#[cfg(feature = "std")]
+pub mod diff;
+/// Module for updating a street index incrementally
+#[cfg(feature = "std")]
+pub mod update;
+/// Module for the `Importer` trait, a streaming interface of all input sources
+#[cfg(feature = "std")]
+pub mod import;
+/// Module for bundling all settings of building an index into one shareable type
+#[cfg(feature = "std")]
+pub mod pipeline;
+/// Module for reporting the progress of long pipeline runs
+#[cfg(feature = "std")]
+pub mod progress;
+/// Module for aborting long index builds
+#[cfg(feature = "std")]
+pub mod cancel;
+/// Module for planning how the index flows into columns and pages
+#[cfg(feature = "std")]
+pub mod layout;
+/// Module for the UTM projection and MGRS grid references
+#[cfg(feature = "std")]
+pub mod utm;
+/// Module for labeling grid columns with letters, numbers or custom labels
+#[cfg(feature = "std")]
+pub mod labeling;
+/// Module for ordering street names (natural, locale-aware or Unicode collation order)
+#[cfg(feature = "std")]
+pub mod sorting;
+/// Module for Latin sort keys and romanized names of non-Latin street names (requires the `transliterate` feature)
+#[cfg(feature = "transliterate")]
+pub mod transliterate;
+/// Module for indexing streets by the position of their label
+#[cfg(feature = "std")]
+pub mod labels2roads;
+/// Module for reading road geometries from GeoJSON (requires the `geojson` feature)
+#[cfg(feature = "geojson")]
+pub mod geojson2roads;
+/// Module for reading named highways from OpenStreetMap `.osm.pbf` files (requires the `osm` feature)
+#[cfg(feature = "osm")]
+pub mod osm2roads;
+/// Module for reading road geometries from ESRI Shapefiles (requires the `shapefile` feature)
+#[cfg(feature = "shapefile")]
+pub mod shapefile2roads;
+/// Module for reading named LineString placemarks from KML / KMZ files (requires the `kml` feature)
+#[cfg(feature = "kml")]
+pub mod kml2roads;
+/// Module for reading named tracks from GPX files (requires the `gpx` feature)
+#[cfg(feature = "gpx")]
+pub mod gpx2roads;
+/// Module for reading road geometries from any GDAL / OGR vector format (requires the `gdal` feature)
+#[cfg(feature = "gdal")]
+pub mod ogr2roads;
+/// Module for querying named highways from the Overpass API (requires the `overpass` feature)
+#[cfg(feature = "overpass")]
+pub mod overpass;
+/// Module for streaming road geometries from PostGIS (requires the `postgres` feature)
+#[cfg(feature = "postgres")]
+pub mod postgis2roads;
+/// Module with the JavaScript bindings (requires the `wasm` feature)
+#[cfg(feature = "wasm")]
+pub mod wasm;
+/// Module with panic-free entry points for fuzzing (requires the `arbitrary` feature)
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+#[cfg(feature = "std")]
+pub use error::{Error, Result};
+
 /// Quick re-exports for wildcard imports
+#[cfg(feature = "std")]
 pub mod prelude {
 	pub use roads2csv::{
-	    InputStreetValue, DeduplicatedRoads, ProcessedRoad,
+	    InputStreetValue, DeduplicatedRoads, DeduplicatedRoadsBuilder, ProcessedRoad,
 	    ProcessedRoadNames, UnprocessedRoad, UnprocessedRoadNames,
-	    StreetName, GridPosition, FinalizedGridPositon,
+	    StreetName, GridPosition, GridPositionError, FinalizedGridPositon, StreetIndex, IndexEntry,
+	    CsvQuoting, CsvOptions, LineEnding, PositionRemap, ProcessOptions, Weight,
 	};
 
 	pub use gridconfig::{
-		Grid, GridConfig, Bbox, Millimeter, StreetNameRect,
+		Grid, GridConfig, Bbox, Millimeter, StreetNameRect, GeoGrid, Projection, RowDirection, GridKind, CellGeometry, MinLength,
+	};
+
+	pub use labeling::{
+		ColumnScheme, GridLabeler, PositionFormatter, PositionStyle,
+	};
+
+	pub use normalize::{Normalizer, DedupOptions, UnicodeForm};
+	pub use flat::DedupBackend;
+
+	pub use duplicates::ProbableDuplicate;
+
+	pub use review::{Resolution, ReportFormat};
+
+	pub use category::Category;
+
+	pub use statistics::IndexStatistics;
+
+	pub use filter::Filter;
+
+	pub use alias::AliasTable;
+	pub use cross_reference::CrossReference;
+	pub use house_numbers::{NumberRange, HouseNumbers};
+	pub use bilingual::NameSelection;
+	pub use attributes::{ConflictPolicy, Conflict};
+	pub use validation::{ValidationWarning, InputIssue, InputIssueKind, InputLimits};
+	pub use verify::Violation;
+	pub use bounds::{GeoBounds, StreetBounds};
+	pub use diff::IndexDiff;
+	pub use layout::{LayoutPlanner, IndexLayout, LayoutLine, SectionStart};
+	pub use pipeline::Pipeline;
+	pub use progress::{Progress, Stage};
+	pub use cancel::CancellationToken;
+	pub use csv_exporter::CsvExporter;
+	pub use export::{Exporter, ExporterRegistry};
+	pub use import::{Importer, CsvImporter, StreetsImporter};
+	pub use encoding::{CsvEncoding, Unmappable};
+
+	pub use sorting::{
+		SortStrategy, ByteOrder, NaturalOrder, Locale, InitialRules,
+		SortKeyExtractor, ArticleStripper, SortByKey, OrderBy,
 	};
 }
\ No newline at end of file
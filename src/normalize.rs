@@ -0,0 +1,204 @@
+//! Normalization of street names before deduplication
+//!
+//! Input data frequently mixes `"Main St"`, `"Main St."` and `"Main Street"`,
+//! or `"MAIN STREET"` and `"Main Street"`, which would otherwise end up as
+//! separate entries in the index.
+
+use std::borrow::Cow;
+use attributes::ConflictPolicy;
+use category::Category;
+#[cfg(feature = "unicode")]
+use unicode_normalization::UnicodeNormalization as _;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Expands abbreviated words in street names (`"Main St."` => `"Main Street"`)
+///
+/// Words are compared case-insensitively, a trailing `.` is ignored. The first
+/// word of a name is never expanded, so that `"St James Street"` (Saint James)
+/// and `"Ave Maria Lane"` stay as they are.
+///
+/// ```no_run,ignore
+/// let normalizer = Normalizer::english();
+/// normalizer.normalize("Canterbury Rd.") // "Canterbury Road"
+///
+/// let german = Normalizer::new(&[("Str", "Straße"), ("Pl", "Platz")]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Normalizer {
+    /// `(abbreviation, expansion)` pairs, the abbreviation without the trailing `.`
+    pub abbreviations: Vec<(String, String)>,
+}
+
+impl Normalizer {
+
+    pub fn new(abbreviations: &[(&str, &str)]) -> Self {
+        Self {
+            abbreviations: abbreviations.iter().map(|(short, long)| (short.to_string(), long.to_string())).collect(),
+        }
+    }
+
+    /// Adds (or replaces) an abbreviation
+    pub fn with(mut self, abbreviation: &str, expansion: &str) -> Self {
+        self.abbreviations.retain(|(short, _)| !short.eq_ignore_ascii_case(abbreviation));
+        self.abbreviations.push((abbreviation.to_string(), expansion.to_string()));
+        self
+    }
+
+    /// Common abbreviations of English street types and directions
+    pub fn english() -> Self {
+        Self::new(&[
+            ("St", "Street"), ("Str", "Street"), ("Ave", "Avenue"), ("Av", "Avenue"),
+            ("Rd", "Road"), ("Blvd", "Boulevard"), ("Dr", "Drive"), ("Ln", "Lane"),
+            ("Ct", "Court"), ("Pl", "Place"), ("Sq", "Square"), ("Pkwy", "Parkway"),
+            ("Hwy", "Highway"), ("Ter", "Terrace"), ("Terr", "Terrace"), ("Cres", "Crescent"),
+            ("Cl", "Close"), ("Gdns", "Gardens"), ("Gr", "Grove"), ("Cir", "Circle"),
+            ("Expy", "Expressway"), ("Fwy", "Freeway"), ("Trl", "Trail"), ("Aly", "Alley"),
+            ("N", "North"), ("S", "South"), ("E", "East"), ("W", "West"),
+            ("NE", "Northeast"), ("NW", "Northwest"), ("SE", "Southeast"), ("SW", "Southwest"),
+        ])
+    }
+
+    /// Returns the normalized name, whitespace between words is collapsed
+    /// to a single space if any word was expanded
+    pub fn normalize<'a>(&self, name: &'a str) -> Cow<'a, str> {
+
+        let mut changed = false;
+        let words = name.split_whitespace().enumerate().map(|(i, word)| {
+            if i == 0 {
+                return word;
+            }
+            match self.expansion(word) {
+                Some(expansion) => { changed = true; expansion },
+                None => word,
+            }
+        }).collect::<Vec<&str>>();
+
+        if changed {
+            Cow::Owned(words.join(" "))
+        } else {
+            Cow::Borrowed(name)
+        }
+    }
+
+    fn expansion(&self, word: &str) -> Option<&str> {
+        let word = word.strip_suffix('.').unwrap_or(word);
+        self.abbreviations.iter()
+            .find(|(short, _)| short.to_lowercase() == word.to_lowercase())
+            .map(|(_, long)| long.as_str())
+    }
+}
+
+/// Unicode normalization form applied to street names before deduplication
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum UnicodeForm {
+    /// Names are compared by their exact code points (the default)
+    #[default]
+    None,
+    /// Canonical composition, `"e\u{301}"` and `"é"` are the same name
+    #[cfg(feature = "unicode")]
+    Nfc,
+    /// Compatibility composition, additionally `"ﬁ"` and `"fi"` are the same name
+    #[cfg(feature = "unicode")]
+    Nfkc,
+}
+
+/// Controls which street names are considered equal by the deduplication,
+/// see `DeduplicatedRoads::from_streets_with_options`
+///
+/// The name that appears in the index is the most frequent spelling of the
+/// road (after whitespace collapsing, Unicode and abbreviation normalization),
+/// so `["MAIN STREET", "Main Street", "Main Street"]` becomes `"Main Street"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DedupOptions {
+    /// Compare names case-insensitively
+    pub case_insensitive: bool,
+    /// Trim names and collapse runs of whitespace into a single space
+    pub collapse_whitespace: bool,
+    pub unicode_form: UnicodeForm,
+    /// Expands abbreviations (`"Main St."` => `"Main Street"`)
+    pub normalizer: Option<Normalizer>,
+    /// Which value an attribute gets if the rows of a road disagree
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attribute_conflict: ConflictPolicy,
+    /// Merges the rows of a name with different categories into one road,
+    /// with the category picked by the policy. `None` (and
+    /// `ConflictPolicy::Collect`) keeps one road per category.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub category_conflict: Option<ConflictPolicy>,
+}
+
+impl DedupOptions {
+
+    /// Category under which a row with the `category` is deduplicated,
+    /// `None` for all rows if categories are merged (see `category_conflict`)
+    pub(crate) fn category_key(&self, category: Option<Category>) -> Option<Category> {
+        match self.category_conflict {
+            Some(ConflictPolicy::Collect(_)) | None => category,
+            Some(_) => None,
+        }
+    }
+
+    /// Whether the rows of a name with different categories are merged
+    pub(crate) fn merges_categories(&self) -> bool {
+        self.category_key(Some(Category::Street)).is_none()
+    }
+}
+
+impl DedupOptions {
+
+    /// Returns the name as it should appear in the index
+    pub fn display_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let mut name = Cow::Borrowed(name);
+
+        match self.unicode_form {
+            UnicodeForm::None => { },
+            #[cfg(feature = "unicode")]
+            UnicodeForm::Nfc => name = Cow::Owned(name.nfc().collect()),
+            #[cfg(feature = "unicode")]
+            UnicodeForm::Nfkc => name = Cow::Owned(name.nfkc().collect()),
+        }
+
+        if self.collapse_whitespace {
+            let collapsed = name.split_whitespace().collect::<Vec<&str>>().join(" ");
+            if collapsed != name {
+                name = Cow::Owned(collapsed);
+            }
+        }
+
+        if let Some(normalizer) = &self.normalizer {
+            if let Cow::Owned(normalized) = normalizer.normalize(&name) {
+                name = Cow::Owned(normalized);
+            }
+        }
+
+        name
+    }
+
+    /// Returns the key that names are compared by (`display_name`,
+    /// lowercased if `case_insensitive` is set)
+    pub fn key(&self, display_name: &str) -> String {
+        if self.case_insensitive {
+            display_name.to_lowercase()
+        } else {
+            display_name.to_string()
+        }
+    }
+}
+
+#[test]
+fn test_normalizer() {
+    let english = Normalizer::english();
+    assert_eq!(english.normalize("Main St"), "Main Street");
+    assert_eq!(english.normalize("Main  st."), "Main Street");
+    assert_eq!(english.normalize("Main Street"), "Main Street");
+    assert_eq!(english.normalize("St James Sq"), "St James Square");
+    assert_eq!(english.normalize("Elm Ave N"), "Elm Avenue North");
+
+    let german = Normalizer::new(&[("Str", "Straße")]).with("Pl", "Platz");
+    assert_eq!(german.normalize("Berliner Str."), "Berliner Straße");
+    assert_eq!(german.normalize("Potsdamer Pl."), "Potsdamer Platz");
+}
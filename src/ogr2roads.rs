@@ -0,0 +1,124 @@
+//! Reads road geometries from any vector format supported by GDAL / OGR
+//! (GeoPackage, FileGDB, DXF, MapInfo TAB, ...) and assigns grid cells to them
+//!
+//! Requires the `gdal` feature and the GDAL library (see the `gdal` crate for
+//! the build requirements). The coordinates of the layer have to be in the
+//! same coordinate system as the `GeoGrid`, no reprojection is done.
+//!
+//! ```no_run,ignore
+//! let dataset = gdal::Dataset::open("roads.gpkg")?;
+//! let streets = from_ogr_layer_by_name(&dataset, "roads", "STRNAME", &grid)?;
+//! ```
+
+use gdal::Dataset;
+use gdal::errors::GdalError;
+use gdal::vector::{LayerAccess, Geometry};
+use roads2csv::InputStreetValue;
+use gridconfig::GeoGrid;
+
+/// Reads the first layer of the `dataset` (i.e. the only layer of a
+/// shapefile or a `.tab` file) and returns one `InputStreetValue` for every
+/// cell of the `grid` that a feature touches. The street name is read from
+/// the field `name_field`.
+///
+/// Features with an empty name or without geometry are ignored, a missing
+/// `name_field` is an error (`GdalError::InvalidFieldName`).
+pub fn from_ogr_layer(dataset: &Dataset, name_field: &str, grid: &GeoGrid) -> Result<Vec<InputStreetValue>, GdalError> {
+    let mut layer = dataset.layer(0)?;
+    read_layer(&mut layer, name_field, grid)
+}
+
+/// Same as `from_ogr_layer`, but reads the layer with the name `layer_name`
+/// (for datasets with several layers, i.e. GeoPackages)
+pub fn from_ogr_layer_by_name(dataset: &Dataset, layer_name: &str, name_field: &str, grid: &GeoGrid)
+-> Result<Vec<InputStreetValue>, GdalError>
+{
+    let mut layer = dataset.layer_by_name(layer_name)?;
+    read_layer(&mut layer, name_field, grid)
+}
+
+fn read_layer<L: LayerAccess>(layer: &mut L, name_field: &str, grid: &GeoGrid) -> Result<Vec<InputStreetValue>, GdalError> {
+    let mut streets = Vec::new();
+
+    for feature in layer.features() {
+
+        let name = match feature.field_as_string_by_name(name_field)? {
+            Some(name) if !name.trim().is_empty() => name,
+            _ => continue,
+        };
+
+        let geometry = match feature.geometry() {
+            Some(geometry) => geometry,
+            None => continue,
+        };
+
+        let mut lines = Vec::new();
+        collect_lines(geometry, &mut lines);
+
+        let mut cells = Vec::new();
+        for line in &lines {
+            for cell in grid.cells_for_linestring(line) {
+                if !cells.contains(&cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+
+        streets.extend(cells.into_iter().map(|position| InputStreetValue::new(&name, position)));
+    }
+
+    Ok(streets)
+}
+
+/// Collects the points of all (multi-)points, line strings and polygon rings,
+/// the Z values are ignored
+fn collect_lines(geometry: &Geometry, lines: &mut Vec<Vec<(f64, f64)>>) {
+    if geometry.geometry_count() == 0 {
+        let points = geometry.get_point_vec();
+        if !points.is_empty() {
+            lines.push(points.into_iter().map(|(x, y, _)| (x, y)).collect());
+        }
+        return;
+    }
+
+    for index in 0..geometry.geometry_count() {
+        collect_lines(&geometry.get_geometry(index), lines);
+    }
+}
+
+#[test]
+fn test_from_ogr_layer() {
+    use std::fs;
+    use roads2csv::GridPosition;
+
+    let geojson = r#"{
+        "type": "FeatureCollection",
+        "features": [
+            { "type": "Feature", "properties": { "STRNAME": "Canterbury Road" },
+              "geometry": { "type": "LineString", "coordinates": [[0.5, 9.5], [1.5, 9.5]] } },
+            { "type": "Feature", "properties": { "STRNAME": "Mayer Street" },
+              "geometry": { "type": "MultiLineString", "coordinates": [[[0.5, 8.5]], [[3.5, 8.5]]] } },
+            { "type": "Feature", "properties": { "STRNAME": "" },
+              "geometry": { "type": "LineString", "coordinates": [[0.5, 9.5], [1.5, 9.5]] } }
+        ]
+    }"#;
+
+    let path = ::std::env::temp_dir().join("street_index_test_from_ogr_layer.geojson");
+    fs::write(&path, geojson).unwrap();
+    let dataset = Dataset::open(&path).unwrap();
+
+    let grid = GeoGrid::new(0.0, 10.0, 1.0, 1.0);
+    let streets = from_ogr_layer(&dataset, "STRNAME", &grid).unwrap();
+
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let cells = streets.iter().map(|s| (s.street_name.0.as_str(), s.position.clone())).collect::<Vec<_>>();
+    assert_eq!(cells, vec![
+        ("Canterbury Road", cell("A", 1)),
+        ("Canterbury Road", cell("B", 1)),
+        ("Mayer Street", cell("A", 2)),
+        ("Mayer Street", cell("D", 2)),
+    ]);
+
+    assert!(from_ogr_layer(&dataset, "NAME", &grid).is_err());
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,510 @@
+//! Streams named `highway=*` ways from an OpenStreetMap `.osm.pbf` file
+//! and assigns grid cells to them
+//!
+//! The reader only decodes the parts of the PBF format that are needed for
+//! building a street index (nodes, dense nodes and ways). Node coordinates
+//! are kept in memory until the end of the file, since ways only reference
+//! their nodes by id. As usual for `.osm.pbf` files, nodes have to come
+//! before the ways that reference them.
+
+use std::{fmt, error::Error, io::{self, Read}};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use flate2::read::ZlibDecoder;
+use roads2csv::{InputStreetValue, StreetName};
+use gridconfig::GeoGrid;
+
+/// Maximum size of a `BlobHeader`, as defined by the PBF format
+const MAX_BLOB_HEADER_SIZE: usize = 64 * 1024;
+/// Maximum (uncompressed) size of a `Blob`, as defined by the PBF format
+const MAX_BLOB_SIZE: usize = 32 * 1024 * 1024;
+
+/// Error that can happen while reading an `.osm.pbf` file
+#[derive(Debug)]
+pub enum OsmError {
+    /// Reading from the underlying reader failed
+    Io(io::Error),
+    /// The file is not a valid `.osm.pbf` file
+    InvalidData(&'static str),
+    /// The file uses a compression other than zlib (i.e. lzma or zstd)
+    UnsupportedCompression,
+}
+
+impl fmt::Display for OsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::OsmError::*;
+        match self {
+            Io(e) => write!(f, "I/O error: {}", e),
+            InvalidData(reason) => write!(f, "invalid .osm.pbf file: {}", reason),
+            UnsupportedCompression => write!(f, "unsupported blob compression (only raw and zlib are supported)"),
+        }
+    }
+}
+
+impl Error for OsmError { }
+
+impl From<io::Error> for OsmError {
+    fn from(e: io::Error) -> Self {
+        OsmError::Io(e)
+    }
+}
+
+/// Streaming reader that yields one `InputStreetValue` for every cell of
+/// the `grid` that a named `highway=*` way touches.
+///
+/// ```no_run,ignore
+/// let file = File::open("city.osm.pbf")?;
+/// for street in OsmRoadReader::new(BufReader::new(file), grid) {
+///     builder.push(street?);
+/// }
+/// ```
+pub struct OsmRoadReader<R: Read> {
+    reader: R,
+    grid: GeoGrid,
+    /// Node id -> (lon, lat)
+    nodes: HashMap<i64, (f64, f64)>,
+    /// Streets of the last decoded block that weren't returned yet
+    pending: VecDeque<InputStreetValue>,
+    finished: bool,
+}
+
+impl<R: Read> OsmRoadReader<R> {
+
+    /// Creates a new reader, the coordinates of the `grid` have to be
+    /// in WGS84 longitude / latitude
+    pub fn new(reader: R, grid: GeoGrid) -> Self {
+        Self {
+            reader,
+            grid,
+            nodes: HashMap::new(),
+            pending: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    /// Reads the next blob from the file, returns `false` on the end of the file
+    fn read_next_blob(&mut self) -> Result<bool, OsmError> {
+
+        let mut header_len = [0; 4];
+        match self.reader.read_exact(&mut header_len) {
+            Ok(()) => { },
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+
+        let header_len = u32::from_be_bytes(header_len) as usize;
+        if header_len > MAX_BLOB_HEADER_SIZE {
+            return Err(OsmError::InvalidData("blob header too large"));
+        }
+
+        let header = read_bytes(&mut self.reader, header_len)?;
+        let mut blob_type = None;
+        let mut data_size = None;
+        for field in Message::new(&header) {
+            match field? {
+                (1, Field::Bytes(t)) => blob_type = Some(t.to_vec()),
+                (3, Field::Varint(size)) => data_size = Some(size as usize),
+                _ => { },
+            }
+        }
+
+        let data_size = data_size.ok_or(OsmError::InvalidData("blob header without data size"))?;
+        if data_size > MAX_BLOB_SIZE {
+            return Err(OsmError::InvalidData("blob too large"));
+        }
+
+        let blob = read_bytes(&mut self.reader, data_size)?;
+
+        // Only "OSMData" blobs contain nodes and ways, "OSMHeader" only
+        // contains metadata, unknown blob types have to be skipped
+        if blob_type.as_ref().map(|t| &t[..]) == Some(&b"OSMData"[..]) {
+            let block = decode_blob(&blob)?;
+            self.decode_primitive_block(&block)?;
+        }
+
+        Ok(true)
+    }
+
+    fn decode_primitive_block(&mut self, block: &[u8]) -> Result<(), OsmError> {
+
+        let mut string_table = Vec::new();
+        let mut groups = Vec::new();
+        let mut granularity = 100_i64;
+        let mut lat_offset = 0_i64;
+        let mut lon_offset = 0_i64;
+
+        for field in Message::new(block) {
+            match field? {
+                (1, Field::Bytes(table)) => {
+                    for field in Message::new(table) {
+                        if let (1, Field::Bytes(s)) = field? {
+                            string_table.push(String::from_utf8_lossy(s).into_owned());
+                        }
+                    }
+                },
+                (2, Field::Bytes(group)) => groups.push(group),
+                (17, Field::Varint(v)) => granularity = v as i64,
+                (19, Field::Varint(v)) => lat_offset = v as i64,
+                (20, Field::Varint(v)) => lon_offset = v as i64,
+                _ => { },
+            }
+        }
+
+        let to_degrees = |offset: i64, value: i64| 1e-9 * (offset + granularity * value) as f64;
+
+        for group in groups {
+            for field in Message::new(group) {
+                match field? {
+                    (1, Field::Bytes(node)) => {
+                        let mut id = 0;
+                        let (mut lat, mut lon) = (0, 0);
+                        for field in Message::new(node) {
+                            match field? {
+                                (1, Field::Varint(v)) => id = zigzag(v),
+                                (8, Field::Varint(v)) => lat = zigzag(v),
+                                (9, Field::Varint(v)) => lon = zigzag(v),
+                                _ => { },
+                            }
+                        }
+                        self.nodes.insert(id, (to_degrees(lon_offset, lon), to_degrees(lat_offset, lat)));
+                    },
+                    (2, Field::Bytes(dense)) => {
+                        let (mut ids, mut lats, mut lons) = (Vec::new(), Vec::new(), Vec::new());
+                        for field in Message::new(dense) {
+                            match field? {
+                                (1, field) => ids.extend(packed_varints(field)?.into_iter().map(zigzag)),
+                                (8, field) => lats.extend(packed_varints(field)?.into_iter().map(zigzag)),
+                                (9, field) => lons.extend(packed_varints(field)?.into_iter().map(zigzag)),
+                                _ => { },
+                            }
+                        }
+                        if ids.len() != lats.len() || ids.len() != lons.len() {
+                            return Err(OsmError::InvalidData("dense nodes with mismatched lengths"));
+                        }
+                        // all values are delta-coded
+                        let (mut id, mut lat, mut lon) = (0_i64, 0_i64, 0_i64);
+                        for i in 0..ids.len() {
+                            id += ids[i];
+                            lat += lats[i];
+                            lon += lons[i];
+                            self.nodes.insert(id, (to_degrees(lon_offset, lon), to_degrees(lat_offset, lat)));
+                        }
+                    },
+                    (3, Field::Bytes(way)) => {
+                        self.decode_way(way, &string_table)?;
+                    },
+                    _ => { },
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decode_way(&mut self, way: &[u8], string_table: &[String]) -> Result<(), OsmError> {
+
+        let (mut keys, mut values, mut refs) = (Vec::new(), Vec::new(), Vec::new());
+        for field in Message::new(way) {
+            match field? {
+                (2, field) => keys.extend(packed_varints(field)?),
+                (3, field) => values.extend(packed_varints(field)?),
+                (8, field) => refs.extend(packed_varints(field)?.into_iter().map(zigzag)),
+                _ => { },
+            }
+        }
+
+        let mut is_highway = false;
+        let mut name = None;
+        for (key, value) in keys.iter().zip(values.iter()) {
+            let key = string_table.get(*key as usize).ok_or(OsmError::InvalidData("invalid string table index"))?;
+            let value = string_table.get(*value as usize).ok_or(OsmError::InvalidData("invalid string table index"))?;
+            match key.as_str() {
+                "highway" => is_highway = true,
+                "name" if !value.trim().is_empty() => name = Some(value),
+                _ => { },
+            }
+        }
+
+        let name = match name {
+            Some(name) if is_highway => name,
+            _ => return Ok(()),
+        };
+
+        // refs are delta-coded, nodes that are not in the file are skipped
+        let mut node_id = 0;
+        let mut points = Vec::with_capacity(refs.len());
+        for delta in refs {
+            node_id += delta;
+            if let Some(point) = self.nodes.get(&node_id) {
+                points.push(*point);
+            }
+        }
+
+        for position in self.grid.cells_for_linestring(&points) {
+            self.pending.push_back(InputStreetValue {
+                street_name: StreetName(name.clone()),
+                position,
+                category: None,
+                number_range: None,
+                weight: None,
+                secondary_name: None,
+                district: None,
+                attributes: BTreeMap::new(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for OsmRoadReader<R> {
+    type Item = Result<InputStreetValue, OsmError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(street) = self.pending.pop_front() {
+                return Some(Ok(street));
+            }
+
+            if self.finished {
+                return None;
+            }
+
+            match self.read_next_blob() {
+                Ok(true) => { },
+                Ok(false) => self.finished = true,
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Reads all named `highway=*` ways from an `.osm.pbf` file, see `OsmRoadReader`
+pub fn from_osm_pbf<R: Read>(reader: R, grid: &GeoGrid) -> Result<Vec<InputStreetValue>, OsmError> {
+    OsmRoadReader::new(reader, grid.clone()).collect()
+}
+
+fn read_bytes<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>, OsmError> {
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Returns the decompressed content of a `Blob` message
+fn decode_blob(blob: &[u8]) -> Result<Vec<u8>, OsmError> {
+
+    let mut raw_size = None;
+    let mut data = None;
+
+    for field in Message::new(blob) {
+        match field? {
+            (1, Field::Bytes(raw)) => return Ok(raw.to_vec()),
+            (2, Field::Varint(size)) => raw_size = Some(size as usize),
+            (3, Field::Bytes(zlib_data)) => data = Some(zlib_data),
+            (4, _) | (5, _) | (6, _) | (7, _) => return Err(OsmError::UnsupportedCompression),
+            _ => { },
+        }
+    }
+
+    let data = data.ok_or(OsmError::InvalidData("blob without data"))?;
+    let raw_size = raw_size.unwrap_or(0).min(MAX_BLOB_SIZE);
+
+    let mut decompressed = Vec::with_capacity(raw_size);
+    ZlibDecoder::new(data).take(MAX_BLOB_SIZE as u64).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Decodes a zigzag-encoded `sint64`
+fn zigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// Repeated numeric fields are usually packed, but may also be stored one by one
+fn packed_varints(field: Field) -> Result<Vec<u64>, OsmError> {
+    match field {
+        Field::Varint(v) => Ok(vec![v]),
+        Field::Bytes(mut data) => {
+            let mut values = Vec::new();
+            while !data.is_empty() {
+                values.push(read_varint(&mut data)?);
+            }
+            Ok(values)
+        },
+        Field::Fixed => Err(OsmError::InvalidData("unexpected fixed-size field")),
+    }
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<u64, OsmError> {
+    let mut value = 0_u64;
+    for shift in 0..10 {
+        let (byte, rest) = data.split_first().ok_or(OsmError::InvalidData("truncated varint"))?;
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << (shift * 7);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(OsmError::InvalidData("varint too long"))
+}
+
+/// One field of a protobuf message, only the wire types used by the PBF format
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    /// 32 or 64 bit value, not used by any of the decoded fields
+    Fixed,
+}
+
+/// Iterator over the `(field number, value)` pairs of a protobuf message
+struct Message<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Message<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn next_field(&mut self) -> Result<(u64, Field<'a>), OsmError> {
+        let key = read_varint(&mut self.data)?;
+        let field = match key & 0x7 {
+            0 => Field::Varint(read_varint(&mut self.data)?),
+            1 | 5 => {
+                let len = if key & 0x7 == 1 { 8 } else { 4 };
+                if self.data.len() < len {
+                    return Err(OsmError::InvalidData("truncated field"));
+                }
+                self.data = &self.data[len..];
+                Field::Fixed
+            },
+            2 => {
+                let len = read_varint(&mut self.data)? as usize;
+                if self.data.len() < len {
+                    return Err(OsmError::InvalidData("truncated field"));
+                }
+                let (bytes, rest) = self.data.split_at(len);
+                self.data = rest;
+                Field::Bytes(bytes)
+            },
+            _ => return Err(OsmError::InvalidData("unsupported wire type")),
+        };
+        Ok((key >> 3, field))
+    }
+}
+
+impl<'a> Iterator for Message<'a> {
+    type Item = Result<(u64, Field<'a>), OsmError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let field = self.next_field();
+        if field.is_err() {
+            // don't return the same error over and over
+            self.data = &[];
+        }
+        Some(field)
+    }
+}
+
+#[test]
+fn test_from_osm_pbf() {
+    use std::io::Write;
+    use flate2::{Compression, write::ZlibEncoder};
+    use roads2csv::GridPosition;
+
+    fn varint(mut v: u64, out: &mut Vec<u8>) {
+        while v >= 0x80 {
+            out.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        out.push(v as u8);
+    }
+    fn sint(v: i64) -> u64 { ((v << 1) ^ (v >> 63)) as u64 }
+    fn bytes_field(field: u64, data: &[u8], out: &mut Vec<u8>) {
+        varint(field << 3 | 2, out);
+        varint(data.len() as u64, out);
+        out.extend_from_slice(data);
+    }
+    fn varint_field(field: u64, value: u64, out: &mut Vec<u8>) {
+        varint(field << 3, out);
+        varint(value, out);
+    }
+    fn packed(values: &[u64]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for v in values { varint(*v, &mut out); }
+        out
+    }
+    fn blob(blob_type: &str, block: &[u8], compress: bool, out: &mut Vec<u8>) {
+        let mut blob = Vec::new();
+        if compress {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(block).unwrap();
+            varint_field(2, block.len() as u64, &mut blob);
+            bytes_field(3, &encoder.finish().unwrap(), &mut blob);
+        } else {
+            bytes_field(1, block, &mut blob);
+        }
+        let mut header = Vec::new();
+        bytes_field(1, blob_type.as_bytes(), &mut header);
+        varint_field(3, blob.len() as u64, &mut header);
+        out.extend_from_slice(&(header.len() as u32).to_be_bytes());
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&blob);
+    }
+
+    // string table: 0 = "", 1 = "highway", 2 = "residential", 3 = "name", 4 = "Canterbury Road", 5 = "Mill Lane"
+    let mut string_table = Vec::new();
+    for s in &["", "highway", "residential", "name", "Canterbury Road", "Mill Lane"] {
+        bytes_field(1, s.as_bytes(), &mut string_table);
+    }
+
+    // granularity 100: 1 degree = 10_000_000 units
+    let deg = |d: f64| (d * 1e7) as i64;
+
+    // three dense nodes (delta coded): (lon 0.5, lat 9.5), (1.5, 9.5), (1.5, 8.5)
+    let mut dense = Vec::new();
+    bytes_field(1, &packed(&[sint(1), sint(1), sint(1)]), &mut dense);
+    bytes_field(8, &packed(&[sint(deg(9.5)), sint(0), sint(-deg(1.0))]), &mut dense);
+    bytes_field(9, &packed(&[sint(deg(0.5)), sint(deg(1.0)), sint(0)]), &mut dense);
+
+    // highway with a name, highway without name, named way that isn't a highway
+    let way = |id: u64, keys: &[u64], vals: &[u64], refs: &[i64]| {
+        let mut way = Vec::new();
+        varint_field(1, id, &mut way);
+        bytes_field(2, &packed(keys), &mut way);
+        bytes_field(3, &packed(vals), &mut way);
+        bytes_field(8, &packed(&refs.iter().map(|r| sint(*r)).collect::<Vec<_>>()), &mut way);
+        way
+    };
+
+    let mut group = Vec::new();
+    bytes_field(2, &dense, &mut group);
+    bytes_field(3, &way(10, &[1, 3], &[2, 4], &[1, 1, 1]), &mut group);
+    bytes_field(3, &way(11, &[1], &[2], &[1, 1]), &mut group);
+    bytes_field(3, &way(12, &[3], &[5], &[1, 1]), &mut group);
+
+    let mut block = Vec::new();
+    bytes_field(1, &string_table, &mut block);
+    bytes_field(2, &group, &mut block);
+
+    let mut file = Vec::new();
+    blob("OSMHeader", &[], false, &mut file);
+    blob("OSMData", &block, true, &mut file);
+
+    let grid = GeoGrid::new(0.0, 10.0, 1.0, 1.0);
+    let streets = from_osm_pbf(&file[..], &grid).unwrap();
+
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let cells = streets.iter().map(|s| (s.street_name.0.as_str(), s.position.clone())).collect::<Vec<_>>();
+    assert_eq!(cells, vec![
+        ("Canterbury Road", cell("A", 1)),
+        ("Canterbury Road", cell("B", 1)),
+        ("Canterbury Road", cell("B", 2)),
+    ]);
+
+    assert!(from_osm_pbf(&file[..file.len() - 1], &grid).is_err());
+}
@@ -0,0 +1,160 @@
+//! Queries named highways of an area from an Overpass API endpoint
+//! (OpenStreetMap) and assigns grid cells to them
+//!
+//! The request is made asynchronously with `reqwest`, so the returned future
+//! needs to run inside of a Tokio runtime:
+//!
+//! ```no_run,ignore
+//! let bbox = BoundingBox { west: 13.37, south: 52.50, east: 13.42, north: 52.53 };
+//! let grid = GeoGrid::new(13.37, 52.53, 0.005, 0.003);
+//! let streets = query_overpass(DEFAULT_ENDPOINT, &bbox, &grid).await?;
+//! ```
+//!
+//! The response coordinates are longitude / latitude, use
+//! `Projection::WebMercator` for grids in Web Mercator meters.
+
+use std::{fmt, error::Error, future::Future};
+use futures_util::{future, FutureExt, TryFutureExt};
+use serde_json::{self, Value};
+use roads2csv::InputStreetValue;
+use gridconfig::GeoGrid;
+
+/// Public Overpass API instance of the OpenStreetMap community
+pub const DEFAULT_ENDPOINT: &str = "https://overpass-api.de/api/interpreter";
+
+/// Area to query, in degrees (WGS 84)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingBox {
+    pub west: f64,
+    pub south: f64,
+    pub east: f64,
+    pub north: f64,
+}
+
+/// Error that can happen while querying the Overpass API
+#[derive(Debug)]
+pub enum OverpassError {
+    /// The request failed or the endpoint returned an error status
+    Http(reqwest::Error),
+    /// The response is not valid JSON
+    Json(serde_json::Error),
+    /// The response JSON has no `elements` array
+    InvalidResponse,
+}
+
+impl fmt::Display for OverpassError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::OverpassError::*;
+        match self {
+            Http(e) => write!(f, "Overpass request failed: {}", e),
+            Json(e) => write!(f, "invalid Overpass response: {}", e),
+            InvalidResponse => write!(f, "invalid Overpass response: missing \"elements\" array"),
+        }
+    }
+}
+
+impl Error for OverpassError { }
+
+impl From<reqwest::Error> for OverpassError {
+    fn from(e: reqwest::Error) -> Self {
+        OverpassError::Http(e)
+    }
+}
+
+impl From<serde_json::Error> for OverpassError {
+    fn from(e: serde_json::Error) -> Self {
+        OverpassError::Json(e)
+    }
+}
+
+/// Queries all named `highway=*` ways inside of the `bbox` from the Overpass
+/// `endpoint` (i.e. `DEFAULT_ENDPOINT`) and returns one `InputStreetValue`
+/// for every cell of the `grid` that a way touches
+pub fn query_overpass(endpoint: &str, bbox: &BoundingBox, grid: &GeoGrid)
+-> impl Future<Output = Result<Vec<InputStreetValue>, OverpassError>>
+{
+    let grid = grid.clone();
+    reqwest::Client::new()
+        .post(endpoint)
+        .form(&[("data", overpass_query(bbox))])
+        .send()
+        .and_then(|response| future::ready(response.error_for_status()))
+        .and_then(|response| response.text())
+        .map(move |response| from_overpass_json(&response?, &grid))
+}
+
+/// Overpass QL query for the named highways in the `bbox`, with the
+/// coordinates of the ways inlined (`out geom`)
+pub fn overpass_query(bbox: &BoundingBox) -> String {
+    format!(
+        "[out:json][timeout:120];way[\"highway\"][\"name\"]({},{},{},{});out geom;",
+        bbox.south, bbox.west, bbox.north, bbox.east,
+    )
+}
+
+/// Converts the JSON response of `overpass_query` into input streets, ways
+/// without a name or without geometry are ignored
+pub fn from_overpass_json(json: &str, grid: &GeoGrid) -> Result<Vec<InputStreetValue>, OverpassError> {
+    let root: Value = serde_json::from_str(json)?;
+    let elements = root.get("elements").and_then(Value::as_array).ok_or(OverpassError::InvalidResponse)?;
+
+    let mut streets = Vec::new();
+
+    for element in elements {
+
+        if element.get("type").and_then(Value::as_str) != Some("way") {
+            continue;
+        }
+
+        let name = match element.get("tags").and_then(|tags| tags.get("name")).and_then(Value::as_str) {
+            Some(name) if !name.trim().is_empty() => name,
+            _ => continue,
+        };
+
+        let line = element.get("geometry")
+            .and_then(Value::as_array)
+            .map(|points| points.iter().filter_map(|point| {
+                Some((point.get("lon")?.as_f64()?, point.get("lat")?.as_f64()?))
+            }).collect::<Vec<(f64, f64)>>())
+            .unwrap_or_default();
+
+        let mut cells = Vec::new();
+        for cell in grid.cells_for_linestring(&line) {
+            if !cells.contains(&cell) {
+                cells.push(cell);
+            }
+        }
+
+        streets.extend(cells.into_iter().map(|position| InputStreetValue::new(name, position)));
+    }
+
+    Ok(streets)
+}
+
+#[test]
+fn test_from_overpass_json() {
+    use roads2csv::GridPosition;
+
+    let bbox = BoundingBox { west: 0.0, south: 5.0, east: 4.0, north: 10.0 };
+    assert_eq!(overpass_query(&bbox), "[out:json][timeout:120];way[\"highway\"][\"name\"](5,0,10,4);out geom;");
+
+    let json = r#"{
+        "version": 0.6,
+        "elements": [
+            { "type": "way", "id": 1, "tags": { "highway": "residential", "name": "Canterbury Road" },
+              "geometry": [{ "lat": 9.5, "lon": 0.5 }, { "lat": 9.5, "lon": 1.5 }] },
+            { "type": "way", "id": 2, "tags": { "highway": "service" },
+              "geometry": [{ "lat": 9.5, "lon": 0.5 }] },
+            { "type": "node", "id": 3, "lat": 8.5, "lon": 3.5, "tags": { "name": "Town Hall" } }
+        ]
+    }"#;
+
+    let grid = GeoGrid::new(0.0, 10.0, 1.0, 1.0);
+    let streets = from_overpass_json(json, &grid).unwrap();
+
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let cells = streets.iter().map(|s| (s.street_name.0.as_str(), s.position.clone())).collect::<Vec<_>>();
+    assert_eq!(cells, vec![("Canterbury Road", cell("A", 1)), ("Canterbury Road", cell("B", 1))]);
+
+    assert!(from_overpass_json(r#"{ "remark": "runtime error" }"#, &grid).is_err());
+}
@@ -0,0 +1,298 @@
+//! All settings for building an index in one place, i.e. for a web service
+//! that builds indexes concurrently: configure one `Pipeline` at startup,
+//! share it between threads (it is `Send + Sync`) and `run` it per request
+//!
+//! ```no_run,ignore
+//! let pipeline = Arc::new(Pipeline::new()
+//!     .with_normalizer(Normalizer::english())
+//!     .with_filter(Filter::new().exclude_empty())
+//!     .with_process_options(ProcessOptions::default().with_ranges(true)));
+//!
+//! // per request, on any thread
+//! let index = pipeline.run(streets);
+//! let csv = pipeline.to_csv(&index);
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+use roads2csv::{DeduplicatedRoadsBuilder, InputStreetValue, StreetIndex, ProcessOptions, CsvOptions};
+use normalize::{Normalizer, DedupOptions};
+use filter::Filter;
+use alias::AliasTable;
+use import::Importer;
+use progress::{Progress, Stage, PROGRESS_INTERVAL};
+use cancel::CancellationToken;
+use error::Result;
+
+/// Filter, aliases, deduplication, processing and CSV export settings
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    pub dedup_options: DedupOptions,
+    /// Streets excluded by the filter are skipped before deduplication
+    pub filter: Option<Filter>,
+    pub aliases: Option<AliasTable>,
+    pub process_options: ProcessOptions,
+    /// Settings for `to_csv()`
+    pub csv_options: CsvOptions,
+    /// Receives the progress of every run (see `Stage`)
+    pub progress: Option<Arc<dyn Progress>>,
+    /// Aborts `try_run()` and `run_importer()` once it is cancelled
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("dedup_options", &self.dedup_options)
+            .field("filter", &self.filter)
+            .field("aliases", &self.aliases)
+            .field("process_options", &self.process_options)
+            .field("csv_options", &self.csv_options)
+            .field("progress", &self.progress.is_some())
+            .field("cancellation", &self.cancellation)
+            .finish()
+    }
+}
+
+impl Pipeline {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_dedup_options(self, dedup_options: DedupOptions) -> Self {
+        Self { dedup_options, .. self }
+    }
+
+    /// Normalizes the names before deduplication (see `DedupOptions::normalizer`)
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.dedup_options.normalizer = Some(normalizer);
+        self
+    }
+
+    pub fn with_filter(self, filter: Filter) -> Self {
+        Self { filter: Some(filter), .. self }
+    }
+
+    pub fn with_aliases(self, aliases: AliasTable) -> Self {
+        Self { aliases: Some(aliases), .. self }
+    }
+
+    pub fn with_process_options(self, process_options: ProcessOptions) -> Self {
+        Self { process_options, .. self }
+    }
+
+    pub fn with_csv_options(self, csv_options: CsvOptions) -> Self {
+        Self { csv_options, .. self }
+    }
+
+    /// Reports the progress of every run to `progress`, i.e. a closure
+    /// `|stage, done, total| ...` or an `indicatif::ProgressBar`
+    pub fn with_progress<P: Progress + 'static>(self, progress: P) -> Self {
+        Self { progress: Some(Arc::new(progress)), .. self }
+    }
+
+    /// Aborts `try_run()` and `run_importer()` with `Error::Cancelled` once
+    /// the `cancellation` token (or a clone of it) is cancelled
+    pub fn with_cancellation(self, cancellation: CancellationToken) -> Self {
+        Self { cancellation: Some(cancellation), .. self }
+    }
+
+    /// Filters, deduplicates and processes the `streets`. The total number
+    /// of rows is reported if the iterator knows its exact length. Ignores
+    /// the cancellation token, see `try_run()`.
+    pub fn run<I: IntoIterator<Item = InputStreetValue>>(&self, streets: I) -> StreetIndex {
+        self.run_with(streets, None).expect("a run without cancellation can not fail")
+    }
+
+    /// Same as `run()`, but fails with `Error::Cancelled` if the
+    /// cancellation token is cancelled before the index is built
+    pub fn try_run<I: IntoIterator<Item = InputStreetValue>>(&self, streets: I) -> Result<StreetIndex> {
+        self.run_with(streets, self.cancellation.as_ref())
+    }
+
+    fn run_with<I: IntoIterator<Item = InputStreetValue>>(&self, streets: I, cancellation: Option<&CancellationToken>) -> Result<StreetIndex> {
+        let streets = streets.into_iter();
+        let total = match streets.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(lower as u64),
+            _ => None,
+        };
+        let mut builder = self.builder();
+        let mut rows = 0;
+        for street in streets {
+            if let Some(cancellation) = cancellation {
+                cancellation.check()?;
+            }
+            builder.push(street);
+            rows += 1;
+            self.report_rows(rows, total);
+        }
+        self.finish(builder, rows, cancellation)
+    }
+
+    /// Same as `try_run()`, but reads the streets from the `importer`, stops
+    /// at the first error of the importer
+    pub fn run_importer<I: Importer>(&self, mut importer: I) -> Result<StreetIndex> {
+        let cancellation = self.cancellation.as_ref();
+        let mut builder = self.builder();
+        let mut rows = 0;
+        while let Some(street) = importer.read()? {
+            if let Some(cancellation) = cancellation {
+                cancellation.check()?;
+            }
+            builder.push(street);
+            rows += 1;
+            self.report_rows(rows, None);
+        }
+        self.finish(builder, rows, cancellation)
+    }
+
+    fn report(&self, stage: Stage, done: u64, total: Option<u64>) {
+        if let Some(progress) = &self.progress {
+            progress.update(stage, done, total);
+        }
+    }
+
+    fn report_rows(&self, rows: u64, total: Option<u64>) {
+        if rows % PROGRESS_INTERVAL == 0 {
+            self.report(Stage::Reading, rows, total);
+        }
+    }
+
+    /// Deduplicates and processes the roads of the `builder` after all
+    /// `rows` were read
+    fn finish(&self, builder: DeduplicatedRoadsBuilder, rows: u64, cancellation: Option<&CancellationToken>) -> Result<StreetIndex> {
+        self.report(Stage::Reading, rows, Some(rows));
+        self.report(Stage::Deduplicating, 0, Some(1));
+        let deduplicated = match cancellation {
+            Some(cancellation) => builder.finish_cancellable(cancellation)?,
+            None => builder.finish(),
+        };
+        self.report(Stage::Deduplicating, 1, Some(1));
+
+        let roads = (deduplicated.roads.len() + deduplicated.categories.values().map(|roads| roads.len()).sum::<usize>()) as u64;
+        self.report(Stage::Processing, 0, Some(roads));
+        let index = match cancellation {
+            Some(cancellation) => deduplicated.process_cancellable(&self.process_options, cancellation)?,
+            None => deduplicated.process_with_options(&self.process_options),
+        };
+        self.report(Stage::Processing, roads, Some(roads));
+        Ok(index)
+    }
+
+    fn builder(&self) -> DeduplicatedRoadsBuilder {
+        let mut builder = DeduplicatedRoadsBuilder::new().with_options(self.dedup_options.clone());
+        if let Some(filter) = &self.filter {
+            builder = builder.with_filter(filter.clone());
+        }
+        if let Some(aliases) = &self.aliases {
+            builder = builder.with_aliases(aliases.clone());
+        }
+        builder
+    }
+
+    /// Exports the processed roads followed by the unprocessed roads with
+    /// the `csv_options` (the header row is only written once)
+    pub fn to_csv(&self, index: &StreetIndex) -> String {
+        let processed = index.processed.to_csv_with_options(&self.csv_options);
+        let unprocessed = index.unprocessed.to_csv_with_options(&self.csv_options.clone().with_header(false));
+        match (processed.is_empty(), unprocessed.is_empty()) {
+            (_, true) => processed,
+            (true, false) => unprocessed,
+            (false, false) => format!("{}{}{}", processed, self.csv_options.line_ending.as_str(), unprocessed),
+        }
+    }
+}
+
+#[test]
+fn test_pipeline() {
+    use std::sync::Arc;
+    use std::thread;
+    use roads2csv::LineEnding;
+
+    fn assert_send_sync<T: Send + Sync>() { }
+    assert_send_sync::<Pipeline>();
+
+    let pipeline = Arc::new(Pipeline::new()
+        .with_dedup_options(DedupOptions { case_insensitive: true, .. DedupOptions::default() })
+        .with_filter(Filter::new().exclude_names(&["Private Road"]))
+        .with_process_options(ProcessOptions::default().with_ranges(true))
+        .with_csv_options(CsvOptions::default().with_delimiter(';').with_line_ending(LineEnding::Lf).with_header(true)));
+
+    let threads = (0..4).map(|i| {
+        let pipeline = pipeline.clone();
+        thread::spawn(move || {
+            let streets = ["A1", "A2", "A3"].iter()
+                .map(|cell| InputStreetValue::new(if i % 2 == 0 { "Mill Lane" } else { "MILL LANE" }, cell.parse().unwrap()))
+                .chain(vec![
+                    InputStreetValue::new("Private Road", "B1".parse().unwrap()),
+                    InputStreetValue::new("Valley Road", "C1".parse().unwrap()),
+                    InputStreetValue::new("Valley Road", "E5".parse().unwrap()),
+                    InputStreetValue::new("Valley Road", "F9".parse().unwrap()),
+                ]);
+            pipeline.to_csv(&pipeline.run(streets))
+        })
+    }).collect::<Vec<_>>();
+
+    for (i, thread) in threads.into_iter().enumerate() {
+        let name = if i % 2 == 0 { "Mill Lane" } else { "MILL LANE" };
+        assert_eq!(thread.join().unwrap(), format!("Name;Position\n{};A1-A3\nValley Road;\"C1; E5; F9\"", name));
+    }
+
+    let importer = ::import::CsvImporter::new("Mill Lane\tA1-A3\nPrivate Road\tB1\nValley Road\tC1\tE5\tF9".as_bytes(), "\t");
+    let index = pipeline.run_importer(importer).unwrap();
+    assert_eq!(pipeline.to_csv(&index), "Name;Position\nMill Lane;A1-A3\nValley Road;\"C1; E5; F9\"");
+}
+
+#[test]
+fn test_pipeline_progress() {
+    use std::sync::Mutex;
+
+    let updates = Arc::new(Mutex::new(Vec::new()));
+    let pipeline = Pipeline::new().with_progress({
+        let updates = updates.clone();
+        move |stage, done, total| updates.lock().unwrap().push((stage, done, total))
+    });
+
+    let streets = (0..PROGRESS_INTERVAL + 5).map(|i| InputStreetValue::new(if i % 2 == 0 { "Mill Lane" } else { "Valley Road" }, "A1".parse().unwrap()));
+    let streets = streets.collect::<Vec<_>>();
+    pipeline.run(streets.clone());
+    assert_eq!(*updates.lock().unwrap(), [
+        (Stage::Reading, PROGRESS_INTERVAL, Some(PROGRESS_INTERVAL + 5)),
+        (Stage::Reading, PROGRESS_INTERVAL + 5, Some(PROGRESS_INTERVAL + 5)),
+        (Stage::Deduplicating, 0, Some(1)),
+        (Stage::Deduplicating, 1, Some(1)),
+        (Stage::Processing, 0, Some(2)),
+        (Stage::Processing, 2, Some(2)),
+    ]);
+
+    updates.lock().unwrap().clear();
+    pipeline.run_importer(::import::StreetsImporter::new(streets)).unwrap();
+    assert_eq!(updates.lock().unwrap()[0], (Stage::Reading, PROGRESS_INTERVAL, None));
+    assert!(format!("{:?}", pipeline).ends_with("progress: true, cancellation: None }"));
+}
+
+#[test]
+fn test_pipeline_cancellation() {
+    use error::Error;
+    use roads2csv::DeduplicatedRoads;
+
+    let streets = (0..100).map(|i| InputStreetValue::new(&format!("Street {}", i), "A1".parse().unwrap())).collect::<Vec<_>>();
+    let token = CancellationToken::new();
+    let pipeline = Pipeline::new().with_cancellation(token.clone());
+    assert_eq!(pipeline.try_run(streets.clone()).unwrap(), pipeline.run(streets.clone()));
+
+    // cancelled by another clone of the token while deduplicating
+    let pipeline = pipeline.with_progress({
+        let token = token.clone();
+        move |stage, _, _| if stage == Stage::Deduplicating { token.cancel() }
+    });
+    assert!(matches!(pipeline.try_run(streets.clone()), Err(Error::Cancelled)));
+    assert!(matches!(pipeline.run_importer(::import::StreetsImporter::new(streets.clone())), Err(Error::Cancelled)));
+    assert_eq!(pipeline.run(streets.clone()).processed.processed.len(), 100);
+
+    let deduplicated = DeduplicatedRoads::from_streets(&streets);
+    assert!(matches!(deduplicated.process_cancellable(&ProcessOptions::default(), &token), Err(Error::Cancelled)));
+    assert!(matches!(pipeline.builder().finish_cancellable(&token), Err(Error::Cancelled)));
+    assert!(deduplicated.process_cancellable(&ProcessOptions::default(), &CancellationToken::new()).is_ok());
+}
@@ -0,0 +1,221 @@
+//! Streams road geometries from a PostGIS table (or any query) and assigns
+//! grid cells to them
+//!
+//! The query is wrapped, so that PostGIS returns the geometries as WKB
+//! (`ST_AsBinary`), which is then decoded by this module:
+//!
+//! ```no_run,ignore
+//! let mut client = postgres::Client::connect("host=localhost dbname=gis", postgres::NoTls)?;
+//! let streets = from_postgis(&mut client, "SELECT * FROM roads WHERE district = 4", "strname", "geom", &grid)?;
+//! ```
+//!
+//! The geometries have to be in the same coordinate system as the `GeoGrid`,
+//! use `ST_Transform` in the query to reproject them.
+
+use std::{fmt, error::Error, iter};
+use postgres::{Client, types::ToSql, fallible_iterator::FallibleIterator};
+use roads2csv::InputStreetValue;
+use gridconfig::GeoGrid;
+
+/// Error that can happen while reading from PostGIS
+#[derive(Debug)]
+pub enum PostgisError {
+    /// The query failed or a column has an unexpected type
+    Postgres(postgres::Error),
+    /// The geometry of the row at the given index is not valid WKB
+    InvalidGeometry { row: usize },
+}
+
+impl fmt::Display for PostgisError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::PostgisError::*;
+        match self {
+            Postgres(e) => write!(f, "PostgreSQL error: {}", e),
+            InvalidGeometry { row } => write!(f, "row {} has an invalid geometry", row),
+        }
+    }
+}
+
+impl Error for PostgisError { }
+
+impl From<postgres::Error> for PostgisError {
+    fn from(e: postgres::Error) -> Self {
+        PostgisError::Postgres(e)
+    }
+}
+
+/// Runs the `query` and returns one `InputStreetValue` for every cell of the
+/// `grid` that the geometry in `geom_column` touches. The street name is read
+/// from `name_column`. Rows are streamed, so the result of the query doesn't
+/// need to fit into memory at once.
+///
+/// Rows with an empty name or without geometry are ignored.
+pub fn from_postgis(client: &mut Client, query: &str, name_column: &str, geom_column: &str, grid: &GeoGrid)
+-> Result<Vec<InputStreetValue>, PostgisError>
+{
+    let sql = format!(
+        "SELECT {}::text, ST_AsBinary({}) FROM ({}) AS street_index_source",
+        quote_identifier(name_column),
+        quote_identifier(geom_column),
+        query.trim().trim_end_matches(';'),
+    );
+
+    let mut rows = client.query_raw(sql.as_str(), iter::empty::<&dyn ToSql>())?;
+    let mut streets = Vec::new();
+    let mut row_index = 0;
+
+    while let Some(row) = rows.next()? {
+
+        let name: Option<String> = row.try_get(0)?;
+        let wkb: Option<Vec<u8>> = row.try_get(1)?;
+
+        if let (Some(name), Some(wkb)) = (name.filter(|name| !name.trim().is_empty()), wkb) {
+            let lines = parse_wkb(&wkb).ok_or(PostgisError::InvalidGeometry { row: row_index })?;
+            let mut cells = Vec::new();
+            for line in &lines {
+                for cell in grid.cells_for_linestring(line) {
+                    if !cells.contains(&cell) {
+                        cells.push(cell);
+                    }
+                }
+            }
+            streets.extend(cells.into_iter().map(|position| InputStreetValue::new(&name, position)));
+        }
+
+        row_index += 1;
+    }
+
+    Ok(streets)
+}
+
+/// Quotes a column name for SQL, i.e. `"strname"`
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Decodes (ISO or extended) WKB into the point lists of all (multi-)points,
+/// line strings and polygon rings, the Z / M values are ignored
+fn parse_wkb(wkb: &[u8]) -> Option<Vec<Vec<(f64, f64)>>> {
+    let mut reader = WkbReader { data: wkb, little_endian: true };
+    let mut lines = Vec::new();
+    reader.read_geometry(&mut lines)?;
+    Some(lines)
+}
+
+struct WkbReader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> WkbReader<'a> {
+
+    fn read_geometry(&mut self, lines: &mut Vec<Vec<(f64, f64)>>) -> Option<()> {
+        self.little_endian = match self.take(1)?[0] {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+
+        let geometry_type = self.read_u32()?;
+        // extended WKB (PostGIS) stores Z / M / SRID as flags
+        let mut dimensions = 2;
+        if geometry_type & 0x8000_0000 != 0 { dimensions += 1; }
+        if geometry_type & 0x4000_0000 != 0 { dimensions += 1; }
+        if geometry_type & 0x2000_0000 != 0 { self.read_u32()?; }
+        // ISO WKB adds 1000 (Z), 2000 (M) or 3000 (ZM) to the type
+        let geometry_type = geometry_type & 0x0FFF_FFFF;
+        dimensions += match geometry_type / 1000 { 1 | 2 => 1, 3 => 2, _ => 0 };
+
+        match geometry_type % 1000 {
+            1 => {
+                let point = self.read_point(dimensions)?;
+                // empty points are encoded as NaN
+                if !point.0.is_nan() && !point.1.is_nan() {
+                    lines.push(vec![point]);
+                }
+            },
+            2 => lines.push(self.read_points(dimensions)?),
+            3 => {
+                for _ in 0..self.read_u32()? {
+                    lines.push(self.read_points(dimensions)?);
+                }
+            },
+            4..=7 => {
+                for _ in 0..self.read_u32()? {
+                    self.read_geometry(lines)?;
+                }
+            },
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    fn read_points(&mut self, dimensions: usize) -> Option<Vec<(f64, f64)>> {
+        let count = self.read_u32()? as usize;
+        // don't trust the count for the allocation
+        if count.checked_mul(dimensions * 8)? > self.data.len() {
+            return None;
+        }
+        (0..count).map(|_| self.read_point(dimensions)).collect()
+    }
+
+    fn read_point(&mut self, dimensions: usize) -> Option<(f64, f64)> {
+        let x = self.read_f64()?;
+        let y = self.read_f64()?;
+        for _ in 2..dimensions {
+            self.read_f64()?;
+        }
+        Some((x, y))
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        let bytes = self.take(4)?;
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    fn read_f64(&mut self) -> Option<f64> {
+        let bytes = self.take(8)?;
+        let mut array = [0; 8];
+        array.copy_from_slice(bytes);
+        Some(if self.little_endian { f64::from_le_bytes(array) } else { f64::from_be_bytes(array) })
+    }
+
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        if self.data.len() < length {
+            return None;
+        }
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+        Some(taken)
+    }
+}
+
+#[test]
+fn test_parse_wkb() {
+    fn point(x: f64, y: f64, big_endian: bool) -> Vec<u8> {
+        if big_endian { [x.to_be_bytes(), y.to_be_bytes()].concat() } else { [x.to_le_bytes(), y.to_le_bytes()].concat() }
+    }
+
+    // LINESTRING(0.5 9.5, 1.5 9.5), little endian
+    let mut linestring = vec![1, 2, 0, 0, 0, 2, 0, 0, 0];
+    linestring.extend(point(0.5, 9.5, false));
+    linestring.extend(point(1.5, 9.5, false));
+    assert_eq!(parse_wkb(&linestring), Some(vec![vec![(0.5, 9.5), (1.5, 9.5)]]));
+
+    // MULTILINESTRING Z with one line, big endian ISO WKB
+    let mut multi = vec![0, 0, 0, 0x03, 0xED, 0, 0, 0, 1, 0, 0, 0, 0x03, 0xEA, 0, 0, 0, 1];
+    multi.extend(point(3.5, 8.5, true));
+    multi.extend(100.0f64.to_be_bytes().iter());
+    assert_eq!(parse_wkb(&multi), Some(vec![vec![(3.5, 8.5)]]));
+
+    // extended WKB point with SRID 4326
+    let mut ewkb_point = vec![1, 1, 0, 0, 0x20, 0xE6, 0x10, 0, 0];
+    ewkb_point.extend(point(13.4, 52.5, false));
+    assert_eq!(parse_wkb(&ewkb_point), Some(vec![vec![(13.4, 52.5)]]));
+
+    assert_eq!(parse_wkb(&linestring[..20]), None);
+    assert_eq!(parse_wkb(&[1, 2, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]), None);
+    assert_eq!(quote_identifier("street \"name\""), "\"street \"\"name\"\"\"");
+}
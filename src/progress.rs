@@ -0,0 +1,63 @@
+//! Progress reporting for long runs of a `Pipeline`, i.e. to show a
+//! progress bar while a country extract with millions of rows is read
+//!
+//! ```no_run,ignore
+//! let pipeline = Pipeline::new().with_progress(|stage: Stage, done: u64, total: Option<u64>| {
+//!     eprintln!("{}: {} / {:?}", stage, done, total);
+//! });
+//!
+//! // or with the `indicatif` feature
+//! let pipeline = Pipeline::new().with_progress(ProgressBar::new_spinner());
+//! ```
+
+use std::fmt;
+
+/// Rows between two progress updates while reading
+pub const PROGRESS_INTERVAL: u64 = 10_000;
+
+/// Step of a `Pipeline` run, in the order they are reported
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    /// Reading and filtering the rows, `done` is the number of rows read
+    Reading,
+    /// Merging the rows into roads, reported once at the start and at the end
+    Deduplicating,
+    /// Processing the roads, `done` / `total` are numbers of roads
+    Processing,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stage::Reading => write!(f, "reading"),
+            Stage::Deduplicating => write!(f, "deduplicating"),
+            Stage::Processing => write!(f, "processing"),
+        }
+    }
+}
+
+/// Receives the progress of a `Pipeline` run. Implemented for closures
+/// `Fn(Stage, done, total)`, `total` is `None` if it is not known (i.e.
+/// for rows read from an `Importer`).
+pub trait Progress: Send + Sync {
+    fn update(&self, stage: Stage, done: u64, total: Option<u64>);
+}
+
+impl<F: Fn(Stage, u64, Option<u64>) + Send + Sync> Progress for F {
+    fn update(&self, stage: Stage, done: u64, total: Option<u64>) {
+        self(stage, done, total)
+    }
+}
+
+/// Shows the stage as the message and the progress as the position of the
+/// bar, the length is only set if the total is known
+#[cfg(feature = "indicatif")]
+impl Progress for ::indicatif::ProgressBar {
+    fn update(&self, stage: Stage, done: u64, total: Option<u64>) {
+        self.set_message(stage.to_string());
+        if let Some(total) = total {
+            self.set_length(total);
+        }
+        self.set_position(done);
+    }
+}
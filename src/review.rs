@@ -0,0 +1,374 @@
+//! Suggestions for roads that need to be reviewed manually, so that the
+//! review becomes choosing between interpretations instead of editing text
+//!
+//! ```no_run,ignore
+//! let mut index = deduplicated.process();
+//! for road in index.unprocessed.unprocessed.clone() {
+//!     let suggestions = road.suggest_resolutions();
+//!     // let the user pick one, i.e. "A9-A10; E1-E2" or "A9-E2"
+//!     index.apply_resolution(&road.name, &suggestions[0]);
+//! }
+//! ```
+//!
+//! `StreetIndex::review_report` lists all of these roads with their suggestions
+//! and a sketch of their cells, so the decision can be made on paper:
+//!
+//! ```text
+//! Mill Lane: B1, B2, D2
+//! suggestions: B1-B2; D2 | B1-D2
+//!
+//!       B C D
+//!     1 # . .
+//!     2 # . #
+//! ```
+
+use std::{fmt, slice, ops::RangeInclusive, collections::{BTreeMap, BTreeSet}};
+use roads2csv::{
+    UnprocessedRoad, ProcessedRoad, StreetIndex, StreetName, GridPosition,
+    FinalizedGridPositon, ProcessOptions, finalize_positions, cluster_positions,
+};
+use gridconfig::{Grid, number_to_alphabet_value};
+use roads2html::escape_html;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// One interpretation of an `UnprocessedRoad`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Resolution {
+    /// The road is one road that is partly clipped off the map,
+    /// i.e. `"Canterbury Road" => A9-E2`
+    Span(FinalizedGridPositon),
+    /// The road consists of several roads with the same name,
+    /// i.e. `"Canterbury Road" => A9-A10; E1-E2`
+    Split(Vec<FinalizedGridPositon>),
+}
+
+impl Resolution {
+    /// Returns the positions that the road gets in the index
+    pub fn positions(&self) -> &[FinalizedGridPositon] {
+        match self {
+            Resolution::Span(position) => slice::from_ref(position),
+            Resolution::Split(positions) => positions,
+        }
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let positions = self.positions().iter().map(|p| p.to_string()).collect::<Vec<String>>();
+        write!(f, "{}", positions.join("; "))
+    }
+}
+
+impl UnprocessedRoad {
+
+    /// Returns the possible interpretations of the road, the more likely one
+    /// first: the road split into clusters of adjacent cells (if there is
+    /// more than one cluster) and one span from the first to the last cell
+    /// (if all cells are on the same page). Clusters that aren't a straight
+    /// run of cells are written as a span as well.
+    pub fn suggest_resolutions(&self) -> Vec<Resolution> {
+
+        let mut positions = self.positions.clone();
+        positions.sort();
+        positions.dedup();
+
+        let mut pages: Vec<Vec<GridPosition>> = Vec::new();
+        for position in positions {
+            match pages.last_mut() {
+                Some(page) if page[0].page == position.page => page.push(position),
+                _ => pages.push(vec![position]),
+            }
+        }
+
+        let mut suggestions = Vec::new();
+
+        let clusters = pages.iter()
+            .map(|page| cluster_positions(page, 0))
+            .collect::<Option<Vec<_>>>()
+            .map(|clusters| clusters.into_iter().flatten().collect::<Vec<Vec<GridPosition>>>());
+
+        if let Some(clusters) = clusters {
+            if clusters.len() > 1 {
+                let split = clusters.iter()
+                    .filter_map(|cluster| finalize_positions(cluster, &ProcessOptions::default().with_ranges(true)).or_else(|| span(cluster)))
+                    .collect();
+                suggestions.push(Resolution::Split(split));
+            }
+        }
+
+        if let [page] = pages.as_slice() {
+            suggestions.extend(span(page).map(Resolution::Span));
+        }
+
+        suggestions
+    }
+
+    /// Draws the cells of the road into the part of the grid between its
+    /// first and its last cell (one sketch per page), `#` for the cells of
+    /// the road and `.` for all other cells. Grids larger than
+    /// `MAX_SKETCH_SIZE` columns or rows are not drawn.
+    pub fn sketch(&self) -> String {
+        let mut pages = BTreeMap::new();
+        let mut outside = Vec::new();
+        for position in &self.positions {
+            let column = match position.column_index() {
+                Some(column) => column,
+                None => { outside.push(position.to_string()); continue; },
+            };
+            pages.entry(position.page.as_ref()).or_insert_with(BTreeSet::new).insert((column, position.row));
+        }
+
+        let mut lines = Vec::new();
+        for (page, cells) in pages {
+            if let Some(page) = page {
+                lines.push(format!("page {}:", page));
+            }
+            lines.extend(sketch_cells(&cells));
+        }
+        if !outside.is_empty() {
+            lines.push(format!("not in the grid: {}", outside.join(", ")));
+        }
+        lines.join("\n")
+    }
+
+    /// Draws the whole `grid` (i.e. the map sheet), `#` for the cells of the
+    /// road and `.` for all other cells. The pages of the positions are
+    /// ignored, cells outside of the grid are listed below it.
+    pub fn render_grid(&self, grid: &Grid) -> String {
+        let (columns, rows) = (grid.columns(), grid.rows());
+        let mut cells = BTreeSet::new();
+        let mut outside = Vec::new();
+        for position in &self.positions {
+            match position.column_index() {
+                Some(column) if column < columns && position.row >= 1 && position.row <= rows => { cells.insert((column, position.row)); },
+                _ => outside.push(position.to_string()),
+            }
+        }
+
+        let mut lines = if columns == 0 || rows == 0 { Vec::new() } else { draw_cells(&cells, 0..=columns - 1, 1..=rows) };
+        if !outside.is_empty() {
+            lines.push(format!("not in the grid: {}", outside.join(", ")));
+        }
+        lines.join("\n")
+    }
+
+    /// Returns the processed roads for the `resolution`, one per position
+    pub fn apply_resolution(&self, resolution: &Resolution) -> Vec<ProcessedRoad> {
+        resolution.positions().iter().map(|position| ProcessedRoad {
+            name: self.name.clone(),
+            position: position.clone(),
+            category: self.category.clone(),
+        }).collect()
+    }
+}
+
+impl StreetIndex {
+
+    /// Replaces the unprocessed road with the `name` by the processed roads of
+    /// the `resolution` (see `UnprocessedRoad::suggest_resolutions`). The roads
+    /// are inserted behind the processed roads with a smaller or equal name,
+    /// so that an index ordered by name stays ordered.
+    ///
+    /// Returns `false` if there is no unprocessed road with this name.
+    pub fn apply_resolution(&mut self, name: &StreetName, resolution: &Resolution) -> bool {
+        match self.unprocessed.unprocessed.iter().position(|road| road.name == *name) {
+            Some(index) => { self.resolve_unprocessed(index, resolution); true },
+            None => false,
+        }
+    }
+
+    /// Replaces the unprocessed road at `index` by the processed roads of
+    /// the `resolution`, see `apply_resolution()`
+    pub(crate) fn resolve_unprocessed(&mut self, index: usize, resolution: &Resolution) {
+        let road = self.unprocessed.unprocessed.remove(index);
+        let insert_at = self.processed.processed.iter().position(|processed| processed.name > road.name).unwrap_or(self.processed.processed.len());
+        let roads = road.apply_resolution(resolution);
+        self.processed.processed.splice(insert_at..insert_at, roads);
+    }
+}
+
+/// Maximum number of columns and rows drawn by `UnprocessedRoad::sketch`
+pub const MAX_SKETCH_SIZE: usize = 40;
+
+/// Format of `StreetIndex::review_report`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum ReportFormat {
+    /// Plain text, i.e. to print it or to paste it into an e-mail
+    #[default]
+    Text,
+    /// Self-contained HTML page, the sketches are preformatted text
+    Html,
+}
+
+impl StreetIndex {
+
+    /// Lists every road that needs to be reviewed with its cells, the
+    /// suggested resolutions and a sketch of the cells (see `UnprocessedRoad::sketch`)
+    pub fn review_report(&self, format: ReportFormat) -> String {
+        let roads = &self.unprocessed.unprocessed;
+        let summary = match roads.len() {
+            0 => String::from("No roads need to be reviewed"),
+            1 => String::from("1 road needs to be reviewed"),
+            n => format!("{} roads need to be reviewed", n),
+        };
+
+        let entries = roads.iter().map(|road| {
+            let mut positions = road.positions.clone();
+            positions.sort();
+            let positions = positions.iter().map(|position| position.to_string()).collect::<Vec<_>>().join(", ");
+            let suggestions = road.suggest_resolutions().iter().map(|suggestion| suggestion.to_string()).collect::<Vec<_>>();
+            (road, positions, suggestions, road.sketch())
+        });
+
+        match format {
+            ReportFormat::Text => {
+                let mut report = summary;
+                for (road, positions, suggestions, sketch) in entries {
+                    report.push_str(&format!("\n\n{}: {}\n", road.name, positions));
+                    if !suggestions.is_empty() {
+                        report.push_str(&format!("suggestions: {}\n", suggestions.join(" | ")));
+                    }
+                    for line in sketch.lines() {
+                        report.push_str(&format!("\n    {}", line));
+                    }
+                }
+                report.push('\n');
+                report
+            },
+            ReportFormat::Html => {
+                let mut html = format!(
+                    "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Review</title>\n</head>\n<body>\n<h1>{}</h1>\n",
+                    summary,
+                );
+                for (road, positions, suggestions, sketch) in entries {
+                    html.push_str(&format!("<section>\n<h2>{}</h2>\n<p>{}</p>\n", escape_html(&road.name.0), escape_html(&positions)));
+                    if !suggestions.is_empty() {
+                        html.push_str("<ul>\n");
+                        for suggestion in suggestions {
+                            html.push_str(&format!("<li>{}</li>\n", escape_html(&suggestion)));
+                        }
+                        html.push_str("</ul>\n");
+                    }
+                    html.push_str(&format!("<pre>{}</pre>\n</section>\n", escape_html(&sketch)));
+                }
+                html.push_str("</body>\n</html>\n");
+                html
+            },
+        }
+    }
+}
+
+/// Sketch of the `(column index, row)` cells of one page
+fn sketch_cells(cells: &BTreeSet<(usize, usize)>) -> Vec<String> {
+    let (min_column, max_column) = (cells.iter().map(|c| c.0).min().unwrap_or(0), cells.iter().map(|c| c.0).max().unwrap_or(0));
+    let (min_row, max_row) = (cells.iter().map(|c| c.1).min().unwrap_or(0), cells.iter().map(|c| c.1).max().unwrap_or(0));
+    let (columns, rows) = (max_column - min_column + 1, max_row - min_row + 1);
+    if columns > MAX_SKETCH_SIZE || rows > MAX_SKETCH_SIZE {
+        return vec![format!("({} columns x {} rows, too large to sketch)", columns, rows)];
+    }
+    draw_cells(cells, min_column..=max_column, min_row..=max_row)
+}
+
+/// Draws the `columns` and `rows` with a header row of column labels,
+/// `#` for the `cells` and `.` for all other cells
+fn draw_cells(cells: &BTreeSet<(usize, usize)>, columns: RangeInclusive<usize>, rows: RangeInclusive<usize>) -> Vec<String> {
+    let labels = columns.clone().map(number_to_alphabet_value).collect::<Vec<_>>();
+    let width = labels.iter().map(|label| label.len()).max().unwrap_or(1);
+    let row_width = rows.end().to_string().len();
+
+    let mut lines = vec![format!("{:>row_width$} {}", "", labels.iter().map(|label| format!("{:<width$}", label)).collect::<Vec<_>>().join(" "))];
+    for row in rows {
+        let marks = columns.clone()
+            .map(|column| format!("{:<width$}", if cells.contains(&(column, row)) { "#" } else { "." }))
+            .collect::<Vec<_>>();
+        lines.push(format!("{:>row_width$} {}", row, marks.join(" ")));
+    }
+    lines.iter().map(|line| line.trim_end().to_string()).collect()
+}
+
+/// Returns the span from the first to the last of the (ordered) positions
+fn span(positions: &[GridPosition]) -> Option<FinalizedGridPositon> {
+    match positions {
+        [] => None,
+        [single] => Some(FinalizedGridPositon::SingleRect(single.clone())),
+        [first, .., last] => Some(FinalizedGridPositon::Range(first.clone(), last.clone())),
+    }
+}
+
+#[test]
+fn test_resolutions() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Abbey Road\tA1\r\nCanterbury Road\tA9\tA10\tE1\tE2\r\nMill Lane\t3:B2\t4:B2\t4:C2\t4:D5\r\nZoo Road\tZ1";
+    let mut index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+    assert_eq!(index.review_count(), 2);
+
+    let canterbury = index.unprocessed.unprocessed[0].clone();
+    let suggestions = canterbury.suggest_resolutions();
+    assert_eq!(suggestions.iter().map(|s| s.to_string()).collect::<Vec<_>>(), vec!["A9-A10; E1-E2", "A9-E2"]);
+
+    // cells on different pages can't be one span
+    let mill_lane = index.unprocessed.unprocessed[1].suggest_resolutions();
+    assert_eq!(mill_lane.iter().map(|s| s.to_string()).collect::<Vec<_>>(), vec!["3:B2; 4:B2-C2; 4:D5"]);
+
+    assert!(index.apply_resolution(&canterbury.name, &suggestions[0]));
+    assert!(!index.apply_resolution(&canterbury.name, &suggestions[1]));
+    assert_eq!(index.review_count(), 1);
+    assert_eq!(index.processed.to_csv("\t"), "Abbey Road\tA1\r\nCanterbury Road\tA9-A10\r\nCanterbury Road\tE1-E2\r\nZoo Road\tZ1");
+}
+
+#[test]
+fn test_review_report() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Abbey Road\tA1\r\nCanterbury Road\tA9\tA10\tD1\tD2\r\nFar Road\tA1\tZZ99\tB7";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+
+    assert_eq!(index.unprocessed.unprocessed[0].sketch(), [
+        "   A B C D",
+        " 1 . . . #",
+        " 2 . . . #",
+        " 3 . . . .",
+        " 4 . . . .",
+        " 5 . . . .",
+        " 6 . . . .",
+        " 7 . . . .",
+        " 8 . . . .",
+        " 9 # . . .",
+        "10 # . . .",
+    ].join("\n"));
+
+    let report = index.review_report(ReportFormat::Text);
+    assert!(report.starts_with("2 roads need to be reviewed\n\nCanterbury Road: A9, A10, D1, D2\nsuggestions: A9-A10; D1-D2 | A9-D2\n\n       A B C D\n     1 . . . #\n"));
+    assert!(report.contains("Far Road: A1, B7, ZZ99\nsuggestions: A1; B7; ZZ99 | A1-ZZ99\n\n    (702 columns x 99 rows, too large to sketch)\n"));
+
+    let mill_lane = DeduplicatedRoads::from_csv("Mill Lane\tB1\tB2\tD2", "\t").unwrap().process();
+    assert_eq!(mill_lane.review_report(ReportFormat::Text), "1 road needs to be reviewed\n\nMill Lane: B1, B2, D2\nsuggestions: B1-B2; D2 | B1-D2\n\n      B C D\n    1 # . .\n    2 # . #\n");
+
+    let html = index.review_report(ReportFormat::Html);
+    assert!(html.contains("<h2>Canterbury Road</h2>\n<p>A9, A10, D1, D2</p>\n<ul>\n<li>A9-A10; D1-D2</li>\n"));
+}
+
+#[test]
+fn test_render_grid() {
+    use roads2csv::DeduplicatedRoads;
+    use gridconfig::{Bbox, GridConfig, Millimeter};
+
+    let grid = Grid::new(
+        Bbox { width: Millimeter(100.0), height: Millimeter(70.0) },
+        GridConfig { cell_width: Millimeter(20.0), cell_height: Millimeter(20.0) },
+    );
+    assert_eq!((grid.columns(), grid.rows()), (5, 4));
+
+    let index = DeduplicatedRoads::from_csv("Canterbury Road\tA1\tA2\tE3\tE4\tF9", "\t").unwrap().process();
+    assert_eq!(index.unprocessed.unprocessed[0].render_grid(&grid), [
+        "  A B C D E",
+        "1 # . . . .",
+        "2 # . . . .",
+        "3 . . . . #",
+        "4 . . . . #",
+        "not in the grid: F9",
+    ].join("\n"));
+}
@@ -0,0 +1,281 @@
+//! Review file: the roads that need to be reviewed as a TOML file in which
+//! the reviewer fills in the resolved positions, which are then merged back
+//! into the processed roads of the index
+//!
+//! ```no_run,ignore
+//! let mut index = deduplicated.process();
+//! fs::write("review.toml", index.to_review_file())?;
+//! // ... the reviewer edits review.toml ...
+//! let resolved = index.apply_review_file("review.toml")?;
+//! ```
+//!
+//! Every road is one table of the `review` array of tables, `positions` and
+//! `suggestions` are only there to help the reviewer and are not read back:
+//!
+//! ```text
+//! [[review]]
+//! name = "Canterbury Road"
+//! positions = ["A9-A10", "E1-E2"]
+//! suggestions = ["A9-A10; E1-E2", "A9-E2"]
+//! resolved = ["A9-A10", "E1-E2"]
+//! ```
+//!
+//! `resolved` takes cells (`"A9"`) and ranges (`"A9-E2"`, `"12:A9-B2"`),
+//! several per string separated by `;`, so a suggestion can be copied as it
+//! is. Roads with an empty `resolved` stay unprocessed. Only the part of TOML
+//! that is written here is read: basic and literal strings, arrays of
+//! strings (also over several lines) and comments.
+
+use std::{fs, path::Path};
+use roads2csv::{StreetIndex, StreetName, GridPosition, FinalizedGridPositon};
+use roads2toml::toml_string;
+use review::Resolution;
+use category::Category;
+use error::{Error, Result};
+
+/// Comment at the start of a review file
+const REVIEW_FILE_HEADER: &str = "\
+# Roads that need to be reviewed: fill in the positions of each road in
+# `resolved`, i.e. [\"A9-A10\", \"E1-E2\"] or a copy of one of the suggestions.
+# Roads with an empty `resolved` stay unprocessed.
+";
+
+impl StreetIndex {
+
+    /// Exports the roads that need to be reviewed as a review file, see the
+    /// module documentation for the format
+    pub fn to_review_file(&self) -> String {
+        let roads = self.unprocessed.unprocessed.iter().map(|road| {
+            let category = match &road.category {
+                Some(category) => format!("category = {}\n", toml_string(&category.to_string())),
+                None => String::new(),
+            };
+            let positions = road.runs().iter().map(|run| toml_string(&run.to_string())).collect::<Vec<_>>();
+            let suggestions = road.suggest_resolutions().iter().map(|suggestion| toml_string(&suggestion.to_string())).collect::<Vec<_>>();
+            format!(
+                "[[review]]\nname = {}\n{}positions = [{}]\nsuggestions = [{}]\nresolved = []\n",
+                toml_string(&road.name.0), category, positions.join(", "), suggestions.join(", "),
+            )
+        });
+        ::std::iter::once(String::from(REVIEW_FILE_HEADER)).chain(roads).collect::<Vec<String>>().join("\n")
+    }
+
+    /// Reads the review file at the `path` and applies it, see `apply_review()`
+    pub fn apply_review_file<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        let review = fs::read_to_string(path)?;
+        self.apply_review(&review)
+    }
+
+    /// Replaces every unprocessed road (with the same name and category) that
+    /// has `resolved` positions in the `review` file by processed roads, like
+    /// `apply_resolution()`. Roads that are not (or no longer) unprocessed are
+    /// skipped, so a file can be applied twice.
+    ///
+    /// Returns the number of resolved roads. Nothing is applied if the file
+    /// is invalid.
+    pub fn apply_review(&mut self, review: &str) -> Result<usize> {
+        let entries = parse_review_file(review)?;
+        let mut resolved = 0;
+        for entry in entries {
+            let resolution = match entry.resolved.as_slice() {
+                [] => continue,
+                [position] => Resolution::Span(position.clone()),
+                positions => Resolution::Split(positions.to_vec()),
+            };
+            let index = self.unprocessed.unprocessed.iter().position(|road| Some(&road.name) == entry.name.as_ref() && road.category == entry.category);
+            if let Some(index) = index {
+                self.resolve_unprocessed(index, &resolution);
+                resolved += 1;
+            }
+        }
+        Ok(resolved)
+    }
+}
+
+/// One `[[review]]` table of a review file, starting at the `line`
+struct ReviewEntry {
+    line: usize,
+    name: Option<StreetName>,
+    category: Option<Category>,
+    resolved: Vec<FinalizedGridPositon>,
+}
+
+/// Value of a key
+enum Value {
+    String(String),
+    Array(Vec<String>),
+}
+
+fn parse_review_file(review: &str) -> Result<Vec<ReviewEntry>> {
+    let mut tables: Vec<ReviewEntry> = Vec::new();
+    let mut lines = review.lines().enumerate().map(|(index, line)| (index + 1, line));
+
+    while let Some((line_number, line)) = lines.next() {
+        let invalid = |reason: String| Error::InvalidReviewFile(line_number, reason);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            match line.split('#').next().unwrap_or_default().trim() {
+                "[[review]]" => tables.push(ReviewEntry { line: line_number, name: None, category: None, resolved: Vec::new() }),
+                header => return Err(invalid(format!("unknown table {}", header))),
+            }
+            continue;
+        }
+
+        let (key, value) = line.split_once('=').ok_or_else(|| invalid(format!("expected \"key = value\", found \"{}\"", line)))?;
+        let (key, mut value) = (key.trim(), value.trim().to_string());
+        let value = loop {
+            match parse_value(&value).map_err(&invalid)? {
+                Some(value) => break value,
+                None => match lines.next() {
+                    Some((_, next)) => { value.push('\n'); value.push_str(next); },
+                    None => return Err(invalid(String::from("unterminated array"))),
+                },
+            }
+        };
+
+        let table = tables.last_mut().ok_or_else(|| invalid(format!("\"{}\" is not in a [[review]] table", key)))?;
+        match (key, value) {
+            ("name", Value::String(name)) => table.name = Some(StreetName(name)),
+            ("category", Value::String(category)) => table.category = Some(Category::from(category)),
+            ("resolved", Value::String(positions)) => table.resolved = parse_resolved(&positions).map_err(&invalid)?,
+            ("resolved", Value::Array(positions)) => {
+                table.resolved = positions.iter().map(|positions| parse_resolved(positions)).collect::<::std::result::Result<Vec<_>, _>>().map_err(&invalid)?.concat();
+            },
+            ("positions", _) | ("suggestions", _) => { },
+            ("name", _) | ("category", _) => return Err(invalid(format!("\"{}\" has to be a string", key))),
+            (key, _) => return Err(invalid(format!("unknown key \"{}\"", key))),
+        }
+    }
+
+    match tables.iter().find(|table| table.name.is_none()) {
+        Some(table) => Err(Error::InvalidReviewFile(table.line, String::from("[[review]] table without a name"))),
+        None => Ok(tables),
+    }
+}
+
+/// Parses a string or an array of strings (followed by an optional comment),
+/// `None` if the array is not closed yet
+fn parse_value(value: &str) -> ::std::result::Result<Option<Value>, String> {
+    let mut chars = value.chars().peekable();
+    let value = match chars.peek() {
+        Some('"') | Some('\'') => Value::String(parse_string(&mut chars)?),
+        Some('[') => {
+            chars.next();
+            let mut strings = Vec::new();
+            loop {
+                match chars.peek() {
+                    None => return Ok(None),
+                    Some(']') => { chars.next(); break; },
+                    Some(c) if c.is_whitespace() || *c == ',' => { chars.next(); },
+                    Some('#') => { while chars.next_if(|c| *c != '\n').is_some() { } },
+                    Some('"') | Some('\'') => strings.push(parse_string(&mut chars)?),
+                    Some(c) => return Err(format!("unexpected \"{}\" in array", c)),
+                }
+            }
+            Value::Array(strings)
+        },
+        _ => return Err(format!("expected a string or an array of strings, found \"{}\"", value)),
+    };
+    let rest = chars.collect::<String>();
+    match rest.trim() {
+        "" => Ok(Some(value)),
+        rest if rest.starts_with('#') => Ok(Some(value)),
+        rest => Err(format!("unexpected \"{}\" after the value", rest)),
+    }
+}
+
+/// Parses a basic (`"..."`, with escapes) or literal (`'...'`) string
+fn parse_string<I: Iterator<Item = char>>(chars: &mut I) -> ::std::result::Result<String, String> {
+    let quote = chars.next().unwrap_or('"');
+    let mut s = String::new();
+    loop {
+        match chars.next() {
+            None | Some('\n') => return Err(String::from("unterminated string")),
+            Some(c) if c == quote => return Ok(s),
+            Some('\\') if quote == '"' => {
+                let escaped = match chars.next() {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('b') => '\u{8}',
+                    Some('f') => '\u{c}',
+                    Some(u @ 'u') | Some(u @ 'U') => {
+                        let digits = chars.by_ref().take(if u == 'u' { 4 } else { 8 }).collect::<String>();
+                        u32::from_str_radix(&digits, 16).ok().and_then(::std::char::from_u32)
+                            .ok_or_else(|| format!("invalid escape \"\\{}{}\"", u, digits))?
+                    },
+                    Some(c) => return Err(format!("invalid escape \"\\{}\"", c)),
+                    None => return Err(String::from("unterminated string")),
+                };
+                s.push(escaped);
+            },
+            Some(c) => s.push(c),
+        }
+    }
+}
+
+/// Parses positions such as `"A9-A10; E1-E2"`: cells or ranges separated by
+/// `;`, the end of a range is on the page of its start if it has no page
+fn parse_resolved(positions: &str) -> ::std::result::Result<Vec<FinalizedGridPositon>, String> {
+    let invalid = |position: &str| format!("invalid position \"{}\"", position.trim());
+    positions.split(';').filter(|position| !position.trim().is_empty()).map(|position| {
+        let (first, last) = match position.split_once('-') {
+            Some((first, last)) => (first, Some(last)),
+            None => (position, None),
+        };
+        let first = GridPosition::parse(first).map_err(|_| invalid(position))?;
+        let last = match last {
+            Some(last) if last.contains(':') => GridPosition::parse(last).map_err(|_| invalid(position))?,
+            Some(last) => GridPosition { page: first.page.clone(), .. GridPosition::parse(last).map_err(|_| invalid(position))? },
+            None => return Ok(FinalizedGridPositon::SingleRect(first)),
+        };
+        Ok(if first == last { FinalizedGridPositon::SingleRect(first) } else { FinalizedGridPositon::Range(first, last) })
+    }).collect()
+}
+
+#[test]
+fn test_review_file() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Abbey Road\tA1\r\nCanterbury Road\tA9\tA10\tE1\tE2\r\nMill Lane\t3:B2\t4:B2\t4:C2\t4:D5\r\nThe \"Old\" Road\tB1\tB5\tC9";
+    let mut index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+    index.unprocessed.unprocessed[2].category = Some(Category::Water);
+
+    let review = index.to_review_file();
+    assert!(review.starts_with(REVIEW_FILE_HEADER));
+    assert!(review.contains("\n[[review]]\nname = \"Canterbury Road\"\npositions = [\"A9-A10\", \"E1-E2\"]\nsuggestions = [\"A9-A10; E1-E2\", \"A9-E2\"]\nresolved = []\n"));
+    assert!(review.contains("\n[[review]]\nname = \"The \\\"Old\\\" Road\"\ncategory = \"water\"\n"));
+
+    let review = review
+        .replacen("resolved = []", "resolved = [\"A9-A10; E1-E2\"]", 1)
+        .replacen("resolved = []", "resolved = [\n  \"3:B2\", # north\n  \"4:B2-C2\",\n  '4:D5',\n]", 1)
+        .replacen("resolved = []", "resolved = \"B1-C9\"", 1);
+    let mut reviewed = index.clone();
+    assert_eq!(reviewed.apply_review(&review).unwrap(), 3);
+    assert_eq!(reviewed.review_count(), 0);
+    assert_eq!(reviewed.processed.to_csv("\t"), [
+        "Abbey Road\tA1",
+        "Canterbury Road\tA9-A10",
+        "Canterbury Road\tE1-E2",
+        "Mill Lane\t3:B2",
+        "Mill Lane\t4:B2-C2",
+        "Mill Lane\t4:D5",
+        "\"The \"\"Old\"\" Road\"\tB1-C9",
+    ].join("\r\n"));
+    assert_eq!(reviewed.apply_review(&review).unwrap(), 0);
+
+    // the category has to match
+    assert_eq!(index.clone().apply_review("[[review]]\nname = 'The \"Old\" Road'\nresolved = ['B1-C9']").unwrap(), 0);
+
+    let error = |review: &str| index.clone().apply_review(review).unwrap_err().to_string();
+    assert_eq!(error("[[review]]\nname = \"Mill Lane\"\n\nresolved = [\"4:B2-\"]"), "invalid review file, line 4: invalid position \"4:B2-\"");
+    assert_eq!(error("[[review]]\nname = \"Mill Lane\"\nresolved = [\"A1\""), "invalid review file, line 3: unterminated array");
+    assert_eq!(error("[[review]]\nresolve = []"), "invalid review file, line 2: unknown key \"resolve\"");
+    assert_eq!(error("name = \"Mill Lane\""), "invalid review file, line 1: \"name\" is not in a [[review]] table");
+    assert_eq!(error("\n[[review]]\nresolved = []"), "invalid review file, line 2: [[review]] table without a name");
+}
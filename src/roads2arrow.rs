@@ -0,0 +1,122 @@
+//! Exports a `StreetIndex` as an Apache Arrow record batch or a Parquet file,
+//! i.e. to load large indexes into DuckDB or Polars without guessing the
+//! column types of a CSV
+//!
+//! The batch has one row per line of the CSV export:
+//!
+//! ```no_run,ignore
+//! name: Utf8, position: Utf8, status: Utf8, category: Utf8 (nullable), cells: UInt32
+//! ```
+//!
+//! `position` contains the same value as the CSV (the positions of
+//! unprocessed roads are joined with `"; "`, cross-references contain the
+//! name of the street they refer to), `status` is either `"processed"`,
+//! `"unprocessed"` or `"cross_reference"` and `cells` is the number of grid
+//! cells the road covers.
+
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use arrow::array::{ArrayRef, RecordBatch, StringArray, UInt32Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::error::ArrowError;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use roads2csv::StreetIndex;
+
+impl StreetIndex {
+
+    /// Schema of the batch returned by `to_arrow()`
+    pub fn arrow_schema() -> Schema {
+        Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("position", DataType::Utf8, false),
+            Field::new("status", DataType::Utf8, false),
+            Field::new("category", DataType::Utf8, true),
+            Field::new("cells", DataType::UInt32, false),
+        ])
+    }
+
+    /// Converts the index into one Arrow record batch
+    pub fn to_arrow(&self) -> Result<RecordBatch, ArrowError> {
+        let mut names = Vec::new();
+        let mut positions = Vec::new();
+        let mut statuses = Vec::new();
+        let mut categories = Vec::new();
+        let mut cells = Vec::new();
+
+        for road in &self.processed.processed {
+            names.push(road.name.0.clone());
+            positions.push(road.position.to_string());
+            statuses.push("processed");
+            categories.push(road.category.as_ref().map(|c| c.to_string()));
+            cells.push(road.position.cells().len() as u32);
+        }
+
+        for road in &self.unprocessed.unprocessed {
+            names.push(road.name.0.clone());
+            positions.push(road.positions.iter().map(|p| p.to_string()).collect::<Vec<String>>().join("; "));
+            statuses.push("unprocessed");
+            categories.push(road.category.as_ref().map(|c| c.to_string()));
+            cells.push(road.positions.len() as u32);
+        }
+
+        for reference in &self.cross_references {
+            names.push(reference.from.0.clone());
+            positions.push(reference.to.0.clone());
+            statuses.push("cross_reference");
+            categories.push(None);
+            cells.push(0);
+        }
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(names)),
+            Arc::new(StringArray::from(positions)),
+            Arc::new(StringArray::from(statuses)),
+            Arc::new(StringArray::from(categories)),
+            Arc::new(UInt32Array::from(cells)),
+        ];
+
+        RecordBatch::try_new(Arc::new(Self::arrow_schema()), columns)
+    }
+
+    /// Writes the index as a Snappy-compressed Parquet file to `path`
+    /// (see `to_arrow()` for the columns), an existing file is replaced
+    pub fn to_parquet<P: AsRef<Path>>(&self, path: P) -> Result<(), ParquetError> {
+        let batch = self.to_arrow()?;
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let mut writer = ArrowWriter::try_new(File::create(path)?, batch.schema(), Some(properties))?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_to_arrow() {
+    use arrow::array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use roads2csv::DeduplicatedRoads;
+    use cross_reference::CrossReference;
+
+    let csv = "Abbey Road\tA1-A2\r\nMarket St\tB2\r\nValley Road\tE1\tE2\tF4";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process()
+        .with_cross_reference(CrossReference::new("Old Market St", "Market St"));
+
+    let batch = index.to_arrow().unwrap();
+    assert_eq!(batch.num_rows(), 4);
+    let column = |name: &str| batch.column_by_name(name).unwrap().as_any().downcast_ref::<StringArray>().unwrap().iter().map(|v| v.map(String::from)).collect::<Vec<_>>();
+    assert_eq!(column("position"), vec![Some("A1-A2".into()), Some("B2".into()), Some("E1; E2; F4".into()), Some("Market St".into())]);
+    assert_eq!(column("status")[2].as_deref(), Some("unprocessed"));
+    assert!(batch.column_by_name("category").unwrap().is_null(0));
+    assert_eq!(batch.column_by_name("cells").unwrap().as_any().downcast_ref::<UInt32Array>().unwrap().values().to_vec(), vec![2, 1, 3, 0]);
+
+    let path = ::std::env::temp_dir().join(format!("street_index_test_{}.parquet", ::std::process::id()));
+    index.to_parquet(&path).unwrap();
+    let batches = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap().build().unwrap()
+        .collect::<Result<Vec<_>, _>>().unwrap();
+    ::std::fs::remove_file(&path).unwrap();
+    assert_eq!(batches, vec![batch]);
+}
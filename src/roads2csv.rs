@@ -1,171 +1,2352 @@
 //! Converts input roads to a final CSV
 
-use std::{fmt, collections::{BTreeMap, BTreeSet}};
+use std::{fmt, io, error::Error, borrow::Cow, cmp::Ordering, hash::{Hash, Hasher}, ops::AddAssign, collections::{BTreeMap, BTreeSet, HashSet}};
+use gridconfig::{alphabet_value_to_number, number_to_alphabet_value};
+use labeling::{GridLabeler, ColumnScheme, PositionFormatter};
+use normalize::{Normalizer, DedupOptions};
+use sorting::{SortStrategy, SortKeyExtractor, Locale};
+use category::Category;
+use filter::Filter;
+use alias::AliasTable;
+use cross_reference::{CrossReference, Interleave};
+use house_numbers::{NumberRange, HouseNumbers, process_house_numbers};
+use encoding::{CsvEncoding, EncodingError};
+use attributes::{ConflictPolicy, Conflict, ValueStats, add_value};
+use cancel::{CancellationToken, is_cancelled};
+use core_index::{self, CellId};
+use flat::{DedupBackend, FlatRoads};
+use validation::InputLimits;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
 
 /// Name of one street (such as `"Canterbury Road"`)
 #[derive(Debug, Clone, PartialEq, Ord, PartialOrd, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
 pub struct StreetName(pub String);
 
+impl StreetName {
+    /// Returns the uppercase first letter of the name (`"Ä"` for `"ämtliweg"`),
+    /// used to group the index under header letters
+    pub fn initial(&self) -> Option<String> {
+        self.0.trim_start().chars().next().map(|c| c.to_uppercase().collect())
+    }
+}
+
 impl fmt::Display for StreetName {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
+/// Weight of a cell for a street (see `InputStreetValue::weight`). Compared
+/// and hashed by `f64::total_cmp`, so that the streets stay `Eq`, `Ord` and `Hash`.
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+pub struct Weight(pub f64);
+
+impl PartialEq for Weight {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Weight { }
+
+impl Ord for Weight {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl PartialOrd for Weight {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for Weight {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl AddAssign for Weight {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+
+impl From<f64> for Weight {
+    fn from(weight: f64) -> Self {
+        Weight(weight)
+    }
+}
+
 /// Input street to the deduplicator - the street must have a 
 /// name and a position (such as `"A9"`)
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
 pub struct InputStreetValue {
     pub street_name: StreetName,
     pub position: GridPosition,
+    /// Category of the entry (street, waterway, ...), roads are
+    /// deduplicated per name and category
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub category: Option<Category>,
+    /// House numbers of the street in this cell (i.e. `1–99`), indexed as
+    /// sub-entries of the street (see `StreetIndex::house_numbers`)
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub number_range: Option<NumberRange>,
+    /// Weight of the cell for the street, i.e. the length of the street in
+    /// the cell. The weights of all rows of a street and cell are added up,
+    /// see `ProcessOptions::primary_cell`.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub weight: Option<Weight>,
+    /// Second official name of the street in bilingual regions (i.e. the
+    /// French name of a street in Brussels), see `StreetIndex::select_names`
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub secondary_name: Option<StreetName>,
+    /// District (or other sub-area) of the street in this cell, see
+    /// `StreetIndex::split_by_district`
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub district: Option<String>,
+    /// Additional attributes of the street in this cell (i.e. `"postal_code"`),
+    /// see `DedupOptions::attribute_conflict` and `StreetIndex::to_csv_with_attributes`
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl InputStreetValue {
+
+    /// Street `street_name` in the cell `position`, all other fields are
+    /// left empty
+    pub fn new(street_name: &str, position: GridPosition) -> Self {
+        Self {
+            street_name: StreetName(street_name.to_string()),
+            position,
+            category: None,
+            number_range: None,
+            weight: None,
+            secondary_name: None,
+            district: None,
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the second official name of the street, see `secondary_name`
+    pub fn with_secondary_name(self, secondary_name: &str) -> Self {
+        Self { secondary_name: Some(StreetName(secondary_name.to_string())), .. self }
+    }
+
+    /// Sets the district of the street in this cell
+    pub fn with_district(self, district: &str) -> Self {
+        Self { district: Some(district.to_string()), .. self }
+    }
+
+    /// Adds the attribute `key`, replacing an earlier value of the same key
+    pub fn with_attribute(mut self, key: &str, value: &str) -> Self {
+        self.attributes.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Sets the category of the entry, see `category`
+    pub fn with_category(self, category: Category) -> Self {
+        Self { category: Some(category), .. self }
+    }
+
+    /// Sets the house numbers of the street in this cell
+    pub fn with_number_range(self, number_range: NumberRange) -> Self {
+        Self { number_range: Some(number_range), .. self }
+    }
+
+    /// Sets the weight of the cell for the street, see `weight`
+    pub fn with_weight(self, weight: f64) -> Self {
+        Self { weight: Some(Weight(weight)), .. self }
+    }
+
+    /// Parses one line of a CSV previously exported via `to_csv()`, i.e.
+    /// `"Canterbury Road\tA9-A10"` or `"Canterbury Road\tA9\tA10\tE1"`.
+    ///
+    /// The first field is the street name, every following field is a grid
+    /// position (`A9`) or a range of grid positions (`A9-A12`). Ranges within
+    /// one row or column are expanded to all cells of the range, for other
+    /// ranges (`A9-B2`) only the two cells at the start and end are returned.
+    ///
+    /// Fields may be quoted according to RFC 4180 (`"Main Street, North"`) and
+    /// may contain multiple positions separated by `;` (`"12:C4-C6; 14:A1"`).
+    pub fn parse_line(line: &str, delimiter: &str) -> Result<Vec<InputStreetValue>, ParseError> {
+        Self::parse_line_limited(line, delimiter, usize::MAX)
+    }
+
+    /// Same as `parse_line()`, but fails with `ParseErrorKind::RangeTooLong`
+    /// instead of expanding a range to more than `InputLimits::max_range_cells`
+    /// cells (`"A1-A4000000000"`), i.e. for untrusted uploads
+    pub fn parse_line_with_limits(line: &str, delimiter: &str, limits: &InputLimits) -> Result<Vec<InputStreetValue>, ParseError> {
+        Self::parse_line_limited(line, delimiter, limits.max_range_cells)
+    }
+
+    fn parse_line_limited(line: &str, delimiter: &str, max_range_cells: usize) -> Result<Vec<InputStreetValue>, ParseError> {
+
+        let error = |kind| ParseError { line: None, kind };
+
+        let fields = split_csv_fields(line.trim_end_matches(&['\r', '\n'][..]), delimiter);
+        let mut fields = fields.iter().map(|f| f.as_ref());
+        let street_name = fields.next().unwrap_or("").trim();
+
+        if street_name.is_empty() {
+            return Err(error(ParseErrorKind::EmptyName));
+        }
+
+        let mut streets = Vec::new();
+
+        for field in fields.flat_map(|f| f.split(';')).map(str::trim).filter(|f| !f.is_empty()) {
+            let positions = parse_positions(field, max_range_cells).map_err(error)?;
+            streets.extend(positions.into_iter().map(|position| InputStreetValue::new(street_name, position)));
+        }
+
+        if streets.is_empty() {
+            return Err(error(ParseErrorKind::MissingPosition));
+        }
+
+        Ok(streets)
+    }
+}
+
+/// How fields are quoted when exporting a CSV (see RFC 4180)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CsvQuoting {
+    /// Only quote fields that contain the delimiter, a quote or a line break
+    #[default]
+    Necessary,
+    /// Quote every field
+    Always,
+    /// Never quote fields, the fields are written as they are
+    Never,
+}
+
+impl CsvQuoting {
+    /// Quotes the field if necessary, quotes inside the field are doubled
+    /// (`Say "Hi" Lane` becomes `"Say ""Hi"" Lane"`)
+    pub fn quote<'a>(&self, field: &'a str, delimiter: &str) -> Cow<'a, str> {
+        let needs_quotes = match self {
+            CsvQuoting::Always => true,
+            CsvQuoting::Never => false,
+            CsvQuoting::Necessary => {
+                field.contains(&['"', '\r', '\n'][..]) || (!delimiter.is_empty() && field.contains(delimiter))
+            },
+        };
+
+        if needs_quotes {
+            Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+        } else {
+            Cow::Borrowed(field)
+        }
+    }
+
+    pub(crate) fn record<'a, I: IntoIterator<Item = Cow<'a, str>>>(&self, fields: I, delimiter: &str) -> String {
+        fields.into_iter()
+            .map(|field| self.quote(&field, delimiter).into_owned())
+            .collect::<Vec<String>>()
+            .join(delimiter)
+    }
+}
+
+/// Line break between the records of an exported CSV
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LineEnding {
+    /// `\r\n` (the default, see RFC 4180)
+    #[default]
+    CrLf,
+    /// `\n`
+    Lf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Lf => "\n",
+        }
+    }
+}
+
+/// Options for `to_csv_with_options()`. The default is a tab-delimited CSV
+/// with `\r\n` line endings, no header and quoting where necessary
+/// (the same output as `to_csv("\t")`).
+///
+/// ```no_run,ignore
+/// let options = CsvOptions::default()
+///     .with_delimiter(';')
+///     .with_line_ending(LineEnding::Lf)
+///     .with_header_names(&["Straße", "Planquadrat"]);
+/// index.processed.to_csv_with_options(&options) // "Straße;Planquadrat\nAbbey Road;A1"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub line_ending: LineEnding,
+    pub quoting: CsvQuoting,
+    /// Whether the first line of the CSV is a header row
+    pub header: bool,
+    /// Names of the header columns, `None` for the default
+    /// names (`Name`, `Position` or `Name`, `Positions`)
+    pub header_names: Option<Vec<String>>,
+    /// Inserts a separator row (`"— A —"`) before the processed roads of every
+    /// letter, the letters are determined with `Locale::initial`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub section_headers: Option<Locale>,
+    /// Encoding of the bytes written by `write_csv_with_options()`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub encoding: CsvEncoding,
+    /// Joins the positions of an unprocessed road into one field, so that
+    /// every line has two fields. `None` writes every position into a field
+    /// of its own.
+    #[cfg_attr(feature = "serde", serde(default = "default_position_separator"))]
+    pub position_separator: Option<String>,
+}
+
+/// Default of `CsvOptions::position_separator`
+pub const DEFAULT_POSITION_SEPARATOR: &str = "; ";
+
+#[cfg(feature = "serde")]
+fn default_position_separator() -> Option<String> {
+    Some(String::from(DEFAULT_POSITION_SEPARATOR))
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: '\t',
+            line_ending: LineEnding::default(),
+            quoting: CsvQuoting::default(),
+            header: false,
+            header_names: None,
+            section_headers: None,
+            encoding: CsvEncoding::default(),
+            position_separator: Some(String::from(DEFAULT_POSITION_SEPARATOR)),
+        }
+    }
+}
+
+impl CsvOptions {
+
+    pub fn with_delimiter(self, delimiter: char) -> Self {
+        Self { delimiter, .. self }
+    }
+
+    pub fn with_line_ending(self, line_ending: LineEnding) -> Self {
+        Self { line_ending, .. self }
+    }
+
+    pub fn with_quoting(self, quoting: CsvQuoting) -> Self {
+        Self { quoting, .. self }
+    }
+
+    pub fn with_header(self, header: bool) -> Self {
+        Self { header, .. self }
+    }
+
+    /// Enables the header row and sets the names of its columns
+    pub fn with_header_names(self, names: &[&str]) -> Self {
+        Self {
+            header: true,
+            header_names: Some(names.iter().map(|name| name.to_string()).collect()),
+            .. self
+        }
+    }
+
+    /// Enables the separator rows before every letter (see `section_headers`)
+    pub fn with_section_headers(self, locale: Locale) -> Self {
+        Self { section_headers: Some(locale), .. self }
+    }
+
+    /// Sets the encoding of the written files, i.e. `CsvEncoding::Utf8Bom` for Excel
+    pub fn with_encoding(self, encoding: CsvEncoding) -> Self {
+        Self { encoding, .. self }
+    }
+
+    /// Sets the separator between the positions of an unprocessed road,
+    /// `None` for one field per position (see `position_separator`)
+    pub fn with_position_separator(self, separator: Option<&str>) -> Self {
+        Self { position_separator: separator.map(String::from), .. self }
+    }
+
+    /// Encodes a CSV returned by `to_csv_with_options()` in the `encoding`,
+    /// including the byte order mark
+    pub fn encode(&self, csv: &str) -> Result<Vec<u8>, EncodingError> {
+        let mut bytes = self.encoding.bom().to_vec();
+        bytes.extend(self.encoding.encode(csv)?);
+        Ok(bytes)
+    }
+
+    /// Returns the header row (if enabled), using `default_names`
+    /// if no custom header names are set
+    pub(crate) fn header_record(&self, default_names: &[&str], delimiter: &str) -> Option<String> {
+        if !self.header {
+            return None;
+        }
+        Some(match &self.header_names {
+            Some(names) => self.quoting.record(names.iter().map(|name| Cow::Borrowed(name.as_str())), delimiter),
+            None => self.quoting.record(default_names.iter().map(|name| Cow::Borrowed(*name)), delimiter),
+        })
+    }
+}
+
+/// Writes the `records` to the `writer`, separated by the `line_ending`
+/// (without a trailing line break, like `to_csv()`)
+pub(crate) fn write_records<W: io::Write, I: IntoIterator<Item = String>>(mut writer: W, records: I, line_ending: LineEnding) -> io::Result<()> {
+    for (i, record) in records.into_iter().enumerate() {
+        if i != 0 {
+            writer.write_all(line_ending.as_str().as_bytes())?;
+        }
+        writer.write_all(record.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Joins the `positions` with the `separator` into one field, or returns
+/// one field per position if there is no separator
+fn position_fields<'a, I: Iterator<Item = String>>(positions: I, separator: Option<&str>) -> Vec<Cow<'a, str>> {
+    match separator {
+        Some(separator) => vec![Cow::Owned(positions.collect::<Vec<_>>().join(separator))],
+        None => positions.map(Cow::Owned).collect(),
+    }
+}
+
+/// Same as `write_records()`, but with the line endings and the encoding of the
+/// `options`. Characters that can not be encoded fail with `io::ErrorKind::InvalidData`.
+pub(crate) fn write_records_with_options<W: io::Write, I: IntoIterator<Item = String>>(mut writer: W, records: I, options: &CsvOptions) -> io::Result<()> {
+    writer.write_all(options.encoding.bom())?;
+    for (i, record) in records.into_iter().enumerate() {
+        if i != 0 {
+            writer.write_all(options.line_ending.as_str().as_bytes())?;
+        }
+        let bytes = options.encoding.encode(&record).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        writer.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+/// Splits one line of a CSV into its fields, unquoting quoted fields.
+/// Line breaks inside quoted fields are not supported.
+pub(crate) fn split_csv_fields<'a>(line: &'a str, delimiter: &str) -> Vec<Cow<'a, str>> {
+
+    if delimiter.is_empty() {
+        return vec![Cow::Borrowed(line)];
+    }
+
+    let mut fields = Vec::new();
+    let mut rest = line;
+
+    loop {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let mut field = String::new();
+            let mut chars = quoted.char_indices().peekable();
+            let mut end = quoted.len();
+            while let Some((i, c)) = chars.next() {
+                if c != '"' {
+                    field.push(c);
+                } else if let Some((_, '"')) = chars.peek() {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    end = i + 1;
+                    break;
+                }
+            }
+            rest = &quoted[end..];
+            // tolerate text between the closing quote and the next delimiter
+            match rest.find(delimiter) {
+                Some(next) => {
+                    field.push_str(&rest[..next]);
+                    fields.push(Cow::Owned(field));
+                    rest = &rest[next + delimiter.len()..];
+                },
+                None => {
+                    field.push_str(rest);
+                    fields.push(Cow::Owned(field));
+                    break;
+                },
+            }
+        } else {
+            match rest.find(delimiter) {
+                Some(next) => {
+                    fields.push(Cow::Borrowed(&rest[..next]));
+                    rest = &rest[next + delimiter.len()..];
+                },
+                None => {
+                    fields.push(Cow::Borrowed(rest));
+                    break;
+                },
+            }
+        }
+    }
+
+    fields
+}
+
+/// Parses `"A9"` or `"A9-A12"` (see `InputStreetValue::parse_line`), fails
+/// if a range has more than `max_range_cells` cells
+pub(crate) fn parse_positions(field: &str, max_range_cells: usize) -> Result<Vec<GridPosition>, ParseErrorKind> {
+
+    let invalid = || ParseErrorKind::InvalidPosition(field.to_string());
+    let mut range = field.splitn(2, '-');
+    let start = range.next().and_then(|start| GridPosition::parse(start).ok()).ok_or_else(invalid)?;
+    let mut end = match range.next() {
+        Some(end) => GridPosition::parse(end).map_err(|_| invalid())?,
+        None => return Ok(vec![start]),
+    };
+
+    // "12:C4-C6" is a range on page 12
+    if end.page.is_none() {
+        end.page = start.page.clone();
+    }
+
+    let start_column = alphabet_value_to_number(&start.column).ok_or_else(invalid)?;
+    let end_column = alphabet_value_to_number(&end.column).ok_or_else(invalid)?;
+
+    let cells = if start.page != end.page {
+        2
+    } else if start_column == end_column {
+        start.row.abs_diff(end.row).saturating_add(1)
+    } else if start.row == end.row {
+        start_column.abs_diff(end_column).saturating_add(1)
+    } else {
+        2
+    };
+    if cells > max_range_cells {
+        return Err(ParseErrorKind::RangeTooLong(field.to_string()));
+    }
+
+    let positions = if start.page != end.page {
+        vec![start, end]
+    } else if start_column == end_column {
+        (start.row.min(end.row)..=start.row.max(end.row))
+            .map(|row| GridPosition { column: start.column.clone(), row, page: start.page.clone() })
+            .collect()
+    } else if start.row == end.row {
+        (start_column.min(end_column)..=start_column.max(end_column))
+            .map(|column| GridPosition { column: number_to_alphabet_value(column), row: start.row, page: start.page.clone() })
+            .collect()
+    } else {
+        vec![start, end]
+    };
+
+    Ok(positions)
+}
+
+/// Error that can happen when reading a previously exported CSV
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line number (starting at 1) of the offending line,
+    /// `None` if a single line was parsed via `InputStreetValue::parse_line`
+    pub line: Option<usize>,
+    pub kind: ParseErrorKind,
+}
+
+/// The reason why parsing a line failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The street name (first field) is empty
+    EmptyName,
+    /// The line contains a street name, but no grid position
+    MissingPosition,
+    /// The field is not a grid position such as `"A9"` or `"A9-A12"`
+    InvalidPosition(String),
+    /// The field is not a coordinate (see `labels2roads::from_label_csv`)
+    InvalidCoordinate(String),
+    /// The range has more than `InputLimits::max_range_cells` cells (see
+    /// `InputStreetValue::parse_line_with_limits`)
+    RangeTooLong(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ParseErrorKind::*;
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        match &self.kind {
+            EmptyName => write!(f, "empty street name"),
+            MissingPosition => write!(f, "missing grid position"),
+            InvalidPosition(field) => write!(f, "invalid grid position \"{}\"", field),
+            InvalidCoordinate(field) => write!(f, "invalid coordinate \"{}\"", field),
+            RangeTooLong(field) => write!(f, "grid position range \"{}\" has too many cells", field),
+        }
+    }
+}
+
+impl Error for ParseError { }
+
+impl CellId {
+    /// Converts the id back into a position on the `page`
+    pub(crate) fn to_position(self, page: Option<String>) -> GridPosition {
+        GridPosition { column: number_to_alphabet_value(self.column()), row: self.row(), page }
+    }
 }
 
 /// Grid position such as "A9", "B4" or similar
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+///
+/// Positions are ordered by their page first (numeric pages by their
+/// number), then like spreadsheet cells, by their column index
+/// (`A, B, ..., Z, AA, AB, ...`) first, then by their row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
 pub struct GridPosition {
     pub column: String,
     pub row: usize,
+    /// Page (map sheet) of the position in multi-page atlases, i.e. `"12"`
+    /// for `"12:C4"`. Positions on different pages are never merged into one range.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub page: Option<String>,
+}
+
+impl GridPosition {
+
+    /// Creates the position of the cell at the (zero-based) `column` and the
+    /// `row`. Fails if the `scheme` has no label for the column (i.e. a
+    /// `ColumnScheme::Custom` with fewer labels). Like every `GridPosition`,
+    /// the column is stored as `A..Z` (see `labeling`), so the position is
+    /// labeled in the `scheme` by `GridLabeler::format`.
+    pub fn from_indices(column: usize, row: usize, scheme: &ColumnScheme) -> Result<Self, GridPositionError> {
+        scheme.label(column).ok_or(GridPositionError::UnlabeledColumn(column))?;
+        Ok(Self { column: number_to_alphabet_value(column), row, page: None })
+    }
+
+    /// Parses a position such as `"AB12"` or `"12:AB12"` (on page `"12"`),
+    /// surrounding whitespace is ignored. Use `GridLabeler::parse` for
+    /// positions in other labeling schemes.
+    pub fn parse(position: &str) -> Result<Self, GridPositionError> {
+        use self::GridPositionError::*;
+
+        let (page, position) = split_page(position.trim());
+        if page == Some("") {
+            return Err(EmptyPage);
+        }
+        if position.is_empty() {
+            return Err(Empty);
+        }
+
+        let row_start = position.find(|c: char| c.is_ascii_digit()).ok_or(MissingRow)?;
+        let (column, row) = position.split_at(row_start);
+
+        if column.is_empty() {
+            return Err(MissingColumn);
+        }
+        if alphabet_value_to_number(column).is_none() {
+            return Err(InvalidColumn(column.to_string()));
+        }
+        if !row.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(InvalidRow(row.to_string()));
+        }
+
+        Ok(Self {
+            column: column.to_string(),
+            row: row.parse().map_err(|_| InvalidRow(row.to_string()))?,
+            page: page.map(|page| page.to_string()),
+        })
+    }
+
+    /// Returns the same position on the page `page`
+    pub fn with_page(self, page: &str) -> Self {
+        Self { page: Some(page.to_string()), .. self }
+    }
+
+    /// Returns the (zero-based) index of the column, `None` if the
+    /// column is not a valid column name such as `"AB"`
+    pub fn column_index(&self) -> Option<usize> {
+        alphabet_value_to_number(&self.column)
+    }
+
+    /// Returns the packed column index and row, `None` if the column is
+    /// not a valid column name or the cell does not fit into a `CellId`
+    pub(crate) fn cell_id(&self) -> Option<CellId> {
+        CellId::new(self.column_index()?, self.row)
+    }
+}
+
+impl Ord for GridPosition {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // invalid columns are ordered after all valid columns
+        let self_column = self.column_index().unwrap_or(usize::MAX);
+        let other_column = other.column_index().unwrap_or(usize::MAX);
+        compare_pages(self.page.as_deref(), other.page.as_deref())
+            .then_with(|| (self_column, self.row).cmp(&(other_column, other.row)))
+            .then_with(|| self.column.cmp(&other.column))
+    }
+}
+
+/// Positions without a page come first, numeric pages are ordered by their
+/// number (`"9"` before `"12"`) and before all other pages
+fn compare_pages(a: Option<&str>, b: Option<&str>) -> Ordering {
+    fn key(page: &str) -> (bool, Option<u64>, &str) {
+        let number = page.parse::<u64>().ok();
+        (number.is_none(), number, page)
+    }
+    a.map(key).cmp(&b.map(key))
+}
+
+impl PartialOrd for GridPosition {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ::std::str::FromStr for GridPosition {
+    type Err = GridPositionError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
 }
 
-impl fmt::Display for GridPosition {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}{}", self.column, self.row)
-    }
+impl fmt::Display for GridPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(page) = &self.page {
+            write!(f, "{}:", page)?;
+        }
+        write!(f, "{}{}", self.column, self.row)
+    }
+}
+
+/// Splits `"12:C4"` into the page `"12"` and the position `"C4"`
+pub(crate) fn split_page(position: &str) -> (Option<&str>, &str) {
+    match position.rfind(':') {
+        Some(separator) => (Some(position[..separator].trim()), position[separator + 1..].trim()),
+        None => (None, position),
+    }
+}
+
+/// Error that can happen when creating or parsing a `GridPosition`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridPositionError {
+    /// The position is an empty string
+    Empty,
+    /// The position has an empty page, i.e. `":C4"`
+    EmptyPage,
+    /// The position has no column, i.e. `"12"`
+    MissingColumn,
+    /// The position has no row, i.e. `"AB"`
+    MissingRow,
+    /// The column is not a column name such as `"AB"` (only uppercase letters)
+    InvalidColumn(String),
+    /// The row is not a number, i.e. `"A1b"` or a number that's too large
+    InvalidRow(String),
+    /// The column scheme has no label for the (zero-based) column index
+    UnlabeledColumn(usize),
+}
+
+impl fmt::Display for GridPositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::GridPositionError::*;
+        match self {
+            Empty => write!(f, "empty grid position"),
+            EmptyPage => write!(f, "grid position has an empty page"),
+            MissingColumn => write!(f, "grid position has no column"),
+            MissingRow => write!(f, "grid position has no row"),
+            InvalidColumn(column) => write!(f, "invalid column \"{}\"", column),
+            InvalidRow(row) => write!(f, "invalid row \"{}\"", row),
+            UnlabeledColumn(index) => write!(f, "column {} has no label in the column scheme", index),
+        }
+    }
+}
+
+impl Error for GridPositionError { }
+
+/// Moves grid positions of one map sheet into the grid of an atlas,
+/// see `DeduplicatedRoads::merge_remapped`
+///
+/// ```no_run,ignore
+/// // the sheet is 8 columns to the right of the origin and on page 12
+/// let remap = PositionRemap::new().with_offset(8, 0).with_page("12");
+/// remap.apply(&"C4".parse()?) // 12:K4
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PositionRemap {
+    /// Number of columns that are added to the column of every position
+    pub column_offset: usize,
+    /// Number of rows that are added to the row of every position
+    pub row_offset: usize,
+    /// Page that every position is moved to (replacing its current page)
+    pub page: Option<String>,
+}
+
+impl PositionRemap {
+
+    /// Remap that leaves positions unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_offset(self, column_offset: usize, row_offset: usize) -> Self {
+        Self { column_offset, row_offset, .. self }
+    }
+
+    pub fn with_page(self, page: &str) -> Self {
+        Self { page: Some(page.to_string()), .. self }
+    }
+
+    /// Returns the remapped position
+    pub fn apply(&self, position: &GridPosition) -> GridPosition {
+        let column = match position.column_index() {
+            Some(index) => number_to_alphabet_value(index + self.column_offset),
+            None => position.column.clone(),
+        };
+        GridPosition {
+            column,
+            row: position.row + self.row_offset,
+            page: self.page.clone().or_else(|| position.page.clone()),
+        }
+    }
+}
+
+/// Deduplicates road names, merging the roads by their name
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeduplicatedRoads {
+    /// Roads without a category
+    pub roads: BTreeMap<StreetName, BTreeSet<GridPosition>>,
+    /// Roads with a category, by their category
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub categories: BTreeMap<Category, BTreeMap<StreetName, BTreeSet<GridPosition>>>,
+    /// Cells of the house number ranges of every street (of all categories)
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub number_ranges: BTreeMap<StreetName, BTreeMap<NumberRange, BTreeSet<GridPosition>>>,
+    /// Summed weights of the cells of every street (of all categories), only
+    /// for the rows that had a weight (see `InputStreetValue::weight`)
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub weights: BTreeMap<StreetName, BTreeMap<GridPosition, Weight>>,
+    /// Secondary name of every street (of all categories) that has one, by
+    /// its primary name (see `InputStreetValue::secondary_name`)
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub secondary_names: BTreeMap<StreetName, StreetName>,
+    /// Districts of every street (of all categories) that has one, see
+    /// `InputStreetValue::district`
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub districts: BTreeMap<StreetName, BTreeSet<String>>,
+    /// Attributes of every street (of all categories) that has any, with
+    /// conflicting values resolved (see `DedupOptions::attribute_conflict`)
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub attributes: BTreeMap<StreetName, BTreeMap<String, String>>,
+}
+
+impl DeduplicatedRoads {
+    /// Deduplicates road names, i.e.:
+    ///
+    /// Input:
+    ///
+    /// ```no_run,ignore
+    /// Mayer Street A4
+    /// Mayer Street A5
+    /// Mayer Street A6
+    /// ```
+    ///
+    /// Output:
+    ///
+    /// ```no_run,ignore
+    /// Mayer Street -> [A4, A5, A6]
+    /// ```
+    ///
+    /// The output road name positions are ordered.
+    pub fn from_streets(streets: &[InputStreetValue]) -> Self {
+        Self::from_streets_with_options(streets, &DedupOptions::default())
+    }
+
+    /// Same as `from_streets`, but names that are equal according to the
+    /// `options` (i.e. `"MAIN STREET"` and `"Main Street"`) become one road
+    pub fn from_streets_with_options(streets: &[InputStreetValue], options: &DedupOptions) -> Self {
+
+        #[cfg(feature = "parallel")]
+        let builder = {
+            use rayon::prelude::*;
+            let new_builder = || DeduplicatedRoadsBuilder::new().with_options(options.clone());
+            streets.par_iter()
+                .fold(new_builder, |mut builder, street| { builder.push(street.clone()); builder })
+                .reduce(new_builder, |mut a, b| { a.merge(b); a })
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        let builder = {
+            let mut builder = DeduplicatedRoadsBuilder::new().with_options(options.clone());
+            builder.extend(streets.iter().cloned());
+            builder
+        };
+
+        builder.finish()
+    }
+
+    /// Reads a CSV previously exported via `ProcessedRoadNames::to_csv()` or
+    /// `UnprocessedRoadNames::to_csv()` (see `InputStreetValue::parse_line`),
+    /// so that it can be merged with new data and exported again.
+    ///
+    /// Empty lines are ignored.
+    pub fn from_csv(csv: &str, delimiter: &str) -> Result<Self, ParseError> {
+        Self::from_csv_limited(csv, delimiter, usize::MAX)
+    }
+
+    /// Same as `from_csv`, but with a limit of the cells per range (see
+    /// `InputStreetValue::parse_line_with_limits`)
+    pub fn from_csv_with_limits(csv: &str, delimiter: &str, limits: &InputLimits) -> Result<Self, ParseError> {
+        Self::from_csv_limited(csv, delimiter, limits.max_range_cells)
+    }
+
+    fn from_csv_limited(csv: &str, delimiter: &str, max_range_cells: usize) -> Result<Self, ParseError> {
+        let mut streets = Vec::new();
+        for (line_number, line) in csv.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed = InputStreetValue::parse_line_limited(line, delimiter, max_range_cells)
+                .map_err(|e| ParseError { line: Some(line_number + 1), kind: e.kind })?;
+            streets.extend(parsed);
+        }
+        Ok(Self::from_streets(&streets))
+    }
+
+    /// Same as `from_csv`, but reads the CSV line by line from the `reader`.
+    /// Returns `Error::EmptyInput` if the CSV contains no streets.
+    pub fn from_csv_reader<R: io::BufRead>(reader: R, delimiter: &str) -> ::error::Result<Self> {
+        let mut builder = DeduplicatedRoadsBuilder::new();
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed = InputStreetValue::parse_line(&line, delimiter)
+                .map_err(|e| ParseError { line: Some(line_number + 1), kind: e.kind })?;
+            builder.extend(parsed);
+        }
+
+        let deduplicated = builder.finish();
+        if deduplicated.roads.is_empty() && deduplicated.categories.is_empty() {
+            return Err(::error::Error::EmptyInput);
+        }
+        Ok(deduplicated)
+    }
+
+    /// Adds the roads of `other` (i.e. the index of another map sheet) to
+    /// these roads, the positions of roads with the same name are combined
+    pub fn merge(&mut self, other: DeduplicatedRoads) {
+        self.merge_remapped(other, &PositionRemap::new());
+    }
+
+    /// Same as `merge`, but moves every position of `other` with the `remap`
+    /// first, i.e. to place a sheet to the right of this one or on another page
+    pub fn merge_remapped(&mut self, other: DeduplicatedRoads, remap: &PositionRemap) {
+        fn merge_roads(target: &mut BTreeMap<StreetName, BTreeSet<GridPosition>>, roads: BTreeMap<StreetName, BTreeSet<GridPosition>>, remap: &PositionRemap) {
+            for (name, positions) in roads {
+                target.entry(name).or_default().extend(positions.iter().map(|position| remap.apply(position)));
+            }
+        }
+
+        merge_roads(&mut self.roads, other.roads, remap);
+        for (category, roads) in other.categories {
+            merge_roads(self.categories.entry(category).or_default(), roads, remap);
+        }
+        for (name, ranges) in other.number_ranges {
+            let target = self.number_ranges.entry(name).or_default();
+            for (range, positions) in ranges {
+                target.entry(range).or_default().extend(positions.iter().map(|position| remap.apply(position)));
+            }
+        }
+        for (name, weights) in other.weights {
+            add_weights(self.weights.entry(name).or_default(), weights.into_iter().map(|(position, weight)| (remap.apply(&position), weight)));
+        }
+        for (name, secondary_name) in other.secondary_names {
+            self.secondary_names.entry(name).or_insert(secondary_name);
+        }
+        for (name, districts) in other.districts {
+            self.districts.entry(name).or_default().extend(districts);
+        }
+        for (name, attributes) in other.attributes {
+            let target = self.attributes.entry(name).or_default();
+            for (key, value) in attributes {
+                target.entry(key).or_insert(value);
+            }
+        }
+    }
+
+    /// Keeps the roads (of all categories) for which `keep` returns `true`,
+    /// i.e. to drop streets with a single cell at the map margin. The house
+    /// numbers of streets that are dropped everywhere are removed as well.
+    pub fn retain<F: FnMut(&StreetName, &BTreeSet<GridPosition>) -> bool>(mut self, mut keep: F) -> Self {
+        self.roads.retain(|name, positions| keep(name, positions));
+        for roads in self.categories.values_mut() {
+            roads.retain(|name, positions| keep(name, positions));
+        }
+        self.without_empty()
+    }
+
+    /// Renames every road (and its house numbers), roads that get the same
+    /// name are combined. `rename` is called once per distinct name.
+    pub fn map_names<F: FnMut(&StreetName) -> StreetName>(self, mut rename: F) -> Self {
+        fn rename_all(roads: BTreeMap<StreetName, BTreeSet<GridPosition>>, names: &BTreeMap<StreetName, StreetName>) -> BTreeMap<StreetName, BTreeSet<GridPosition>> {
+            let mut renamed = BTreeMap::<StreetName, BTreeSet<GridPosition>>::new();
+            for (name, positions) in roads {
+                renamed.entry(names[&name].clone()).or_default().extend(positions);
+            }
+            renamed
+        }
+
+        let mut names = BTreeMap::new();
+        let all_names = self.roads.keys()
+            .chain(self.categories.values().flat_map(|roads| roads.keys()))
+            .chain(self.number_ranges.keys())
+            .chain(self.weights.keys())
+            .chain(self.secondary_names.keys())
+            .chain(self.districts.keys())
+            .chain(self.attributes.keys());
+        for name in all_names {
+            if !names.contains_key(name) {
+                names.insert(name.clone(), rename(name));
+            }
+        }
+
+        let mut number_ranges = BTreeMap::<StreetName, BTreeMap<NumberRange, BTreeSet<GridPosition>>>::new();
+        for (name, ranges) in self.number_ranges {
+            let target = number_ranges.entry(names[&name].clone()).or_default();
+            for (range, positions) in ranges {
+                target.entry(range).or_default().extend(positions);
+            }
+        }
+
+        let mut weights = BTreeMap::<StreetName, BTreeMap<GridPosition, Weight>>::new();
+        for (name, cells) in self.weights {
+            add_weights(weights.entry(names[&name].clone()).or_default(), cells);
+        }
+
+        let mut secondary_names = BTreeMap::new();
+        for (name, secondary_name) in self.secondary_names {
+            secondary_names.entry(names[&name].clone()).or_insert(secondary_name);
+        }
+
+        let mut districts = BTreeMap::<StreetName, BTreeSet<String>>::new();
+        for (name, street_districts) in self.districts {
+            districts.entry(names[&name].clone()).or_default().extend(street_districts);
+        }
+
+        let mut attributes = BTreeMap::<StreetName, BTreeMap<String, String>>::new();
+        for (name, street_attributes) in self.attributes {
+            let target = attributes.entry(names[&name].clone()).or_default();
+            for (key, value) in street_attributes {
+                target.entry(key).or_insert(value);
+            }
+        }
+
+        DeduplicatedRoads {
+            roads: rename_all(self.roads, &names),
+            categories: self.categories.into_iter().map(|(category, roads)| (category, rename_all(roads, &names))).collect(),
+            number_ranges,
+            weights,
+            secondary_names,
+            districts,
+            attributes,
+        }
+    }
+
+    /// Keeps the cells (of all roads and house numbers) for which `keep`
+    /// returns `true`, roads without any cells left are removed
+    pub fn filter_cells<F: FnMut(&GridPosition) -> bool>(mut self, mut keep: F) -> Self {
+        let roads = ::std::iter::once(&mut self.roads).chain(self.categories.values_mut()).flat_map(|roads| roads.values_mut());
+        let ranges = self.number_ranges.values_mut().flat_map(|ranges| ranges.values_mut());
+        for positions in roads.chain(ranges) {
+            positions.retain(|position| keep(position));
+        }
+        for weights in self.weights.values_mut() {
+            weights.retain(|position, _| keep(position));
+        }
+        self.without_empty()
+    }
+
+    /// Removes roads without cells, empty categories and the house numbers,
+    /// weights, secondary names, districts and attributes of streets that are in no category anymore
+    fn without_empty(mut self) -> Self {
+        for roads in ::std::iter::once(&mut self.roads).chain(self.categories.values_mut()) {
+            roads.retain(|_, positions| !positions.is_empty());
+        }
+        self.categories.retain(|_, roads| !roads.is_empty());
+
+        let (roads, categories) = (&self.roads, &self.categories);
+        self.number_ranges.retain(|name, ranges| {
+            ranges.retain(|_, positions| !positions.is_empty());
+            !ranges.is_empty() && (roads.contains_key(name) || categories.values().any(|roads| roads.contains_key(name)))
+        });
+        self.weights.retain(|name, weights| {
+            !weights.is_empty() && (roads.contains_key(name) || categories.values().any(|roads| roads.contains_key(name)))
+        });
+        self.secondary_names.retain(|name, _| roads.contains_key(name) || categories.values().any(|roads| roads.contains_key(name)));
+        self.districts.retain(|name, _| roads.contains_key(name) || categories.values().any(|roads| roads.contains_key(name)));
+        self.attributes.retain(|name, _| roads.contains_key(name) || categories.values().any(|roads| roads.contains_key(name)));
+        self
+    }
+
+    /// Returns the street names (of the roads without a category)
+    /// ordered by the `strategy` instead of their byte order
+    pub fn sorted_names<S: SortStrategy + ?Sized>(&self, strategy: &S) -> Vec<&StreetName> {
+        let mut names = self.roads.keys().collect::<Vec<&StreetName>>();
+        names.sort_by(|a, b| strategy.compare(&a.0, &b.0));
+        names
+    }
+
+    /// Processes road names (`[A1, A2]` => `A1-A2`) if they span less than 2 grids.
+    /// 
+    /// Processing road names in a cartographic manner is tricky. For example, a 
+    /// street that appears in two locations on the map (such as a city having 
+    /// the same street name as a neighbouring city). Because of this, street name
+    /// processing can't be fully automated, since there are always weird edge cases 
+    /// to worry about. However, 90% of roads aren't like that.
+    /// 
+    /// Because of this limitation `process()` gives you two types of roads back:
+    ///
+    /// `ProcessedRoadName` is for roads that span only 1 or 2 grid cells
+    /// (i.e. `"Canterbury Road" => A9`, `"Canterbury Road" => A9-A10`).
+    /// In these cases (which cover 90% of street index names), the mapping is not
+    /// ambigouus. Roads whose cells exactly fill a rectangle (`[A4, A5, B4, B5]`)
+    /// are processed as well, as a range between two corners (`A4-B5`).
+    /// 
+    /// `UnprocessedRoadName` is for anything else (e.g. `"Canterbury Road" => [A9, A10, E1, E2]`. 
+    /// Usually these roads need to be manually reviewed - it could likely be that 
+    /// there are two roads `"Canterbury Road" => A9-10;E1-E2`, but it could also
+    /// be that the road is just one road and part of it is just clipped off the map,
+    /// in which case you'd write `"Canterbury Road" => A9-E2`. 
+    pub fn process(&self) -> StreetIndex {
+        self.process_with_options(&ProcessOptions::default())
+    }
+
+    /// Same as `process()`, but returns the processed and unprocessed roads as a tuple
+    #[deprecated(note = "use `process()`, which returns a `StreetIndex`")]
+    pub fn process_tuple(&self) -> (ProcessedRoadNames, UnprocessedRoadNames) {
+        self.process().into_parts()
+    }
+
+    /// Same as `process()`, but additionally collapses straight, gap-free runs
+    /// of cells into a range, i.e. `[A4, A5, A6, A7]` => `A4-A7` or
+    /// `[A4, B4, C4]` => `A4-C4`.
+    ///
+    /// Only roads whose cells are not adjacent (or don't lie in a single
+    /// row or column) end up as an `UnprocessedRoad`.
+    pub fn process_with_ranges(&self) -> StreetIndex {
+        self.process_with_options(&ProcessOptions::default().with_ranges(true))
+    }
+
+    /// Splits every road into clusters of cells that are at most `max_gap`
+    /// empty cells apart from each other and emits one `ProcessedRoad` per
+    /// cluster, i.e. with a `max_gap` of `0`:
+    ///
+    /// ```no_run,ignore
+    /// "Canterbury Road" => [A9, A10, E1, E2]
+    /// ```
+    ///
+    /// becomes:
+    ///
+    /// ```no_run,ignore
+    /// "Canterbury Road" => A9-A10
+    /// "Canterbury Road" => E1-E2
+    /// ```
+    ///
+    /// Diagonal neighbours count as adjacent. Clusters that span more than two
+    /// cells are collapsed into a range (see `process_with_ranges()`), where
+    /// gaps of up to `max_gap` cells are allowed. If any cluster of a road
+    /// can't be expressed as a single entry, the whole road is returned as
+    /// an `UnprocessedRoad`.
+    pub fn process_with_clustering(&self, max_gap: usize) -> StreetIndex {
+        self.process_with_options(&ProcessOptions::default().with_ranges(true).with_cluster_gap(Some(max_gap)))
+    }
+
+    /// Same as `process()`, but with a configurable number of cells that
+    /// are accepted without review, ranges and clustering (see `ProcessOptions`)
+    pub fn process_with_options(&self, options: &ProcessOptions) -> StreetIndex {
+        self.process_roads(options, None)
+    }
+
+    /// Same as `process_with_options()`, but checks the `cancellation`
+    /// token before every road, fails with `Error::Cancelled`
+    pub fn process_cancellable(&self, options: &ProcessOptions, cancellation: &CancellationToken) -> ::error::Result<StreetIndex> {
+        let index = self.process_roads(options, Some(cancellation));
+        cancellation.check()?;
+        Ok(index)
+    }
+
+    /// Skips the remaining roads once the `cancellation` token is cancelled
+    fn process_roads(&self, options: &ProcessOptions, cancellation: Option<&CancellationToken>) -> StreetIndex {
+
+        let roads = self.all_roads();
+        let process = |(category, name, positions): &(Option<&Category>, &StreetName, &BTreeSet<GridPosition>)| {
+            if is_cancelled(cancellation) {
+                None
+            } else {
+                process_road(name, *category, &self.primary_positions(name, positions, options), options)
+            }
+        };
+
+        #[cfg(feature = "parallel")]
+        let results = {
+            use rayon::prelude::*;
+            roads.par_iter().map(process).collect::<Vec<_>>()
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        let results = roads.iter().map(process).collect::<Vec<_>>();
+
+        let mut processed = Vec::new();
+        let mut unprocessed = Vec::new();
+
+        for result in results {
+            match result {
+                Some(Ok(roads)) => processed.extend(roads),
+                Some(Err(road)) => unprocessed.push(road),
+                None => { },
+            }
+        }
+
+        // roads of all categories by name, see `ProcessedRoad::tie_break`
+        processed.sort_by(|a, b| a.tie_break(b));
+        unprocessed.sort_by(|a, b| a.tie_break(b));
+
+        let mut index = StreetIndex::new(ProcessedRoadNames { processed }, UnprocessedRoadNames { unprocessed });
+        index.house_numbers = self.number_ranges.iter()
+            .map(|(name, ranges)| (name.clone(), process_house_numbers(name, ranges, options)))
+            .collect();
+        index.secondary_names = self.secondary_names.clone();
+        index.districts = self.districts.clone();
+        index.attributes = self.attributes.clone();
+        index
+    }
+
+    /// Returns only the cell with the highest weight of the road (the first
+    /// one on a tie) if `ProcessOptions::primary_cell` is set, all positions
+    /// otherwise or if none of the positions has a weight
+    pub(crate) fn primary_positions<'a>(&self, name: &StreetName, positions: &'a BTreeSet<GridPosition>, options: &ProcessOptions) -> Cow<'a, BTreeSet<GridPosition>> {
+        let weights = match self.weights.get(name) {
+            Some(weights) if options.primary_cell => weights,
+            _ => return Cow::Borrowed(positions),
+        };
+        let mut primary = None::<(&GridPosition, Weight)>;
+        for (position, weight) in positions.iter().filter_map(|position| weights.get(position).map(|weight| (position, *weight))) {
+            if primary.is_none_or(|(_, best)| weight > best) {
+                primary = Some((position, weight));
+            }
+        }
+        match primary {
+            Some((position, _)) => Cow::Owned(::std::iter::once(position.clone()).collect()),
+            None => Cow::Borrowed(positions),
+        }
+    }
+
+    /// Returns all roads with their category, roads without a category first
+    pub(crate) fn all_roads(&self) -> Vec<(Option<&Category>, &StreetName, &BTreeSet<GridPosition>)> {
+        let uncategorized = self.roads.iter().map(|(name, positions)| (None, name, positions));
+        let categorized = self.categories.iter().flat_map(|(category, roads)| {
+            roads.iter().map(move |(name, positions)| (Some(category), name, positions))
+        });
+        uncategorized.chain(categorized).collect()
+    }
+}
+
+/// Options for `DeduplicatedRoads::process_with_options`
+///
+/// ```no_run,ignore
+/// // accept straight, gap-free runs of up to 6 cells without review
+/// let options = ProcessOptions::default().with_max_auto_cells(6);
+/// deduplicated.process_with_options(&options) // "Mill Lane\tC3-C8"
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+pub struct ProcessOptions {
+    /// Roads with up to this many cells are processed automatically (default:
+    /// 2, i.e. `A9-A10`), roads with more than two cells only if they are a
+    /// straight, gap-free run of cells. Longer roads need `allow_ranges`.
+    /// Roads that exactly fill a rectangle of cells (`A4-B5`) are always processed.
+    pub max_auto_cells: usize,
+    /// Collapses runs of any length into a range (see `process_with_ranges`)
+    pub allow_ranges: bool,
+    /// Splits the roads into clusters of cells that are at most this many
+    /// empty cells apart (see `process_with_clustering`)
+    pub cluster_gap: Option<usize>,
+    /// Reduces every road with weights to its cell with the highest weight
+    /// (i.e. the cell with the longest part of the road), see `InputStreetValue::weight`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub primary_cell: bool,
+}
+
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        Self {
+            max_auto_cells: 2,
+            allow_ranges: false,
+            cluster_gap: None,
+            primary_cell: false,
+        }
+    }
+}
+
+impl ProcessOptions {
+
+    pub fn with_max_auto_cells(self, max_auto_cells: usize) -> Self {
+        Self { max_auto_cells, .. self }
+    }
+
+    pub fn with_ranges(self, allow_ranges: bool) -> Self {
+        Self { allow_ranges, .. self }
+    }
+
+    pub fn with_cluster_gap(self, cluster_gap: Option<usize>) -> Self {
+        Self { cluster_gap, .. self }
+    }
+
+    pub fn with_primary_cell(self, primary_cell: bool) -> Self {
+        Self { primary_cell, .. self }
+    }
+}
+
+/// Processes the positions of one road, see `DeduplicatedRoads::process_with_options`.
+/// Returns `None` if the road has no positions.
+pub(crate) fn process_road(road_name: &StreetName, category: Option<&Category>, positions: &BTreeSet<GridPosition>, options: &ProcessOptions)
+-> Option<Result<Vec<ProcessedRoad>, UnprocessedRoad>>
+{
+    let positions_vec = positions.iter().cloned().collect::<Vec<GridPosition>>();
+    if positions_vec.is_empty() {
+        return None;
+    }
+
+    // every page is processed on its own (positions are ordered by their page)
+    let mut pages: Vec<Vec<GridPosition>> = Vec::new();
+    for position in &positions_vec {
+        match pages.last_mut() {
+            Some(page) if page[0].page == position.page => page.push(position.clone()),
+            _ => pages.push(vec![position.clone()]),
+        }
+    }
+
+    let clusters = pages.into_iter().flat_map(|page| {
+        options.cluster_gap
+            .and_then(|gap| cluster_positions(&page, gap))
+            .unwrap_or_else(|| vec![page])
+    }).collect::<Vec<Vec<GridPosition>>>();
+
+    let finalized = clusters
+        .iter()
+        .map(|cluster| finalize_positions(cluster, options))
+        .collect::<Option<Vec<FinalizedGridPositon>>>();
+
+    Some(match finalized {
+        Some(finalized) => Ok(finalized.into_iter().map(|position| ProcessedRoad {
+            name: road_name.clone(),
+            position,
+            category: category.cloned(),
+        }).collect()),
+        None => Err(UnprocessedRoad { name: road_name.clone(), positions: positions_vec, category: category.cloned() }),
+    })
+}
+
+/// Turns the (ordered) positions of one road into a `FinalizedGridPositon`,
+/// returns `None` if the road needs to be reviewed manually.
+pub(crate) fn finalize_positions(positions: &[GridPosition], options: &ProcessOptions) -> Option<FinalizedGridPositon> {
+    let max_gap = options.cluster_gap.unwrap_or(0);
+    match positions.len() {
+        0 => None,
+        1 => Some(FinalizedGridPositon::SingleRect(positions[0].clone())),
+        n if n > options.max_auto_cells && !options.allow_ranges => {
+            filled_rectangle(positions).map(|(first, last)| FinalizedGridPositon::Range(first, last))
+        },
+        2 => Some(FinalizedGridPositon::TwoRect(positions[0].clone(), positions[1].clone())),
+        _ => contiguous_run(positions, max_gap)
+            .or_else(|| filled_rectangle(positions))
+            .map(|(first, last)| FinalizedGridPositon::Range(first, last)),
+    }
+}
+
+/// If the (distinct) positions exactly fill the rectangle between their
+/// extremes and span at least two columns and two rows (i.e. `A4, A5, B4,
+/// B5`), returns the top left and the bottom right corner (`A4-B5`)
+fn filled_rectangle(positions: &[GridPosition]) -> Option<(GridPosition, GridPosition)> {
+    let indexed = index_positions(positions)?;
+    let (first, last) = core_index::filled_rectangle(&indexed.iter().map(|&(cell, _)| cell).collect::<Vec<_>>())?;
+    Some((position_of(&indexed, first)?, position_of(&indexed, last)?))
+}
+
+/// Converts the positions to `(cell id, position)` pairs, returns `None`
+/// if any of the positions has no `CellId` (see `GridPosition::cell_id`).
+fn index_positions(positions: &[GridPosition]) -> Option<Vec<(CellId, &GridPosition)>> {
+    positions.iter().map(|position| position.cell_id().map(|cell| (cell, position))).collect()
+}
+
+/// Returns the (first) position with the `cell` id
+fn position_of(indexed: &[(CellId, &GridPosition)], cell: CellId) -> Option<GridPosition> {
+    indexed.iter().find(|&&(id, _)| id == cell).map(|&(_, position)| position.clone())
+}
+
+/// Groups the positions into clusters, where each cell of a cluster is
+/// at most `max_gap` empty cells away from another cell of the same cluster
+/// (see `core_index::cluster_cells`).
+///
+/// The clusters are ordered by their first position, the positions inside
+/// of each cluster keep the order of the input.
+pub(crate) fn cluster_positions(positions: &[GridPosition], max_gap: usize) -> Option<Vec<Vec<GridPosition>>> {
+    let indexed = index_positions(positions)?;
+    let clusters = core_index::cluster_cells(&indexed.iter().map(|&(cell, _)| cell).collect::<Vec<_>>(), max_gap);
+    Some(clusters.into_iter().map(|cluster| cluster.into_iter().map(|i| indexed[i].1.clone()).collect()).collect())
+}
+
+/// Builds `DeduplicatedRoads` incrementally, so that huge inputs can be
+/// fed from a streaming parser without collecting them into a `Vec` first:
+///
+/// ```no_run,ignore
+/// let mut builder = DeduplicatedRoadsBuilder::new();
+/// for street in OsmRoadReader::new(file, grid) {
+///     builder.push(street?);
+/// }
+/// let deduplicated = builder.finish();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeduplicatedRoadsBuilder {
+    /// Roads by their category and their `DedupOptions::key`
+    roads: BTreeMap<(Option<Category>, String), DedupEntry>,
+    backend: DedupBackend,
+    /// Rows that were added with the `DedupBackend::Flat`
+    flat: FlatRoads,
+    options: DedupOptions,
+    filter: Option<Filter>,
+    aliases: Option<AliasTable>,
+    /// Number of rows that were added, for `ConflictPolicy::First` / `Last`
+    rows: usize,
+}
+
+/// Positions of one road and how often each spelling of the name occurred
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DedupEntry {
+    pub spellings: BTreeMap<String, usize>,
+    pub positions: BTreeSet<GridPosition>,
+    pub number_ranges: BTreeMap<NumberRange, BTreeSet<GridPosition>>,
+    pub weights: BTreeMap<GridPosition, Weight>,
+    pub secondary_spellings: BTreeMap<String, usize>,
+    pub districts: BTreeSet<String>,
+    /// Values of every attribute
+    pub attributes: BTreeMap<String, BTreeMap<String, ValueStats>>,
+    /// Categories of the rows, see `DedupOptions::category_conflict`
+    pub categories: BTreeMap<Option<Category>, ValueStats>,
+}
+
+impl DeduplicatedRoadsBuilder {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Controls which names are considered equal, see `DedupOptions`
+    pub fn with_options(self, options: DedupOptions) -> Self {
+        Self { options, .. self }
+    }
+
+    /// Normalizes the name of every street before it is added, so that
+    /// `"Main St."` and `"Main Street"` end up as one road
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.options.normalizer = Some(normalizer);
+        self
+    }
+
+    /// Skips streets that are excluded by the `filter`
+    pub fn with_filter(self, filter: Filter) -> Self {
+        Self { filter: Some(filter), .. self }
+    }
+
+    /// Replaces aliases by their canonical name, so that `"Boston Post Road"`
+    /// and `"Route 9"` become one road (see `AliasTable`)
+    pub fn with_aliases(self, aliases: AliasTable) -> Self {
+        Self { aliases: Some(aliases), .. self }
+    }
+
+    /// Selects how the rows are stored until `finish()`, see `DedupBackend`
+    pub fn with_backend(self, backend: DedupBackend) -> Self {
+        Self { backend, .. self }
+    }
+
+    /// Drops rows of the `DedupBackend::Flat` whose cell was already added
+    /// to the road immediately instead of in `finish()`. This costs a hash
+    /// set lookup per row, but keeps the memory bounded by the number of
+    /// unique cells. The `DedupBackend::BTree` always merges immediately.
+    pub fn with_dedup_on_insert(mut self, dedup_on_insert: bool) -> Self {
+        self.flat.dedup_on_insert = dedup_on_insert;
+        self
+    }
+
+    /// Adds one street, duplicated positions are merged immediately (with
+    /// the `DedupBackend::BTree`) or in `finish()`
+    pub fn push(&mut self, street: InputStreetValue) {
+        if self.backend == DedupBackend::Flat {
+            let (options, filter, aliases) = (&self.options, self.filter.as_ref(), self.aliases.as_ref());
+            if self.flat.push(street, self.rows, options, |name| filtered_display_name(name, options, filter, aliases).map(Cow::into_owned)) {
+                self.rows += 1;
+            }
+            return;
+        }
+        let display_name = match filtered_display_name(&street.street_name.0, &self.options, self.filter.as_ref(), self.aliases.as_ref()) {
+            Some(display_name) => display_name,
+            None => return,
+        };
+        let row = self.rows;
+        self.rows += 1;
+        let entry = self.roads.entry((self.options.category_key(street.category.clone()), self.options.key(&display_name))).or_default();
+        add_value(&mut entry.categories, street.category, ValueStats::new(row));
+        *entry.spellings.entry(display_name.into_owned()).or_insert(0) += 1;
+        if let Some(range) = street.number_range {
+            entry.number_ranges.entry(range).or_default().insert(street.position.clone());
+        }
+        if let Some(weight) = street.weight {
+            *entry.weights.entry(street.position.clone()).or_default() += weight;
+        }
+        if let Some(secondary_name) = street.secondary_name {
+            *entry.secondary_spellings.entry(secondary_name.0).or_insert(0) += 1;
+        }
+        entry.districts.extend(street.district);
+        for (key, value) in street.attributes {
+            add_value(entry.attributes.entry(key).or_default(), value, ValueStats::new(row));
+        }
+        entry.positions.insert(street.position);
+    }
+
+    /// Adds all streets of the iterator
+    pub fn extend<I: IntoIterator<Item = InputStreetValue>>(&mut self, streets: I) {
+        for street in streets {
+            self.push(street);
+        }
+    }
+
+    /// Adds all streets of `other` (i.e. a builder that was filled on another
+    /// thread). The options of `other` are ignored, its rows count as
+    /// added after the rows of this builder.
+    pub fn merge(&mut self, other: DeduplicatedRoadsBuilder) {
+        let offset = self.rows;
+        self.rows += other.rows;
+        self.flat.merge(other.flat, offset, &self.options);
+        self.merge_entries(other.roads, offset);
+    }
+
+    /// Adds the `roads` of another builder, whose rows are moved back by `offset`
+    fn merge_entries(&mut self, roads: BTreeMap<(Option<Category>, String), DedupEntry>, offset: usize) {
+        for ((category, key), entry) in roads {
+            let target = self.roads.entry((self.options.category_key(category), key)).or_default();
+            for (spelling, count) in entry.spellings {
+                *target.spellings.entry(spelling).or_insert(0) += count;
+            }
+            target.positions.extend(entry.positions);
+            for (range, positions) in entry.number_ranges {
+                target.number_ranges.entry(range).or_default().extend(positions);
+            }
+            add_weights(&mut target.weights, entry.weights);
+            for (spelling, count) in entry.secondary_spellings {
+                *target.secondary_spellings.entry(spelling).or_insert(0) += count;
+            }
+            target.districts.extend(entry.districts);
+            for (key, values) in entry.attributes {
+                let target = target.attributes.entry(key).or_default();
+                for (value, stats) in values {
+                    add_value(target, value, stats.offset(offset));
+                }
+            }
+            for (category, stats) in entry.categories {
+                add_value(&mut target.categories, category, stats.offset(offset));
+            }
+        }
+    }
+
+    /// Names every road by its most frequent spelling (on a tie, the
+    /// spelling that comes first in byte order), the same goes for the
+    /// secondary name. Conflicting attribute values and categories are
+    /// resolved with the `ConflictPolicy` of the `DedupOptions`.
+    pub fn finish(self) -> DeduplicatedRoads {
+        self.finish_with_conflicts(None).0
+    }
+
+    /// Same as `finish()`, but checks the `cancellation` token before
+    /// every road, fails with `Error::Cancelled`
+    pub fn finish_cancellable(self, cancellation: &CancellationToken) -> ::error::Result<DeduplicatedRoads> {
+        let (deduplicated, _) = self.finish_with_conflicts(Some(cancellation));
+        cancellation.check()?;
+        Ok(deduplicated)
+    }
+
+    /// Same as `finish()`, but fails with `Error::Conflict` on the first
+    /// conflict of a `ConflictPolicy::Error`
+    pub fn try_finish(self) -> ::error::Result<DeduplicatedRoads> {
+        let (deduplicated, conflicts) = self.finish_with_conflicts(None);
+        match conflicts.into_iter().next() {
+            Some(conflict) => Err(conflict.into()),
+            None => Ok(deduplicated),
+        }
+    }
+
+    /// Stops at the first road after the `cancellation` token is cancelled
+    fn finish_with_conflicts(mut self, cancellation: Option<&CancellationToken>) -> (DeduplicatedRoads, Vec<Conflict>) {
+        let flat = ::std::mem::take(&mut self.flat).into_entries();
+        if self.roads.is_empty() {
+            self.roads = flat;
+        } else {
+            self.merge_entries(flat, 0);
+        }
+        let mut conflicts = Vec::new();
+        let mut deduplicated = DeduplicatedRoads { roads: BTreeMap::new(), categories: BTreeMap::new(), number_ranges: BTreeMap::new(), weights: BTreeMap::new(), secondary_names: BTreeMap::new(), districts: BTreeMap::new(), attributes: BTreeMap::new() };
+
+        for ((category, _), entry) in self.roads {
+            if is_cancelled(cancellation) {
+                break;
+            }
+            let name = most_frequent(entry.spellings).unwrap_or_default();
+            let category = match &self.options.category_conflict {
+                Some(policy) if self.options.merges_categories() => {
+                    resolve_category(policy, &StreetName(name.clone()), &entry.categories, &mut conflicts)
+                },
+                _ => category,
+            };
+            if let Some(secondary_name) = most_frequent(entry.secondary_spellings) {
+                deduplicated.secondary_names.entry(StreetName(name.clone())).or_insert(StreetName(secondary_name));
+            }
+            if !entry.districts.is_empty() {
+                deduplicated.districts.entry(StreetName(name.clone())).or_default().extend(entry.districts);
+            }
+            let attributes = self.options.attribute_conflict.resolve_attributes(&StreetName(name.clone()), entry.attributes, &mut conflicts);
+            if !attributes.is_empty() {
+                deduplicated.attributes.entry(StreetName(name.clone())).or_default().extend(attributes);
+            }
+            if !entry.number_ranges.is_empty() {
+                let ranges = deduplicated.number_ranges.entry(StreetName(name.clone())).or_default();
+                for (range, positions) in entry.number_ranges {
+                    ranges.entry(range).or_default().extend(positions);
+                }
+            }
+            if !entry.weights.is_empty() {
+                add_weights(deduplicated.weights.entry(StreetName(name.clone())).or_default(), entry.weights);
+            }
+            let roads = match category {
+                Some(category) => deduplicated.categories.entry(category).or_default(),
+                None => &mut deduplicated.roads,
+            };
+            roads.insert(StreetName(name), entry.positions);
+        }
+
+        (deduplicated, conflicts)
+    }
+}
+
+/// Category of a road whose rows are merged across categories, on a
+/// conflict the category of the first row
+pub(crate) fn resolve_category(policy: &ConflictPolicy, street: &StreetName, categories: &BTreeMap<Option<Category>, ValueStats>, conflicts: &mut Vec<Conflict>) -> Option<Category> {
+    let values = policy.resolve(categories).unwrap_or_else(|values| {
+        conflicts.push(Conflict {
+            street: street.clone(),
+            field: String::from("category"),
+            values: values.iter().map(|category| category.as_ref().map(|category| category.to_string()).unwrap_or_default()).collect(),
+        });
+        values
+    });
+    values.into_iter().next().flatten()
+}
+
+/// Applies the `filter`, the `aliases` and the `DedupOptions::display_name`
+/// to the name of a street, `None` if the street is excluded
+fn filtered_display_name<'a>(name: &'a str, options: &DedupOptions, filter: Option<&Filter>, aliases: Option<&'a AliasTable>) -> Option<Cow<'a, str>> {
+    if filter.is_some_and(|filter| filter.is_excluded(name)) {
+        return None;
+    }
+    let name = match aliases {
+        Some(aliases) => aliases.canonical(name),
+        None => name,
+    };
+    Some(options.display_name(name))
+}
+
+/// Most frequent spelling, on a tie the one that comes first in byte order
+fn most_frequent(spellings: BTreeMap<String, usize>) -> Option<String> {
+    let mut spellings = spellings.into_iter().collect::<Vec<(String, usize)>>();
+    spellings.sort_by(|(a_name, a_count), (b_name, b_count)| b_count.cmp(a_count).then_with(|| a_name.cmp(b_name)));
+    spellings.into_iter().next().map(|(name, _)| name)
+}
+
+/// Adds the `weights` to the weights of the same cells in `target`
+pub(crate) fn add_weights<I: IntoIterator<Item = (GridPosition, Weight)>>(target: &mut BTreeMap<GridPosition, Weight>, weights: I) {
+    for (position, weight) in weights {
+        *target.entry(position).or_default() += weight;
+    }
+}
+
+impl Extend<InputStreetValue> for DeduplicatedRoadsBuilder {
+    fn extend<I: IntoIterator<Item = InputStreetValue>>(&mut self, streets: I) {
+        DeduplicatedRoadsBuilder::extend(self, streets);
+    }
+}
+
+impl ::std::iter::FromIterator<InputStreetValue> for DeduplicatedRoads {
+    fn from_iter<I: IntoIterator<Item = InputStreetValue>>(streets: I) -> Self {
+        let mut builder = DeduplicatedRoadsBuilder::new();
+        builder.extend(streets);
+        builder.finish()
+    }
+}
+
+/// If the positions form a straight run of cells within one column or one
+/// row (where no more than `max_gap` cells are missing between two cells),
+/// returns the first and the last cell of the run.
+///
+/// Columns are compared by their numeric value, so `Z` and `AA` are adjacent.
+fn contiguous_run(positions: &[GridPosition], max_gap: usize) -> Option<(GridPosition, GridPosition)> {
+    let indexed = index_positions(positions)?;
+    let (first, last) = core_index::contiguous_run(&indexed.iter().map(|&(cell, _)| cell).collect::<Vec<_>>(), max_gap)?;
+    Some((position_of(&indexed, first)?, position_of(&indexed, last)?))
+}
+
+#[test]
+fn test_tie_break_order() {
+    use sorting::NaturalOrder;
+
+    let cell = |page: &str, column: &str, row: usize| GridPosition { column: String::from(column), row, page: Some(String::from(page)) };
+    let streets = vec![
+        InputStreetValue::new("Main Street", cell("2", "A", 1)),
+        InputStreetValue::new("main street", cell("1", "B", 1)),
+        InputStreetValue::new("Main Street", cell("1", "C", 1)),
+        InputStreetValue::new("Main Street", cell("1", "C", 1)).with_category(Category::Poi),
+    ];
+
+    let expected = "Main Street\t1:C1\r\nMain Street\t2:A1\r\nMain Street\t1:C1\r\nmain street\t1:B1";
+    let mut index = DeduplicatedRoads::from_streets(&streets).process();
+    assert_eq!(index.processed.to_csv("\t"), expected);
+
+    // the order doesn't depend on the order of the input
+    index.processed.processed.reverse();
+    index.processed.sort_by_strategy(&NaturalOrder::default());
+    assert_eq!(index.processed.to_csv("\t"), expected);
+    assert_eq!(index.processed.processed[2].category, Some(Category::Poi));
+}
+
+#[test]
+fn test_process_with_options() {
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let rows = |rows: ::std::ops::RangeInclusive<usize>| rows.map(|row| InputStreetValue::new("Mill Lane", cell("C", row))).collect::<Vec<_>>();
+
+    let six = DeduplicatedRoads::from_streets(&rows(3..=8));
+    let options = ProcessOptions::default().with_max_auto_cells(6);
+    assert_eq!(six.process().processed.processed.len(), 0);
+    assert_eq!(six.process_with_options(&options).to_csv("\t"), "Mill Lane\tC3-C8");
+
+    // too many cells or a gap still need a review
+    let seven = DeduplicatedRoads::from_streets(&rows(3..=9));
+    assert_eq!(seven.process_with_options(&options).unprocessed.unprocessed.len(), 1);
+    assert_eq!(seven.process_with_options(&options.with_ranges(true)).to_csv("\t"), "Mill Lane\tC3-C9");
+    let mut gap = rows(3..=4);
+    gap.extend(rows(6..=7));
+    assert_eq!(DeduplicatedRoads::from_streets(&gap).process_with_options(&options).unprocessed.unprocessed.len(), 1);
+
+    // no automatic processing of two cells
+    let two = DeduplicatedRoads::from_streets(&rows(3..=4));
+    assert_eq!(two.process_with_options(&ProcessOptions::default().with_max_auto_cells(1)).unprocessed.unprocessed.len(), 1);
+    assert_eq!(two.process_with_options(&ProcessOptions::default()), two.process());
+}
+
+#[test]
+fn test_process_filled_rectangle() {
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let streets = |cells: &[(&str, usize)]| cells.iter().map(|&(column, row)| InputStreetValue::new("Market Square", cell(column, row))).collect::<Vec<_>>();
+
+    let block = DeduplicatedRoads::from_streets(&streets(&[("A", 4), ("A", 5), ("B", 4), ("B", 5), ("C", 4), ("C", 5)]));
+    assert_eq!(block.process().processed.processed[0].position, FinalizedGridPositon::Range(cell("A", 4), cell("C", 5)));
+    assert_eq!(block.process_with_ranges().to_csv("\t"), "Market Square\tA4-C5");
+
+    // one cell missing, or a straight run without ranges
+    let holes = DeduplicatedRoads::from_streets(&streets(&[("A", 4), ("A", 5), ("B", 4)]));
+    assert_eq!(holes.process().unprocessed.unprocessed.len(), 1);
+    let run = DeduplicatedRoads::from_streets(&streets(&[("A", 4), ("A", 5), ("A", 6)]));
+    assert_eq!(run.process().unprocessed.unprocessed.len(), 1);
+}
+
+#[test]
+fn test_deduplicate_streets() {
+    let input = [
+        InputStreetValue::new("Valley View Road", "A4".parse().unwrap()),
+        InputStreetValue::new("Valley View Road", "A5".parse().unwrap()),
+        InputStreetValue::new("Valley View Road", "B6".parse().unwrap()),
+    ];
+
+    // "Valley View Road" -> ["A4", "A5", "B6"]
+    let mut output_expected = BTreeMap::new();
+    let mut valley_view_road_expected = BTreeSet::new();
+    valley_view_road_expected.insert(GridPosition { column: String::from("A"), row: 4, page: None });
+    valley_view_road_expected.insert(GridPosition { column: String::from("A"), row: 5, page: None });
+    valley_view_road_expected.insert(GridPosition { column: String::from("B"), row: 6, page: None });
+    output_expected.insert(StreetName(String::from("Valley View Road")), valley_view_road_expected);
+
+    assert_eq!(DeduplicatedRoads::from_streets(&input), DeduplicatedRoads { roads: output_expected, categories: BTreeMap::new(), number_ranges: BTreeMap::new(), weights: BTreeMap::new(), secondary_names: BTreeMap::new(), districts: BTreeMap::new(), attributes: BTreeMap::new() });
+}
+
+#[test]
+fn test_deduplicated_roads_builder() {
+    let street = |name: &str, row: usize| InputStreetValue::new(name, GridPosition { column: String::from("A"), row, page: None });
+
+    let mut builder = DeduplicatedRoadsBuilder::new();
+    builder.push(street("Mayer Street", 1));
+    builder.extend((1..4).map(|row| street("Mayer Street", row)));
+    builder.extend(vec![street("Valley View Road", 2)]);
+    let deduplicated = builder.finish();
+
+    assert_eq!(deduplicated.roads[&StreetName(String::from("Mayer Street"))].len(), 3);
+    assert_eq!(deduplicated, vec![street("Valley View Road", 2), street("Mayer Street", 3), street("Mayer Street", 1), street("Mayer Street", 2)]
+        .into_iter()
+        .collect::<DeduplicatedRoads>());
+}
+
+#[test]
+fn test_merge_builders() {
+    let street = |i: usize| {
+        let name = if i % 3 == 0 { format!("ROAD {}", i % 7) } else { format!("Road {}", i % 7) };
+        InputStreetValue::new(&name, GridPosition { column: number_to_alphabet_value(i % 5), row: i % 11 + 1, page: None })
+    };
+    let streets = (0..1000).map(street).collect::<Vec<_>>();
+    let options = DedupOptions { case_insensitive: true, .. DedupOptions::default() };
+
+    let mut first = DeduplicatedRoadsBuilder::new().with_options(options.clone());
+    let mut second = DeduplicatedRoadsBuilder::new().with_options(options.clone());
+    first.extend(streets[..500].iter().cloned());
+    second.extend(streets[500..].iter().cloned());
+    first.merge(second);
+
+    // with the "parallel" feature, from_streets_with_options uses a parallel fold / reduce
+    let merged = first.finish();
+    assert_eq!(merged, DeduplicatedRoads::from_streets_with_options(&streets, &options));
+    assert_eq!(merged.roads.keys().next(), Some(&StreetName(String::from("Road 0"))));
+    assert_eq!(merged.process_with_ranges(), DeduplicatedRoads::from_streets_with_options(&streets, &options).process_with_ranges());
+}
+
+#[test]
+fn test_deduplicate_normalized() {
+    let street = |name: &str, column: &str, row: usize| InputStreetValue::new(name, GridPosition { column: String::from(column), row, page: None });
+
+    let mut builder = DeduplicatedRoadsBuilder::new().with_normalizer(Normalizer::english());
+    builder.extend(vec![street("Main St", "A", 1), street("Main St.", "A", 2), street("Main Street", "A", 3)]);
+
+    let roads = builder.finish().roads;
+    assert_eq!(roads.keys().collect::<Vec<_>>(), vec![&StreetName(String::from("Main Street"))]);
+    assert_eq!(roads.values().next().map(|positions| positions.len()), Some(3));
+}
+
+#[test]
+fn test_deduplicate_with_options() {
+    let street = |name: &str, row: usize| InputStreetValue::new(name, GridPosition { column: String::from("A"), row, page: None });
+
+    let streets = vec![street("MAIN STREET", 1), street("Main  Street ", 2), street("Main Street", 3), street("Mill Lane", 4)];
+    let options = DedupOptions { case_insensitive: true, collapse_whitespace: true, .. DedupOptions::default() };
+    let roads = DeduplicatedRoads::from_streets_with_options(&streets, &options).roads;
+
+    let names = roads.keys().map(|name| name.0.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Main Street", "Mill Lane"]);
+    assert_eq!(roads[&StreetName(String::from("Main Street"))].len(), 3);
+
+    assert_eq!(DeduplicatedRoads::from_streets(&streets).roads.len(), 4);
+
+    #[cfg(feature = "unicode")] {
+        let streets = vec![street("Rue du Cafe\u{301}", 1), street("Rue du Café", 2)];
+        let options = DedupOptions { unicode_form: ::normalize::UnicodeForm::Nfc, .. DedupOptions::default() };
+        assert_eq!(DeduplicatedRoads::from_streets_with_options(&streets, &options).roads.len(), 1);
+    }
+}
+
+#[test]
+fn test_grid_position_parse() {
+    let position = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+
+    assert_eq!(GridPosition::parse(" AB12 "), Ok(position("AB", 12)));
+    assert_eq!("Z1".parse::<GridPosition>(), Ok(position("Z", 1)));
+    assert_eq!(GridPosition::parse(""), Err(GridPositionError::Empty));
+    assert_eq!(GridPosition::parse("12"), Err(GridPositionError::MissingColumn));
+    assert_eq!(GridPosition::parse("AB"), Err(GridPositionError::MissingRow));
+    assert_eq!(GridPosition::parse("ab12"), Err(GridPositionError::InvalidColumn(String::from("ab"))));
+    assert_eq!(GridPosition::parse("A1b"), Err(GridPositionError::InvalidRow(String::from("1b"))));
+
+    assert_eq!(GridPosition::from_indices(27, 4, &ColumnScheme::Numeric), Ok(position("AB", 4)));
+    assert_eq!(GridPosition::from_indices(27, 4, &ColumnScheme::Custom(vec![String::from("North")])), Err(GridPositionError::UnlabeledColumn(27)));
+    assert_eq!(position("AB", 4).column_index(), Some(27));
+}
+
+#[test]
+fn test_unprocessed_runs() {
+    let csv = "Mill Lane\tC2\tC3\tC4\tC5\tF7\t2:F8\t2:F9";
+    let (_, unprocessed) = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process().into_parts();
+    let road = &unprocessed.unprocessed[0];
+    assert_eq!(road.to_string(), "Mill Lane\tC2-C5\tF7\t2:F8-F9");
+    assert_eq!(road.runs().len(), 3);
+
+    // reading the compressed CSV again gives the same cells
+    let again = DeduplicatedRoads::from_csv(&unprocessed.to_csv("\t"), "\t").unwrap();
+    assert_eq!(again.roads[&road.name].len(), 7);
+}
+
+#[test]
+fn test_position_separator() {
+    let csv = "Mill Lane\tA9\tE1\tH5\r\nValley Road\tA1\tB5\tD9";
+    let (_, unprocessed) = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process().into_parts();
+    let options = CsvOptions::default().with_delimiter(',');
+    let csv = unprocessed.to_csv_with_options(&options);
+    assert_eq!(csv, "Mill Lane,A9; E1; H5\r\nValley Road,A1; B5; D9");
+    assert!(csv.lines().all(|line| line.split(',').count() == 2));
+
+    let options = options.with_position_separator(Some(" | "));
+    assert_eq!(unprocessed.to_csv_with_options(&options), "Mill Lane,A9 | E1 | H5\r\nValley Road,A1 | B5 | D9");
+    let options = options.with_position_separator(None);
+    assert_eq!(unprocessed.to_csv_with_options(&options), "Mill Lane,A9,E1,H5\r\nValley Road,A1,B5,D9");
+}
+
+#[test]
+fn test_cell_id() {
+    let position = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+
+    let id = position("AB", 12).cell_id().unwrap();
+    assert_eq!((id.column(), id.row()), (27, 12));
+    assert_eq!(id.to_position(Some(String::from("3"))), position("AB", 12).with_page("3"));
+    assert!(position("Z", 1).cell_id() < position("AA", 1).cell_id());
+    assert!(position("B", 1).cell_id() < position("B", 2).cell_id());
+    assert_eq!(position("A", 65_536).cell_id().map(|id| id.row()), Some(65_536));
+    assert_eq!(position("A", u32::MAX as usize + 1).cell_id(), None);
+    assert_eq!(position("a", 1).cell_id(), None);
+}
+
+#[test]
+fn test_road_names_accessors() {
+    let csv = "Abbey Road\tA1\r\nMill Lane\tB2-B3\r\nValley Road\tC1\tE5\tH2";
+    let (processed, unprocessed) = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process().into_parts();
+
+    assert_eq!((processed.len(), unprocessed.len()), (2, 1));
+    assert!(!processed.is_empty());
+    assert_eq!(processed.get(&StreetName(String::from("Mill Lane"))).map(|road| road.position.to_string()), Some(String::from("B2-B3")));
+    assert_eq!(processed.get(&StreetName(String::from("Valley Road"))), None);
+    assert_eq!(unprocessed.get(&StreetName(String::from("Valley Road"))).map(|road| road.positions.len()), Some(3));
+    assert_eq!((&processed).into_iter().count(), 2);
+
+    let filtered = processed.clone().into_iter().filter(|road| road.name.0 != "Abbey Road").collect::<ProcessedRoadNames>();
+    assert_eq!(filtered.iter().map(|road| road.name.0.as_str()).collect::<Vec<_>>(), vec!["Mill Lane"]);
+    assert!(Vec::new().into_iter().collect::<UnprocessedRoadNames>().is_empty());
+}
+
+#[test]
+fn test_grid_position_order() {
+    let position = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+
+    let mut positions = vec![position("AA", 1), position("B", 2), position("Z", 1), position("B", 1), position("AB", 1)];
+    positions.sort();
+    assert_eq!(positions, vec![position("B", 1), position("B", 2), position("Z", 1), position("AA", 1), position("AB", 1)]);
+
+    let csv = "Long Road\tZ9\tAA9";
+    let (processed, _) = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process().into_parts();
+    assert_eq!(processed.to_csv("\t"), "Long Road\tZ9-AA9");
+}
+
+#[test]
+fn test_paged_positions() {
+    let csv = "Canterbury Road\t12:C4-C6\t14:A1\r\nMill Lane\t9:A1\t12:A1";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process_with_ranges();
+
+    assert_eq!(index.review_count(), 0);
+    assert_eq!(index.processed.to_csv("\t"), "Canterbury Road\t12:C4-C6\r\nCanterbury Road\t14:A1\r\nMill Lane\t9:A1\r\nMill Lane\t12:A1");
+    assert_eq!(index.processed.to_csv_grouped("\t", "; "), "Canterbury Road\t12:C4-C6; 14:A1\r\nMill Lane\t9:A1; 12:A1");
+    assert_eq!(index.processed.to_csv_grouped_with_quoting(",", ", ", CsvQuoting::Always), "\"Canterbury Road\",\"12:C4-C6, 14:A1\"\r\n\"Mill Lane\",\"9:A1, 12:A1\"");
+
+    let reparsed = DeduplicatedRoads::from_csv(&index.processed.to_csv_grouped("\t", "; "), "\t").unwrap().process_with_ranges();
+    assert_eq!(reparsed, index);
+
+    assert_eq!(GridPosition::parse(":C4"), Err(GridPositionError::EmptyPage));
+}
+
+#[test]
+fn test_format_street() {
+    let street_grid_1 = GridPosition { column: String::from("A"), row: 9, page: None };
+    let street_grid_2 = GridPosition { column: String::from("I"), row: 5, page: None };
+
+    let road_pos_1 = FinalizedGridPositon::TwoRect(street_grid_1.clone(), street_grid_2);
+    assert_eq!(format!("{}", street_grid_1), String::from("A9"));
+    assert_eq!(format!("{}", road_pos_1), String::from("A9-I5"));
+}
+
+#[test]
+fn test_process_with_ranges() {
+    let street = |name: &str, column: &str, row: usize| InputStreetValue::new(name, GridPosition { column: String::from(column), row, page: None });
+
+    let input = [
+        street("Valley View Road", "A", 4),
+        street("Valley View Road", "A", 5),
+        street("Valley View Road", "A", 6),
+        street("Valley View Road", "A", 7),
+        street("Mayer Street", "Y", 2),
+        street("Mayer Street", "Z", 2),
+        street("Mayer Street", "AA", 2),
+        street("Canterbury Road", "C", 1),
+        street("Canterbury Road", "C", 3),
+        street("Canterbury Road", "D", 1),
+    ];
+
+    let (processed, unprocessed) = DeduplicatedRoads::from_streets(&input).process_with_ranges().into_parts();
+
+    assert_eq!(processed.to_csv("\t"), String::from("Mayer Street\tY2-AA2\r\nValley View Road\tA4-A7"));
+    assert_eq!(unprocessed.to_csv("\t"), String::from("Canterbury Road\tC1; C3; D1"));
+
+    // rows of fine UTM grids don't fit into 16 bits
+    let fine = [street("Utm Road", "B", 70_000), street("Utm Road", "B", 70_001), street("Utm Road", "B", 70_003), street("Utm Road", "C", 65_536)];
+    let deduplicated = DeduplicatedRoads::from_streets(&fine);
+    assert_eq!(deduplicated.process_with_options(&ProcessOptions::default().with_ranges(true).with_cluster_gap(Some(1))).to_csv("\t"), "Utm Road\tB70000-B70003\r\nUtm Road\tC65536");
+
+    // the area of the rectangle between the extreme cells overflows a `usize`
+    let extreme = [street("Far Road", "A", 0), street("Far Road", "A", u32::MAX as usize), street("Far Road", "MWLQKWV", 0)];
+    assert_eq!(DeduplicatedRoads::from_streets(&extreme).process().unprocessed.to_csv("\t"), "Far Road\tA0; A4294967295; MWLQKWV0");
+}
+
+#[test]
+fn test_process_with_clustering() {
+    let street = |name: &str, column: &str, row: usize| InputStreetValue::new(name, GridPosition { column: String::from(column), row, page: None });
+
+    let input = [
+        street("Canterbury Road", "A", 9),
+        street("Canterbury Road", "A", 10),
+        street("Canterbury Road", "E", 1),
+        street("Canterbury Road", "E", 2),
+        street("Mayer Street", "B", 1),
+        street("Mayer Street", "B", 3),
+        street("Mayer Street", "B", 5),
+        street("Valley View Road", "C", 1),
+        street("Valley View Road", "C", 2),
+        street("Valley View Road", "D", 2),
+    ];
+
+    let deduplicated = DeduplicatedRoads::from_streets(&input);
+
+    let (processed, unprocessed) = deduplicated.process_with_clustering(0).into_parts();
+    assert_eq!(processed.to_csv("\t"), String::from("Canterbury Road\tA9-A10\r\nCanterbury Road\tE1-E2\r\nMayer Street\tB1\r\nMayer Street\tB3\r\nMayer Street\tB5"));
+    assert_eq!(unprocessed.to_csv("\t"), String::from("Valley View Road\tC1-C2; D2"));
+
+    // with a gap of one cell, the three cells of "Mayer Street" form one range
+    let (processed, _) = deduplicated.process_with_clustering(1).into_parts();
+    assert!(processed.to_csv("\t").contains("Mayer Street\tB1-B5"));
+
+    // a river through a whole country has thousands of cells
+    let river = (0..20_000).map(|i| InputStreetValue::new("River", GridPosition { column: number_to_alphabet_value(i / 2), row: 1 + 9 * (i % 2), page: None })).collect::<Vec<_>>();
+    let processed = DeduplicatedRoads::from_streets(&river).process_with_clustering(0).processed;
+    assert_eq!(processed.to_csv("\t"), "River\tA1-NTP1\r\nRiver\tA10-NTP10");
+}
+
+#[test]
+fn test_process_primary_cell() {
+    let street = |name: &str, position: &str, weight: f64| InputStreetValue::new(name, position.parse().unwrap()).with_weight(weight);
+    let input = [
+        street("Canterbury Road", "A9", 120.0),
+        street("Canterbury Road", "E1", 80.0),
+        street("Canterbury Road", "E1", 60.0),
+        street("Canterbury Road", "E2", 10.0),
+        street("Mayer Street", "B1", 5.0),
+        street("Mayer Street", "B3", 5.0),
+        InputStreetValue::new("Valley View Road", "C1".parse().unwrap()),
+        InputStreetValue::new("Valley View Road", "D4".parse().unwrap()),
+        InputStreetValue::new("Valley View Road", "F7".parse().unwrap()),
+    ];
+    let mut deduplicated = DeduplicatedRoads::from_streets(&input);
+    assert_eq!(deduplicated.weights[&StreetName(String::from("Canterbury Road"))][&"E1".parse().unwrap()], Weight(140.0));
+
+    // streets and roads with weights can still be hashed and ordered
+    assert_eq!(input.iter().collect::<HashSet<_>>().len(), input.len());
+    assert!(DeduplicatedRoads::from_streets(&input[..2]) < deduplicated);
+
+    let options = ProcessOptions::default().with_primary_cell(true);
+    let index = deduplicated.process_with_options(&options);
+    assert_eq!(index.processed.to_csv("\t"), "Canterbury Road\tE1\r\nMayer Street\tB1");
+    assert_eq!(index.unprocessed.to_csv("\t"), "Valley View Road\tC1; D4; F7");
+
+    // the weights follow the cells when the roads are updated
+    deduplicated.insert(street("Canterbury Road", "A9", 30.0));
+    let canterbury = StreetName(String::from("Canterbury Road"));
+    let mut index = index;
+    index.reprocess_street(&deduplicated, &canterbury, &options);
+    assert_eq!(index.processed.to_csv("\t"), "Canterbury Road\tA9\r\nMayer Street\tB1");
+    assert_eq!(deduplicated.process().unprocessed.len(), 2);
+}
+
+#[test]
+fn test_csv_roundtrip() {
+    let csv = "Canterbury Road\tA9-A11\r\nMayer Street\tB2-C3\r\n\r\nValley View Road\tE1\tE2\tF4";
+    let deduplicated = DeduplicatedRoads::from_csv(csv, "\t").unwrap();
+
+    let (processed, unprocessed) = deduplicated.process_with_ranges().into_parts();
+    assert_eq!(processed.to_csv("\t"), String::from("Canterbury Road\tA9-A11\r\nMayer Street\tB2-C3"));
+    assert_eq!(unprocessed.to_csv("\t"), String::from("Valley View Road\tE1-E2; F4"));
+    assert_eq!(deduplicated.roads[&StreetName(String::from("Canterbury Road"))].len(), 3);
+
+    assert_eq!(InputStreetValue::parse_line("Mayer Street;Y2-AA2", ";").unwrap().len(), 3);
+    assert_eq!(InputStreetValue::parse_line("Mayer Street", ";").unwrap_err().kind, ParseErrorKind::MissingPosition);
+    assert_eq!(
+        DeduplicatedRoads::from_csv("Mayer Street;A1\nValley View Road;1A", ";").unwrap_err(),
+        ParseError { line: Some(2), kind: ParseErrorKind::InvalidPosition(String::from("1A")) }
+    );
+}
+
+#[test]
+fn test_csv_quoting() {
+    let csv = "\"Main Street, North\",A1,A2\r\n\"Say \"\"Hi\"\" Lane\",B4\r\nThe \"Old\" Road,C1";
+    let (processed, unprocessed) = DeduplicatedRoads::from_csv(csv, ",").unwrap().process().into_parts();
+
+    assert_eq!(processed.to_csv(","), "\"Main Street, North\",A1-A2\r\n\"Say \"\"Hi\"\" Lane\",B4\r\n\"The \"\"Old\"\" Road\",C1");
+    assert_eq!(processed.to_csv("\t"), "Main Street, North\tA1-A2\r\n\"Say \"\"Hi\"\" Lane\"\tB4\r\n\"The \"\"Old\"\" Road\"\tC1");
+    assert_eq!(processed.to_csv_with_quoting(",", CsvQuoting::Never), "Main Street, North,A1-A2\r\nSay \"Hi\" Lane,B4\r\nThe \"Old\" Road,C1");
+    assert_eq!(processed.to_csv_with_quoting(";", CsvQuoting::Always).lines().next(), Some("\"Main Street, North\";\"A1-A2\""));
+    assert!(unprocessed.unprocessed.is_empty());
+
+    let reparsed = DeduplicatedRoads::from_csv(&processed.to_csv(","), ",").unwrap().process().processed;
+    assert_eq!(reparsed, processed);
+}
+
+#[test]
+fn test_to_csv_with_labeler() {
+    use labeling::ColumnScheme;
+
+    let csv = "Canterbury Road\tA9-B9\r\nValley View Road\tAA1\tZ2\tB3";
+    let (processed, unprocessed) = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process().into_parts();
+    let labeler = GridLabeler::new(ColumnScheme::Numeric, "/");
+
+    assert_eq!(processed.to_csv_with_labeler(";", &labeler), String::from("Canterbury Road;1/9-2/9"));
+    assert_eq!(unprocessed.to_csv_with_labeler(";", &labeler), String::from("Valley View Road;\"2/3; 26/2; 27/1\""));
+
+    use labeling::PositionStyle;
+    let index = StreetIndex::new(processed, unprocessed);
+    assert_eq!(index.to_csv_with_formatter("\t", &PositionStyle::spaced()), "Canterbury Road\tA 9 \u{2013} B 9\r\nValley View Road\tB 3; Z 2; AA 1");
+
+    // consecutive positions are written as a range, the same as `to_csv()`
+    let csv = "Valley Road\tC2\tC3\tC4\tF7\t2:F8\t2:F9";
+    let unprocessed = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process().unprocessed;
+    assert_eq!(unprocessed.to_csv_with_labeler("\t", &GridLabeler::default()), unprocessed.to_csv("\t"));
+    assert_eq!(unprocessed.to_csv_with_labeler("\t", &labeler), "Valley Road\t3/2-3/4; 6/7; 2:6/8-6/9");
+}
+
+#[test]
+fn test_sort_by_strategy() {
+    use sorting::NaturalOrder;
+
+    let csv = "Zebrastraße;A1\r\nÜber den Linden;B1\r\n11th Street;C1\r\n2nd Street;D1;D2;D4";
+    let deduplicated = DeduplicatedRoads::from_csv(csv, ";").unwrap();
+    assert_eq!(deduplicated.sorted_names(&NaturalOrder::default())[0], &StreetName(String::from("2nd Street")));
+
+    let (mut processed, _) = deduplicated.process().into_parts();
+    processed.sort_by_strategy(&NaturalOrder::default());
+    assert_eq!(processed.to_csv(";"), String::from("11th Street;C1\r\nÜber den Linden;B1\r\nZebrastraße;A1"));
+}
+
+#[test]
+fn test_street_index() {
+    let first = DeduplicatedRoads::from_csv("Abbey Road\tA1\r\nValley View Road\tE1\tE2\tF4", "\t").unwrap().process();
+    let second = DeduplicatedRoads::from_csv("Abbey Road\tA1\r\nAcacia Avenue\tB2-B3", "\t").unwrap().process();
+
+    assert_eq!(first.review_count(), 1);
+    assert_eq!(first.to_csv("\t"), "Abbey Road\tA1\r\nValley View Road\tE1-E2; F4");
+
+    let mut merged = first.clone();
+    merged.merge(second);
+    let entries = merged.iter_all().map(|e| (e.name().0.as_str(), e.needs_review())).collect::<Vec<_>>();
+    assert_eq!(entries, vec![("Abbey Road", false), ("Acacia Avenue", false), ("Valley View Road", true)]);
+
+    let (processed, unprocessed) = merged.into_parts();
+    assert_eq!(processed.processed.len(), 2);
+    assert_eq!(unprocessed, first.unprocessed);
+}
+
+#[test]
+fn test_merge_sheets() {
+    let mut west = DeduplicatedRoads::from_csv("Abbey Road\tA1\r\nLong Road\tH2", "\t").unwrap();
+    let east = DeduplicatedRoads::from_csv("Long Road\tA2\r\nMill Lane\tZ3", "\t").unwrap();
+
+    let mut merged = west.clone();
+    merged.merge(east.clone());
+    assert_eq!(merged.process().unprocessed.to_csv("\t"), "");
+    assert_eq!(merged.process_with_ranges().processed.to_csv("\t"), "Abbey Road\tA1\r\nLong Road\tA2-H2\r\nMill Lane\tZ3");
+
+    west.merge_remapped(east.clone(), &PositionRemap::new().with_offset(8, 0));
+    assert_eq!(west.process().processed.to_csv("\t"), "Abbey Road\tA1\r\nLong Road\tH2-I2\r\nMill Lane\tAH3");
+
+    let mut atlas = DeduplicatedRoads::from_csv("Long Road\t12:H2", "\t").unwrap();
+    atlas.merge_remapped(east, &PositionRemap::new().with_offset(0, 10).with_page("13"));
+    assert_eq!(atlas.process().processed.to_csv("\t"), "Long Road\t12:H2\r\nLong Road\t13:A12\r\nMill Lane\t13:Z13");
 }
 
-/// Deduplicates road names, merging the roads by their name
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct DeduplicatedRoads {
-    pub roads: BTreeMap<StreetName, BTreeSet<GridPosition>>,
+#[test]
+fn test_road_combinators() {
+    use category::Category;
+
+    let mut roads = DeduplicatedRoads::from_csv("Abbey Road\tA1\r\nLong Road\tA2\tB2\r\nMill Ln\tB3\r\nMill Lane\tC3", "\t").unwrap();
+    roads.insert(InputStreetValue::new("Mill Ln", "A3".parse().unwrap()).with_category(Category::Poi));
+    roads.insert(InputStreetValue::new("Abbey Road", "A1".parse().unwrap()).with_number_range(NumberRange::new(1, 9)));
+
+    // drop streets with a single cell in the margin column A
+    let margin = |positions: &BTreeSet<GridPosition>| positions.len() == 1 && positions.iter().all(|p| p.column == "A");
+    let retained = roads.clone().retain(|_, positions| !margin(positions));
+    assert_eq!(retained.process().to_csv("\t"), "Long Road\tA2-B2\r\nMill Lane\tC3\r\nMill Ln\tB3");
+    assert!(retained.categories.is_empty());
+    assert!(retained.number_ranges.is_empty());
+
+    let mut calls = 0;
+    let renamed = roads.clone().map_names(|name| {
+        calls += 1;
+        StreetName(name.0.replace("Ln", "Lane"))
+    });
+    assert_eq!(calls, 4);
+    assert_eq!(renamed.roads[&StreetName(String::from("Mill Lane"))].len(), 2);
+    assert_eq!(renamed.categories[&Category::Poi].keys().collect::<Vec<_>>(), [&StreetName(String::from("Mill Lane"))]);
+
+    let filtered = roads.filter_cells(|position| position.column != "A");
+    assert_eq!(filtered.process().to_csv("\t"), "Long Road\tB2\r\nMill Lane\tC3\r\nMill Ln\tB3");
+    assert!(filtered.categories.is_empty() && filtered.number_ranges.is_empty());
 }
 
-impl DeduplicatedRoads {
-    /// Deduplicates road names, i.e.:
-    ///
-    /// Input:
-    ///
-    /// ```no_run,ignore
-    /// Mayer Street A4
-    /// Mayer Street A5
-    /// Mayer Street A6
-    /// ```
-    ///
-    /// Output:
-    ///
-    /// ```no_run,ignore
-    /// Mayer Street -> [A4, A5, A6]
-    /// ```
-    ///
-    /// The output road name positions are ordered.
-    pub fn from_streets(streets: &[InputStreetValue]) -> Self {
-        let mut deduplicated_names = BTreeMap::new();
+#[test]
+fn test_write_csv() {
+    let index = DeduplicatedRoads::from_csv("Abbey Road\tA1\r\nValley View Road\tE1\tE2\tF4", "\t").unwrap().process();
 
-        for input_street in streets {
-            deduplicated_names
-            .entry(input_street.street_name.clone())
-            .or_insert_with(|| BTreeSet::new())
-            .insert(input_street.position.clone());
-        }
+    let mut processed = Vec::new();
+    index.processed.write_csv(&mut processed, ",").unwrap();
+    assert_eq!(String::from_utf8(processed).unwrap(), index.processed.to_csv(","));
 
-        Self { roads: deduplicated_names }
-    }
+    let mut unprocessed = Vec::new();
+    index.unprocessed.write_csv(&mut unprocessed, ",").unwrap();
+    assert_eq!(String::from_utf8(unprocessed).unwrap(), index.unprocessed.to_csv(","));
 
-    /// Processes road names (`[A1, A2]` => `A1-A2`) if they span less than 2 grids.
-    /// 
-    /// Processing road names in a cartographic manner is tricky. For example, a 
-    /// street that appears in two locations on the map (such as a city having 
-    /// the same street name as a neighbouring city). Because of this, street name
-    /// processing can't be fully automated, since there are always weird edge cases 
-    /// to worry about. However, 90% of roads aren't like that.
-    /// 
-    /// Because of this limitation `process()` gives you two types of roads back: 
-    /// - `ProcessedRoadName` is for roads that span only 1 or 2 grid cells 
-    /// (i.e. `"Canterbury Road" => A9`, `"Canterbury Road" => A9-A10`).
-    /// In these cases (which cover 90% of street index names), the mapping is not
-    /// ambigouus.
-    /// 
-    /// `UnprocessedRoadName` is for anything else (e.g. `"Canterbury Road" => [A9, A10, E1, E2]`. 
-    /// Usually these roads need to be manually reviewed - it could likely be that 
-    /// there are two roads `"Canterbury Road" => A9-10;E1-E2`, but it could also
-    /// be that the road is just one road and part of it is just clipped off the map,
-    /// in which case you'd write `"Canterbury Road" => A9-E2`. 
-    pub fn process(&self) -> (ProcessedRoadNames, UnprocessedRoadNames) {
+    let mut all = Vec::new();
+    index.write_csv(&mut all, "\t").unwrap();
+    assert_eq!(String::from_utf8(all).unwrap(), "Abbey Road\tA1\r\nValley View Road\tE1-E2; F4");
+}
 
-        let mut processed = BTreeMap::new();
-        let mut unprocessed = BTreeMap::new();
+#[test]
+fn test_csv_options() {
+    let index = DeduplicatedRoads::from_csv("Abbey Road\tA1\r\nMain Street; North\tB1-B2\r\nValley View Road\tE1\tE2\tF4", "\t").unwrap().process_with_ranges();
 
-        for (road_name, positions) in &self.roads {
-            let positions_vec = positions.into_iter().cloned().collect::<Vec<GridPosition>>();
-            match positions_vec.len() {
-                0 => { },
-                1 => { processed.insert(road_name.clone(), FinalizedGridPositon::SingleRect(positions_vec[0].clone())); }
-                2 => { processed.insert(road_name.clone(), FinalizedGridPositon::TwoRect(positions_vec[0].clone(), positions_vec[1].clone())); }
-                _ => { unprocessed.insert(road_name.clone(), positions_vec); }
-            }
-        }
+    assert_eq!(index.processed.to_csv_with_options(&CsvOptions::default()), index.processed.to_csv("\t"));
 
-        (ProcessedRoadNames {
-            processed: processed.into_iter().map(|(k, v)| ProcessedRoad { name: k, position: v }).collect(),
-        },
-        UnprocessedRoadNames {
-            unprocessed: unprocessed.into_iter().map(|(k, v)| UnprocessedRoad { name: k, positions: v }).collect(),
-        })
-    }
+    let options = CsvOptions::default().with_delimiter(';').with_line_ending(LineEnding::Lf).with_header(true);
+    assert_eq!(index.processed.to_csv_with_options(&options), "Name;Position\nAbbey Road;A1\n\"Main Street; North\";B1-B2");
+    assert_eq!(index.unprocessed.to_csv_with_options(&options), "Name;Positions\nValley View Road;\"E1-E2; F4\"");
+
+    let options = options.with_header_names(&["Straße", "Planquadrat"]).with_quoting(CsvQuoting::Always);
+    assert_eq!(index.processed.to_csv_with_options(&options).lines().next(), Some("\"Straße\";\"Planquadrat\""));
+
+    let mut written = Vec::new();
+    index.processed.write_csv_with_options(&mut written, &options).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), index.processed.to_csv_with_options(&options));
 }
 
 #[test]
-fn test_deduplicate_streets() {
-    let input = [
-        InputStreetValue {
-            street_name: StreetName(String::from("Valley View Road")),
-            position: GridPosition {
-                column: String::from("A"),
-                row: 4,
-            }
-        },
-        InputStreetValue {
-            street_name: StreetName(String::from("Valley View Road")),
-            position: GridPosition {
-                column: String::from("A"),
-                row: 5,
-            }
-        },
-        InputStreetValue {
-            street_name: StreetName(String::from("Valley View Road")),
-            position: GridPosition {
-                column: String::from("B"),
-                row: 6,
-            }
-        },
-    ];
+fn test_csv_section_headers() {
+    use sorting::NaturalOrder;
 
-    // "Valley View Road" -> ["A4", "A5", "B6"]
-    let mut output_expected = BTreeMap::new();
-    let mut valley_view_road_expected = BTreeSet::new();
-    valley_view_road_expected.insert(GridPosition { column: String::from("A"), row: 4 });
-    valley_view_road_expected.insert(GridPosition { column: String::from("A"), row: 5 });
-    valley_view_road_expected.insert(GridPosition { column: String::from("B"), row: 6 });
-    output_expected.insert(StreetName(String::from("Valley View Road")), valley_view_road_expected);
+    let csv = "Ängsvägen\tA1\r\nAsgatan\tB1\r\nÅsgatan\tC1\r\n2:a Tvärgatan\tD1\r\nZinkgatan\tE1";
+    let mut index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+    index.processed.sort_by_strategy(&NaturalOrder::new(Locale::Swedish));
+
+    let options = CsvOptions::default().with_line_ending(LineEnding::Lf).with_section_headers(Locale::Swedish);
+    assert_eq!(index.processed.to_csv_with_options(&options), [
+        "\u{2014} 0-9 \u{2014}", "2:a Tvärgatan\tD1",
+        "\u{2014} A \u{2014}", "Asgatan\tB1",
+        "\u{2014} Z \u{2014}", "Zinkgatan\tE1",
+        "\u{2014} Å \u{2014}", "Åsgatan\tC1",
+        "\u{2014} Ä \u{2014}", "Ängsvägen\tA1",
+    ].join("\n"));
 
-    assert_eq!(DeduplicatedRoads::from_streets(&input), DeduplicatedRoads { roads: output_expected });
+    assert_eq!(Locale::Root.initial("ängsvägen"), Some(String::from("A")));
+    assert_eq!(Locale::GermanPhonebook.initial(" über"), Some(String::from("U")));
+    assert_eq!(Locale::Spanish.initial("ñandú"), Some(String::from("Ñ")));
+    assert_eq!(Locale::Danish.initial("Ærøvej"), Some(String::from("Æ")));
+    assert_eq!(Locale::Root.initial(""), None);
+
+    let mut written = Vec::new();
+    index.processed.write_csv_with_options(&mut written, &options).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), index.processed.to_csv_with_options(&options));
 }
 
+#[cfg(feature = "serde")]
 #[test]
-fn test_format_street() {
-    let street_grid_1 = GridPosition { column: String::from("A"), row: 9 };
-    let street_grid_2 = GridPosition { column: String::from("I"), row: 5 };
+fn test_serde_roundtrip() {
+    let csv = "Canterbury Road\tA9-A10\r\nValley View Road\tE1\tE2\tF4";
+    let deduplicated = DeduplicatedRoads::from_csv(csv, "\t").unwrap();
 
-    let road_pos_1 = FinalizedGridPositon::TwoRect(street_grid_1.clone(), street_grid_2);
-    assert_eq!(format!("{}", street_grid_1), String::from("A9"));
-    assert_eq!(format!("{}", road_pos_1), String::from("A9-I5"));
+    let json = ::serde_json::to_string(&deduplicated).unwrap();
+    assert!(json.starts_with(r#"{"roads":{"Canterbury Road":[{"column":"A","row":9}"#));
+    assert_eq!(::serde_json::from_str::<DeduplicatedRoads>(&json).unwrap(), deduplicated);
+
+    let (processed, unprocessed) = deduplicated.process().into_parts();
+    let json = ::serde_json::to_string(&processed).unwrap();
+    assert_eq!(::serde_json::from_str::<ProcessedRoadNames>(&json).unwrap(), processed);
+    let json = ::serde_json::to_string(&unprocessed).unwrap();
+    assert_eq!(::serde_json::from_str::<UnprocessedRoadNames>(&json).unwrap(), unprocessed);
 }
 
 /// Wrapper for grid positions that span less than 2 grid cells
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FinalizedGridPositon {
     /// Road is contained within a single rect, i.e. "Valley Road -> A6"
     SingleRect(GridPosition),
     /// Road crosses exactly two grids
     TwoRect(GridPosition, GridPosition),
+    /// Road spans a gap-free run of cells in one row or column, i.e.
+    /// "Valley Road -> A4-A7" (only created by `process_with_ranges()`
+    /// or by resolving an `UnprocessedRoad`, see `Resolution::Span`), or
+    /// exactly fills a rectangle of cells, i.e. "Valley Road -> A4-B5".
+    Range(GridPosition, GridPosition),
+}
+
+impl FinalizedGridPositon {
+    /// Returns every cell covered by the position: both cells of a `TwoRect`
+    /// and all cells of the rectangle spanned by the ends of a `Range`
+    pub fn cells(&self) -> Vec<GridPosition> {
+        use self::FinalizedGridPositon::*;
+        match self {
+            SingleRect(single) => vec![single.clone()],
+            TwoRect(a, b) => vec![a.clone(), b.clone()],
+            Range(a, b) => {
+                let (a_column, b_column) = match (a.column_index(), b.column_index()) {
+                    (Some(a_column), Some(b_column)) if a.page == b.page => (a_column, b_column),
+                    _ => return vec![a.clone(), b.clone()],
+                };
+                let columns = a_column.min(b_column)..=a_column.max(b_column);
+                columns.flat_map(|column| (a.row.min(b.row)..=a.row.max(b.row)).map(move |row| GridPosition {
+                    column: number_to_alphabet_value(column),
+                    row,
+                    page: a.page.clone(),
+                })).collect()
+            },
+        }
+    }
+
+    /// First and last cell of the position (the same cell for a `SingleRect`)
+    pub fn corners(&self) -> (&GridPosition, &GridPosition) {
+        use self::FinalizedGridPositon::*;
+        match self {
+            SingleRect(single) => (single, single),
+            TwoRect(a, b) | Range(a, b) => (a, b),
+        }
+    }
+}
+
+/// Orders by the first cell (page, column, row), then by the last cell,
+/// a `TwoRect` comes before a `Range` with the same cells
+impl Ord for FinalizedGridPositon {
+    fn cmp(&self, other: &Self) -> Ordering {
+        use self::FinalizedGridPositon::*;
+        let rank = |position: &FinalizedGridPositon| match position { SingleRect(_) => 0, TwoRect(..) => 1, Range(..) => 2 };
+        self.corners().cmp(&other.corners()).then_with(|| rank(self).cmp(&rank(other)))
+    }
+}
+
+impl PartialOrd for FinalizedGridPositon {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl fmt::Display for FinalizedGridPositon {
@@ -173,17 +2354,35 @@ impl fmt::Display for FinalizedGridPositon {
         use self::FinalizedGridPositon::*;
         // single rect: "A9"
         // two rects "A9-B2"
+        // two rects on one page: "12:A9-B2"
         match self {
             SingleRect(single) => write!(f, "{}", single),
-            TwoRect(a, b) => write!(f, "{}-{}", a, b),
+            TwoRect(a, b) | Range(a, b) if a.page.is_some() && a.page == b.page => write!(f, "{}-{}{}", a, b.column, b.row),
+            TwoRect(a, b) | Range(a, b) => write!(f, "{}-{}", a, b),
         }
     }
 }
 
 /// Road name that spans less than 2 grid cells
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ProcessedRoad {
     pub name: StreetName,
     pub position: FinalizedGridPositon,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub category: Option<Category>,
+}
+
+impl ProcessedRoad {
+    /// Orders roads whose names are equal according to a `SortStrategy`
+    /// (i.e. `"Main Street"` and `"main street"`, or the same name in two
+    /// categories or on two pages): by the exact name, the category (roads
+    /// without a category first) and the position (page, first cell)
+    pub(crate) fn tie_break(&self, other: &Self) -> Ordering {
+        self.name.cmp(&other.name)
+            .then_with(|| self.category.cmp(&other.category))
+            .then_with(|| self.position.cmp(&other.position))
+    }
 }
 
 impl fmt::Display for ProcessedRoad {
@@ -193,48 +2392,508 @@ impl fmt::Display for ProcessedRoad {
 }
 
 /// Road name that spans more than 2 grid cells
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UnprocessedRoad {
     pub name: StreetName,
     pub positions: Vec<GridPosition>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub category: Option<Category>,
 }
 
+impl UnprocessedRoad {
+    /// See `ProcessedRoad::tie_break`
+    pub(crate) fn tie_break(&self, other: &Self) -> Ordering {
+        self.name.cmp(&other.name)
+            .then_with(|| self.category.cmp(&other.category))
+            .then_with(|| self.positions.cmp(&other.positions))
+    }
+
+    /// Compresses consecutive positions in one column into a range, so that
+    /// `[C2, C3, C4, C5, F7]` becomes `[C2-C5, F7]` (the order of the positions
+    /// is kept). Used by the `Display` impl and `to_csv()`, reading the CSV
+    /// again (`DeduplicatedRoads::from_csv`) expands the ranges.
+    pub fn runs(&self) -> Vec<FinalizedGridPositon> {
+        let mut runs: Vec<(&GridPosition, &GridPosition)> = Vec::new();
+        for position in &self.positions {
+            match runs.last_mut() {
+                Some((first, last)) if last.page == position.page && last.column == position.column && last.row + 1 == position.row && first.column_index().is_some() => *last = position,
+                _ => runs.push((position, position)),
+            }
+        }
+        runs.into_iter().map(|(first, last)| if first == last {
+            FinalizedGridPositon::SingleRect(first.clone())
+        } else {
+            FinalizedGridPositon::Range(first.clone(), last.clone())
+        }).collect()
+    }
+}
+
+/// `"Name<tab>C2-C5<tab>F7"`, see `UnprocessedRoad::runs`
 impl fmt::Display for UnprocessedRoad {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let unprocessed_string = self.positions.iter().map(|pos| format!("{}", pos)).collect::<Vec<String>>().join("\t");
+        let unprocessed_string = self.runs().iter().map(|run| run.to_string()).collect::<Vec<String>>().join("\t");
         write!(f, "{}\t{}", self.name, unprocessed_string)
     }
 }
 
 /// Simple wrapper for `Vec<ProcessedRoad>` with `.to_csv()` exporting function
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ProcessedRoadNames {
     pub processed: Vec<ProcessedRoad>,
 }
 
 impl ProcessedRoadNames {
+
+    pub fn iter(&self) -> ::std::slice::Iter<'_, ProcessedRoad> {
+        self.processed.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.processed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processed.is_empty()
+    }
+
+    /// Returns the first road named `name` (a road can have several
+    /// entries, i.e. one per cluster or category)
+    pub fn get(&self, name: &StreetName) -> Option<&ProcessedRoad> {
+        self.processed.iter().find(|road| road.name == *name)
+    }
+
+    /// Re-orders the roads by their name, so that the CSV export follows the
+    /// `strategy` (i.e. `NaturalOrder`). Roads with equal names are ordered by
+    /// the exact name, category and position, so that repeated runs produce
+    /// identical files.
+    pub fn sort_by_strategy<S: SortStrategy + ?Sized>(&mut self, strategy: &S) {
+        self.processed.sort_by(|a, b| strategy.compare(&a.name.0, &b.name.0).then_with(|| a.tie_break(b)));
+    }
+
+    /// Replaces every name with its display name, i.e. `"Paix (Rue de la)"`
+    /// for `"Rue de la Paix"` (see `ArticleStripper::inverted`)
+    pub fn apply_display_names<E: SortKeyExtractor + ?Sized>(&mut self, extractor: &E) {
+        for road in &mut self.processed {
+            road.name = StreetName(extractor.display_name(&road.name.0).into_owned());
+        }
+    }
+
+    /// Exports the roads as `name<delimiter>position` lines, fields are
+    /// quoted if necessary (see `CsvQuoting::Necessary`)
     pub fn to_csv(&self, delimiter: &str) -> String {
-        self.processed.iter().map(|processed_road| 
-            format!("{}{}{}", processed_road.name, delimiter, processed_road.position))
+        self.to_csv_with_quoting(delimiter, CsvQuoting::default())
+    }
+
+    /// Same as `to_csv()`, but with configurable quoting
+    pub fn to_csv_with_quoting(&self, delimiter: &str, quoting: CsvQuoting) -> String {
+        self.csv_records(delimiter, quoting).collect::<Vec<String>>().join("\r\n")
+    }
+
+    /// Same as `to_csv()`, but with configurable delimiter, line endings,
+    /// quoting and header row (see `CsvOptions`)
+    pub fn to_csv_with_options(&self, options: &CsvOptions) -> String {
+        let delimiter = options.delimiter.to_string();
+        options.header_record(&["Name", "Position"], &delimiter).into_iter()
+            .chain(self.csv_records_with_sections(&delimiter, options))
+            .collect::<Vec<String>>()
+            .join(options.line_ending.as_str())
+    }
+
+    /// Same as `to_csv()`, but writes the CSV line by line to the `writer`
+    /// instead of building it in memory
+    pub fn write_csv<W: io::Write>(&self, writer: W, delimiter: &str) -> io::Result<()> {
+        write_records(writer, self.csv_records(delimiter, CsvQuoting::default()), LineEnding::default())
+    }
+
+    /// Same as `to_csv_with_options()`, but writes the CSV to the `writer` in
+    /// the `CsvOptions::encoding`
+    pub fn write_csv_with_options<W: io::Write>(&self, writer: W, options: &CsvOptions) -> io::Result<()> {
+        let delimiter = options.delimiter.to_string();
+        let header = options.header_record(&["Name", "Position"], &delimiter);
+        write_records_with_options(writer, header.into_iter().chain(self.csv_records_with_sections(&delimiter, options)), options)
+    }
+
+    /// `csv_records()` with the separator rows of `CsvOptions::section_headers`
+    fn csv_records_with_sections<'a>(&'a self, delimiter: &'a str, options: &'a CsvOptions) -> impl Iterator<Item = String> + 'a {
+        let mut current_letter = None;
+        self.processed.iter().zip(self.csv_records(delimiter, options.quoting)).flat_map(move |(road, record)| {
+            let letter = options.section_headers.and_then(|locale| locale.initial(&road.name.0));
+            let separator = if letter.is_some() && letter != current_letter {
+                current_letter = letter.clone();
+                letter.map(|letter| options.quoting.record(::std::iter::once(Cow::Owned(format!("\u{2014} {} \u{2014}", letter))), delimiter))
+            } else {
+                None
+            };
+            separator.into_iter().chain(::std::iter::once(record))
+        })
+    }
+
+    fn csv_records<'a>(&'a self, delimiter: &'a str, quoting: CsvQuoting) -> impl Iterator<Item = String> + 'a {
+        self.processed.iter().map(move |processed_road|
+            quoting.record(vec![
+                Cow::Borrowed(processed_road.name.0.as_str()),
+                Cow::Owned(processed_road.position.to_string()),
+            ], delimiter))
+    }
+
+    /// Same as `to_csv()`, but consecutive roads with the same name (i.e. the
+    /// clusters of `process_with_clustering` or the pages of an atlas) are
+    /// written as one line, with the positions joined by the `separator`:
+    /// `"Canterbury Road\t12:C4-C6; 14:A1"` for a `separator` of `"; "`
+    pub fn to_csv_grouped(&self, delimiter: &str, separator: &str) -> String {
+        self.to_csv_grouped_with_quoting(delimiter, separator, CsvQuoting::default())
+    }
+
+    /// Same as `to_csv_grouped()`, but with configurable quoting
+    pub fn to_csv_grouped_with_quoting(&self, delimiter: &str, separator: &str, quoting: CsvQuoting) -> String {
+        let mut lines: Vec<(&str, Vec<String>)> = Vec::new();
+        for road in &self.processed {
+            match lines.last_mut() {
+                Some((name, positions)) if *name == road.name.0 => positions.push(road.position.to_string()),
+                _ => lines.push((&road.name.0, vec![road.position.to_string()])),
+            }
+        }
+
+        lines.into_iter().map(|(name, positions)|
+            quoting.record(vec![Cow::Borrowed(name), Cow::Owned(positions.join(separator))], delimiter))
+        .collect::<Vec<String>>()
+        .join("\r\n")
+    }
+
+    /// Same as `to_csv()`, but formats the positions with the `labeler`,
+    /// i.e. `"Canterbury Road\t1/9-2/9"`
+    pub fn to_csv_with_labeler(&self, delimiter: &str, labeler: &GridLabeler) -> String {
+        self.to_csv_with_formatter(delimiter, labeler)
+    }
+
+    /// Same as `to_csv()`, but formats the positions with the `formatter`,
+    /// i.e. `"Canterbury Road\tA9–A10"` (see `PositionStyle::en_dash`)
+    pub fn to_csv_with_formatter<F: PositionFormatter + ?Sized>(&self, delimiter: &str, formatter: &F) -> String {
+        self.processed.iter().map(|processed_road|
+            CsvQuoting::default().record(vec![
+                Cow::Borrowed(processed_road.name.0.as_str()),
+                Cow::Owned(formatter.format_finalized(&processed_road.position)),
+            ], delimiter))
         .collect::<Vec<String>>()
         .join("\r\n")
     }
 }
 
+impl IntoIterator for ProcessedRoadNames {
+    type Item = ProcessedRoad;
+    type IntoIter = ::std::vec::IntoIter<ProcessedRoad>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.processed.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ProcessedRoadNames {
+    type Item = &'a ProcessedRoad;
+    type IntoIter = ::std::slice::Iter<'a, ProcessedRoad>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.processed.iter()
+    }
+}
+
+impl ::std::iter::FromIterator<ProcessedRoad> for ProcessedRoadNames {
+    fn from_iter<I: IntoIterator<Item = ProcessedRoad>>(roads: I) -> Self {
+        ProcessedRoadNames { processed: roads.into_iter().collect() }
+    }
+}
+
 /// Simple wrapper for `Vec<UnprocessedRoad>` with `.to_csv()` exporting function
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UnprocessedRoadNames {
     pub unprocessed: Vec<UnprocessedRoad>,
 }
 
 impl UnprocessedRoadNames {
+
+    pub fn iter(&self) -> ::std::slice::Iter<'_, UnprocessedRoad> {
+        self.unprocessed.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.unprocessed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unprocessed.is_empty()
+    }
+
+    /// Returns the first road named `name`
+    pub fn get(&self, name: &StreetName) -> Option<&UnprocessedRoad> {
+        self.unprocessed.iter().find(|road| road.name == *name)
+    }
+
+    /// Re-orders the roads by their name, see `ProcessedRoadNames::sort_by_strategy`
+    pub fn sort_by_strategy<S: SortStrategy + ?Sized>(&mut self, strategy: &S) {
+        self.unprocessed.sort_by(|a, b| strategy.compare(&a.name.0, &b.name.0).then_with(|| a.tie_break(b)));
+    }
+
+    /// Replaces every name with its display name, see `ProcessedRoadNames::apply_display_names`
+    pub fn apply_display_names<E: SortKeyExtractor + ?Sized>(&mut self, extractor: &E) {
+        for road in &mut self.unprocessed {
+            road.name = StreetName(extractor.display_name(&road.name.0).into_owned());
+        }
+    }
+
+    /// Exports the roads as `name<delimiter>positions` lines, with the
+    /// positions joined by `DEFAULT_POSITION_SEPARATOR` (`"Valley Road\tC2-C5; F7"`).
+    /// Fields are quoted if necessary (see `CsvQuoting::Necessary`).
+    /// Consecutive cells in one column are written as one range (`C2-C5`,
+    /// see `UnprocessedRoad::runs`).
     pub fn to_csv(&self, delimiter: &str) -> String {
+        self.to_csv_with_quoting(delimiter, CsvQuoting::default())
+    }
+
+    /// Same as `to_csv()`, but with configurable quoting
+    pub fn to_csv_with_quoting(&self, delimiter: &str, quoting: CsvQuoting) -> String {
+        self.csv_records(delimiter, quoting, Some(DEFAULT_POSITION_SEPARATOR)).collect::<Vec<String>>().join("\r\n")
+    }
+
+    /// Same as `to_csv()`, but with configurable delimiter, line endings,
+    /// quoting and header row (see `CsvOptions`)
+    pub fn to_csv_with_options(&self, options: &CsvOptions) -> String {
+        let delimiter = options.delimiter.to_string();
+        options.header_record(&["Name", "Positions"], &delimiter).into_iter()
+            .chain(self.csv_records(&delimiter, options.quoting, options.position_separator.as_deref()))
+            .collect::<Vec<String>>()
+            .join(options.line_ending.as_str())
+    }
+
+    /// Same as `to_csv()`, but writes the CSV line by line to the `writer`
+    /// instead of building it in memory
+    pub fn write_csv<W: io::Write>(&self, writer: W, delimiter: &str) -> io::Result<()> {
+        write_records(writer, self.csv_records(delimiter, CsvQuoting::default(), Some(DEFAULT_POSITION_SEPARATOR)), LineEnding::default())
+    }
+
+    /// Same as `to_csv_with_options()`, but writes the CSV to the `writer` in
+    /// the `CsvOptions::encoding`
+    pub fn write_csv_with_options<W: io::Write>(&self, writer: W, options: &CsvOptions) -> io::Result<()> {
+        let delimiter = options.delimiter.to_string();
+        let header = options.header_record(&["Name", "Positions"], &delimiter);
+        let records = self.csv_records(&delimiter, options.quoting, options.position_separator.as_deref());
+        write_records_with_options(writer, header.into_iter().chain(records), options)
+    }
+
+    pub(crate) fn csv_records<'a>(&'a self, delimiter: &'a str, quoting: CsvQuoting, separator: Option<&'a str>) -> impl Iterator<Item = String> + 'a {
+        self.unprocessed.iter().map(move |unprocessed_road| {
+            let name = Cow::Borrowed(unprocessed_road.name.0.as_str());
+            let positions = position_fields(unprocessed_road.runs().iter().map(|run| run.to_string()), separator);
+            quoting.record(::std::iter::once(name).chain(positions), delimiter)
+        })
+    }
+
+    /// Same as `to_csv()`, but formats the positions with the `labeler`
+    pub fn to_csv_with_labeler(&self, delimiter: &str, labeler: &GridLabeler) -> String {
+        self.to_csv_with_formatter(delimiter, labeler)
+    }
+
+    /// Same as `to_csv()`, but formats the positions (and the ranges of
+    /// consecutive positions, see `UnprocessedRoad::runs`) with the `formatter`
+    pub fn to_csv_with_formatter<F: PositionFormatter + ?Sized>(&self, delimiter: &str, formatter: &F) -> String {
         self.unprocessed.iter().map(|unprocessed_road| {
-            let unprocessed_string = unprocessed_road.positions
-                .iter()
-                .map(|pos| format!("{}", pos))
-                .collect::<Vec<String>>()
-                .join(delimiter);
-            format!("{}{}{}", unprocessed_road.name, delimiter, unprocessed_string)
+            let name = Cow::Borrowed(unprocessed_road.name.0.as_str());
+            let positions = position_fields(unprocessed_road.runs().iter().map(|run| formatter.format_finalized(run)), Some(DEFAULT_POSITION_SEPARATOR));
+            CsvQuoting::default().record(::std::iter::once(name).chain(positions), delimiter)
         })
         .collect::<Vec<String>>()
         .join("\r\n")
     }
-}
\ No newline at end of file
+}
+
+impl IntoIterator for UnprocessedRoadNames {
+    type Item = UnprocessedRoad;
+    type IntoIter = ::std::vec::IntoIter<UnprocessedRoad>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.unprocessed.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a UnprocessedRoadNames {
+    type Item = &'a UnprocessedRoad;
+    type IntoIter = ::std::slice::Iter<'a, UnprocessedRoad>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.unprocessed.iter()
+    }
+}
+
+impl ::std::iter::FromIterator<UnprocessedRoad> for UnprocessedRoadNames {
+    fn from_iter<I: IntoIterator<Item = UnprocessedRoad>>(roads: I) -> Self {
+        UnprocessedRoadNames { unprocessed: roads.into_iter().collect() }
+    }
+}
+
+/// Street index consisting of the processed roads and the roads that
+/// still need to be reviewed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StreetIndex {
+    pub processed: ProcessedRoadNames,
+    pub unprocessed: UnprocessedRoadNames,
+    /// "See" entries, ordered by `from` (see `StreetIndex::add_cross_reference`)
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub cross_references: Vec<CrossReference>,
+    /// House number sub-entries of the streets, ordered by the range
+    /// (see `InputStreetValue::number_range`)
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub house_numbers: BTreeMap<StreetName, Vec<HouseNumbers>>,
+    /// Secondary names of the streets, by their primary name (see `select_names`)
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub secondary_names: BTreeMap<StreetName, StreetName>,
+    /// Districts of the streets (see `split_by_district`)
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub districts: BTreeMap<StreetName, BTreeSet<String>>,
+    /// Attributes of the streets (see `to_csv_with_attributes`)
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub attributes: BTreeMap<StreetName, BTreeMap<String, String>>,
+}
+
+impl StreetIndex {
+
+    pub fn new(processed: ProcessedRoadNames, unprocessed: UnprocessedRoadNames) -> Self {
+        Self { processed, unprocessed, cross_references: Vec::new(), house_numbers: BTreeMap::new(), secondary_names: BTreeMap::new(), districts: BTreeMap::new(), attributes: BTreeMap::new() }
+    }
+
+    /// Splits the index into the processed and the unprocessed roads
+    pub fn into_parts(self) -> (ProcessedRoadNames, UnprocessedRoadNames) {
+        (self.processed, self.unprocessed)
+    }
+
+    /// Iterates over all processed roads, followed by all unprocessed roads
+    pub fn iter_all(&self) -> impl Iterator<Item = IndexEntry<'_>> {
+        self.processed.processed.iter().map(IndexEntry::Processed)
+            .chain(self.unprocessed.unprocessed.iter().map(IndexEntry::Unprocessed))
+    }
+
+    /// Number of roads that need to be reviewed manually
+    pub fn review_count(&self) -> usize {
+        self.unprocessed.unprocessed.len()
+    }
+
+    /// Exports the processed roads, followed by the unprocessed roads, as one
+    /// CSV (see `ProcessedRoadNames::to_csv` and `UnprocessedRoadNames::to_csv`).
+    /// The cross-references are written between the processed roads, as
+    /// `"Old Market St<delimiter>see Market St"`.
+    pub fn to_csv(&self, delimiter: &str) -> String {
+        let processed = self.processed_csv_records(delimiter, |road| road.position.to_string());
+        join_csv(processed.collect::<Vec<String>>().join("\r\n"), self.unprocessed.to_csv(delimiter))
+    }
+
+    /// Same as `to_csv()`, but formats the positions with the `formatter`
+    pub fn to_csv_with_formatter<F: PositionFormatter + ?Sized>(&self, delimiter: &str, formatter: &F) -> String {
+        let processed = self.processed_csv_records(delimiter, |road| formatter.format_finalized(&road.position));
+        join_csv(processed.collect::<Vec<String>>().join("\r\n"), self.unprocessed.to_csv_with_formatter(delimiter, formatter))
+    }
+
+    /// Same as `to_csv()`, but writes the CSV line by line to the `writer`
+    /// instead of building it in memory
+    pub fn write_csv<W: io::Write>(&self, writer: W, delimiter: &str) -> io::Result<()> {
+        let processed = self.processed_csv_records(delimiter, |road| road.position.to_string());
+        let unprocessed = self.unprocessed.csv_records(delimiter, CsvQuoting::default(), Some(DEFAULT_POSITION_SEPARATOR));
+        write_records(writer, processed.chain(unprocessed), LineEnding::default())
+    }
+
+    /// CSV records of the processed roads (with the position formatted by
+    /// `position`) and the cross-references
+    fn processed_csv_records<'a, P: Fn(&'a ProcessedRoad) -> String + 'a>(&'a self, delimiter: &'a str, position: P) -> impl Iterator<Item = String> + 'a {
+        let quoting = CsvQuoting::default();
+        let roads = self.processed.processed.iter().map(move |road|
+            (&road.name, quoting.record(vec![Cow::Borrowed(road.name.0.as_str()), Cow::Owned(position(road))], delimiter)));
+        Interleave::new(roads, &self.cross_references, move |reference|
+            quoting.record(vec![Cow::Borrowed(reference.from.0.as_str()), Cow::Owned(reference.see())], delimiter))
+    }
+
+    /// Adds the roads of `other` to this index. Entries that are in both
+    /// indexes are only kept once, the roads are ordered by their name
+    /// (like the output of `process()`). The cross-references, house numbers,
+    /// secondary names, districts and attributes are merged as well, for a
+    /// secondary name or an attribute that is in both indexes the value of
+    /// this index is kept.
+    pub fn merge(&mut self, other: StreetIndex) {
+        for reference in other.cross_references {
+            self.add_cross_reference(reference);
+        }
+        for (name, entries) in other.house_numbers {
+            let target = self.house_numbers.entry(name).or_default();
+            for entry in entries {
+                if !target.contains(&entry) {
+                    target.push(entry);
+                }
+            }
+            target.sort_by(|a, b| a.range.cmp(&b.range).then_with(|| a.positions.cmp(&b.positions)));
+        }
+        for (name, secondary_name) in other.secondary_names {
+            self.secondary_names.entry(name).or_insert(secondary_name);
+        }
+        for (name, districts) in other.districts {
+            self.districts.entry(name).or_default().extend(districts);
+        }
+        for (name, attributes) in other.attributes {
+            let target = self.attributes.entry(name).or_default();
+            for (key, value) in attributes {
+                target.entry(key).or_insert(value);
+            }
+        }
+        self.processed.processed.extend(other.processed.processed);
+        self.unprocessed.unprocessed.extend(other.unprocessed.unprocessed);
+
+        let mut seen = HashSet::new();
+        self.processed.processed.retain(|road| seen.insert(road.clone()));
+        let mut seen = HashSet::new();
+        self.unprocessed.unprocessed.retain(|road| seen.insert(road.clone()));
+
+        self.processed.processed.sort_by(|a, b| a.tie_break(b));
+        self.unprocessed.unprocessed.sort_by(|a, b| a.tie_break(b));
+    }
+}
+
+/// Appends the `unprocessed` CSV to the `processed` CSV
+fn join_csv(processed: String, unprocessed: String) -> String {
+    match (processed.is_empty(), unprocessed.is_empty()) {
+        (_, true) => processed,
+        (true, false) => unprocessed,
+        (false, false) => format!("{}\r\n{}", processed, unprocessed),
+    }
+}
+
+impl From<(ProcessedRoadNames, UnprocessedRoadNames)> for StreetIndex {
+    fn from((processed, unprocessed): (ProcessedRoadNames, UnprocessedRoadNames)) -> Self {
+        Self::new(processed, unprocessed)
+    }
+}
+
+impl From<StreetIndex> for (ProcessedRoadNames, UnprocessedRoadNames) {
+    fn from(index: StreetIndex) -> Self {
+        index.into_parts()
+    }
+}
+
+/// One road of a `StreetIndex`, see `StreetIndex::iter_all`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum IndexEntry<'a> {
+    Processed(&'a ProcessedRoad),
+    Unprocessed(&'a UnprocessedRoad),
+}
+
+impl<'a> IndexEntry<'a> {
+    pub fn name(&self) -> &'a StreetName {
+        match self {
+            IndexEntry::Processed(road) => &road.name,
+            IndexEntry::Unprocessed(road) => &road.name,
+        }
+    }
+
+    /// Whether the road needs to be reviewed manually
+    pub fn needs_review(&self) -> bool {
+        matches!(self, IndexEntry::Unprocessed(_))
+    }
+}
@@ -0,0 +1,102 @@
+//! Exports the cells of a `StreetIndex` as a GeoJSON `FeatureCollection`,
+//! to overlay the index on the map (i.e. in QGIS) and check the cells
+//!
+//! Every (road, cell) pair becomes one polygon feature:
+//!
+//! ```no_run,ignore
+//! { "type": "Feature",
+//!   "properties": { "name": "Canterbury Road", "cell": "A9", "status": "processed" },
+//!   "geometry": { "type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 1], [0, 0]]] } }
+//! ```
+//!
+//! Roads with a category have an additional `"category"` property. Cells
+//! outside of the grid are skipped. Cross-references have no cells of their
+//! own, they are exported as features without geometry:
+//!
+//! ```no_run,ignore
+//! { "type": "Feature",
+//!   "properties": { "name": "Old Market St", "see": "Market St", "status": "cross_reference" },
+//!   "geometry": null }
+//! ```
+
+use roads2csv::{StreetIndex, GridPosition};
+use roads2json::json_string;
+use gridconfig::CellGeometry;
+use category::Category;
+
+impl StreetIndex {
+
+    /// Exports one polygon feature for every cell of every road, the
+    /// coordinates are the cell bounds of the `grid` (see `CellGeometry`)
+    pub fn to_geojson<G: CellGeometry + ?Sized>(&self, grid: &G) -> String {
+        let mut features = Vec::new();
+
+        for road in &self.processed.processed {
+            for cell in road.position.cells() {
+                features.extend(feature(grid, &road.name.0, road.category.as_ref(), "processed", &cell));
+            }
+        }
+
+        for road in &self.unprocessed.unprocessed {
+            for cell in &road.positions {
+                features.extend(feature(grid, &road.name.0, road.category.as_ref(), "unprocessed", cell));
+            }
+        }
+
+        features.extend(self.cross_references.iter().map(|reference| format!(
+            "{{\"type\":\"Feature\",\"properties\":{{\"name\":{},\"see\":{},\"status\":\"cross_reference\"}},\"geometry\":null}}",
+            json_string(&reference.from.0),
+            json_string(&reference.to.0),
+        )));
+
+        format!("{{\"type\":\"FeatureCollection\",\"features\":[{}]}}", features.join(","))
+    }
+}
+
+fn feature<G: CellGeometry + ?Sized>(grid: &G, name: &str, category: Option<&Category>, status: &str, cell: &GridPosition) -> Option<String> {
+    let (min_x, min_y, max_x, max_y) = grid.cell_bounds(cell)?;
+    let category = match category {
+        Some(category) => format!(",\"category\":{}", json_string(&category.to_string())),
+        None => String::new(),
+    };
+    // exterior rings are counterclockwise (RFC 7946)
+    let ring = [(min_x, min_y), (max_x, min_y), (max_x, max_y), (min_x, max_y), (min_x, min_y)].iter()
+        .map(|(x, y)| format!("[{},{}]", x, y))
+        .collect::<Vec<String>>()
+        .join(",");
+    Some(format!(
+        "{{\"type\":\"Feature\",\"properties\":{{\"name\":{},\"cell\":{},\"status\":{}{}}},\"geometry\":{{\"type\":\"Polygon\",\"coordinates\":[[{}]]}}}}",
+        json_string(name),
+        json_string(&grid.cell_label(cell)),
+        json_string(status),
+        category,
+        ring,
+    ))
+}
+
+#[test]
+fn test_to_geojson() {
+    use roads2csv::DeduplicatedRoads;
+    use gridconfig::GeoGrid;
+
+    let csv = "Canterbury Road\tA1-A2\r\nValley View Road\tA1\tC1\tC3\r\nOutside Road\tZ9";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+    let grid = GeoGrid::new(10.0, 50.0, 0.5, 0.25).with_size(5, 5);
+
+    let geojson = index.to_geojson(&grid);
+    let parsed: ::serde_json::Value = ::serde_json::from_str(&geojson).unwrap();
+    let features = parsed["features"].as_array().unwrap();
+    assert_eq!(features.len(), 5);
+
+    assert_eq!(features[0]["properties"]["name"], "Canterbury Road");
+    assert_eq!(features[0]["properties"]["cell"], "A1");
+    let ring: Vec<Vec<Vec<f64>>> = ::serde_json::from_value(features[0]["geometry"]["coordinates"].clone()).unwrap();
+    assert_eq!(ring, vec![vec![vec![10.0, 49.75], vec![10.5, 49.75], vec![10.5, 50.0], vec![10.0, 50.0], vec![10.0, 49.75]]]);
+    assert_eq!(features[1]["properties"]["cell"], "A2");
+    assert_eq!(features[4]["properties"]["status"], "unprocessed");
+
+    let referenced = index.with_cross_reference(::cross_reference::CrossReference::new("Old Road", "Canterbury Road"));
+    let parsed: ::serde_json::Value = ::serde_json::from_str(&referenced.to_geojson(&grid)).unwrap();
+    assert_eq!(parsed["features"][5]["properties"]["see"], "Canterbury Road");
+    assert!(parsed["features"][5]["geometry"].is_null());
+}
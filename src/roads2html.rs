@@ -0,0 +1,105 @@
+//! Exports the processed roads of a `StreetIndex` as a self-contained HTML
+//! page, i.e. to publish it next to an interactive web map
+//!
+//! The roads are grouped by their first letter (in the order of
+//! `StreetIndex::processed`, so sort them first). Every group gets a
+//! section with the anchor `#letter-A`, `#letter-B`, ... and the page
+//! starts with a navigation bar linking to all sections. Cross-references
+//! are listed between the roads, with `"see Market St"` as the position.
+
+use roads2csv::StreetIndex;
+use cross_reference::Interleave;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #222; }
+nav { margin-bottom: 1em; }
+nav a { display: inline-block; padding: 0.2em 0.4em; text-decoration: none; }
+section { break-inside: avoid; }
+h2 { border-bottom: 1px solid #ccc; margin-bottom: 0.3em; }
+table { border-collapse: collapse; width: 100%; max-width: 40em; }
+td { padding: 0.1em 0.5em 0.1em 0; }
+td.position { text-align: right; white-space: nowrap; }";
+
+impl StreetIndex {
+
+    /// Exports the processed roads as an HTML page with the `title`
+    pub fn to_html(&self, title: &str) -> String {
+
+        let roads = self.processed.processed.iter().map(|road| (&road.name, (&road.name, format!(
+            "<tr><td>{}</td><td class=\"position\">{}</td></tr>",
+            escape_html(&road.name.0),
+            escape_html(&road.position.to_string()),
+        ))));
+        let rows = Interleave::new(roads, &self.cross_references, |reference| (&reference.from, format!(
+            "<tr class=\"see\"><td>{}</td><td class=\"position\"><i>see</i> {}</td></tr>",
+            escape_html(&reference.from.0),
+            escape_html(&reference.to.0),
+        )));
+
+        let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+        for (name, row) in rows {
+            let letter = name.initial().unwrap_or_default();
+            match sections.last_mut() {
+                Some((last, rows)) if *last == letter => rows.push(row),
+                _ => sections.push((letter, vec![row])),
+            }
+        }
+
+        let navigation = sections.iter()
+            .map(|(letter, _)| format!("<a href=\"#letter-{0}\">{0}</a>", escape_html(letter)))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let body = sections.iter()
+            .map(|(letter, rows)| format!(
+                "<section id=\"letter-{0}\">\n<h2>{0}</h2>\n<table>\n{1}\n</table>\n</section>",
+                escape_html(letter),
+                rows.join("\n"),
+            ))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{style}\n</style>\n</head>\n<body>\n<h1>{title}</h1>\n<nav>\n{navigation}\n</nav>\n{body}\n</body>\n</html>\n",
+            title = escape_html(title),
+            style = STYLE,
+            navigation = navigation,
+            body = body,
+        )
+    }
+}
+
+/// Escapes text for use in HTML elements and (quoted) attributes
+pub(crate) fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_to_html() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Abbey Road\tA1\r\nAcacia Avenue\tB2\r\nBaker & Sons Lane\tC3";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+    let html = index.to_html("Index <London>");
+
+    assert!(html.contains("<title>Index &lt;London&gt;</title>"));
+    assert!(html.contains("<a href=\"#letter-A\">A</a>\n<a href=\"#letter-B\">B</a>"));
+    assert!(html.contains("<section id=\"letter-A\">\n<h2>A</h2>\n<table>\n<tr><td>Abbey Road</td><td class=\"position\">A1</td></tr>\n<tr><td>Acacia Avenue</td>"));
+    assert!(html.contains("<td>Baker &amp; Sons Lane</td>"));
+    assert_eq!(html.matches("<section").count(), 2);
+
+    let referenced = index.with_cross_reference(::cross_reference::CrossReference::new("Crown Street", "Baker & Sons Lane"));
+    assert!(referenced.to_html("Index").contains("<a href=\"#letter-C\">C</a>"));
+    assert!(referenced.to_html("Index").contains("<tr class=\"see\"><td>Crown Street</td><td class=\"position\"><i>see</i> Baker &amp; Sons Lane</td></tr>"));
+}
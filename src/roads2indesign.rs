@@ -0,0 +1,125 @@
+//! Exports the processed roads of a `StreetIndex` as Adobe InDesign tagged
+//! text, so that the index can be placed into the layout document with its
+//! paragraph styles
+//!
+//! The roads are grouped by their first letter (in the order of
+//! `StreetIndex::processed`, so sort them first). Every group starts with a
+//! header paragraph, every road is one paragraph with a tab between the
+//! name and the position (set the tab stop / dot leaders in the paragraph
+//! style). Cross-references are listed with `"see Market St"` as the position.
+//!
+//! ```no_run,ignore
+//! index.to_tagged_text(&TaggedTextOptions::default())
+//! // <ASCII-WIN>
+//! // <Version:7.5><FeatureSet:InDesign-Roman>
+//! // <DefineParaStyle:Index Letter=><DefineParaStyle:Index Entry=>
+//! // <ParaStyle:Index Letter>A
+//! // <ParaStyle:Index Entry>Abbey Road<tab>A1
+//! ```
+//!
+//! The file is ASCII with Windows line endings, other characters are written
+//! as `<0x00E4>` (UTF-16 code units), which InDesign reads on all platforms.
+
+use roads2csv::StreetIndex;
+use cross_reference::Interleave;
+
+/// Paragraph styles of the tagged text
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TaggedTextOptions {
+    /// Paragraph style of the header letters
+    pub header_style: String,
+    /// Paragraph style of the roads and cross-references
+    pub entry_style: String,
+    /// Whether to insert a header letter before every group of roads
+    pub letter_headers: bool,
+}
+
+impl Default for TaggedTextOptions {
+    /// `"Index Letter"` and `"Index Entry"`, with header letters
+    fn default() -> Self {
+        Self {
+            header_style: String::from("Index Letter"),
+            entry_style: String::from("Index Entry"),
+            letter_headers: true,
+        }
+    }
+}
+
+impl StreetIndex {
+
+    /// Exports the processed roads and cross-references as InDesign tagged text
+    pub fn to_tagged_text(&self, options: &TaggedTextOptions) -> String {
+
+        let header_style = escape_tagged_text(&options.header_style);
+        let entry_style = escape_tagged_text(&options.entry_style);
+
+        let mut paragraphs = vec![
+            String::from("<ASCII-WIN>"),
+            String::from("<Version:7.5><FeatureSet:InDesign-Roman>"),
+            format!("<DefineParaStyle:{}=><DefineParaStyle:{}=>", header_style, entry_style),
+        ];
+
+        let roads = self.processed.processed.iter().map(|road| (&road.name, (&road.name, road.position.to_string())));
+        let lines = Interleave::new(roads, &self.cross_references, |reference| (&reference.from, reference.see()));
+
+        let mut current_letter = None;
+        for (name, position) in lines {
+            let letter = name.initial();
+            if options.letter_headers && letter.is_some() && letter != current_letter {
+                paragraphs.push(format!("<ParaStyle:{}>{}", header_style, escape_tagged_text(letter.as_deref().unwrap_or_default())));
+                current_letter = letter;
+            }
+            paragraphs.push(format!("<ParaStyle:{}>{}\t{}", entry_style, escape_tagged_text(&name.0), escape_tagged_text(&position)));
+        }
+
+        paragraphs.join("\r\n")
+    }
+}
+
+/// Escapes the tag delimiters and writes non-ASCII characters as `<0xXXXX>`
+fn escape_tagged_text(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' | '>' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            c if c.is_ascii() && !c.is_ascii_control() => escaped.push(c),
+            c => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    escaped.push_str(&format!("<0x{:04X}>", unit));
+                }
+            },
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_to_tagged_text() {
+    use roads2csv::DeduplicatedRoads;
+    use cross_reference::CrossReference;
+
+    let csv = "Abbey Road\tA1\r\nAcacia Avenue\tB2\r\nÄußere <Ring>straße\tC3";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process()
+        .with_cross_reference(CrossReference::new("Crown Street", "Abbey Road"));
+
+    assert_eq!(index.to_tagged_text(&TaggedTextOptions::default()), [
+        "<ASCII-WIN>",
+        "<Version:7.5><FeatureSet:InDesign-Roman>",
+        "<DefineParaStyle:Index Letter=><DefineParaStyle:Index Entry=>",
+        "<ParaStyle:Index Letter>A",
+        "<ParaStyle:Index Entry>Abbey Road\tA1",
+        "<ParaStyle:Index Entry>Acacia Avenue\tB2",
+        "<ParaStyle:Index Letter>C",
+        "<ParaStyle:Index Entry>Crown Street\tsee Abbey Road",
+        "<ParaStyle:Index Letter><0x00C4>",
+        "<ParaStyle:Index Entry><0x00C4>u<0x00DF>ere \\<Ring\\>stra<0x00DF>e\tC3",
+    ].join("\r\n"));
+
+    let options = TaggedTextOptions { entry_style: String::from("Entry"), letter_headers: false, ..TaggedTextOptions::default() };
+    assert!(index.to_tagged_text(&options).ends_with("<DefineParaStyle:Entry=>\r\n<ParaStyle:Entry>Abbey Road\tA1\r\n<ParaStyle:Entry>Acacia Avenue\tB2\r\n<ParaStyle:Entry>Crown Street\tsee Abbey Road\r\n<ParaStyle:Entry><0x00C4>u<0x00DF>ere \\<Ring\\>stra<0x00DF>e\tC3"));
+    assert_eq!(escape_tagged_text("\u{1F600}"), "<0xD83D><0xDE00>");
+}
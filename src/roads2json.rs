@@ -0,0 +1,140 @@
+//! Exports processed / unprocessed roads to JSON
+//!
+//! The schema is stable, every road is exported as:
+//!
+//! ```no_run,ignore
+//! { "name": "Canterbury Road", "status": "processed", "positions": ["A9-A10"] }
+//! { "name": "Valley View Road", "status": "unprocessed", "positions": ["E1", "E2", "F4"] }
+//! ```
+//!
+//! `positions` contains the same values as the CSV export: one range for
+//! processed roads and every cell for unprocessed roads. Roads with a
+//! category additionally have a `"category"` field (i.e. `"water"`).
+//!
+//! If the index has cross-references, `StreetIndex::to_json` adds a
+//! `"cross_references"` array with `{ "name": "Old Market St", "see": "Market St" }`.
+//! Roads of streets with attributes (see `InputStreetValue::attributes`)
+//! additionally have an `"attributes"` object (i.e. `{ "postal_code": "NW6 5SH" }`).
+
+use std::fmt::Write;
+use std::collections::BTreeMap;
+use roads2csv::{ProcessedRoadNames, UnprocessedRoadNames, StreetIndex, StreetName};
+use category::Category;
+
+impl ProcessedRoadNames {
+    /// Exports the roads as a JSON array, see the module documentation for the schema
+    pub fn to_json(&self) -> String {
+        self.to_json_with_attributes(&BTreeMap::new())
+    }
+
+    fn to_json_with_attributes(&self, attributes: &BTreeMap<StreetName, BTreeMap<String, String>>) -> String {
+        json_array(self.processed.iter().map(|road| {
+            json_road(&road.name, road.category.as_ref(), attributes.get(&road.name), "processed", &[road.position.to_string()])
+        }))
+    }
+}
+
+impl UnprocessedRoadNames {
+    /// Exports the roads as a JSON array, see the module documentation for the schema
+    pub fn to_json(&self) -> String {
+        self.to_json_with_attributes(&BTreeMap::new())
+    }
+
+    fn to_json_with_attributes(&self, attributes: &BTreeMap<StreetName, BTreeMap<String, String>>) -> String {
+        json_array(self.unprocessed.iter().map(|road| {
+            let positions = road.positions.iter().map(|p| p.to_string()).collect::<Vec<String>>();
+            json_road(&road.name, road.category.as_ref(), attributes.get(&road.name), "unprocessed", &positions)
+        }))
+    }
+}
+
+impl StreetIndex {
+    /// Exports the index as `{ "processed": [...], "unprocessed": [...] }`
+    pub fn to_json(&self) -> String {
+        let cross_references = if self.cross_references.is_empty() {
+            String::new()
+        } else {
+            let references = json_array(self.cross_references.iter().map(|reference|
+                format!("{{\"name\":{},\"see\":{}}}", json_string(&reference.from.0), json_string(&reference.to.0))));
+            format!(",\"cross_references\":{}", references)
+        };
+        format!(
+            "{{\"processed\":{},\"unprocessed\":{}{}}}",
+            self.processed.to_json_with_attributes(&self.attributes),
+            self.unprocessed.to_json_with_attributes(&self.attributes),
+            cross_references,
+        )
+    }
+}
+
+fn json_road(name: &StreetName, category: Option<&Category>, attributes: Option<&BTreeMap<String, String>>, status: &str, positions: &[String]) -> String {
+    let category = match category {
+        Some(category) => format!(",\"category\":{}", json_string(&category.to_string())),
+        None => String::new(),
+    };
+    let attributes = match attributes {
+        Some(attributes) if !attributes.is_empty() => {
+            let fields = attributes.iter().map(|(key, value)| format!("{}:{}", json_string(key), json_string(value))).collect::<Vec<String>>();
+            format!(",\"attributes\":{{{}}}", fields.join(","))
+        },
+        _ => String::new(),
+    };
+    format!(
+        "{{\"name\":{}{}{},\"status\":{},\"positions\":{}}}",
+        json_string(&name.0),
+        category,
+        attributes,
+        json_string(status),
+        json_array(positions.iter().map(|p| json_string(p))),
+    )
+}
+
+fn json_array<I: Iterator<Item = String>>(items: I) -> String {
+    format!("[{}]", items.collect::<Vec<String>>().join(","))
+}
+
+/// Quotes and escapes a string according to RFC 8259
+pub(crate) fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => { let _ = write!(escaped, "\\u{:04x}", c as u32); },
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[test]
+fn test_to_json() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Canterbury Road\tA9-A10\r\nThe \"Old\" Road\tB1\r\nValley View Road\tE1\tE2\tF4";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+
+    assert_eq!(index.to_json(), String::from(concat!(
+        r#"{"processed":["#,
+        r#"{"name":"Canterbury Road","status":"processed","positions":["A9-A10"]},"#,
+        r#"{"name":"The \"Old\" Road","status":"processed","positions":["B1"]}"#,
+        r#"],"unprocessed":["#,
+        r#"{"name":"Valley View Road","status":"unprocessed","positions":["E1","E2","F4"]}"#,
+        r#"]}"#,
+    )));
+
+    let parsed: ::serde_json::Value = ::serde_json::from_str(&index.to_json()).unwrap();
+    assert_eq!(parsed["processed"][1]["name"], "The \"Old\" Road");
+
+    let referenced = index.clone().with_cross_reference(::cross_reference::CrossReference::new("Old Road", "Canterbury Road"));
+    assert!(referenced.to_json().ends_with(r#"],"cross_references":[{"name":"Old Road","see":"Canterbury Road"}]}"#));
+
+    let mut water = index.processed.clone();
+    water.processed[0].category = Some(Category::Water);
+    assert_eq!(water.to_json().split("},{").next(), Some(r#"[{"name":"Canterbury Road","category":"water","status":"processed","positions":["A9-A10"]"#));
+}
@@ -0,0 +1,129 @@
+//! Exports the processed roads of a `StreetIndex` as LaTeX, i.e. to typeset
+//! the index of a map booklet
+//!
+//! The roads are set in a `multicols` environment (package `multicol`),
+//! grouped by their first letter (in the order of `StreetIndex::processed`,
+//! so sort them first) with a `\section*{A}` heading before every group.
+//! Name and position are separated by dot leaders (`\dotfill`),
+//! cross-references are listed with `"\emph{see} Market St"` as the position.
+//!
+//! ```no_run,ignore
+//! let body = index.to_latex(&LatexOptions::default());
+//! // \begin{multicols}{3}
+//! // \section*{A}
+//! // \noindent Abbey Road\dotfill A1\par
+//! // ...
+//! // \end{multicols}
+//! ```
+
+use roads2csv::StreetIndex;
+use cross_reference::Interleave;
+
+/// Layout of the LaTeX output
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LatexOptions {
+    /// Number of columns (at least 1)
+    pub columns: usize,
+    /// Whether to emit a complete document (with `\documentclass`, preamble
+    /// and the `title`) instead of only the `multicols` environment
+    pub standalone: bool,
+    /// Title of the standalone document
+    pub title: String,
+}
+
+impl Default for LatexOptions {
+    /// Three columns, only the index body
+    fn default() -> Self {
+        Self {
+            columns: 3,
+            standalone: false,
+            title: String::from("Street index"),
+        }
+    }
+}
+
+impl StreetIndex {
+
+    /// Exports the processed roads and cross-references as LaTeX
+    pub fn to_latex(&self, options: &LatexOptions) -> String {
+
+        let roads = self.processed.processed.iter().map(|road| (&road.name, (&road.name, escape_latex(&road.position.to_string()))));
+        let lines = Interleave::new(roads, &self.cross_references, |reference| {
+            (&reference.from, format!("\\emph{{see}} {}", escape_latex(&reference.to.0)))
+        });
+
+        let mut body = Vec::new();
+        let mut current_letter = None;
+        for (name, position) in lines {
+            let letter = name.initial();
+            if letter.is_some() && letter != current_letter {
+                body.push(format!("\\section*{{{}}}", escape_latex(letter.as_deref().unwrap_or_default())));
+                current_letter = letter;
+            }
+            body.push(format!("\\noindent {}\\dotfill {}\\par", escape_latex(&name.0), position));
+        }
+
+        let environment = format!(
+            "\\begin{{multicols}}{{{}}}\n{}\n\\end{{multicols}}\n",
+            options.columns.max(1),
+            body.join("\n"),
+        );
+
+        if !options.standalone {
+            return environment;
+        }
+
+        format!(
+            "\\documentclass{{article}}\n\\usepackage[utf8]{{inputenc}}\n\\usepackage[T1]{{fontenc}}\n\\usepackage{{multicol}}\n\\title{{{}}}\n\\date{{}}\n\\begin{{document}}\n\\maketitle\n{}\\end{{document}}\n",
+            escape_latex(&options.title),
+            environment,
+        )
+    }
+}
+
+/// Escapes the special characters of LaTeX in text mode
+fn escape_latex(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_to_latex() {
+    use roads2csv::DeduplicatedRoads;
+    use cross_reference::CrossReference;
+
+    let csv = "Abbey Road\tA1\r\nAcacia Avenue\tB2\r\nBaker & Sons Lane\tC3";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process()
+        .with_cross_reference(CrossReference::new("Crown Street", "Baker & Sons Lane"));
+
+    assert_eq!(index.to_latex(&LatexOptions { columns: 2, ..LatexOptions::default() }), [
+        "\\begin{multicols}{2}",
+        "\\section*{A}",
+        "\\noindent Abbey Road\\dotfill A1\\par",
+        "\\noindent Acacia Avenue\\dotfill B2\\par",
+        "\\section*{B}",
+        "\\noindent Baker \\& Sons Lane\\dotfill C3\\par",
+        "\\section*{C}",
+        "\\noindent Crown Street\\dotfill \\emph{see} Baker \\& Sons Lane\\par",
+        "\\end{multicols}\n",
+    ].join("\n"));
+
+    let document = index.to_latex(&LatexOptions { standalone: true, title: String::from("Index 100%"), ..LatexOptions::default() });
+    assert!(document.starts_with("\\documentclass{article}\n"));
+    assert!(document.contains("\\title{Index 100\\%}"));
+    assert!(document.contains("\\begin{multicols}{3}\n"));
+    assert!(document.ends_with("\\end{multicols}\n\\end{document}\n"));
+    assert_eq!(escape_latex("a_b~{c}^\\"), "a\\_b\\textasciitilde{}\\{c\\}\\textasciicircum{}\\textbackslash{}");
+}
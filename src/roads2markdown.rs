@@ -0,0 +1,102 @@
+//! Exports the processed roads of a `StreetIndex` as Markdown, i.e. to
+//! publish a preview of the index in a wiki
+//!
+//! The roads are grouped by their first letter (in the order of
+//! `StreetIndex::processed`, so sort them first), every group starts with a
+//! `## A` heading followed by a table or a definition list.
+//! Cross-references are listed with `"*see* Market St"` as the position.
+//!
+//! ```no_run,ignore
+//! index.to_markdown(MarkdownStyle::Table)
+//! // ## A
+//! //
+//! // | Street | Position |
+//! // | --- | --- |
+//! // | Abbey Road | A1 |
+//! ```
+
+use roads2csv::StreetIndex;
+use cross_reference::Interleave;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// How the roads of a letter group are written
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MarkdownStyle {
+    /// `| Abbey Road | A1 |`, one table per letter
+    #[default]
+    Table,
+    /// `Abbey Road` followed by `: A1` (definition list, supported by
+    /// Pandoc, PHP Markdown Extra and most wiki renderers)
+    DefinitionList,
+}
+
+impl StreetIndex {
+
+    /// Exports the processed roads and cross-references as Markdown
+    pub fn to_markdown(&self, style: MarkdownStyle) -> String {
+
+        let roads = self.processed.processed.iter().map(|road| (&road.name, (&road.name, escape_markdown(&road.position.to_string()))));
+        let lines = Interleave::new(roads, &self.cross_references, |reference| {
+            (&reference.from, format!("*see* {}", escape_markdown(&reference.to.0)))
+        });
+
+        let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+        for (name, position) in lines {
+            let letter = name.initial().unwrap_or_default();
+            let line = match style {
+                MarkdownStyle::Table => format!("| {} | {} |", escape_markdown(&name.0), position),
+                MarkdownStyle::DefinitionList => format!("{}\n: {}", escape_markdown(&name.0), position),
+            };
+            match sections.last_mut() {
+                Some((last, lines)) if *last == letter => lines.push(line),
+                _ => sections.push((letter, vec![line])),
+            }
+        }
+
+        sections.iter()
+            .map(|(letter, lines)| match style {
+                MarkdownStyle::Table => format!("## {}\n\n| Street | Position |\n| --- | --- |\n{}\n", escape_markdown(letter), lines.join("\n")),
+                MarkdownStyle::DefinitionList => format!("## {}\n\n{}\n", escape_markdown(letter), lines.join("\n\n")),
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// Escapes characters that would start Markdown formatting or end a table cell
+fn escape_markdown(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[test]
+fn test_to_markdown() {
+    use roads2csv::DeduplicatedRoads;
+    use cross_reference::CrossReference;
+
+    let csv = "Abbey Road\tA1\r\nAcacia Avenue\tB2\r\nBaker | Sons Lane\tC3";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process()
+        .with_cross_reference(CrossReference::new("Crown Street", "Abbey Road"));
+
+    assert_eq!(index.to_markdown(MarkdownStyle::Table), [
+        "## A", "",
+        "| Street | Position |", "| --- | --- |",
+        "| Abbey Road | A1 |", "| Acacia Avenue | B2 |", "",
+        "## B", "",
+        "| Street | Position |", "| --- | --- |",
+        "| Baker \\| Sons Lane | C3 |", "",
+        "## C", "",
+        "| Street | Position |", "| --- | --- |",
+        "| Crown Street | *see* Abbey Road |", "",
+    ].join("\n"));
+
+    assert!(index.to_markdown(MarkdownStyle::DefinitionList).starts_with("## A\n\nAbbey Road\n: A1\n\nAcacia Avenue\n: B2\n\n## B\n"));
+}
@@ -0,0 +1,209 @@
+//! Renders the processed roads of a `StreetIndex` as a PDF, ready to be
+//! appended to a printed map
+//!
+//! The roads are laid out in multiple columns per page (in the order of
+//! `StreetIndex::processed`, so sort them first), with a header letter
+//! (`"A"`, `"B"`, ...) before every group of roads starting with the same
+//! letter. Roads that need to be reviewed are not part of the PDF,
+//! cross-references are listed with `"see Market St"` as the position.
+
+use std::io::{Write, BufWriter};
+use printpdf::{PdfDocument, BuiltinFont, Mm, Pt, Error};
+use gridconfig::Millimeter;
+use roads2csv::StreetIndex;
+use cross_reference::Interleave;
+
+/// Space between two columns
+const COLUMN_GAP: Millimeter = Millimeter(5.0);
+/// Line height, relative to the font size
+const LINE_SPACING: f32 = 1.4;
+
+/// Font used for the index
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum PdfFont {
+    #[default]
+    Helvetica,
+    TimesRoman,
+    Courier,
+    /// TrueType / OpenType font file, the header letters use the same font
+    External(Vec<u8>),
+}
+
+/// Layout of the PDF
+#[derive(Debug, Clone)]
+pub struct PdfOptions {
+    pub title: String,
+    pub page_width: Millimeter,
+    pub page_height: Millimeter,
+    /// Margin on all four sides of the page
+    pub margin: Millimeter,
+    /// Number of columns per page (at least 1)
+    pub columns: usize,
+    pub font: PdfFont,
+    /// Font size of the road names in pt, header letters are 1.5x larger
+    pub font_size: f32,
+    /// Whether to insert a header letter before every group of roads
+    pub letter_headers: bool,
+}
+
+impl Default for PdfOptions {
+    /// A4 portrait, three columns, 8pt Helvetica
+    fn default() -> Self {
+        Self {
+            title: String::from("Street index"),
+            page_width: Millimeter(210.0),
+            page_height: Millimeter(297.0),
+            margin: Millimeter(15.0),
+            columns: 3,
+            font: PdfFont::default(),
+            font_size: 8.0,
+            letter_headers: true,
+        }
+    }
+}
+
+/// One line of the index, placed on a page
+#[derive(Debug, Clone, PartialEq)]
+struct PlacedLine<'a> {
+    page: usize,
+    /// Baseline of the text, from the bottom left corner of the page
+    x: f32,
+    y: f32,
+    kind: Line<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Line<'a> {
+    Header(String),
+    /// Road name + right-aligned position (x coordinate of the position)
+    Road(&'a str, String, f32),
+}
+
+impl StreetIndex {
+
+    /// Renders the processed roads as a PDF and writes it to `writer`
+    pub fn to_pdf<W: Write>(&self, options: &PdfOptions, writer: W) -> Result<(), Error> {
+        let bytes = self.to_pdf_bytes(options)?;
+        let mut writer = BufWriter::new(writer);
+        writer.write_all(&bytes)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Same as `to_pdf`, but returns the contents of the PDF file
+    pub fn to_pdf_bytes(&self, options: &PdfOptions) -> Result<Vec<u8>, Error> {
+
+        let page_width = Mm(options.page_width.0);
+        let page_height = Mm(options.page_height.0);
+        let (document, first_page, first_layer) = PdfDocument::new(options.title.as_str(), page_width, page_height, "Index");
+
+        let (font, header_font) = match &options.font {
+            PdfFont::Helvetica => (document.add_builtin_font(BuiltinFont::Helvetica)?, document.add_builtin_font(BuiltinFont::HelveticaBold)?),
+            PdfFont::TimesRoman => (document.add_builtin_font(BuiltinFont::TimesRoman)?, document.add_builtin_font(BuiltinFont::TimesBold)?),
+            PdfFont::Courier => (document.add_builtin_font(BuiltinFont::Courier)?, document.add_builtin_font(BuiltinFont::CourierBold)?),
+            PdfFont::External(data) => {
+                let font = document.add_external_font(&data[..])?;
+                (font.clone(), font)
+            },
+        };
+
+        let mut layers = vec![document.get_page(first_page).get_layer(first_layer)];
+
+        for line in self.pdf_layout(options) {
+            while line.page >= layers.len() {
+                let (page, layer) = document.add_page(page_width, page_height, "Index");
+                layers.push(document.get_page(page).get_layer(layer));
+            }
+            let layer = &layers[line.page];
+            match &line.kind {
+                Line::Header(letter) => {
+                    layer.use_text(letter.as_str(), options.font_size * 1.5, Mm(line.x), Mm(line.y), &header_font);
+                },
+                Line::Road(name, position, position_x) => {
+                    layer.use_text(*name, options.font_size, Mm(line.x), Mm(line.y), &font);
+                    layer.use_text(position.as_str(), options.font_size, Mm(*position_x), Mm(line.y), &font);
+                },
+            }
+        }
+
+        document.save_to_bytes()
+    }
+
+    /// Distributes the lines of the index over the columns and pages
+    fn pdf_layout(&self, options: &PdfOptions) -> Vec<PlacedLine<'_>> {
+
+        let columns = options.columns.max(1);
+        let margin = options.margin.0;
+        let column_width = (options.page_width.0 - 2.0 * margin - (columns - 1) as f32 * COLUMN_GAP.0) / columns as f32;
+        let line_height = Mm::from(Pt(options.font_size * LINE_SPACING)).0;
+        let header_height = Mm::from(Pt(options.font_size * 1.5 * LINE_SPACING)).0 + line_height * 0.5;
+        let top = options.page_height.0 - margin;
+
+        let mut placed = Vec::new();
+        let mut page = 0;
+        let mut column = 0;
+        let mut y = top;
+        let mut current_letter = None;
+
+        let roads = self.processed.processed.iter().map(|road| (&road.name, (&road.name, road.position.to_string())));
+        let lines = Interleave::new(roads, &self.cross_references, |reference| (&reference.from, reference.see()));
+
+        for (name, position) in lines {
+
+            let letter = name.initial();
+            let header = if options.letter_headers && letter.is_some() && letter != current_letter {
+                current_letter = letter.clone();
+                letter
+            } else {
+                None
+            };
+
+            // keep the header together with the first road of the group, but
+            // always put at least one line into every column
+            let height = line_height + if header.is_some() { header_height } else { 0.0 };
+            if y - height < margin && y < top {
+                column += 1;
+                y = top;
+                if column >= columns {
+                    page += 1;
+                    column = 0;
+                }
+            }
+
+            let x = margin + column as f32 * (column_width + COLUMN_GAP.0);
+
+            if let Some(letter) = header {
+                y -= header_height;
+                placed.push(PlacedLine { page, x, y, kind: Line::Header(letter) });
+            }
+
+            // right-align the position, estimated with an average glyph width of 0.6 em
+            let position_width = Mm::from(Pt(position.chars().count() as f32 * options.font_size * 0.6)).0;
+            let position_x = x + (column_width - position_width).max(0.0);
+
+            y -= line_height;
+            placed.push(PlacedLine { page, x, y, kind: Line::Road(&name.0, position, position_x) });
+        }
+
+        placed
+    }
+}
+
+#[test]
+fn test_to_pdf() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = (0..200).map(|i| format!("{} Road\tA{}", i, i + 1)).collect::<Vec<_>>().join("\r\n");
+    let index = DeduplicatedRoads::from_csv(&csv, "\t").unwrap().process();
+
+    let options = PdfOptions { columns: 2, ..PdfOptions::default() };
+    let layout = index.pdf_layout(&options);
+
+    let headers = layout.iter().filter_map(|l| match &l.kind { Line::Header(h) => Some(h.as_str()), _ => None }).collect::<Vec<_>>();
+    assert_eq!(headers, vec!["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]);
+    assert_eq!(layout.iter().map(|l| l.page).max(), Some(1));
+    assert!(layout.iter().all(|l| l.y >= options.margin.0 && l.x >= options.margin.0));
+
+    let pdf = index.to_pdf_bytes(&options).unwrap();
+    assert!(pdf.starts_with(b"%PDF-"));
+}
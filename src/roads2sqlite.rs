@@ -0,0 +1,112 @@
+//! Exports a `StreetIndex` to an SQLite database
+//!
+//! The database has three tables (existing tables with the same names are
+//! replaced):
+//!
+//! ```no_run,ignore
+//! roads(name TEXT, position TEXT, status TEXT, category TEXT)
+//! road_cells(name TEXT, page TEXT, column TEXT, row INTEGER)
+//! cross_references(name TEXT, see TEXT)
+//! ```
+//!
+//! `roads` has one row per line of the CSV export, `position` contains the
+//! same value as the CSV (the positions of unprocessed roads are joined with
+//! `"; "`) and `status` is either `"processed"` or `"unprocessed"`.
+//! `road_cells` has one row for every cell that a road covers.
+//! `cross_references` has one row per `CrossReference` (`name` → `see`).
+
+use std::path::Path;
+use rusqlite::{Connection, Transaction, Result, params};
+use roads2csv::{StreetIndex, GridPosition};
+
+impl StreetIndex {
+
+    /// Writes the index to the SQLite database at `path`,
+    /// the database is created if it doesn't exist
+    pub fn to_sqlite<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut connection = Connection::open(path)?;
+        self.to_sqlite_connection(&mut connection)
+    }
+
+    /// Same as `to_sqlite`, but writes to an open database
+    /// (i.e. an in-memory database), in one transaction
+    pub fn to_sqlite_connection(&self, connection: &mut Connection) -> Result<()> {
+        let transaction = connection.transaction()?;
+
+        transaction.execute_batch("
+            DROP TABLE IF EXISTS roads;
+            DROP TABLE IF EXISTS road_cells;
+            DROP TABLE IF EXISTS cross_references;
+            CREATE TABLE roads (name TEXT NOT NULL, position TEXT NOT NULL, status TEXT NOT NULL, category TEXT);
+            CREATE TABLE road_cells (name TEXT NOT NULL, page TEXT, \"column\" TEXT NOT NULL, row INTEGER NOT NULL);
+            CREATE INDEX road_cells_name ON road_cells (name);
+            CREATE TABLE cross_references (name TEXT NOT NULL, see TEXT NOT NULL);
+        ")?;
+
+        for road in &self.processed.processed {
+            let category = road.category.as_ref().map(|c| c.to_string());
+            insert_road(&transaction, &road.name.0, &road.position.to_string(), "processed", category.as_deref())?;
+            insert_cells(&transaction, &road.name.0, &road.position.cells())?;
+        }
+
+        for road in &self.unprocessed.unprocessed {
+            let category = road.category.as_ref().map(|c| c.to_string());
+            let positions = road.positions.iter().map(|p| p.to_string()).collect::<Vec<String>>().join("; ");
+            insert_road(&transaction, &road.name.0, &positions, "unprocessed", category.as_deref())?;
+            insert_cells(&transaction, &road.name.0, &road.positions)?;
+        }
+
+        {
+            let mut statement = transaction.prepare_cached("INSERT INTO cross_references (name, see) VALUES (?1, ?2)")?;
+            for reference in &self.cross_references {
+                statement.execute(params![reference.from.0, reference.to.0])?;
+            }
+        }
+
+        transaction.commit()
+    }
+}
+
+fn insert_road(transaction: &Transaction, name: &str, position: &str, status: &str, category: Option<&str>) -> Result<()> {
+    let mut statement = transaction.prepare_cached("INSERT INTO roads (name, position, status, category) VALUES (?1, ?2, ?3, ?4)")?;
+    statement.execute(params![name, position, status, category])?;
+    Ok(())
+}
+
+fn insert_cells(transaction: &Transaction, name: &str, cells: &[GridPosition]) -> Result<()> {
+    let mut statement = transaction.prepare_cached("INSERT INTO road_cells (name, page, \"column\", row) VALUES (?1, ?2, ?3, ?4)")?;
+    for cell in cells {
+        statement.execute(params![name, cell.page, cell.column, cell.row as i64])?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_to_sqlite() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Canterbury Road\tA9-A11\r\nValley View Road\tE1\tE2\tF4";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process_with_ranges()
+        .with_cross_reference(::cross_reference::CrossReference::new("Old Road", "Canterbury Road"));
+
+    let mut connection = Connection::open_in_memory().unwrap();
+    index.to_sqlite_connection(&mut connection).unwrap();
+    // exporting twice replaces the tables
+    index.to_sqlite_connection(&mut connection).unwrap();
+
+    let roads = connection.prepare("SELECT name, position, status FROM roads ORDER BY name").unwrap()
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))).unwrap()
+        .collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(roads, vec![
+        (String::from("Canterbury Road"), String::from("A9-A11"), String::from("processed")),
+        (String::from("Valley View Road"), String::from("E1; E2; F4"), String::from("unprocessed")),
+    ]);
+
+    let cells = connection.prepare("SELECT \"column\" || row FROM road_cells WHERE name = 'Canterbury Road' ORDER BY row").unwrap()
+        .query_map([], |row| row.get::<_, String>(0)).unwrap()
+        .collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(cells, vec!["A9", "A10", "A11"]);
+
+    let see: String = connection.query_row("SELECT see FROM cross_references WHERE name = 'Old Road'", [], |row| row.get(0)).unwrap();
+    assert_eq!(see, "Canterbury Road");
+}
@@ -0,0 +1,136 @@
+//! Renders a `StreetIndex` as an aligned table for the terminal, i.e. to
+//! inspect an index without importing the CSV into a spreadsheet
+//!
+//! The processed roads are listed first, the roads that need to be reviewed
+//! follow in a second table with the same column widths. Columns are aligned
+//! by the number of `char`s, so wide (CJK) characters may still be off.
+//!
+//! ```no_run,ignore
+//! print!("{}", index.to_table(TableStyle::Unicode));
+//! // ┌────────────┬──────────┐
+//! // │ Name       │ Position │
+//! // ├────────────┼──────────┤
+//! // │ Abbey Road │ A1       │
+//! // └────────────┴──────────┘
+//! ```
+
+use roads2csv::{StreetIndex, DEFAULT_POSITION_SEPARATOR};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Characters used for the borders of the table
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TableStyle {
+    /// `+`, `-` and `|`, works in every terminal
+    #[default]
+    Ascii,
+    /// Unicode box drawing characters (`┌`, `─`, `│`, ...)
+    Unicode,
+}
+
+/// Border characters of a style: horizontal line, vertical line, then the
+/// left / middle / right corners of the top, middle and bottom rule
+struct Borders {
+    horizontal: char,
+    vertical: char,
+    top: [char; 3],
+    middle: [char; 3],
+    bottom: [char; 3],
+}
+
+impl TableStyle {
+    fn borders(&self) -> Borders {
+        match self {
+            TableStyle::Ascii => Borders { horizontal: '-', vertical: '|', top: ['+'; 3], middle: ['+'; 3], bottom: ['+'; 3] },
+            TableStyle::Unicode => Borders {
+                horizontal: '─',
+                vertical: '│',
+                top: ['┌', '┬', '┐'],
+                middle: ['├', '┼', '┤'],
+                bottom: ['└', '┴', '┘'],
+            },
+        }
+    }
+}
+
+impl StreetIndex {
+
+    /// Renders the processed roads and (if there are any) the roads that
+    /// need to be reviewed as two aligned tables, every line ends with `\n`
+    pub fn to_table(&self, style: TableStyle) -> String {
+
+        let processed = self.processed.processed.iter()
+            .map(|road| [road.name.0.clone(), road.position.to_string()])
+            .collect::<Vec<[String; 2]>>();
+        let unprocessed = self.unprocessed.unprocessed.iter()
+            .map(|road| [road.name.0.clone(), road.runs().iter().map(|run| run.to_string()).collect::<Vec<_>>().join(DEFAULT_POSITION_SEPARATOR)])
+            .collect::<Vec<[String; 2]>>();
+
+        let processed_header = [String::from("Name"), String::from("Position")];
+        let unprocessed_header = [String::from("Name"), String::from("Positions")];
+        let mut widths = [0, 0];
+        for row in ::std::iter::once(&processed_header).chain(&processed).chain(::std::iter::once(&unprocessed_header)).chain(&unprocessed) {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let borders = style.borders();
+        let mut table = render_table(&processed_header, &processed, &widths, &borders);
+        if !unprocessed.is_empty() {
+            table.push_str("\nNeeds review:\n");
+            table.push_str(&render_table(&unprocessed_header, &unprocessed, &widths, &borders));
+        }
+        table
+    }
+}
+
+fn render_table(header: &[String; 2], rows: &[[String; 2]], widths: &[usize; 2], borders: &Borders) -> String {
+    let rule = |[left, middle, right]: [char; 3]| {
+        let lines = widths.iter().map(|width| borders.horizontal.to_string().repeat(width + 2)).collect::<Vec<_>>();
+        format!("{}{}{}\n", left, lines.join(&middle.to_string()), right)
+    };
+    let row = |cells: &[String; 2]| {
+        let cells = cells.iter().zip(widths)
+            .map(|(cell, width)| format!(" {}{} ", cell, " ".repeat(width - cell.chars().count())))
+            .collect::<Vec<_>>();
+        format!("{}{}{}\n", borders.vertical, cells.join(&borders.vertical.to_string()), borders.vertical)
+    };
+
+    let mut table = rule(borders.top);
+    table.push_str(&row(header));
+    table.push_str(&rule(borders.middle));
+    for cells in rows {
+        table.push_str(&row(cells));
+    }
+    table.push_str(&rule(borders.bottom));
+    table
+}
+
+#[test]
+fn test_to_table() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Abbey Road\tA1\r\nGroße Straße\tB2\r\nValley Road\tC1\r\nValley Road\tC2\r\nValley Road\tC3";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+
+    assert_eq!(index.to_table(TableStyle::Ascii), [
+        "+--------------+-----------+",
+        "| Name         | Position  |",
+        "+--------------+-----------+",
+        "| Abbey Road   | A1        |",
+        "| Große Straße | B2        |",
+        "+--------------+-----------+",
+        "",
+        "Needs review:",
+        "+--------------+-----------+",
+        "| Name         | Positions |",
+        "+--------------+-----------+",
+        "| Valley Road  | C1-C3     |",
+        "+--------------+-----------+",
+        "",
+    ].join("\n"));
+
+    assert!(index.to_table(TableStyle::Unicode).starts_with("┌──────────────┬───────────┐\n│ Name         │ Position  │\n├──────────────┼───────────┤\n"));
+}
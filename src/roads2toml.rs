@@ -0,0 +1,96 @@
+//! Exports a `StreetIndex` as TOML, i.e. as a data file for static site
+//! generators
+//!
+//! The schema is the same as the one of `to_json()`, every road is one
+//! table in the `processed` or `unprocessed` array of tables:
+//!
+//! ```no_run,ignore
+//! [[processed]]
+//! name = "Canterbury Road"
+//! status = "processed"
+//! positions = ["A9-A10"]
+//!
+//! [[cross_references]]
+//! name = "Old Market St"
+//! see = "Market St"
+//! ```
+//!
+//! Empty arrays are left out.
+
+use roads2csv::StreetIndex;
+use roads2json::json_string;
+use category::Category;
+
+impl StreetIndex {
+    /// Exports the index as TOML, see the module documentation for the schema
+    pub fn to_toml(&self) -> String {
+        let processed = self.processed.processed.iter().map(|road| {
+            toml_road("processed", &road.name.0, road.category.as_ref(), &[road.position.to_string()])
+        });
+        let unprocessed = self.unprocessed.unprocessed.iter().map(|road| {
+            let positions = road.positions.iter().map(|p| p.to_string()).collect::<Vec<String>>();
+            toml_road("unprocessed", &road.name.0, road.category.as_ref(), &positions)
+        });
+        let cross_references = self.cross_references.iter().map(|reference| {
+            format!("[[cross_references]]\nname = {}\nsee = {}\n", toml_string(&reference.from.0), toml_string(&reference.to.0))
+        });
+
+        processed.chain(unprocessed).chain(cross_references).collect::<Vec<String>>().join("\n")
+    }
+}
+
+fn toml_road(status: &str, name: &str, category: Option<&Category>, positions: &[String]) -> String {
+    let category = match category {
+        Some(category) => format!("category = {}\n", toml_string(&category.to_string())),
+        None => String::new(),
+    };
+    format!(
+        "[[{0}]]\nname = {1}\n{2}status = {3}\npositions = [{4}]\n",
+        status,
+        toml_string(name),
+        category,
+        toml_string(status),
+        positions.iter().map(|p| toml_string(p)).collect::<Vec<String>>().join(", "),
+    )
+}
+
+/// TOML basic string: the JSON escapes, plus DEL (not allowed unescaped in TOML)
+pub(crate) fn toml_string(s: &str) -> String {
+    json_string(s).replace('\u{7f}', "\\u007f")
+}
+
+#[test]
+fn test_to_toml() {
+    use roads2csv::DeduplicatedRoads;
+    use cross_reference::CrossReference;
+
+    let csv = "Canterbury Road\tA9-A10\r\nThe \"Old\" Road\tB1\r\nValley View Road\tE1\tE2\tF4";
+    let mut index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process()
+        .with_cross_reference(CrossReference::new("Old Road", "Canterbury Road"));
+    index.processed.processed[0].category = Some(Category::Water);
+
+    assert_eq!(index.to_toml(), [
+        "[[processed]]",
+        "name = \"Canterbury Road\"",
+        "category = \"water\"",
+        "status = \"processed\"",
+        "positions = [\"A9-A10\"]",
+        "",
+        "[[processed]]",
+        "name = \"The \\\"Old\\\" Road\"",
+        "status = \"processed\"",
+        "positions = [\"B1\"]",
+        "",
+        "[[unprocessed]]",
+        "name = \"Valley View Road\"",
+        "status = \"unprocessed\"",
+        "positions = [\"E1\", \"E2\", \"F4\"]",
+        "",
+        "[[cross_references]]",
+        "name = \"Old Road\"",
+        "see = \"Canterbury Road\"",
+        "",
+    ].join("\n"));
+
+    assert_eq!(toml_string("a\u{7f}\tb"), "\"a\\u007f\\tb\"");
+}
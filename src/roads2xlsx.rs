@@ -0,0 +1,83 @@
+//! Exports a `StreetIndex` to an Excel workbook (`.xlsx`)
+//!
+//! The workbook has two sheets: "Index" with the processed roads and
+//! "Review" with the roads that need to be placed manually. Both sheets have
+//! a bold header row that stays visible when scrolling. Cross-references
+//! are listed on the "Index" sheet, with `"see Market St"` as the position.
+
+use std::{convert::TryFrom, path::Path};
+use rust_xlsxwriter::{Workbook, Worksheet, Format, XlsxError};
+use roads2csv::StreetIndex;
+use cross_reference::Interleave;
+
+/// Width of the street name column, in characters
+const NAME_COLUMN_WIDTH: f64 = 40.0;
+
+impl StreetIndex {
+
+    /// Writes the index as an `.xlsx` workbook to `path`
+    pub fn to_xlsx<P: AsRef<Path>>(&self, path: P) -> Result<(), XlsxError> {
+        self.to_xlsx_workbook()?.save(path)
+    }
+
+    /// Same as `to_xlsx`, but returns the contents of the `.xlsx` file
+    pub fn to_xlsx_buffer(&self) -> Result<Vec<u8>, XlsxError> {
+        self.to_xlsx_workbook()?.save_to_buffer()
+    }
+
+    fn to_xlsx_workbook(&self) -> Result<Workbook, XlsxError> {
+        let mut workbook = Workbook::new();
+
+        let index = workbook.add_worksheet();
+        write_header(index, "Index", &["Street", "Position"])?;
+        let roads = self.processed.processed.iter().map(|road| (&road.name, (&road.name, road.position.to_string())));
+        let lines = Interleave::new(roads, &self.cross_references, |reference| (&reference.from, reference.see()));
+        for (row, (name, position)) in lines.enumerate() {
+            let row = row_number(row)?;
+            index.write_string(row, 0, name.0.as_str())?;
+            index.write_string(row, 1, position)?;
+        }
+
+        let review = workbook.add_worksheet();
+        write_header(review, "Review", &["Street", "Positions"])?;
+        for (row, road) in self.unprocessed.unprocessed.iter().enumerate() {
+            let row = row_number(row)?;
+            review.write_string(row, 0, road.name.0.as_str())?;
+            for (column, position) in road.positions.iter().enumerate() {
+                let column = u16::try_from(column + 1).map_err(|_| XlsxError::RowColumnLimitError)?;
+                review.write_string(row, column, position.to_string())?;
+            }
+        }
+
+        Ok(workbook)
+    }
+}
+
+/// Names the sheet, writes the column headers and freezes the header row
+fn write_header(sheet: &mut Worksheet, name: &str, headers: &[&str]) -> Result<(), XlsxError> {
+    let bold = Format::new().set_bold();
+    sheet.set_name(name)?;
+    for (column, header) in headers.iter().enumerate() {
+        sheet.write_string_with_format(0, column as u16, *header, &bold)?;
+    }
+    sheet.set_column_width(0, NAME_COLUMN_WIDTH)?;
+    sheet.set_freeze_panes(1, 0)?;
+    Ok(())
+}
+
+/// Converts the index of a road to the sheet row (below the header row)
+fn row_number(index: usize) -> Result<u32, XlsxError> {
+    u32::try_from(index + 1).map_err(|_| XlsxError::RowColumnLimitError)
+}
+
+#[test]
+fn test_to_xlsx() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Canterbury Road\tA9-A10\r\nValley View Road\tE1\tE2\tF4";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+    let xlsx = index.to_xlsx_buffer().unwrap();
+
+    // .xlsx files are zip archives
+    assert!(xlsx.starts_with(b"PK\x03\x04"));
+}
@@ -0,0 +1,166 @@
+//! Exports a `StreetIndex` as an XML document in the namespace `XML_NAMESPACE`,
+//! described by the schema `XML_SCHEMA` (XSD)
+//!
+//! ```xml
+//! <?xml version="1.0" encoding="UTF-8"?>
+//! <streetIndex xmlns="https://github.com/fschutt/street_index/xml/1">
+//!   <street name="Abbey Road" status="processed">
+//!     <position type="pair" from="A1" to="A2">A1-A2</position>
+//!   </street>
+//!   <crossReference from="Old Market St" to="Market St"/>
+//!   <street name="Valley Road" status="review" category="water">
+//!     <position type="cell" from="E1" to="E1">E1</position>
+//!     <position type="cell" from="F4" to="F4">F4</position>
+//!   </street>
+//! </streetIndex>
+//! ```
+//!
+//! The processed roads and cross-references come first (in the order of
+//! `StreetIndex::processed`), followed by the roads that need to be reviewed.
+//! New versions of this crate only add optional attributes to the schema,
+//! incompatible changes get a new namespace.
+
+use roads2csv::{StreetIndex, FinalizedGridPositon, GridPosition};
+use category::Category;
+use cross_reference::Interleave;
+use roads2html::escape_html as escape_xml;
+
+/// Namespace of the elements of `to_xml()`
+pub const XML_NAMESPACE: &str = "https://github.com/fschutt/street_index/xml/1";
+
+/// XML schema of the output of `to_xml()`
+pub const XML_SCHEMA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"
+           xmlns:si="https://github.com/fschutt/street_index/xml/1"
+           targetNamespace="https://github.com/fschutt/street_index/xml/1"
+           elementFormDefault="qualified">
+  <xs:element name="streetIndex">
+    <xs:complexType>
+      <xs:choice minOccurs="0" maxOccurs="unbounded">
+        <xs:element name="street" type="si:street"/>
+        <xs:element name="crossReference" type="si:crossReference"/>
+      </xs:choice>
+    </xs:complexType>
+  </xs:element>
+  <xs:complexType name="street">
+    <xs:sequence>
+      <xs:element name="position" type="si:position" maxOccurs="unbounded"/>
+    </xs:sequence>
+    <xs:attribute name="name" type="xs:string" use="required"/>
+    <xs:attribute name="status" use="required">
+      <xs:simpleType>
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="processed"/>
+          <xs:enumeration value="review"/>
+        </xs:restriction>
+      </xs:simpleType>
+    </xs:attribute>
+    <xs:attribute name="category" type="xs:string"/>
+  </xs:complexType>
+  <xs:complexType name="position">
+    <xs:simpleContent>
+      <xs:extension base="xs:string">
+        <xs:attribute name="type" use="required">
+          <xs:simpleType>
+            <xs:restriction base="xs:string">
+              <xs:enumeration value="single"/>
+              <xs:enumeration value="pair"/>
+              <xs:enumeration value="range"/>
+              <xs:enumeration value="cell"/>
+            </xs:restriction>
+          </xs:simpleType>
+        </xs:attribute>
+        <xs:attribute name="from" type="xs:string" use="required"/>
+        <xs:attribute name="to" type="xs:string" use="required"/>
+      </xs:extension>
+    </xs:simpleContent>
+  </xs:complexType>
+  <xs:complexType name="crossReference">
+    <xs:attribute name="from" type="xs:string" use="required"/>
+    <xs:attribute name="to" type="xs:string" use="required"/>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+impl StreetIndex {
+
+    /// Exports the index as an XML document (see `XML_SCHEMA`)
+    pub fn to_xml(&self) -> String {
+
+        let roads = self.processed.processed.iter().map(|road| {
+            let (from, to) = road.position.corners();
+            let kind = match road.position {
+                FinalizedGridPositon::SingleRect(_) => "single",
+                FinalizedGridPositon::TwoRect(..) => "pair",
+                FinalizedGridPositon::Range(..) => "range",
+            };
+            (&road.name, street_xml(&road.name.0, "processed", road.category.as_ref(), &[position_xml(kind, from, to, &road.position.to_string())]))
+        });
+        let lines = Interleave::new(roads, &self.cross_references, |reference| format!(
+            "  <crossReference from=\"{}\" to=\"{}\"/>",
+            escape_xml(&reference.from.0),
+            escape_xml(&reference.to.0),
+        ));
+
+        let unprocessed = self.unprocessed.unprocessed.iter().map(|road| {
+            let mut cells = road.positions.clone();
+            cells.sort();
+            let positions = cells.iter().map(|cell| position_xml("cell", cell, cell, &cell.to_string())).collect::<Vec<_>>();
+            street_xml(&road.name.0, "review", road.category.as_ref(), &positions)
+        });
+
+        let mut xml = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<streetIndex xmlns=\"{}\">\n", XML_NAMESPACE);
+        for line in lines.chain(unprocessed) {
+            xml.push_str(&line);
+            xml.push('\n');
+        }
+        xml.push_str("</streetIndex>\n");
+        xml
+    }
+}
+
+fn street_xml(name: &str, status: &str, category: Option<&Category>, positions: &[String]) -> String {
+    let category = category.map(|c| format!(" category=\"{}\"", escape_xml(&c.to_string()))).unwrap_or_default();
+    format!("  <street name=\"{}\" status=\"{}\"{}>\n{}\n  </street>", escape_xml(name), status, category, positions.join("\n"))
+}
+
+fn position_xml(kind: &str, from: &GridPosition, to: &GridPosition, text: &str) -> String {
+    format!(
+        "    <position type=\"{}\" from=\"{}\" to=\"{}\">{}</position>",
+        kind,
+        escape_xml(&from.to_string()),
+        escape_xml(&to.to_string()),
+        escape_xml(text),
+    )
+}
+
+#[test]
+fn test_to_xml() {
+    use roads2csv::DeduplicatedRoads;
+    use cross_reference::CrossReference;
+
+    let csv = "Abbey Road\tA1-A2\r\nMarket & Co St\tB2\r\nValley Road\tE1\tF4\tE2";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process()
+        .with_cross_reference(CrossReference::new("Old Market St", "Market & Co St"));
+
+    assert_eq!(index.to_xml(), [
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>",
+        "<streetIndex xmlns=\"https://github.com/fschutt/street_index/xml/1\">",
+        "  <street name=\"Abbey Road\" status=\"processed\">",
+        "    <position type=\"pair\" from=\"A1\" to=\"A2\">A1-A2</position>",
+        "  </street>",
+        "  <street name=\"Market &amp; Co St\" status=\"processed\">",
+        "    <position type=\"single\" from=\"B2\" to=\"B2\">B2</position>",
+        "  </street>",
+        "  <crossReference from=\"Old Market St\" to=\"Market &amp; Co St\"/>",
+        "  <street name=\"Valley Road\" status=\"review\">",
+        "    <position type=\"cell\" from=\"E1\" to=\"E1\">E1</position>",
+        "    <position type=\"cell\" from=\"E2\" to=\"E2\">E2</position>",
+        "    <position type=\"cell\" from=\"F4\" to=\"F4\">F4</position>",
+        "  </street>",
+        "</streetIndex>",
+        "",
+    ].join("\n"));
+
+    assert!(XML_SCHEMA.contains(&format!("targetNamespace=\"{}\"", XML_NAMESPACE)));
+}
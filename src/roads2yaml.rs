@@ -0,0 +1,107 @@
+//! Exports a `StreetIndex` as YAML, i.e. as a data file for static site
+//! generators
+//!
+//! The schema is the same as the one of `to_json()`:
+//!
+//! ```no_run,ignore
+//! processed:
+//!   - name: "Canterbury Road"
+//!     status: "processed"
+//!     positions: ["A9-A10"]
+//! unprocessed:
+//!   - name: "Valley View Road"
+//!     status: "unprocessed"
+//!     positions: ["E1", "E2", "F4"]
+//! cross_references:
+//!   - name: "Old Market St"
+//!     see: "Market St"
+//! ```
+//!
+//! All strings are double-quoted (JSON strings are valid YAML strings), so
+//! names like `"No"` or `"1st Avenue"` are never read as booleans or numbers.
+
+use roads2csv::StreetIndex;
+use roads2json::json_string;
+use category::Category;
+
+impl StreetIndex {
+    /// Exports the index as YAML, see the module documentation for the schema
+    pub fn to_yaml(&self) -> String {
+        let processed = self.processed.processed.iter().map(|road| {
+            yaml_road(&road.name.0, road.category.as_ref(), "processed", &[road.position.to_string()])
+        }).collect::<Vec<String>>();
+        let unprocessed = self.unprocessed.unprocessed.iter().map(|road| {
+            let positions = road.positions.iter().map(|p| p.to_string()).collect::<Vec<String>>();
+            yaml_road(&road.name.0, road.category.as_ref(), "unprocessed", &positions)
+        }).collect::<Vec<String>>();
+
+        let mut yaml = format!("processed:{}\nunprocessed:{}\n", yaml_list(&processed), yaml_list(&unprocessed));
+        if !self.cross_references.is_empty() {
+            let references = self.cross_references.iter().map(|reference| {
+                format!("  - name: {}\n    see: {}", json_string(&reference.from.0), json_string(&reference.to.0))
+            }).collect::<Vec<String>>();
+            yaml.push_str(&format!("cross_references:{}\n", yaml_list(&references)));
+        }
+        yaml
+    }
+}
+
+fn yaml_road(name: &str, category: Option<&Category>, status: &str, positions: &[String]) -> String {
+    let category = match category {
+        Some(category) => format!("\n    category: {}", json_string(&category.to_string())),
+        None => String::new(),
+    };
+    format!(
+        "  - name: {}{}\n    status: {}\n    positions: [{}]",
+        json_string(name),
+        category,
+        json_string(status),
+        positions.iter().map(|p| json_string(p)).collect::<Vec<String>>().join(", "),
+    )
+}
+
+/// Block sequence of the `items`, or an empty flow sequence
+fn yaml_list(items: &[String]) -> String {
+    if items.is_empty() {
+        String::from(" []")
+    } else {
+        format!("\n{}", items.join("\n"))
+    }
+}
+
+#[test]
+fn test_to_yaml() {
+    use roads2csv::DeduplicatedRoads;
+    use cross_reference::CrossReference;
+
+    let csv = "Canterbury Road\tA9-A10\r\nThe \"Old\" Road\tB1";
+    let mut index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+    index.processed.processed[0].category = Some(Category::Water);
+
+    assert_eq!(index.to_yaml(), [
+        "processed:",
+        "  - name: \"Canterbury Road\"",
+        "    category: \"water\"",
+        "    status: \"processed\"",
+        "    positions: [\"A9-A10\"]",
+        "  - name: \"The \\\"Old\\\" Road\"",
+        "    status: \"processed\"",
+        "    positions: [\"B1\"]",
+        "unprocessed: []",
+        "",
+    ].join("\n"));
+
+    let referenced = DeduplicatedRoads::from_csv("Valley View Road\tE1\tE2\tF4", "\t").unwrap().process()
+        .with_cross_reference(CrossReference::new("Old Road", "Valley View Road"));
+    assert_eq!(referenced.to_yaml(), [
+        "processed: []",
+        "unprocessed:",
+        "  - name: \"Valley View Road\"",
+        "    status: \"unprocessed\"",
+        "    positions: [\"E1\", \"E2\", \"F4\"]",
+        "cross_references:",
+        "  - name: \"Old Road\"",
+        "    see: \"Valley View Road\"",
+        "",
+    ].join("\n"));
+}
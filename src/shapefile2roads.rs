@@ -0,0 +1,297 @@
+//! Reads road geometries from an ESRI Shapefile (`.shp` + `.dbf`) and
+//! assigns grid cells to them
+//!
+//! Only the parts of the format that are needed for a street index are
+//! supported: (multi-)points, polylines and polygons (including their Z / M
+//! variants, the Z / M values are ignored) and dBase III attribute tables.
+
+use std::{fmt, error::Error, fs::File, path::Path, collections::BTreeMap};
+use std::io::{self, Read, BufReader};
+use roads2csv::{InputStreetValue, StreetName};
+use gridconfig::GeoGrid;
+
+/// Error that can happen while reading a shapefile
+#[derive(Debug)]
+pub enum ShapefileError {
+    /// Reading one of the files failed
+    Io(io::Error),
+    /// The `.shp` file is malformed
+    InvalidShp(&'static str),
+    /// The `.dbf` file is malformed
+    InvalidDbf(&'static str),
+    /// The `.dbf` file has no field with the given name
+    MissingField(String),
+}
+
+impl fmt::Display for ShapefileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ShapefileError::*;
+        match self {
+            Io(e) => write!(f, "I/O error: {}", e),
+            InvalidShp(reason) => write!(f, "invalid .shp file: {}", reason),
+            InvalidDbf(reason) => write!(f, "invalid .dbf file: {}", reason),
+            MissingField(name) => write!(f, "the .dbf file has no field \"{}\"", name),
+        }
+    }
+}
+
+impl Error for ShapefileError { }
+
+impl From<io::Error> for ShapefileError {
+    fn from(e: io::Error) -> Self {
+        ShapefileError::Io(e)
+    }
+}
+
+/// Reads the shapefile at `shp_path` (the `.dbf` file is expected next to it,
+/// with the same file name) and returns one `InputStreetValue` for every cell
+/// of the `grid` that a shape touches. The street name is read from the
+/// attribute `name_field` (i.e. `"STRNAME"`).
+///
+/// Shapes with an empty name are ignored.
+pub fn from_shapefile<P: AsRef<Path>>(shp_path: P, name_field: &str, grid: &GeoGrid)
+-> Result<Vec<InputStreetValue>, ShapefileError>
+{
+    let shp_path = shp_path.as_ref();
+    let shp = BufReader::new(File::open(shp_path)?);
+    let dbf = BufReader::new(File::open(shp_path.with_extension("dbf"))?);
+    from_shapefile_readers(shp, dbf, name_field, grid)
+}
+
+/// Same as `from_shapefile`, but reads the `.shp` and `.dbf` contents
+/// from arbitrary readers
+pub fn from_shapefile_readers<S: Read, D: Read>(mut shp: S, mut dbf: D, name_field: &str, grid: &GeoGrid)
+-> Result<Vec<InputStreetValue>, ShapefileError>
+{
+    let mut shp_data = Vec::new();
+    shp.read_to_end(&mut shp_data)?;
+    let mut dbf_data = Vec::new();
+    dbf.read_to_end(&mut dbf_data)?;
+
+    let shapes = parse_shp(&shp_data)?;
+    let names = parse_dbf_field(&dbf_data, name_field)?;
+
+    let mut streets = Vec::new();
+
+    // records in the .shp and .dbf file correspond by their index
+    for (parts, name) in shapes.iter().zip(names.iter()) {
+        if name.is_empty() {
+            continue;
+        }
+
+        let mut cells = Vec::new();
+        for part in parts {
+            for cell in grid.cells_for_linestring(part) {
+                if !cells.contains(&cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+
+        streets.extend(cells.into_iter().map(|position| InputStreetValue {
+            street_name: StreetName(name.clone()),
+            position,
+            category: None,
+            number_range: None,
+            weight: None,
+            secondary_name: None,
+            district: None,
+            attributes: BTreeMap::new(),
+        }));
+    }
+
+    Ok(streets)
+}
+
+/// Parts (lists of points) of one shape
+type Shape = Vec<Vec<(f64, f64)>>;
+
+/// Returns the parts of every shape in the file
+fn parse_shp(data: &[u8]) -> Result<Vec<Shape>, ShapefileError> {
+
+    const HEADER_LEN: usize = 100;
+
+    if data.len() < HEADER_LEN || read_i32_be(data, 0) != Some(9994) {
+        return Err(ShapefileError::InvalidShp("missing file header"));
+    }
+
+    let mut shapes = Vec::new();
+    let mut offset = HEADER_LEN;
+
+    while offset < data.len() {
+        // record header: record number + content length (in 16-bit words), big endian
+        let content_len = read_i32_be(data, offset + 4).ok_or(ShapefileError::InvalidShp("truncated record header"))?;
+        let start = offset + 8;
+        let end = start + (content_len.max(0) as usize) * 2;
+        let content = data.get(start..end).ok_or(ShapefileError::InvalidShp("truncated record"))?;
+        shapes.push(parse_shape(content).ok_or(ShapefileError::InvalidShp("malformed shape"))?);
+        offset = end;
+    }
+
+    Ok(shapes)
+}
+
+fn parse_shape(content: &[u8]) -> Option<Shape> {
+
+    let point = |offset: usize| Some((read_f64_le(content, offset)?, read_f64_le(content, offset + 8)?));
+
+    match read_i32_le(content, 0)? {
+        // Null shape
+        0 => Some(Vec::new()),
+        // Point, PointZ, PointM
+        1 | 11 | 21 => Some(vec![vec![point(4)?]]),
+        // MultiPoint, MultiPointZ, MultiPointM: shape type, bbox, number of points, points
+        8 | 18 | 28 => {
+            let num_points = read_i32_le(content, 36)?.max(0) as usize;
+            let points = (0..num_points).map(|i| point(40 + i * 16)).collect::<Option<Vec<_>>>()?;
+            Some(points.into_iter().map(|p| vec![p]).collect())
+        },
+        // PolyLine, Polygon and their Z / M variants:
+        // shape type, bbox, number of parts, number of points, parts, points
+        3 | 5 | 13 | 15 | 23 | 25 => {
+            let num_parts = read_i32_le(content, 36)?.max(0) as usize;
+            let num_points = read_i32_le(content, 40)?.max(0) as usize;
+            let points_offset = 44 + num_parts * 4;
+
+            let mut part_starts = (0..num_parts)
+                .map(|i| read_i32_le(content, 44 + i * 4).map(|s| s.max(0) as usize))
+                .collect::<Option<Vec<_>>>()?;
+            part_starts.push(num_points);
+
+            part_starts.windows(2).map(|w| {
+                if w[0] > w[1] {
+                    return None;
+                }
+                (w[0]..w[1]).map(|i| point(points_offset + i * 16)).collect()
+            }).collect()
+        },
+        _ => None,
+    }
+}
+
+/// Returns the (trimmed) values of the field `field_name` for all records
+/// (including deleted records, so that the indices match the `.shp` file)
+fn parse_dbf_field(data: &[u8], field_name: &str) -> Result<Vec<String>, ShapefileError> {
+
+    const FIELD_DESCRIPTOR_LEN: usize = 32;
+
+    let header = data.get(..32).ok_or(ShapefileError::InvalidDbf("missing file header"))?;
+    let num_records = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let header_len = u16::from_le_bytes([header[8], header[9]]) as usize;
+    let record_len = u16::from_le_bytes([header[10], header[11]]) as usize;
+
+    // Field descriptors start at byte 32 and are terminated by 0x0D.
+    // The first byte of every record is the deletion flag.
+    let mut field = None;
+    let mut field_offset = 1;
+    let mut descriptor_offset = 32;
+
+    while let Some(descriptor) = data.get(descriptor_offset..descriptor_offset + FIELD_DESCRIPTOR_LEN) {
+        if descriptor[0] == 0x0D {
+            break;
+        }
+        let name_len = descriptor[..11].iter().position(|b| *b == 0).unwrap_or(11);
+        let name = String::from_utf8_lossy(&descriptor[..name_len]);
+        let field_len = descriptor[16] as usize;
+        if name.eq_ignore_ascii_case(field_name) {
+            field = Some((field_offset, field_len));
+        }
+        field_offset += field_len;
+        descriptor_offset += FIELD_DESCRIPTOR_LEN;
+    }
+
+    let (field_offset, field_len) = field.ok_or_else(|| ShapefileError::MissingField(field_name.to_string()))?;
+
+    (0..num_records).map(|i| {
+        let start = header_len + i * record_len + field_offset;
+        let value = data.get(start..start + field_len).ok_or(ShapefileError::InvalidDbf("truncated record"))?;
+        Ok(decode_dbf_string(value).trim().to_string())
+    }).collect()
+}
+
+/// dBase files don't have a defined encoding: UTF-8 is used if the
+/// value is valid UTF-8, otherwise the value is decoded as Latin-1
+fn decode_dbf_string(value: &[u8]) -> String {
+    match ::std::str::from_utf8(value) {
+        Ok(s) => s.to_string(),
+        Err(_) => value.iter().map(|b| *b as char).collect(),
+    }
+}
+
+fn read_i32_be(data: &[u8], offset: usize) -> Option<i32> {
+    let b = data.get(offset..offset + 4)?;
+    Some(i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_i32_le(data: &[u8], offset: usize) -> Option<i32> {
+    let b = data.get(offset..offset + 4)?;
+    Some(i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_f64_le(data: &[u8], offset: usize) -> Option<f64> {
+    let b = data.get(offset..offset + 8)?;
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(b);
+    Some(f64::from_le_bytes(bytes))
+}
+
+#[test]
+fn test_from_shapefile() {
+    use roads2csv::GridPosition;
+
+    fn polyline(points: &[(f64, f64)], out: &mut Vec<u8>, record_number: i32) {
+        let mut content = Vec::new();
+        content.extend_from_slice(&3_i32.to_le_bytes());
+        content.extend_from_slice(&[0; 32]); // bbox, not used
+        content.extend_from_slice(&1_i32.to_le_bytes());
+        content.extend_from_slice(&(points.len() as i32).to_le_bytes());
+        content.extend_from_slice(&0_i32.to_le_bytes());
+        for (x, y) in points {
+            content.extend_from_slice(&x.to_le_bytes());
+            content.extend_from_slice(&y.to_le_bytes());
+        }
+        out.extend_from_slice(&record_number.to_be_bytes());
+        out.extend_from_slice(&((content.len() / 2) as i32).to_be_bytes());
+        out.extend_from_slice(&content);
+    }
+
+    let mut shp = vec![0; 100];
+    shp[..4].copy_from_slice(&9994_i32.to_be_bytes());
+    polyline(&[(0.5, 9.5), (1.5, 9.5)], &mut shp, 1);
+    polyline(&[(0.5, 8.5)], &mut shp, 2);
+    polyline(&[(2.5, 9.5)], &mut shp, 3);
+
+    // two fields: "ID" (N, 4 characters) and "STRNAME" (C, 12 characters)
+    let mut dbf = vec![0; 32];
+    dbf[0] = 3;
+    dbf[4] = 3;
+    dbf[8..10].copy_from_slice(&(32_u16 * 3 + 1).to_le_bytes());
+    dbf[10..12].copy_from_slice(&(1_u16 + 4 + 12).to_le_bytes());
+    for (name, kind, len) in &[("ID", b'N', 4), ("STRNAME", b'C', 12)] {
+        let mut descriptor = [0; 32];
+        descriptor[..name.len()].copy_from_slice(name.as_bytes());
+        descriptor[11] = *kind;
+        descriptor[16] = *len;
+        dbf.extend_from_slice(&descriptor);
+    }
+    dbf.push(0x0D);
+    dbf.extend_from_slice(b"    1Mill Lane   ");
+    dbf.extend_from_slice(b"    2M\xfchlenweg   ");
+    dbf.extend_from_slice(b"    3            ");
+
+    let grid = GeoGrid::new(0.0, 10.0, 1.0, 1.0);
+    let streets = from_shapefile_readers(&shp[..], &dbf[..], "strname", &grid).unwrap();
+
+    let cell = |column: &str, row: usize| GridPosition { column: String::from(column), row, page: None };
+    let cells = streets.iter().map(|s| (s.street_name.0.as_str(), s.position.clone())).collect::<Vec<_>>();
+    assert_eq!(cells, vec![
+        ("Mill Lane", cell("A", 1)),
+        ("Mill Lane", cell("B", 1)),
+        ("Mühlenweg", cell("A", 2)),
+    ]);
+
+    match from_shapefile_readers(&shp[..], &dbf[..], "NAME", &grid) {
+        Err(ShapefileError::MissingField(ref field)) if field == "NAME" => { },
+        other => panic!("expected a missing field error, got {:?}", other),
+    }
+}
@@ -0,0 +1,667 @@
+//! Pluggable ordering of street names
+//!
+//! By default, street names are ordered by their bytes (that's how the
+//! `BTreeMap` in `DeduplicatedRoads` stores them), which puts `"Über den Linden"`
+//! after `"Zebrastraße"` and `"11th Street"` before `"2nd Street"`. A
+//! `SortStrategy` can be used to re-order the output before exporting it.
+//!
+//! Instead of ordering by name, the index can also be exported grouped by
+//! grid cell (`OrderBy::Position`), i.e. for the legend of a tourist map:
+//! `"A1\tCanterbury Road, Mayer Street"`.
+
+use std::{borrow::Cow, cmp::Ordering, collections::BTreeMap};
+use roads2csv::{StreetIndex, StreetName, GridPosition, CsvQuoting, ProcessedRoad};
+#[cfg(feature = "collation")]
+use std::sync::Mutex;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Compares two street names for the final index
+pub trait SortStrategy {
+    fn compare(&self, a: &str, b: &str) -> Ordering;
+}
+
+/// Any `Fn(&str, &str) -> Ordering` can be used as a custom strategy
+impl<F: Fn(&str, &str) -> Ordering> SortStrategy for F {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        self(a, b)
+    }
+}
+
+/// Orders street names by their UTF-8 bytes (the default order)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ByteOrder;
+
+impl SortStrategy for ByteOrder {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+/// Language-specific rules for ordering accented letters
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Locale {
+    /// Accented letters are sorted like their base letter (`é` = `e`, `ß` = `ss`)
+    #[default]
+    Root,
+    /// DIN 5007-1, used for most German indexes: `ä` = `a`, `ö` = `o`, `ü` = `u`
+    German,
+    /// DIN 5007-2 (phone book order): `ä` = `ae`, `ö` = `oe`, `ü` = `ue`
+    GermanPhonebook,
+    /// `ñ` is sorted after `n`
+    Spanish,
+    /// `å`, `ä`, `ö` are sorted after `z`
+    Swedish,
+    /// `å`, `ä`, `ö` are sorted after `z`
+    Finnish,
+    /// `æ`, `ø`, `å` are sorted after `z`
+    Danish,
+    /// `æ`, `ø`, `å` are sorted after `z`
+    Norwegian,
+}
+
+impl Locale {
+    /// Returns the letter that the name is grouped under in the index:
+    /// the uppercase first letter, with accents removed unless the letter
+    /// is sorted as a letter of its own (`"A"` for `"Ängsvägen"`, but `"Ä"`
+    /// in Swedish). Names starting with a digit are grouped under `"0-9"`.
+    pub fn initial(&self, name: &str) -> Option<String> {
+        let first = name.trim_start().chars().next()?;
+        if first.is_ascii_digit() {
+            return Some(String::from("0-9"));
+        }
+
+        let lower = first.to_lowercase().next().unwrap_or(first);
+        let mut folded = String::new();
+        fold_char(lower, *self, &mut folded);
+
+        // letters of their own are folded to the ASCII characters after `z`,
+        // or to `"n~"` for the Spanish `ñ`
+        let letter = match folded.chars().next() {
+            Some(c) if c.is_ascii_lowercase() && !folded.ends_with('~') => c,
+            _ => lower,
+        };
+        Some(letter.to_uppercase().collect())
+    }
+}
+
+/// Rules for the letter that a name is grouped under, see `StreetIndex::group_by_initial`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InitialRules {
+    /// Accented letters are grouped under their base letter, unless they
+    /// are a letter of their own in the locale (see `Locale::initial`)
+    pub locale: Locale,
+    /// Letter combinations that are grouped as one letter, i.e. `"Cs"` in
+    /// Hungarian. The longest matching combination wins, case-insensitive.
+    pub digraphs: Vec<String>,
+    /// Group of the names starting with a digit, `None` to group
+    /// them by their first digit
+    pub numerals: Option<String>,
+}
+
+impl Default for InitialRules {
+    fn default() -> Self {
+        Self::new(Locale::default())
+    }
+}
+
+impl InitialRules {
+
+    /// Rules of the `locale`, numerals are grouped as `"0-9"`
+    pub fn new(locale: Locale) -> Self {
+        Self { locale, digraphs: Vec::new(), numerals: Some(String::from("0-9")) }
+    }
+
+    /// Hungarian digraphs and trigraphs (`"Cs"`, `"Dz"`, `"Dzs"`, `"Gy"`, `"Ly"`,
+    /// `"Ny"`, `"Sz"`, `"Ty"`, `"Zs"`), `Ö` and `Ü` are grouped under `O` and `U`
+    pub fn hungarian() -> Self {
+        Self::new(Locale::Root).with_digraphs(&["Cs", "Dz", "Dzs", "Gy", "Ly", "Ny", "Sz", "Ty", "Zs"])
+    }
+
+    pub fn with_digraphs(self, digraphs: &[&str]) -> Self {
+        Self { digraphs: digraphs.iter().map(|d| d.to_string()).collect(), .. self }
+    }
+
+    pub fn with_numerals(self, numerals: Option<&str>) -> Self {
+        Self { numerals: numerals.map(String::from), .. self }
+    }
+
+    /// Returns the group of the `name`, `None` for an empty name
+    pub fn initial(&self, name: &str) -> Option<String> {
+        let name = name.trim_start();
+        let first = name.chars().next()?;
+
+        if first.is_ascii_digit() {
+            return Some(self.numerals.clone().unwrap_or_else(|| first.to_string()));
+        }
+
+        let lower = name.to_lowercase();
+        let digraph = self.digraphs.iter()
+            .filter(|digraph| !digraph.is_empty() && lower.starts_with(&digraph.to_lowercase()))
+            .max_by_key(|digraph| digraph.chars().count());
+
+        match digraph {
+            Some(digraph) => {
+                let mut chars = digraph.chars();
+                chars.next().map(|c| c.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect())
+            },
+            None => self.locale.initial(name),
+        }
+    }
+}
+
+/// "Natural" order: case-insensitive, accented letters are sorted according
+/// to the `locale` and numbers are compared by their value, so
+/// `"2nd Street"` comes before `"11th Street"`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct NaturalOrder {
+    pub locale: Locale,
+}
+
+impl NaturalOrder {
+    pub fn new(locale: Locale) -> Self {
+        Self { locale }
+    }
+}
+
+impl SortStrategy for NaturalOrder {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        // fall back to the byte order so that the order is total
+        natural_key(a, self.locale).cmp(&natural_key(b, self.locale)).then_with(|| a.cmp(b))
+    }
+}
+
+/// Unicode Collation Algorithm (CLDR root collation order), for
+/// indexes that mix scripts or need full Unicode support
+#[cfg(feature = "collation")]
+pub struct UnicodeCollation {
+    // collating needs scratch space, so the collator needs to be mutable
+    collator: Mutex<::feruca::Collator>,
+}
+
+#[cfg(feature = "collation")]
+impl Default for UnicodeCollation {
+    fn default() -> Self {
+        Self { collator: Mutex::new(::feruca::Collator::default()) }
+    }
+}
+
+#[cfg(feature = "collation")]
+impl SortStrategy for UnicodeCollation {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self.collator.lock() {
+            Ok(mut collator) => collator.collate(a, b),
+            Err(poisoned) => poisoned.into_inner().collate(a, b),
+        }
+    }
+}
+
+/// Derives the part of a street name that the street is sorted by, i.e.
+/// `"Paix"` for `"Rue de la Paix"`, and optionally a different display
+/// name for the index, i.e. `"Paix (Rue de la)"`
+pub trait SortKeyExtractor {
+    /// Returns the part of the name that the street should be sorted by
+    fn sort_key<'a>(&self, name: &'a str) -> Cow<'a, str>;
+
+    /// Returns the name as it should appear in the index (by default the
+    /// unchanged name)
+    fn display_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Strips a leading street type (`"Rue"`, `"Calle"`) and articles /
+/// particles (`"de la"`, `"l'"`, `"van der"`) from street names, and
+/// optionally a generic suffix (`"straße"`, `"weg"`), so that
+/// `"Goethestraße"` is sorted by `"Goethe"`. Suffixes only change the
+/// sort key, never the display name. Endings that are also common at the
+/// end of names (`"ring"` in `"Hering"`) belong in `word_suffixes`.
+///
+/// If `invert` is set, the display name is rewritten in inverted form,
+/// so `"Rue de la Paix"` becomes `"Paix (Rue de la)"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ArticleStripper {
+    /// Street types that may appear as the first word, compared case-insensitively
+    pub street_types: Vec<String>,
+    /// Articles and particles that may follow the street type (or start the
+    /// name), compared case-insensitively. Particles ending with an apostrophe
+    /// (`"l'"`) may be directly followed by the next word.
+    pub particles: Vec<String>,
+    /// Generic endings that are ignored when sorting, compared
+    /// case-insensitively, either attached (`"Goethestraße"`) or as a
+    /// separate word (`"Berliner Straße"`). The longest matching suffix is stripped.
+    pub suffixes: Vec<String>,
+    /// Same as `suffixes`, but only stripped as a separate word or after a
+    /// hyphen (`"Alter Markt"`, `"Kaiser-Ring"`), never from `"Neumarkt"`
+    pub word_suffixes: Vec<String>,
+    pub invert: bool,
+}
+
+impl ArticleStripper {
+
+    pub fn new(street_types: &[&str], particles: &[&str]) -> Self {
+        Self {
+            street_types: street_types.iter().map(|s| s.to_string()).collect(),
+            particles: particles.iter().map(|s| s.to_string()).collect(),
+            suffixes: Vec::new(),
+            word_suffixes: Vec::new(),
+            invert: false,
+        }
+    }
+
+    pub fn with_suffixes(self, suffixes: &[&str]) -> Self {
+        Self { suffixes: suffixes.iter().map(|s| s.to_string()).collect(), .. self }
+    }
+
+    pub fn with_word_suffixes(self, word_suffixes: &[&str]) -> Self {
+        Self { word_suffixes: word_suffixes.iter().map(|s| s.to_string()).collect(), .. self }
+    }
+
+    /// Rewrites the display name in inverted form, see `ArticleStripper`
+    pub fn inverted(self) -> Self {
+        Self { invert: true, .. self }
+    }
+
+    /// `"Rue de la Paix"` is sorted under `"Paix"`
+    pub fn french() -> Self {
+        Self::new(&[
+            "rue", "avenue", "boulevard", "place", "chemin", "impasse", "allée", "quai",
+            "route", "cours", "passage", "square", "esplanade", "ruelle", "sentier", "voie",
+        ], &[
+            "de", "du", "des", "la", "le", "les", "l'", "l’", "d'", "d’",
+        ])
+    }
+
+    /// `"Calle de los Reyes"` is sorted under `"Reyes"`
+    pub fn spanish() -> Self {
+        Self::new(&[
+            "calle", "avenida", "plaza", "paseo", "camino", "carretera", "ronda",
+            "travesía", "glorieta", "callejón", "pasaje", "rambla",
+        ], &[
+            "de", "del", "la", "las", "el", "los",
+        ])
+    }
+
+    /// `"Am Markt"` is sorted under `"Markt"` and `"Goethestraße"` by `"Goethe"`
+    /// (so it comes before `"Goetheplatz"`), `"Neumarkt"` and `"Hering"` keep
+    /// their full name
+    pub fn german() -> Self {
+        Self::new(&[], &[
+            "am", "an", "auf", "im", "in", "zum", "zur", "der", "die", "das", "dem", "den",
+        ]).with_suffixes(&[
+            "straße", "strasse", "str.", "weg", "gasse", "platz", "allee", "damm",
+            "ufer", "chaussee", "pfad", "steig", "steg",
+        ]).with_word_suffixes(&["ring", "markt"])
+    }
+
+    /// `"The Avenue"` is sorted under `"Avenue"`
+    pub fn english() -> Self {
+        Self::new(&[], &["the"])
+    }
+
+    /// `"Van Baerlestraat"` is sorted under `"Baerlestraat"`
+    pub fn dutch() -> Self {
+        Self::new(&[], &[
+            "de", "het", "'t", "’t", "van", "der", "den", "ter", "ten", "te", "op",
+        ])
+    }
+
+    /// Returns the stripped prefix (`"Rue de la"`) and the remaining name (`"Paix"`),
+    /// `None` if nothing (or everything) would be stripped
+    fn split<'a>(&self, name: &'a str) -> Option<(&'a str, &'a str)> {
+
+        let mut rest = name.trim_start();
+
+        if let Some(first_word) = rest.split_whitespace().next() {
+            if self.street_types.iter().any(|t| t.to_lowercase() == first_word.to_lowercase()) {
+                rest = rest[first_word.len()..].trim_start();
+            }
+        }
+
+        'outer: loop {
+            for particle in &self.particles {
+                let lowercase_particle = particle.to_lowercase();
+                let candidate = match rest.get(..lowercase_particle.len()) {
+                    Some(candidate) if candidate.to_lowercase() == lowercase_particle => candidate,
+                    _ => continue,
+                };
+                let after = &rest[candidate.len()..];
+                let is_apostrophe = particle.ends_with('\'') || particle.ends_with('’');
+                if is_apostrophe || after.is_empty() || after.starts_with(char::is_whitespace) {
+                    rest = after.trim_start();
+                    continue 'outer;
+                }
+            }
+            break;
+        }
+
+        let prefix = name[..name.len() - rest.len()].trim();
+        if prefix.is_empty() || rest.is_empty() {
+            None
+        } else {
+            Some((prefix, rest))
+        }
+    }
+
+    /// Removes the longest matching suffix, unless nothing would be left
+    fn strip_suffix<'a>(&self, name: &'a str) -> &'a str {
+        let lowercase = name.to_lowercase();
+        // compare by characters, lowercasing may change the length in bytes
+        let start_of = |suffix: &str| name.char_indices().rev().nth(suffix.chars().count() - 1).map(|(i, _)| i).unwrap_or(0);
+        let attached = self.suffixes.iter().map(|suffix| (suffix, false));
+        let separate = self.word_suffixes.iter().map(|suffix| (suffix, true));
+        let start = attached.chain(separate)
+            .map(|(suffix, separate)| (suffix.to_lowercase(), separate))
+            .filter(|(suffix, _)| !suffix.is_empty() && lowercase.ends_with(suffix.as_str()))
+            .map(|(suffix, separate)| (start_of(&suffix), separate))
+            .filter(|(start, separate)| !separate || name[..*start].ends_with(|c: char| c.is_whitespace() || c == '-'))
+            .map(|(start, _)| start)
+            .min();
+
+        let start = match start {
+            Some(start) => start,
+            None => return name,
+        };
+        let stripped = name[..start].trim_end_matches(|c: char| c.is_whitespace() || c == '-');
+        if stripped.is_empty() { name } else { stripped }
+    }
+}
+
+impl SortKeyExtractor for ArticleStripper {
+    fn sort_key<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let rest = match self.split(name) {
+            Some((_, rest)) => rest,
+            None => name,
+        };
+        Cow::Borrowed(self.strip_suffix(rest))
+    }
+
+    fn display_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match self.split(name) {
+            Some((prefix, rest)) if self.invert => Cow::Owned(format!("{} ({})", rest, prefix)),
+            _ => Cow::Borrowed(name),
+        }
+    }
+}
+
+/// Sorts street names by the key of the `extractor` (using the `strategy`),
+/// names with equal keys are ordered by their full name
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SortByKey<E, S> {
+    pub extractor: E,
+    pub strategy: S,
+}
+
+impl<E: SortKeyExtractor, S: SortStrategy> SortStrategy for SortByKey<E, S> {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.strategy.compare(&self.extractor.sort_key(a), &self.extractor.sort_key(b))
+            .then_with(|| self.strategy.compare(a, b))
+    }
+}
+
+/// Order of the exported index, see `StreetIndex::to_csv_ordered`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum OrderBy {
+    /// One line per road, in the order of the index (the default)
+    #[default]
+    Name,
+    /// One line per grid cell (ordered by page, column and row), listing
+    /// all roads that cross the cell
+    Position,
+}
+
+impl StreetIndex {
+
+    /// Groups the processed roads by their initial (see `InitialRules`),
+    /// the roads of each group are in the order of the index. The initials
+    /// are strings, since a group can be a digraph (`"Cs"`) or `"0-9"`.
+    pub fn group_by_initial(&self, rules: &InitialRules) -> BTreeMap<String, Vec<&ProcessedRoad>> {
+        let mut groups: BTreeMap<String, Vec<&ProcessedRoad>> = BTreeMap::new();
+        for road in &self.processed.processed {
+            if let Some(initial) = rules.initial(&road.name.0) {
+                groups.entry(initial).or_default().push(road);
+            }
+        }
+        groups
+    }
+
+    /// Returns the roads crossing each cell, in the order of the index. Unprocessed
+    /// roads are included, processed ranges are expanded to their cells.
+    pub fn roads_by_cell(&self) -> BTreeMap<GridPosition, Vec<&StreetName>> {
+        let mut cells: BTreeMap<GridPosition, Vec<&StreetName>> = BTreeMap::new();
+        let mut add = |cell: GridPosition, name| {
+            let roads = cells.entry(cell).or_default();
+            if !roads.contains(&name) {
+                roads.push(name);
+            }
+        };
+
+        for road in &self.processed.processed {
+            for cell in road.position.cells() {
+                add(cell, &road.name);
+            }
+        }
+        for road in &self.unprocessed.unprocessed {
+            for cell in &road.positions {
+                add(cell.clone(), &road.name);
+            }
+        }
+
+        cells
+    }
+
+    /// Exports the index in the given `order`: `OrderBy::Name` is the same as
+    /// `to_csv()`, `OrderBy::Position` writes `"A1<delimiter>Canterbury Road, Mayer Street"`
+    pub fn to_csv_ordered(&self, delimiter: &str, order: OrderBy) -> String {
+        match order {
+            OrderBy::Name => self.to_csv(delimiter),
+            OrderBy::Position => self.roads_by_cell().iter().map(|(cell, roads)| {
+                let names = roads.iter().map(|name| name.0.as_str()).collect::<Vec<&str>>().join(", ");
+                CsvQuoting::default().record(vec![Cow::Owned(cell.to_string()), Cow::Owned(names)], delimiter)
+            })
+            .collect::<Vec<String>>()
+            .join("\r\n"),
+        }
+    }
+}
+
+/// Part of a natural sort key, numbers are sorted before text
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Chunk {
+    /// Number of digits (without leading zeros) + the digits,
+    /// so that shorter numbers are sorted first
+    Number(usize, String),
+    Text(String),
+}
+
+fn natural_key(name: &str, locale: Locale) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut text = String::new();
+    let mut digits = String::new();
+
+    for c in name.chars() {
+        if c.is_ascii_digit() {
+            if !text.is_empty() {
+                chunks.push(Chunk::Text(::std::mem::take(&mut text)));
+            }
+            digits.push(c);
+        } else {
+            if !digits.is_empty() {
+                chunks.push(number_chunk(&::std::mem::take(&mut digits)));
+            }
+            for lower in c.to_lowercase() {
+                fold_char(lower, locale, &mut text);
+            }
+        }
+    }
+
+    if !text.is_empty() {
+        chunks.push(Chunk::Text(text));
+    }
+    if !digits.is_empty() {
+        chunks.push(number_chunk(&digits));
+    }
+
+    chunks
+}
+
+fn number_chunk(digits: &str) -> Chunk {
+    let trimmed = digits.trim_start_matches('0');
+    Chunk::Number(trimmed.len(), trimmed.to_string())
+}
+
+/// Appends the sort representation of a (lowercase) character. Letters that
+/// are sorted after `z` are mapped to the ASCII characters after `z`.
+fn fold_char(c: char, locale: Locale, out: &mut String) {
+    use self::Locale::*;
+
+    let special = match (locale, c) {
+        (GermanPhonebook, 'ä') => Some("ae"),
+        (GermanPhonebook, 'ö') => Some("oe"),
+        (GermanPhonebook, 'ü') => Some("ue"),
+        (Spanish, 'ñ') => Some("n~"),
+        (Swedish, 'å') | (Finnish, 'å') => Some("{"),
+        (Swedish, 'ä') | (Finnish, 'ä') | (Swedish, 'æ') | (Finnish, 'æ') => Some("|"),
+        (Swedish, 'ö') | (Finnish, 'ö') | (Swedish, 'ø') | (Finnish, 'ø') => Some("}"),
+        (Danish, 'æ') | (Norwegian, 'æ') | (Danish, 'ä') | (Norwegian, 'ä') => Some("{"),
+        (Danish, 'ø') | (Norwegian, 'ø') | (Danish, 'ö') | (Norwegian, 'ö') => Some("|"),
+        (Danish, 'å') | (Norwegian, 'å') => Some("}"),
+        _ => None,
+    };
+
+    match special.or_else(|| fold_diacritic(c)) {
+        Some(folded) => out.push_str(folded),
+        None => out.push(c),
+    }
+}
+
+/// Maps accented (lowercase) latin letters to their base letter(s)
+fn fold_diacritic(c: char) -> Option<&'static str> {
+    let folded = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => "s",
+        'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        'ß' => "ss",
+        'æ' => "ae",
+        'œ' => "oe",
+        'þ' => "th",
+        _ => return None,
+    };
+    Some(folded)
+}
+
+#[test]
+fn test_natural_order() {
+    let sorted = |strategy: &dyn SortStrategy, names: &[&'static str]| {
+        let mut names = names.to_vec();
+        names.sort_by(|a, b| strategy.compare(a, b));
+        names
+    };
+
+    let names = ["Zebrastraße", "11th Street", "Über den Linden", "2nd Street", "main street", "Main Street"];
+    assert_eq!(sorted(&ByteOrder, &names), vec!["11th Street", "2nd Street", "Main Street", "Zebrastraße", "main street", "Über den Linden"]);
+    assert_eq!(sorted(&NaturalOrder::default(), &names), vec!["2nd Street", "11th Street", "Main Street", "main street", "Über den Linden", "Zebrastraße"]);
+
+    let swedish = ["Ödevägen", "Zinkgatan", "Åsgatan", "Ängsvägen", "Ostgatan"];
+    assert_eq!(sorted(&NaturalOrder::new(Locale::Swedish), &swedish), vec!["Ostgatan", "Zinkgatan", "Åsgatan", "Ängsvägen", "Ödevägen"]);
+
+    let german = ["Müllerstraße", "Muldeweg"];
+    assert_eq!(sorted(&NaturalOrder::new(Locale::German), &german), vec!["Muldeweg", "Müllerstraße"]);
+    assert_eq!(sorted(&NaturalOrder::new(Locale::GermanPhonebook), &german), vec!["Müllerstraße", "Muldeweg"]);
+
+    let spanish = ["Calle Ñandú", "Calle Nube", "Calle Oro"];
+    assert_eq!(sorted(&NaturalOrder::new(Locale::Spanish), &spanish), vec!["Calle Nube", "Calle Ñandú", "Calle Oro"]);
+
+    let by_length = |a: &str, b: &str| a.len().cmp(&b.len());
+    assert_eq!(sorted(&by_length, &["Long Road", "Lane"]), vec!["Lane", "Long Road"]);
+}
+
+#[test]
+fn test_article_stripper() {
+    let french = ArticleStripper::french().inverted();
+    assert_eq!(french.sort_key("Rue de la Paix"), "Paix");
+    assert_eq!(french.display_name("Rue de la Paix"), "Paix (Rue de la)");
+    assert_eq!(french.display_name("Place d'Italie"), "Italie (Place d')");
+    assert_eq!(french.display_name("Rue Lepic"), "Lepic (Rue)");
+    assert_eq!(french.display_name("La Canebière"), "Canebière (La)");
+    assert_eq!(french.display_name("Rue de la"), "Rue de la");
+    assert_eq!(french.display_name("Deauville"), "Deauville");
+
+    assert_eq!(ArticleStripper::spanish().sort_key("Calle de los Reyes"), "Reyes");
+    assert_eq!(ArticleStripper::dutch().sort_key("Van Baerlestraat"), "Baerlestraat");
+    assert_eq!(ArticleStripper::dutch().display_name("Van Baerlestraat"), "Van Baerlestraat");
+
+    let german = ArticleStripper::german();
+    assert_eq!(german.sort_key("Goethestraße"), "Goethe");
+    assert_eq!(german.sort_key("Berliner Straße"), "Berliner");
+    assert_eq!(german.sort_key("Am Markt"), "Markt");
+    assert_eq!(german.sort_key("Karl-Marx-Allee"), "Karl-Marx");
+    assert_eq!(german.sort_key("Alter Markt"), "Alter");
+    assert_eq!(german.sort_key("Kaiser-Ring"), "Kaiser");
+    assert_eq!(german.sort_key("Neumarkt"), "Neumarkt");
+    assert_eq!(german.sort_key("Hering"), "Hering");
+    assert_eq!(german.display_name("Goethestraße"), "Goethestraße");
+    assert_eq!(ArticleStripper::english().sort_key("The Avenue"), "Avenue");
+
+    let order = SortByKey { extractor: german, strategy: NaturalOrder::new(Locale::German) };
+    let mut names = vec!["Goetheplatz", "Am Markt", "Goethestraße", "Goldweg", "Hauptstraße"];
+    names.sort_by(|a, b| order.compare(a, b));
+    assert_eq!(names, vec!["Goetheplatz", "Goethestraße", "Goldweg", "Hauptstraße", "Am Markt"]);
+
+    let order = SortByKey { extractor: ArticleStripper::french(), strategy: NaturalOrder::default() };
+    let mut names = vec!["Rue de la Paix", "Avenue Montaigne", "Boulevard Haussmann"];
+    names.sort_by(|a, b| order.compare(a, b));
+    assert_eq!(names, vec!["Boulevard Haussmann", "Avenue Montaigne", "Rue de la Paix"]);
+}
+
+#[test]
+fn test_order_by_position() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Canterbury Road\tA1-A2\r\nMayer Street\tA1\r\nValley View Road\tB1\tA2\tD4";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+
+    assert_eq!(index.to_csv_ordered("\t", OrderBy::Name), index.to_csv("\t"));
+    assert_eq!(index.to_csv_ordered("\t", OrderBy::Position), "A1\tCanterbury Road, Mayer Street\r\nA2\tCanterbury Road, Valley View Road\r\nB1\tValley View Road\r\nD4\tValley View Road");
+}
+
+#[test]
+fn test_group_by_initial() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Csillag utca\tA1\r\nCserje utca\tA2\r\nCukor utca\tA3\r\nDzsungel utca\tB1\r\nDózsa György út\tB2\r\nÖrs vezér tere\tC1\r\n1-es út\tD1\r\n56-os utca\tD2";
+    let index = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process();
+    let hungarian = index.group_by_initial(&InitialRules::hungarian());
+    let groups = hungarian.iter()
+        .map(|(initial, roads)| format!("{}: {}", initial, roads.iter().map(|road| road.name.0.as_str()).collect::<Vec<_>>().join(", ")))
+        .collect::<Vec<String>>();
+    assert_eq!(groups, vec![
+        "0-9: 1-es út, 56-os utca",
+        "C: Cukor utca",
+        "Cs: Cserje utca, Csillag utca",
+        "D: Dózsa György út",
+        "Dzs: Dzsungel utca",
+        "O: Örs vezér tere",
+    ]);
+
+    let plain = index.group_by_initial(&InitialRules::new(Locale::Swedish).with_numerals(None));
+    assert_eq!(plain.keys().cloned().collect::<Vec<_>>(), vec!["1", "5", "C", "D", "Ö"]);
+}
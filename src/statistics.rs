@@ -0,0 +1,107 @@
+//! Summary numbers of a `StreetIndex`, i.e. for the QA sign-off of an
+//! atlas edition
+//!
+//! ```no_run,ignore
+//! let statistics = index.statistics();
+//! println!("{}", statistics);
+//! // streets: 1204
+//! // processed entries: 1187
+//! // needing review: 31
+//! // ...
+//! ```
+
+use std::fmt;
+use std::collections::{BTreeMap, BTreeSet};
+use roads2csv::{StreetIndex, StreetName, GridPosition};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Counts of a `StreetIndex`, see `StreetIndex::statistics`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IndexStatistics {
+    /// Number of distinct street names
+    pub total_streets: usize,
+    /// Number of processed entries (a clustered road can have several entries)
+    pub processed: usize,
+    /// Number of roads that need to be reviewed manually
+    pub needs_review: usize,
+    /// Average number of cells that a street covers
+    pub average_cells_per_street: f64,
+    /// Street that covers the most cells (on a tie, the first one in
+    /// byte order) and the number of its cells
+    pub longest_street: Option<(StreetName, usize)>,
+    /// Number of distinct street names per initial (see `StreetName::initial`)
+    pub per_letter: BTreeMap<String, usize>,
+}
+
+impl StreetIndex {
+
+    /// Counts the streets, entries and cells of the index
+    pub fn statistics(&self) -> IndexStatistics {
+
+        let mut cells: BTreeMap<&StreetName, BTreeSet<GridPosition>> = BTreeMap::new();
+        for road in &self.processed.processed {
+            cells.entry(&road.name).or_default().extend(road.position.cells());
+        }
+        for road in &self.unprocessed.unprocessed {
+            cells.entry(&road.name).or_default().extend(road.positions.iter().cloned());
+        }
+
+        let total_cells = cells.values().map(|cells| cells.len()).sum::<usize>();
+        let average_cells_per_street = if cells.is_empty() { 0.0 } else { total_cells as f64 / cells.len() as f64 };
+
+        let longest_street = cells.iter()
+            .fold(None, |longest: Option<(&StreetName, usize)>, (name, cells)| match longest {
+                Some((_, max)) if max >= cells.len() => longest,
+                _ => Some((name, cells.len())),
+            })
+            .map(|(name, count)| (name.clone(), count));
+
+        let mut per_letter = BTreeMap::new();
+        for name in cells.keys() {
+            *per_letter.entry(name.initial().unwrap_or_default()).or_insert(0) += 1;
+        }
+
+        IndexStatistics {
+            total_streets: cells.len(),
+            processed: self.processed.processed.len(),
+            needs_review: self.review_count(),
+            average_cells_per_street,
+            longest_street,
+            per_letter,
+        }
+    }
+}
+
+impl fmt::Display for IndexStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "streets: {}", self.total_streets)?;
+        writeln!(f, "processed entries: {}", self.processed)?;
+        writeln!(f, "needing review: {}", self.needs_review)?;
+        writeln!(f, "average cells per street: {:.2}", self.average_cells_per_street)?;
+        if let Some((name, cells)) = &self.longest_street {
+            writeln!(f, "longest street: {} ({} cells)", name, cells)?;
+        }
+        let letters = self.per_letter.iter().map(|(letter, count)| format!("{}: {}", letter, count)).collect::<Vec<String>>();
+        write!(f, "per letter: {}", letters.join(", "))
+    }
+}
+
+#[test]
+fn test_statistics() {
+    use roads2csv::DeduplicatedRoads;
+
+    let csv = "Abbey Road\tA1\r\nAcacia Avenue\tB2-B5\r\nCanterbury Road\tA9\tA10\tE1\tE2\r\nMill Lane\tC3-D3";
+    let statistics = DeduplicatedRoads::from_csv(csv, "\t").unwrap().process_with_ranges().statistics();
+    let report = statistics.to_string();
+
+    assert_eq!(statistics.total_streets, 4);
+    assert_eq!(statistics.processed, 3);
+    assert_eq!(statistics.needs_review, 1);
+    assert_eq!(statistics.average_cells_per_street, 11.0 / 4.0);
+    assert_eq!(statistics.longest_street, Some((StreetName(String::from("Acacia Avenue")), 4)));
+    assert_eq!(statistics.per_letter.into_iter().collect::<Vec<_>>(), vec![(String::from("A"), 2), (String::from("C"), 1), (String::from("M"), 1)]);
+    assert!(report.starts_with("streets: 4\nprocessed entries: 3\nneeding review: 1\naverage cells per street: 2.75\nlongest street: Acacia Avenue (4 cells)\n"));
+    assert!(report.ends_with("per letter: A: 2, C: 1, M: 1"));
+}
@@ -0,0 +1,88 @@
+//! Latin sort keys and romanized names for street names in Cyrillic,
+//! Greek, Arabic or other non-Latin scripts (via `any_ascii`), so that an
+//! index that mixes scripts (i.e. `NameSelection::Both` of a bilingual
+//! index) is sorted as if every name was written in Latin letters
+//!
+//! ```no_run,ignore
+//! let mut index = index.select_names(NameSelection::Both);
+//! index.processed.sort_by_strategy(&SortByKey { extractor: Transliterator, strategy: NaturalOrder::default() });
+//! index.to_csv_romanized("\t")
+//! // "Арбат\tArbat\tA1\r\nBolshaya Nikitskaya\t\tB3\r\nТверская улица\tTverskaya ulitsa\tC2"
+//! ```
+
+use std::borrow::Cow;
+use roads2csv::{StreetIndex, CsvQuoting};
+use sorting::SortKeyExtractor;
+
+/// Sorts street names by their transliteration to ASCII (`"Тверская"` is
+/// sorted as `"Tverskaya"`), to be used with `SortByKey`. The display
+/// name is left unchanged.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Transliterator;
+
+impl SortKeyExtractor for Transliterator {
+    fn sort_key<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        if name.is_ascii() {
+            Cow::Borrowed(name)
+        } else {
+            Cow::Owned(::any_ascii::any_ascii(name))
+        }
+    }
+}
+
+/// Returns the romanized form of the `name`, `None` if the name is only
+/// written in Latin letters (including accented letters like `"ß"` or `"é"`)
+pub fn romanize(name: &str) -> Option<String> {
+    if name.chars().all(is_latin) {
+        None
+    } else {
+        Some(::any_ascii::any_ascii(name))
+    }
+}
+
+fn is_latin(c: char) -> bool {
+    !c.is_alphabetic() || matches!(c, '\u{0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' | '\u{2C60}'..='\u{2C7F}' | '\u{A720}'..='\u{A7FF}')
+}
+
+impl StreetIndex {
+
+    /// Same as `to_csv()`, but with a romanized name column between the name
+    /// and the position: `"Арбат<delimiter>Arbat<delimiter>A1"`. The column
+    /// is empty for names that are already written in Latin letters.
+    pub fn to_csv_romanized(&self, delimiter: &str) -> String {
+        let quoting = CsvQuoting::default();
+        self.processed.iter().map(|road| quoting.record(vec![
+            Cow::Borrowed(road.name.0.as_str()),
+            Cow::Owned(romanize(&road.name.0).unwrap_or_default()),
+            Cow::Owned(road.position.to_string()),
+        ], delimiter))
+        .collect::<Vec<String>>()
+        .join("\r\n")
+    }
+}
+
+#[test]
+fn test_transliteration() {
+    use roads2csv::{DeduplicatedRoads, InputStreetValue};
+    use bilingual::NameSelection;
+    use sorting::{SortByKey, NaturalOrder};
+
+    assert_eq!(Transliterator.sort_key("Οδός Ερμού"), "Odos Ermoy");
+    assert_eq!(romanize("Тверская улица"), Some(String::from("Tverskaya ulitsa")));
+    assert_eq!(romanize("Große Straße"), None);
+
+    let streets = vec![
+        InputStreetValue::new("Тверская улица", "C2".parse().unwrap()).with_secondary_name("Tverskaya Street"),
+        InputStreetValue::new("Арбат", "A1".parse().unwrap()),
+        InputStreetValue::new("Bolshaya Nikitskaya", "B3".parse().unwrap()),
+    ];
+    let mut index = DeduplicatedRoads::from_streets(&streets).process().select_names(NameSelection::Both);
+    index.processed.sort_by_strategy(&SortByKey { extractor: Transliterator, strategy: NaturalOrder::default() });
+
+    assert_eq!(index.to_csv_romanized("\t"), [
+        "Арбат\tArbat\tA1",
+        "Bolshaya Nikitskaya\t\tB3",
+        "Tverskaya Street\t\tC2",
+        "Тверская улица\tTverskaya ulitsa\tC2",
+    ].join("\r\n"));
+}
@@ -0,0 +1,144 @@
+//! Incremental updates of a street index, i.e. for a map editing service
+//! that keeps the index up to date on every edit without processing all
+//! roads again
+//!
+//! ```no_run,ignore
+//! let options = ProcessOptions::default();
+//! let mut index = deduplicated.process_with_options(&options);
+//!
+//! // on every edit
+//! deduplicated.remove_street(&old_name);
+//! deduplicated.insert(InputStreetValue::new("Mill Lane", position));
+//! index.reprocess_street(&deduplicated, &old_name, &options);
+//! index.reprocess_street(&deduplicated, &StreetName(String::from("Mill Lane")), &options);
+//! ```
+
+use std::cmp::Ordering;
+use roads2csv::{DeduplicatedRoads, InputStreetValue, StreetIndex, StreetName, ProcessOptions, process_road, add_weights};
+use house_numbers::process_house_numbers;
+
+impl DeduplicatedRoads {
+
+    /// Adds one position of a street, returns `false` if the street
+    /// already had this position
+    pub fn insert(&mut self, street: InputStreetValue) -> bool {
+        if let Some(range) = street.number_range {
+            let ranges = self.number_ranges.entry(street.street_name.clone()).or_default();
+            ranges.entry(range).or_default().insert(street.position.clone());
+        }
+        if let Some(weight) = street.weight {
+            add_weights(self.weights.entry(street.street_name.clone()).or_default(), Some((street.position.clone(), weight)));
+        }
+        if let Some(secondary_name) = street.secondary_name {
+            self.secondary_names.insert(street.street_name.clone(), secondary_name);
+        }
+        if let Some(district) = street.district {
+            self.districts.entry(street.street_name.clone()).or_default().insert(district);
+        }
+        if !street.attributes.is_empty() {
+            self.attributes.entry(street.street_name.clone()).or_default().extend(street.attributes);
+        }
+        let roads = match street.category {
+            Some(category) => self.categories.entry(category).or_default(),
+            None => &mut self.roads,
+        };
+        roads.entry(street.street_name).or_default().insert(street.position)
+    }
+
+    /// Removes all positions of the street `name` (in all categories),
+    /// returns `false` if there was no street with this name
+    pub fn remove_street(&mut self, name: &StreetName) -> bool {
+        let mut removed = self.roads.remove(name).is_some();
+        for roads in self.categories.values_mut() {
+            removed |= roads.remove(name).is_some();
+        }
+        self.categories.retain(|_, roads| !roads.is_empty());
+        self.number_ranges.remove(name);
+        self.weights.remove(name);
+        self.secondary_names.remove(name);
+        self.districts.remove(name);
+        self.attributes.remove(name);
+        removed
+    }
+}
+
+impl StreetIndex {
+
+    /// Replaces the entries of the street `name` with the result of
+    /// processing its current positions in `roads`, all other entries are
+    /// kept (the same goes for its house numbers). Streets that are no
+    /// longer in `roads` are removed.
+    ///
+    /// The new entries are inserted in the order of `process()` (by name,
+    /// see `ProcessedRoad::tie_break`), `options` should be the options the
+    /// index was processed with.
+    pub fn reprocess_street(&mut self, roads: &DeduplicatedRoads, name: &StreetName, options: &ProcessOptions) {
+        self.processed.processed.retain(|road| road.name != *name);
+        self.unprocessed.unprocessed.retain(|road| road.name != *name);
+        match roads.number_ranges.get(name) {
+            Some(ranges) => { self.house_numbers.insert(name.clone(), process_house_numbers(name, ranges, options)); },
+            None => { self.house_numbers.remove(name); },
+        }
+        match roads.secondary_names.get(name) {
+            Some(secondary_name) => { self.secondary_names.insert(name.clone(), secondary_name.clone()); },
+            None => { self.secondary_names.remove(name); },
+        }
+        match roads.districts.get(name) {
+            Some(districts) => { self.districts.insert(name.clone(), districts.clone()); },
+            None => { self.districts.remove(name); },
+        }
+        match roads.attributes.get(name) {
+            Some(attributes) => { self.attributes.insert(name.clone(), attributes.clone()); },
+            None => { self.attributes.remove(name); },
+        }
+
+        let uncategorized = roads.roads.get(name).map(|positions| (None, positions));
+        let categorized = roads.categories.iter()
+            .filter_map(|(category, roads)| roads.get(name).map(|positions| (Some(category), positions)));
+
+        for (category, positions) in uncategorized.into_iter().chain(categorized) {
+            match process_road(name, category, &roads.primary_positions(name, positions, options), options) {
+                Some(Ok(processed)) => for road in processed {
+                    let index = self.processed.processed.partition_point(|other| other.tie_break(&road) == Ordering::Less);
+                    self.processed.processed.insert(index, road);
+                },
+                Some(Err(road)) => {
+                    let index = self.unprocessed.unprocessed.partition_point(|other| other.tie_break(&road) == Ordering::Less);
+                    self.unprocessed.unprocessed.insert(index, road);
+                },
+                None => { },
+            }
+        }
+    }
+}
+
+#[test]
+fn test_incremental_update() {
+    use roads2csv::GridPosition;
+
+    let mut roads = DeduplicatedRoads::from_csv("Canterbury Road\tA9-A10\r\nMayer Street\tA1\r\nValley Road\tC4", "\t").unwrap();
+    let options = ProcessOptions::default();
+    let mut index = roads.process_with_options(&options);
+
+    let mayer = StreetName(String::from("Mayer Street"));
+    let position = "A2".parse::<GridPosition>().unwrap();
+    assert!(roads.insert(InputStreetValue::new("Mayer Street", position.clone())));
+    assert!(!roads.insert(InputStreetValue::new("Mayer Street", position)));
+    index.reprocess_street(&roads, &mayer, &options);
+    assert_eq!(index, roads.process_with_options(&options));
+    assert_eq!(index.to_csv("\t"), "Canterbury Road\tA9-A10\r\nMayer Street\tA1-A2\r\nValley Road\tC4");
+
+    let canterbury = StreetName(String::from("Canterbury Road"));
+    assert!(roads.remove_street(&canterbury));
+    assert!(!roads.remove_street(&canterbury));
+    index.reprocess_street(&roads, &canterbury, &options);
+    assert_eq!(index, roads.process_with_options(&options));
+
+    // new streets are inserted in order
+    roads.insert(InputStreetValue::new("Bridge Street", "E1".parse().unwrap()));
+    roads.insert(InputStreetValue::new("Bridge Street", "E5".parse().unwrap()));
+    roads.insert(InputStreetValue::new("Bridge Street", "E9".parse().unwrap()));
+    index.reprocess_street(&roads, &StreetName(String::from("Bridge Street")), &options);
+    assert_eq!(index, roads.process_with_options(&options));
+    assert_eq!(index.unprocessed.unprocessed.len(), 1);
+}
@@ -0,0 +1,226 @@
+//! UTM projection and MGRS references, used by `GridKind::Utm` and
+//! `GridKind::Mgrs` to assign real-world grid squares instead of `A1` cells
+//!
+//! The projection uses the WGS 84 ellipsoid and the series expansion of
+//! Snyder ("Map Projections - A Working Manual", p. 61), which is accurate
+//! to well below a meter inside of a UTM zone.
+
+use gridconfig::number_to_alphabet_value;
+use roads2csv::GridPosition;
+
+const SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+const FLATTENING: f64 = 1.0 / 298.257_223_563;
+const SCALE_FACTOR: f64 = 0.9996;
+const FALSE_EASTING: f64 = 500_000.0;
+const FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+
+/// Latitude bands of the MGRS grid zone designators, 8° each from 80°S
+/// (`X` spans 12°, up to 84°N)
+const LATITUDE_BANDS: &[u8] = b"CDEFGHJKLMNPQRSTUVWX";
+/// Column letters of the 100 km squares, repeating every three zones
+const SQUARE_COLUMNS: [&[u8]; 3] = [b"ABCDEFGH", b"JKLMNPQR", b"STUVWXYZ"];
+/// Row letters of the 100 km squares, offset by 5 in even zones
+const SQUARE_ROWS: &[u8] = b"ABCDEFGHJKLMNPQRSTUV";
+
+/// Returns the UTM zone (1 - 60) of a point, including the exceptions for
+/// southern Norway and Svalbard. `None` outside of 80°S - 84°N.
+pub fn utm_zone(longitude: f64, latitude: f64) -> Option<u8> {
+    if !(-80.0..84.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return None;
+    }
+
+    if (56.0..64.0).contains(&latitude) && (3.0..12.0).contains(&longitude) {
+        return Some(32);
+    }
+
+    if latitude >= 72.0 && (0.0..42.0).contains(&longitude) {
+        return Some(match longitude {
+            l if l < 9.0 => 31,
+            l if l < 21.0 => 33,
+            l if l < 33.0 => 35,
+            _ => 37,
+        });
+    }
+
+    Some((((longitude + 180.0) / 6.0).floor() as u8 % 60) + 1)
+}
+
+/// Returns the MGRS latitude band letter (`C` - `X`) of a latitude
+pub fn latitude_band(latitude: f64) -> Option<char> {
+    if !(-80.0..84.0).contains(&latitude) {
+        return None;
+    }
+    let index = (((latitude + 80.0) / 8.0).floor() as usize).min(LATITUDE_BANDS.len() - 1);
+    Some(LATITUDE_BANDS[index] as char)
+}
+
+/// Projects longitude / latitude (WGS 84) into the UTM `zone`, returns
+/// `(easting, northing)` in meters. With `south`, the false northing of the
+/// southern hemisphere (10 000 km) is added.
+pub fn to_utm(longitude: f64, latitude: f64, zone: u8, south: bool) -> Option<(f64, f64)> {
+    if !(1..=60).contains(&zone) || !longitude.is_finite() || !(-90.0..=90.0).contains(&latitude) {
+        return None;
+    }
+
+    let e2 = FLATTENING * (2.0 - FLATTENING);
+    let ep2 = e2 / (1.0 - e2);
+    let phi = latitude.to_radians();
+    let (sin_phi, cos_phi, tan_phi) = (phi.sin(), phi.cos(), phi.tan());
+
+    let n = SEMI_MAJOR_AXIS / (1.0 - e2 * sin_phi * sin_phi).sqrt();
+    let t = tan_phi * tan_phi;
+    let c = ep2 * cos_phi * cos_phi;
+    let a = cos_phi * (longitude - central_meridian(zone)).to_radians();
+    let m = meridian_arc(phi, e2);
+
+    let easting = FALSE_EASTING + SCALE_FACTOR * n * (
+        a + (1.0 - t + c) * a.powi(3) / 6.0
+        + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0
+    );
+    let northing = SCALE_FACTOR * (m + n * tan_phi * (
+        a * a / 2.0
+        + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+        + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0
+    ));
+
+    let northing = if south { northing + FALSE_NORTHING_SOUTH } else { northing };
+    if easting.is_finite() && northing.is_finite() { Some((easting, northing)) } else { None }
+}
+
+/// Inverse of `to_utm`, returns `(longitude, latitude)`
+pub fn from_utm(easting: f64, northing: f64, zone: u8, south: bool) -> Option<(f64, f64)> {
+    if !(1..=60).contains(&zone) {
+        return None;
+    }
+
+    let e2 = FLATTENING * (2.0 - FLATTENING);
+    let ep2 = e2 / (1.0 - e2);
+    let x = easting - FALSE_EASTING;
+    let y = if south { northing - FALSE_NORTHING_SOUTH } else { northing };
+
+    let mu = y / SCALE_FACTOR / (SEMI_MAJOR_AXIS * (1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2.powi(3) / 256.0));
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+    let phi1 = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+        + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+    let (sin_phi1, cos_phi1, tan_phi1) = (phi1.sin(), phi1.cos(), phi1.tan());
+    let n1 = SEMI_MAJOR_AXIS / (1.0 - e2 * sin_phi1 * sin_phi1).sqrt();
+    let t1 = tan_phi1 * tan_phi1;
+    let c1 = ep2 * cos_phi1 * cos_phi1;
+    let r1 = SEMI_MAJOR_AXIS * (1.0 - e2) / (1.0 - e2 * sin_phi1 * sin_phi1).powf(1.5);
+    let d = x / (n1 * SCALE_FACTOR);
+
+    let latitude = phi1 - (n1 * tan_phi1 / r1) * (
+        d * d / 2.0
+        - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * ep2) * d.powi(4) / 24.0
+        + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1 - 252.0 * ep2 - 3.0 * c1 * c1) * d.powi(6) / 720.0
+    );
+    let longitude = (
+        d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+        + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * ep2 + 24.0 * t1 * t1) * d.powi(5) / 120.0
+    ) / cos_phi1;
+
+    let (longitude, latitude) = (central_meridian(zone) + longitude.to_degrees(), latitude.to_degrees());
+    if longitude.is_finite() && latitude.is_finite() { Some((longitude, latitude)) } else { None }
+}
+
+/// Label of a UTM cell: zone, easting and northing of its south west corner
+/// in meters, i.e. `"32 512000 5340000"`
+pub(crate) fn utm_label(position: &GridPosition, zone: u8, cell_size_m: f64) -> Option<String> {
+    let easting = position.column_index()? as f64 * cell_size_m;
+    let northing = position.row as f64 * cell_size_m;
+    Some(format!("{} {} {}", zone, easting, northing))
+}
+
+/// Bounds of the square cell with the south west corner `easting` /
+/// `northing` as `(min_lon, min_lat, max_lon, max_lat)`
+pub(crate) fn utm_bounds(zone: u8, south: bool, easting: f64, northing: f64, size: f64) -> Option<(f64, f64, f64, f64)> {
+    let mut bounds = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    for &(x, y) in &[(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+        let (longitude, latitude) = from_utm(easting + x * size, northing + y * size, zone, south)?;
+        bounds = (bounds.0.min(longitude), bounds.1.min(latitude), bounds.2.max(longitude), bounds.3.max(latitude));
+    }
+    Some(bounds)
+}
+
+/// Label of a 1 km MGRS square without its grid zone designator (the
+/// `page` of the position), i.e. `"MV 12 34"`
+pub(crate) fn mgrs_square(position: &GridPosition) -> Option<String> {
+    let (zone, _) = grid_zone(position.page.as_deref()?)?;
+    let easting_km = position.column_index()?;
+    let northing_km = position.row;
+
+    let columns = SQUARE_COLUMNS[(zone as usize - 1) % 3];
+    let column = *columns.get((easting_km / 100).checked_sub(1)?)?;
+    let row_offset = if zone % 2 == 0 { 5 } else { 0 };
+    let row = SQUARE_ROWS[(northing_km / 100 + row_offset) % SQUARE_ROWS.len()];
+
+    Some(format!("{}{} {:02} {:02}", column as char, row as char, easting_km % 100, northing_km % 100))
+}
+
+/// Position of the 1 km MGRS square at the `easting_km` / `northing_km`
+/// (including the false northing in the south), the grid zone designator is
+/// taken from the latitude of the center of the square
+pub(crate) fn mgrs_position(zone: u8, south: bool, easting_km: usize, northing_km: usize) -> Option<GridPosition> {
+    let (_, latitude) = from_utm((easting_km as f64 + 0.5) * 1000.0, (northing_km as f64 + 0.5) * 1000.0, zone, south)?;
+    Some(GridPosition {
+        column: number_to_alphabet_value(easting_km),
+        row: northing_km,
+        page: Some(format!("{}{}", zone, latitude_band(latitude)?)),
+    })
+}
+
+/// Splits a grid zone designator such as `"32U"` into the zone
+/// and whether it lies on the southern hemisphere
+pub(crate) fn grid_zone(designator: &str) -> Option<(u8, bool)> {
+    let band = designator.chars().last()?;
+    let zone = designator[..designator.len() - band.len_utf8()].parse::<u8>().ok()?;
+    if !(1..=60).contains(&zone) || !LATITUDE_BANDS.contains(&(band as u8)) {
+        return None;
+    }
+    Some((zone, band < 'N'))
+}
+
+fn central_meridian(zone: u8) -> f64 {
+    zone as f64 * 6.0 - 183.0
+}
+
+/// Length of the meridian arc from the equator to the latitude `phi`
+fn meridian_arc(phi: f64, e2: f64) -> f64 {
+    let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+    SEMI_MAJOR_AXIS * (
+        (1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * phi
+        - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * phi).sin()
+        + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * phi).sin()
+        - (35.0 * e6 / 3072.0) * (6.0 * phi).sin()
+    )
+}
+
+#[test]
+fn test_utm() {
+    // Eiffel Tower, 31U DQ 48251 11932
+    let (easting, northing) = to_utm(2.294_5, 48.858_2, 31, false).unwrap();
+    assert!((easting - 448_251.0).abs() < 2.0 && (northing - 5_411_932.0).abs() < 2.0, "{} {}", easting, northing);
+    let (longitude, latitude) = from_utm(easting, northing, 31, false).unwrap();
+    assert!((longitude - 2.294_5).abs() < 1e-7 && (latitude - 48.858_2).abs() < 1e-7);
+
+    // Sydney Opera House, 56H LH (southern hemisphere, even zone)
+    let (easting, northing) = to_utm(151.215_3, -33.856_8, 56, true).unwrap();
+    let position = mgrs_position(56, true, easting as usize / 1000, northing as usize / 1000).unwrap();
+    assert_eq!(position.page.as_deref(), Some("56H"));
+    assert_eq!(mgrs_square(&position).unwrap()[..2], *"LH");
+
+    assert_eq!(utm_zone(2.2945, 48.8582), Some(31));
+    assert_eq!(utm_zone(5.3, 60.4), Some(32));
+    assert_eq!(utm_zone(15.6, 78.2), Some(33));
+    assert_eq!(utm_zone(180.0, 0.0), Some(1));
+    assert_eq!(utm_zone(0.0, 85.0), None);
+    assert_eq!(latitude_band(48.8582), Some('U'));
+    assert_eq!(latitude_band(83.0), Some('X'));
+    assert_eq!(grid_zone("32U"), Some((32, false)));
+    assert_eq!(grid_zone("56H"), Some((56, true)));
+    assert_eq!(grid_zone("61U"), None);
+}
@@ -0,0 +1,233 @@
+//! Plausibility checks of a processed `StreetIndex` and of the input streets
+//!
+//! A road whose two cells are far apart (i.e. `"A1-Z40"`) is almost always a
+//! data error (two different streets with the same name, a misplaced label,
+//! ...), so `StreetIndex::validate` reports it instead of silently printing
+//! the range:
+//!
+//! ```no_run,ignore
+//! for warning in index.validate(5) {
+//!     eprintln!("warning: {}", warning);
+//! }
+//! ```
+//!
+//! `validate_streets` checks the input before it is deduplicated, so that a
+//! broken upstream export fails fast with the index of the offending record:
+//!
+//! ```no_run,ignore
+//! if let Some(issue) = validate_streets(&streets).first() {
+//!     return Err(format!("bad export: {}", issue));
+//! }
+//! ```
+
+use std::fmt;
+use roads2csv::{StreetIndex, StreetName, FinalizedGridPositon, GridPosition, InputStreetValue};
+
+/// Suspicious entry found by `StreetIndex::validate`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ValidationWarning {
+    /// The first and the last cell of a processed road are more than the
+    /// maximum span apart
+    ExcessiveSpan {
+        name: StreetName,
+        position: FinalizedGridPositon,
+        /// Number of columns between the first and the last cell
+        columns: usize,
+        /// Number of rows between the first and the last cell
+        rows: usize,
+    },
+}
+
+impl ValidationWarning {
+    /// Name of the road that the warning is about
+    pub fn name(&self) -> &StreetName {
+        match self {
+            ValidationWarning::ExcessiveSpan { name, .. } => name,
+        }
+    }
+}
+
+impl fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationWarning::ExcessiveSpan { name, position, columns, rows } => write!(
+                f, "{} ({}) spans {} columns and {} rows, probably a data error",
+                name, position, columns, rows,
+            ),
+        }
+    }
+}
+
+/// Problem of one input street found by `validate_streets`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InputIssue {
+    /// Index of the street in the validated slice
+    pub index: usize,
+    pub kind: InputIssueKind,
+}
+
+/// The reason why an input street was flagged
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InputIssueKind {
+    /// The name is empty or only consists of whitespace
+    EmptyName,
+    /// The name is longer than `InputLimits::max_name_length` characters
+    NameTooLong(usize),
+    /// The name contains a control character (i.e. a tab or a line break)
+    ControlCharacter(char),
+    /// The column is not a column name such as `"AB"`
+    InvalidColumn(String),
+    /// The row is outside of `InputLimits::min_row..=max_row`
+    InvalidRow(usize),
+}
+
+impl fmt::Display for InputIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::InputIssueKind::*;
+        write!(f, "record {}: ", self.index)?;
+        match &self.kind {
+            EmptyName => write!(f, "empty street name"),
+            NameTooLong(length) => write!(f, "street name is {} characters long", length),
+            ControlCharacter(c) => write!(f, "street name contains the control character U+{:04X}", *c as u32),
+            InvalidColumn(column) => write!(f, "invalid column \"{}\"", column),
+            InvalidRow(row) => write!(f, "invalid row {}", row),
+        }
+    }
+}
+
+/// Limits of `validate_streets_with_limits`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct InputLimits {
+    /// Smallest valid row, `0` for grids numbered from 0 (see `GeoGrid::with_first_row`)
+    pub min_row: usize,
+    pub max_row: usize,
+    /// Maximum length of a name in characters
+    pub max_name_length: usize,
+    /// Maximum number of cells a range such as `"A1-A9"` is expanded to
+    /// (see `InputStreetValue::parse_line_with_limits`), lower when fuzzing
+    pub max_range_cells: usize,
+}
+
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self {
+            min_row: 1,
+            max_row: u16::MAX as usize,
+            max_name_length: 200,
+            max_range_cells: if cfg!(fuzzing) { 100 } else { 10_000 },
+        }
+    }
+}
+
+/// Checks the streets with the default `InputLimits`, see `validate_streets_with_limits`
+pub fn validate_streets(streets: &[InputStreetValue]) -> Vec<InputIssue> {
+    validate_streets_with_limits(streets, &InputLimits::default())
+}
+
+/// Returns every issue of every street, ordered by the index of the street.
+/// A street can have several issues (i.e. an empty name and an invalid row).
+pub fn validate_streets_with_limits(streets: &[InputStreetValue], limits: &InputLimits) -> Vec<InputIssue> {
+    let mut issues = Vec::new();
+    for (index, street) in streets.iter().enumerate() {
+        let mut issue = |kind| issues.push(InputIssue { index, kind });
+        let name = &street.street_name.0;
+        let length = name.chars().count();
+        if name.trim().is_empty() {
+            issue(InputIssueKind::EmptyName);
+        } else if length > limits.max_name_length {
+            issue(InputIssueKind::NameTooLong(length));
+        }
+        if let Some(c) = name.chars().find(|c| c.is_control()) {
+            issue(InputIssueKind::ControlCharacter(c));
+        }
+        if street.position.column_index().is_none() {
+            issue(InputIssueKind::InvalidColumn(street.position.column.clone()));
+        }
+        if street.position.row < limits.min_row || street.position.row > limits.max_row {
+            issue(InputIssueKind::InvalidRow(street.position.row));
+        }
+    }
+    issues
+}
+
+impl StreetIndex {
+
+    /// Checks the processed roads and returns a warning for every road whose
+    /// first and last cell are more than `max_span` columns or rows apart.
+    /// Cells on different pages and invalid columns are not compared.
+    pub fn validate(&self, max_span: usize) -> Vec<ValidationWarning> {
+        self.processed.processed.iter().filter_map(|road| {
+            let (columns, rows) = match &road.position {
+                FinalizedGridPositon::SingleRect(_) => return None,
+                FinalizedGridPositon::TwoRect(a, b) |
+                FinalizedGridPositon::Range(a, b) => distance(a, b)?,
+            };
+            if columns.max(rows) <= max_span {
+                return None;
+            }
+            Some(ValidationWarning::ExcessiveSpan {
+                name: road.name.clone(),
+                position: road.position.clone(),
+                columns,
+                rows,
+            })
+        }).collect()
+    }
+}
+
+/// Number of columns and rows between `a` and `b`, if both are on the same page
+fn distance(a: &GridPosition, b: &GridPosition) -> Option<(usize, usize)> {
+    if a.page != b.page {
+        return None;
+    }
+    let (a_column, b_column) = (a.column_index()?, b.column_index()?);
+    Some((a_column.abs_diff(b_column), a.row.abs_diff(b.row)))
+}
+
+#[test]
+fn test_validate() {
+    use roads2csv::{ProcessedRoad, ProcessedRoadNames, UnprocessedRoadNames};
+
+    let road = |name: &str, position: FinalizedGridPositon| ProcessedRoad { name: StreetName(String::from(name)), position, category: None };
+    let cell = |cell: &str| cell.parse::<GridPosition>().unwrap();
+    let index = StreetIndex::new(ProcessedRoadNames { processed: vec![
+        road("Abbey Road", FinalizedGridPositon::SingleRect(cell("A1"))),
+        road("Acacia Avenue", FinalizedGridPositon::TwoRect(cell("A1"), cell("Z40"))),
+        road("Canterbury Road", FinalizedGridPositon::TwoRect(cell("A9"), cell("A10"))),
+        road("Mill Lane", FinalizedGridPositon::Range(cell("C3"), cell("C9"))),
+        road("Page Road", FinalizedGridPositon::TwoRect(cell("1:A1"), cell("2:Z40"))),
+    ] }, UnprocessedRoadNames { unprocessed: Vec::new() });
+
+    let warnings = index.validate(5);
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].name().0, "Acacia Avenue");
+    assert_eq!(warnings[0].to_string(), "Acacia Avenue (A1-Z40) spans 25 columns and 39 rows, probably a data error");
+    assert_eq!(warnings[1].name().0, "Mill Lane");
+
+    assert!(index.validate(40).is_empty());
+}
+
+#[test]
+fn test_validate_streets() {
+    let street = |name: &str, column: &str, row: usize| InputStreetValue::new(name, GridPosition { column: String::from(column), row, page: None });
+    let streets = [
+        street("Abbey Road", "A", 1),
+        street("  ", "B", 0),
+        street("Mill\tLane", "c", 3),
+        street(&"x".repeat(201), "AB", 70_000),
+    ];
+
+    let issues = validate_streets(&streets);
+    assert_eq!(issues.iter().map(|issue| (issue.index, issue.kind.clone())).collect::<Vec<_>>(), vec![
+        (1, InputIssueKind::EmptyName),
+        (1, InputIssueKind::InvalidRow(0)),
+        (2, InputIssueKind::ControlCharacter('\t')),
+        (2, InputIssueKind::InvalidColumn(String::from("c"))),
+        (3, InputIssueKind::NameTooLong(201)),
+        (3, InputIssueKind::InvalidRow(70_000)),
+    ]);
+    assert_eq!(issues[2].to_string(), "record 2: street name contains the control character U+0009");
+
+    let limits = InputLimits { min_row: 0, max_name_length: 300, .. InputLimits::default() };
+    assert_eq!(validate_streets_with_limits(&streets, &limits).len(), 4);
+}
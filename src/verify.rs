@@ -0,0 +1,249 @@
+//! Invariants of a processed `StreetIndex` against the `DeduplicatedRoads`
+//! it was built from, i.e. to check every dataset in a CI pipeline:
+//!
+//! - every input cell of a street is in exactly one entry of the street
+//! - ranges don't cover cells that are not in the input (unless gaps are
+//!   filled on purpose, see `ProcessOptions::cluster_gap`)
+//! - the entries are ordered by the sort strategy
+//!
+//! ```no_run,ignore
+//! let index = deduplicated.process();
+//! let violations = index.verify(&deduplicated);
+//! assert!(violations.is_empty(), "{}", violations[0]);
+//! ```
+
+use std::fmt;
+use std::collections::{BTreeMap, BTreeSet};
+use roads2csv::{StreetIndex, StreetName, DeduplicatedRoads, FinalizedGridPositon, GridPosition, ProcessOptions};
+use category::Category;
+use sorting::{SortStrategy, ByteOrder};
+use gridconfig::number_to_alphabet_value;
+use validation::InputLimits;
+
+/// Broken invariant found by `StreetIndex::verify`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Violation {
+    /// A cell of the input is not in any entry of its street
+    MissingCell { name: StreetName, category: Option<Category>, cell: GridPosition },
+    /// A cell of the input is in more than one entry of its street
+    DuplicateCell { name: StreetName, category: Option<Category>, cell: GridPosition, entries: usize },
+    /// A range of the street covers a cell that is not in the input
+    ExtraCell { name: StreetName, category: Option<Category>, cell: GridPosition },
+    /// A range of more than `InputLimits::max_range_cells` cells covers
+    /// `count` cells that are not in the input (which are not listed one by one)
+    ExtraRange { name: StreetName, category: Option<Category>, position: FinalizedGridPositon, count: u128 },
+    /// The index has entries of a street (and category) that is not in the input
+    UnknownStreet { name: StreetName, category: Option<Category> },
+    /// The entry at `index` of the processed (or unprocessed) roads is
+    /// ordered before the entry `previous`
+    Unsorted { unprocessed: bool, index: usize, name: StreetName, previous: StreetName },
+}
+
+impl Violation {
+    /// Name of the street that the violation is about
+    pub fn name(&self) -> &StreetName {
+        use self::Violation::*;
+        match self {
+            MissingCell { name, .. } | DuplicateCell { name, .. } | ExtraCell { name, .. } |
+            ExtraRange { name, .. } | UnknownStreet { name, .. } | Unsorted { name, .. } => name,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Violation::*;
+        match self {
+            MissingCell { name, cell, .. } => write!(f, "{}: input cell {} is not in the index", name, cell),
+            DuplicateCell { name, cell, entries, .. } => write!(f, "{}: cell {} is in {} entries", name, cell, entries),
+            ExtraCell { name, cell, .. } => write!(f, "{}: cell {} is not in the input", name, cell),
+            ExtraRange { name, position, count, .. } => write!(f, "{}: {} cells of {} are not in the input", name, count, position),
+            UnknownStreet { name, .. } => write!(f, "{} is not in the input", name),
+            Unsorted { unprocessed, index, name, previous } => write!(
+                f, "{} entry {} ({}) is ordered before {}",
+                if *unprocessed { "unprocessed" } else { "processed" }, index, name, previous,
+            ),
+        }
+    }
+}
+
+impl StreetIndex {
+
+    /// Checks the index against the `roads` it was processed from with the
+    /// default `ProcessOptions`, sorted like `process()` does
+    pub fn verify(&self, roads: &DeduplicatedRoads) -> Vec<Violation> {
+        self.verify_with_options(roads, &ProcessOptions::default(), &ByteOrder)
+    }
+
+    /// Same as `verify()` for an index processed with the `options` and
+    /// sorted by the `strategy` (see `ProcessedRoadNames::sort_by_strategy`).
+    /// Ranges may cover missing cells if `options.cluster_gap` fills gaps, only
+    /// the primary cells are expected if `options.primary_cell` is set.
+    pub fn verify_with_options<S: SortStrategy + ?Sized>(&self, roads: &DeduplicatedRoads, options: &ProcessOptions, strategy: &S) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let fills_gaps = options.cluster_gap.unwrap_or(0) > 0;
+
+        let expected = roads.all_roads().into_iter()
+            .map(|(category, name, positions)| ((name.clone(), category.cloned()), roads.primary_positions(name, positions, options).into_owned()))
+            .collect::<BTreeMap<(StreetName, Option<Category>), BTreeSet<GridPosition>>>();
+
+        let mut entries = BTreeMap::<(StreetName, Option<Category>), Vec<Coverage>>::new();
+        for road in &self.processed.processed {
+            entries.entry((road.name.clone(), road.category.clone())).or_default().push(Coverage::new(&road.position));
+        }
+        for road in &self.unprocessed.unprocessed {
+            let cells = road.positions.iter().cloned().collect();
+            entries.entry((road.name.clone(), road.category.clone())).or_default().push(Coverage::Cells(cells));
+        }
+
+        let max_listed_cells = InputLimits::default().max_range_cells as u128;
+        let no_entries = Vec::new();
+        for ((name, category), cells) in &expected {
+            let found = entries.get(&(name.clone(), category.clone())).unwrap_or(&no_entries);
+            for cell in cells {
+                match found.iter().filter(|coverage| coverage.contains(cell)).count() {
+                    0 => violations.push(Violation::MissingCell { name: name.clone(), category: category.clone(), cell: cell.clone() }),
+                    1 => { },
+                    entries => violations.push(Violation::DuplicateCell { name: name.clone(), category: category.clone(), cell: cell.clone(), entries }),
+                }
+            }
+            if fills_gaps {
+                continue;
+            }
+            // ranges are only expanded if they are small, larger ranges are
+            // checked by comparing their area with the input cells they contain
+            let mut extra_cells = BTreeSet::new();
+            let mut extra_ranges = Vec::new();
+            for coverage in found {
+                match coverage {
+                    Coverage::Cells(covered) => extra_cells.extend(covered.iter().filter(|cell| !cells.contains(cell)).cloned()),
+                    Coverage::Rectangle { position, .. } => {
+                        let extra = coverage.area() - cells.iter().filter(|cell| coverage.contains(cell)).count() as u128;
+                        if extra == 0 {
+                            continue;
+                        } else if coverage.area() <= max_listed_cells {
+                            extra_cells.extend(coverage.cells().filter(|cell| !cells.contains(cell)));
+                        } else {
+                            extra_ranges.push(Violation::ExtraRange { name: name.clone(), category: category.clone(), position: position.clone(), count: extra });
+                        }
+                    },
+                }
+            }
+            violations.extend(extra_cells.into_iter().map(|cell| Violation::ExtraCell { name: name.clone(), category: category.clone(), cell }));
+            violations.extend(extra_ranges);
+        }
+        violations.extend(entries.keys().filter(|key| !expected.contains_key(key)).map(|(name, category)| {
+            Violation::UnknownStreet { name: name.clone(), category: category.clone() }
+        }));
+
+        let processed = &self.processed.processed;
+        violations.extend((1..processed.len())
+            .filter(|&i| strategy.compare(&processed[i - 1].name.0, &processed[i].name.0).then_with(|| processed[i - 1].tie_break(&processed[i])).is_gt())
+            .map(|i| Violation::Unsorted { unprocessed: false, index: i, name: processed[i].name.clone(), previous: processed[i - 1].name.clone() }));
+        let unprocessed = &self.unprocessed.unprocessed;
+        violations.extend((1..unprocessed.len())
+            .filter(|&i| strategy.compare(&unprocessed[i - 1].name.0, &unprocessed[i].name.0).then_with(|| unprocessed[i - 1].tie_break(&unprocessed[i])).is_gt())
+            .map(|i| Violation::Unsorted { unprocessed: true, index: i, name: unprocessed[i].name.clone(), previous: unprocessed[i - 1].name.clone() }));
+
+        violations
+    }
+}
+
+/// Cells of one entry of the index
+enum Coverage {
+    /// Single cells, or both corners of a range on two pages (or with a
+    /// custom column)
+    Cells(BTreeSet<GridPosition>),
+    /// Every cell of a run or a rectangle on one page
+    Rectangle { position: FinalizedGridPositon, page: Option<String>, columns: (usize, usize), rows: (usize, usize) },
+}
+
+impl Coverage {
+
+    fn new(position: &FinalizedGridPositon) -> Self {
+        let (a, b) = match position {
+            FinalizedGridPositon::SingleRect(a) => return Coverage::Cells(::std::iter::once(a.clone()).collect()),
+            FinalizedGridPositon::TwoRect(a, b) => return Coverage::Cells(vec![a.clone(), b.clone()].into_iter().collect()),
+            FinalizedGridPositon::Range(a, b) => (a, b),
+        };
+        match (a.cell_id(), b.cell_id()) {
+            (Some(a_cell), Some(b_cell)) if a.page == b.page => Coverage::Rectangle {
+                position: position.clone(),
+                page: a.page.clone(),
+                columns: (a_cell.column().min(b_cell.column()), a_cell.column().max(b_cell.column())),
+                rows: (a_cell.row().min(b_cell.row()), a_cell.row().max(b_cell.row())),
+            },
+            _ => Coverage::Cells(vec![a.clone(), b.clone()].into_iter().collect()),
+        }
+    }
+
+    fn contains(&self, cell: &GridPosition) -> bool {
+        match self {
+            Coverage::Cells(cells) => cells.contains(cell),
+            Coverage::Rectangle { page, columns, rows, .. } => *page == cell.page && cell.cell_id().is_some_and(|id| {
+                (columns.0..=columns.1).contains(&id.column()) && (rows.0..=rows.1).contains(&id.row())
+            }),
+        }
+    }
+
+    /// Number of covered cells
+    fn area(&self) -> u128 {
+        match self {
+            Coverage::Cells(cells) => cells.len() as u128,
+            Coverage::Rectangle { columns, rows, .. } => ((columns.1 - columns.0) as u128 + 1) * ((rows.1 - rows.0) as u128 + 1),
+        }
+    }
+
+    /// All covered cells, only for small rectangles
+    fn cells(&self) -> Box<dyn Iterator<Item = GridPosition> + '_> {
+        match self {
+            Coverage::Cells(cells) => Box::new(cells.iter().cloned()),
+            Coverage::Rectangle { page, columns, rows, .. } => Box::new((columns.0..=columns.1)
+                .flat_map(move |column| (rows.0..=rows.1).map(move |row| (column, row)))
+                .map(move |(column, row)| GridPosition { column: number_to_alphabet_value(column), row, page: page.clone() })),
+        }
+    }
+}
+
+#[test]
+fn test_verify() {
+    use roads2csv::{InputStreetValue, ProcessedRoad, UnprocessedRoad};
+
+    let cell = |cell: &str| cell.parse::<GridPosition>().unwrap();
+    let streets = [("Mill Lane", "C4"), ("Mill Lane", "D4"), ("Mill Lane", "C5"), ("Mill Lane", "D5"), ("Abbey Road", "A1"), ("Abbey Road", "A3"), ("Abbey Road", "A4"), ("Valley Road", "C1"), ("Valley Road", "E5"), ("Valley Road", "F9")]
+        .iter().map(|(name, position)| InputStreetValue::new(name, cell(position))).collect::<Vec<_>>();
+    let deduplicated = DeduplicatedRoads::from_streets(&streets);
+    let index = deduplicated.process();
+    assert_eq!(index.verify(&deduplicated), []);
+
+    // "A1-A4" fills the gap A2 on purpose
+    let options = ProcessOptions::default().with_ranges(true).with_cluster_gap(Some(1));
+    let filled = deduplicated.process_with_options(&options);
+    assert_eq!(filled.verify_with_options(&deduplicated, &options, &ByteOrder), []);
+    assert_eq!(filled.verify(&deduplicated), [Violation::ExtraCell { name: StreetName(String::from("Abbey Road")), category: None, cell: cell("A2") }]);
+
+    // huge ranges are checked without expanding them
+    let far = ["A0", "A1", "A4294967295"].iter().map(|position| InputStreetValue::new("Far Road", cell(position))).collect::<Vec<_>>();
+    let far = DeduplicatedRoads::from_streets(&far);
+    let options = ProcessOptions::default().with_ranges(true).with_cluster_gap(Some(usize::MAX));
+    let filled = far.process_with_options(&options);
+    assert_eq!(filled.verify_with_options(&far, &options, &ByteOrder), []);
+    assert_eq!(filled.verify(&far).iter().map(|violation| violation.to_string()).collect::<Vec<_>>(), ["Far Road: 4294967293 cells of A0-A4294967295 are not in the input"]);
+
+    let mut broken = index.clone();
+    broken.unprocessed.unprocessed.retain(|road| road.name.0 != "Abbey Road");
+    broken.processed.processed.push(ProcessedRoad { name: StreetName(String::from("Zion Street")), position: FinalizedGridPositon::SingleRect(cell("B2")), category: None });
+    broken.processed.processed.push(ProcessedRoad { name: StreetName(String::from("Mill Lane")), position: FinalizedGridPositon::TwoRect(cell("C4"), cell("C6")), category: None });
+    broken.unprocessed.unprocessed.push(UnprocessedRoad { name: StreetName(String::from("Abbey Road")), positions: vec![cell("A3"), cell("A4")], category: None });
+
+    let violations = broken.verify(&deduplicated);
+    assert_eq!(violations.iter().map(|violation| violation.to_string()).collect::<Vec<_>>(), [
+        "Abbey Road: input cell A1 is not in the index",
+        "Mill Lane: cell C4 is in 2 entries",
+        "Mill Lane: cell C6 is not in the input",
+        "Zion Street is not in the input",
+        "processed entry 2 (Mill Lane) is ordered before Zion Street",
+        "unprocessed entry 1 (Abbey Road) is ordered before Valley Road",
+    ]);
+    assert_eq!(violations[0].name().0, "Abbey Road");
+}
@@ -0,0 +1,99 @@
+//! JavaScript bindings via `wasm-bindgen`, so that a web-based map editor
+//! can generate the street index client-side
+//!
+//! The input is a JSON object with the streets and the processing mode:
+//!
+//! ```no_run,ignore
+//! {
+//!   "streets": [
+//!     { "name": "Canterbury Road", "positions": ["A9", "A10"] },
+//!     { "name": "Elbe", "positions": ["C1-C4"], "category": "water" }
+//!   ],
+//!   "mode": "ranges"
+//! }
+//! ```
+//!
+//! `mode` is `"simple"` (the default, see `DeduplicatedRoads::process`),
+//! `"ranges"` or `"clustering"` (with an optional `"max_gap"`, default `0`).
+//! The output is the JSON of `StreetIndex::to_json`.
+
+use serde_json::{self, Value};
+use wasm_bindgen::prelude::*;
+use roads2csv::{DeduplicatedRoads, InputStreetValue, ParseError, parse_positions};
+use validation::InputLimits;
+use category::Category;
+use error::{Error, Result};
+
+/// Generates the street index from the JSON input (see the module
+/// documentation), errors are thrown as JavaScript strings
+#[wasm_bindgen]
+pub fn generate_index(json_input: &str) -> ::std::result::Result<String, JsValue> {
+    generate_index_json(json_input).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Same as `generate_index`, but usable from Rust
+pub fn generate_index_json(json_input: &str) -> Result<String> {
+    let input: Value = serde_json::from_str(json_input).map_err(|e| Error::InvalidJson(e.to_string()))?;
+
+    let streets = input.get("streets")
+        .and_then(Value::as_array)
+        .ok_or_else(|| Error::InvalidJson(String::from("missing \"streets\" array")))?;
+
+    let mut values = Vec::new();
+    for (index, street) in streets.iter().enumerate() {
+        let invalid = |reason: &str| Error::InvalidJson(format!("street {}: {}", index, reason));
+
+        let name = street.get("name").and_then(Value::as_str).ok_or_else(|| invalid("missing \"name\""))?;
+        let category = street.get("category").and_then(Value::as_str).map(|c| c.parse::<Category>());
+        let positions = street.get("positions").and_then(Value::as_array).ok_or_else(|| invalid("missing \"positions\" array"))?;
+
+        for position in positions {
+            let position = position.as_str().ok_or_else(|| invalid("positions have to be strings"))?;
+            let cells = parse_positions(position.trim(), InputLimits::default().max_range_cells)
+                .map_err(|kind| ParseError { line: None, kind })?;
+            values.extend(cells.into_iter().map(|cell| match &category {
+                Some(Ok(category)) => InputStreetValue::new(name, cell).with_category(category.clone()),
+                _ => InputStreetValue::new(name, cell),
+            }));
+        }
+    }
+
+    if values.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+
+    let deduplicated = DeduplicatedRoads::from_streets(&values);
+    let max_gap = input.get("max_gap").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let index = match input.get("mode").and_then(Value::as_str).unwrap_or("simple") {
+        "simple" => deduplicated.process(),
+        "ranges" => deduplicated.process_with_ranges(),
+        "clustering" => deduplicated.process_with_clustering(max_gap),
+        other => return Err(Error::InvalidJson(format!("unknown mode \"{}\"", other))),
+    };
+
+    Ok(index.to_json())
+}
+
+#[test]
+fn test_generate_index() {
+    let input = r#"{
+        "streets": [
+            { "name": "Canterbury Road", "positions": ["A9", "A10", "A11"] },
+            { "name": "Elbe", "positions": ["C1-C2"], "category": "water" }
+        ],
+        "mode": "ranges"
+    }"#;
+    assert_eq!(generate_index_json(input).unwrap(), concat!(
+        r#"{"processed":["#,
+        r#"{"name":"Canterbury Road","status":"processed","positions":["A9-A11"]},"#,
+        r#"{"name":"Elbe","category":"water","status":"processed","positions":["C1-C2"]}"#,
+        r#"],"unprocessed":[]}"#,
+    ));
+
+    match generate_index_json(r#"{ "streets": [] }"#) {
+        Err(Error::EmptyInput) => { },
+        other => panic!("expected an empty input error, got {:?}", other),
+    }
+    assert_eq!(generate_index_json(r#"{ "streets": [{ "name": "Mill Lane" }] }"#).unwrap_err().to_string(), "invalid JSON input: street 0: missing \"positions\" array");
+    assert_eq!(generate_index_json(r#"{ "streets": [{ "name": "Mill Lane", "positions": ["9Z"] }] }"#).unwrap_err().to_string(), "invalid grid position \"9Z\"");
+}